@@ -0,0 +1,40 @@
+# Messages of gg in English, the ones used when a language has no translation of them.
+#
+# The help of the commands and their arguments is in English in the source, so it is only in
+# the translations, see `localized_command` in src/cli.rs.
+
+## Kinds of the messages printed besides the results
+
+output-warning = Warning:
+output-error = Error:
+
+## Prompts
+
+prompt-game = Game:
+prompt-continue = Continue with { $games }?
+prompt-select-backup = Select the backup to restore
+prompt-delete-them = Delete them?
+prompt-crash-backup = { $game } exited with an error, back up its save?
+prompt-edit-again = Edit it again?
+prompt-secret-value = Value of { $name }:
+prompt-save-location = Select the game's save location
+prompt-prefix-save-location = Select the game's save location in its prefix
+prompt-save-directory = Select the directory where the game saved
+prompt-new-save-location = Select the new save location
+prompt-keep-save-location = Keep the current save location
+prompt-new-root = New root:
+prompt-executable = Select the game's main executable
+prompt-add-game = Add { $game }?
+
+## Results
+
+cancelled = Cancelled, nothing has been changed
+backup-created = Created backup { $path }
+backup-done = Backed up { $game }
+restore-done = Restored { $game }
+game-added = Added { $game }
+game-deleted = Deleted { $game } successfully
+game-renamed = Renamed { $from } to { $to }
+game-moved = Moved { $game } and verified its paths
+game-archived = Archived { $game }, bring it back with 'gg unarchive { $quoted }'
+game-unarchived = Brought back { $game }
@@ -0,0 +1,110 @@
+# Mensajes de gg en español.
+#
+# Los que faltan se muestran en inglés.
+
+## Tipos de los mensajes que acompañan a los resultados
+
+output-warning = Aviso:
+output-error = Error:
+
+## Preguntas
+
+prompt-game = Juego:
+prompt-continue = ¿Continuar con { $games }?
+prompt-select-backup = Elige la copia de seguridad que restaurar
+prompt-delete-them = ¿Borrarlos?
+prompt-crash-backup = { $game } ha terminado con un error, ¿hacer una copia de su partida?
+prompt-edit-again = ¿Editarla de nuevo?
+prompt-secret-value = Valor de { $name }:
+prompt-save-location = Elige dónde guarda las partidas el juego
+prompt-prefix-save-location = Elige dónde guarda las partidas el juego dentro de su prefijo
+prompt-save-directory = Elige el directorio donde el juego ha guardado
+prompt-new-save-location = Elige dónde se guardan ahora las partidas
+prompt-keep-save-location = Mantener la ubicación actual de las partidas
+prompt-new-root = Nueva raíz:
+prompt-executable = Elige el ejecutable principal del juego
+prompt-add-game = ¿Añadir { $game }?
+
+## Resultados
+
+cancelled = Cancelado, no se ha cambiado nada
+backup-created = Copia de seguridad creada en { $path }
+backup-done = Copia de seguridad de { $game } hecha
+restore-done = { $game } restaurado
+game-added = { $game } añadido
+game-deleted = { $game } eliminado
+game-renamed = { $from } renombrado a { $to }
+game-moved = { $game } movido y sus rutas comprobadas
+game-archived = { $game } archivado, recupéralo con 'gg unarchive { $quoted }'
+game-unarchived = { $game } recuperado
+
+## Ayuda de los comandos, con el nombre de su ruta como "gg-backups-cat" y el de sus argumentos
+## tras "--", como "gg-restore--game"
+
+help-commands = Comandos
+
+gg--ignore_config = Usa la configuración por defecto en lugar de leer los ficheros de configuración del sistema y del usuario
+gg--json = Imprime el resultado de list, status, backup, restore, config, stats, 'backups list' y 'cloud status' como JSON, y el resto de mensajes por stderr
+gg--verbose = Imprime también los mensajes del registro, -vv añade las rutas resueltas y las estadísticas de los archivos
+gg--quiet = Oculta los comandos ejecutados y los mensajes de progreso, imprimiendo solo los resultados y los avisos
+gg--no_color = Imprime los mensajes sin colores, también desactivados con la variable de entorno NO_COLOR
+
+gg-add = Empieza a gestionar el juego indicado
+gg-edit = Edita la configuración del juego indicado
+gg-rename = Renombra un juego junto con sus copias de seguridad, sus metadatos y su almacenamiento en la nube
+gg-move = Mueve un juego a una nueva raíz o ubicación de partidas, como otro disco
+gg-remove = Deja de gestionar el juego, preguntando antes si se ejecuta en un terminal
+gg-archive = Archiva juegos, que se conservan con sus copias de seguridad pero se ocultan de 'gg list', el autocompletado y los comandos que actúan sobre todos los juegos, patrones o etiquetas
+gg-unarchive = Recupera juegos archivados
+gg-backup = Crea una copia de seguridad de la partida actual
+gg-backup--desc = Descripción que se añade al nombre de la copia de seguridad
+gg-backup--message = Mensaje del commit en la nube, disponible para los comandos de commit como @MESSAGE
+gg-restore = Restaura la copia de seguridad elegida
+gg-restore--game = Nombre del juego cuya partida se restaura
+gg-restore--backup = Nombre de la copia de seguridad que restaurar, elegida de una lista si no se indica
+gg-restore--slot = Restaura solo esta ranura del contenedor de partidas del juego
+gg-restore--player = Jugador cuyas copias de seguridad y partida se usan, uno de los 'players' del juego
+gg-transfer = Copia la partida de la última copia de seguridad de un juego en la ubicación de partidas de otro
+gg-sync = Sube las copias de seguridad que faltan en la nube y descarga las que faltan en local
+gg-clone = Prepara los juegos de otra máquina, descargando sus copias de seguridad de la nube
+gg-export = Escribe la configuración de los juegos gestionados en un fichero que lee 'gg import'
+gg-import = Añade los juegos de un fichero escrito por 'gg export' o de la base de datos de juegos de otra máquina, o una copia de seguridad hecha por gg
+gg-share = Empaqueta la configuración y la última copia de seguridad de un juego en un único fichero
+gg-backups = Examina las copias de seguridad de un juego
+gg-backups-list = Lista las copias de seguridad de un juego con su tamaño y su commit en la nube
+gg-backups-dedupe = Da un nuevo índice a las copias de seguridad que comparten uno, como las hechas en dos máquinas
+gg-backups-cat = Imprime un fichero guardado en una copia de seguridad
+gg-cloud = Examina el almacenamiento en la nube de los juegos
+gg-cloud-status = Muestra las copias de seguridad de cada juego que no se han subido, y las más nuevas de la nube
+gg-cloud-ls = Lista las copias de seguridad de un juego que están en la nube pero no en esta máquina
+gg-cloud-dry-run = Imprime los comandos de la nube de un juego con sus variables sustituidas, sin ejecutarlos
+gg-list = Lista todos los juegos gestionados
+gg-open = Abre el directorio raíz del juego
+gg-open--save = Abre el directorio de partidas en lugar de la raíz
+gg-open--backups = Abre el directorio de copias de seguridad en lugar de la raíz
+gg-desktop = Añade un lanzador del juego al menú de aplicaciones, que lo inicia con 'gg run'
+gg-metadata = Descarga la portada, el icono y la fecha de lanzamiento de los juegos de SteamGridDB
+gg-steam-shortcut = Añade el juego a Steam como acceso directo, que lo inicia con 'gg run'
+gg-run = Ejecuta el juego elegido
+gg-run--game = Nombre del juego que ejecutar
+gg-doctor = Comprueba que no falten las raíces, ubicaciones de partidas y directorios de copias de seguridad de los juegos, ni haya enlaces gg-save-loc rotos, copias de seguridad truncadas o ficheros temporales abandonados
+gg-sandbox = Prueba gg en un juego desechable, sin tocar los juegos gestionados ni la nube
+gg-stats = Muestra el tiempo jugado, las copias de seguridad guardadas y la actividad de un juego en esta máquina
+gg-status = Muestra las copias de seguridad, el estado de la nube y la última sesión de cada juego, o solo de uno
+gg-search = Busca los juegos cuyo nombre, alias, raíz, ubicación de partidas, ejecutable o etiquetas contienen el texto, sin distinguir mayúsculas, imprimiendo los campos que coinciden
+gg-du = Muestra el espacio que ocupan las copias de seguridad de cada juego, o solo de uno, de mayor a menor, seguido del total y de las copias más grandes
+gg-gc = Recomprime las copias de seguridad antiguas de los juegos con un nivel de zstd más alto para que ocupen menos, e imprime el espacio recuperado
+gg-watch = Hace copias de seguridad de las partidas de los juegos cuando cambian, hasta que se detiene con Ctrl-C
+gg-top = Muestra los juegos en ejecución, las operaciones en curso y los fallos recientes de gg, y el estado de gg serve si está escuchando
+gg-config = Imprime los valores de los ficheros de configuración, con el fichero del que viene cada uno
+gg-config-edit = Abre el fichero de configuración del usuario en el editor, guardándolo solo si la configuración resultante es válida
+gg-config-init = Escribe una configuración inicial comentada en el fichero de configuración del usuario
+gg-config-check = Comprueba la configuración, fallando si tiene algún problema
+gg-config-schema = Imprime el JSON Schema de los ficheros de configuración, para que los editores los validen y completen
+gg-key = Gestiona la contraseña con la que se cifran las copias de seguridad
+gg-key-rotate = Vuelve a cifrar las copias de seguridad cifradas con una nueva contraseña
+gg-secret = Gestiona las credenciales guardadas en el llavero del sistema, usadas en la configuración con `keyring: NAME` en lugar de escribirlas en los ficheros de configuración
+gg-secret-set = Guarda una credencial en el llavero del sistema, sustituyendo la anterior
+gg-secret-remove = Elimina una credencial del llavero del sistema
+gg-completions = Gestiona el autocompletado de la shell
+gg-completions-doctor = Comprueba que el autocompletado está configurado en la shell, imprimiendo la línea que añadir si no
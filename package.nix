@@ -15,6 +15,8 @@
       --fish <(echo "source (COMPLETE=fish $out/bin/gg | psub)") \
       --bash <(echo "source (COMPLETE=bash $out/bin/gg)") \
       --zsh  <(echo "source (COMPLETE=zsh $out/bin/gg)")
+    $out/bin/gg man man
+    installManPage man/*.1
   '';
 
   meta = {
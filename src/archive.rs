@@ -0,0 +1,314 @@
+//! Formats of the backup archives, chosen per game with the name of its archiver.
+//!
+//! The backups are tar archives by default, compressed and encrypted as configured for the game.
+//! Other formats are added by registering an [`Archiver`] with
+//! [`Games::register_archiver`](crate::games::Games::register_archiver).
+
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::{
+    io::{Read, Write},
+    ops::ControlFlow,
+    path::{Component, Path, PathBuf},
+};
+
+/// Name of the built-in [`Tar`] archiver, used by the games that don't choose one.
+pub const TAR: &str = "tar";
+
+/// File stored in an archive, read while the archive is extracted.
+pub trait ArchiveEntry: Read {
+    /// Returns the path of the entry inside the archive.
+    fn path(&self) -> Result<PathBuf>;
+
+    /// Writes the entry to `dest`, with its permissions and modification time if the format
+    /// keeps them.
+    fn unpack(&mut self, dest: &Path) -> Result<()> {
+        let mut file = std::fs::File::create(dest)
+            .context_with(|| format!("Could not create {}", dest.display()))?;
+        std::io::copy(self, &mut file)?;
+        Ok(())
+    }
+
+    /// Returns whether the entry is a symbolic or hard link, which are never restored, as they
+    /// could point anywhere.
+    fn is_link(&self) -> bool {
+        false
+    }
+
+    /// Writes the entry to `dest` like [`unpack`](Self::unpack), failing if `dest` is not a plain
+    /// path inside `dir`, if the entry is a link, or if a symlink already in `dir` would take it
+    /// somewhere else.
+    fn unpack_in(&mut self, dir: &Path, dest: &Path) -> Result<()> {
+        let Ok(rel) = dest.strip_prefix(dir) else {
+            bail!("{} is outside of {}", dest.display(), dir.display())
+        };
+        if rel.as_os_str().is_empty()
+            || rel.components().any(|c| !matches!(c, Component::Normal(_)))
+        {
+            bail!("Refusing to extract to {}", dest.display())
+        }
+        if self.is_link() {
+            bail!("Refusing to extract the link {}", dest.display())
+        }
+        std::fs::create_dir_all(dir)
+            .context_with(|| format!("Could not create directory {}", dir.display()))?;
+        let canonical_dir = dir.canonicalize()?;
+        // Each directory is checked before going into it, so a symlink can't make it create
+        // directories outside
+        let mut parent = dir.to_path_buf();
+        for component in rel.parent().into_iter().flat_map(Path::components) {
+            parent.push(component);
+            if !parent.exists() {
+                std::fs::create_dir(&parent)
+                    .context_with(|| format!("Could not create directory {}", parent.display()))?;
+            }
+            if !parent.canonicalize()?.starts_with(&canonical_dir) {
+                bail!("{} points outside of {}", parent.display(), dir.display())
+            }
+        }
+        if dest.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+            std::fs::remove_file(dest)
+                .context_with(|| format!("Could not replace the symlink {}", dest.display()))?;
+        }
+        self.unpack(dest)
+    }
+}
+
+/// Format of the backup archives.
+///
+/// The archives are written to and read from streams, so they are encrypted like the rest if the
+/// backups of the game are.
+pub trait Archiver: std::fmt::Debug + Send + Sync {
+    /// Extension of the archives without the dot, like "zip", for the formats that compress by
+    /// themselves.
+    ///
+    /// `None` by default, which names the archives "tar.EXT" and compresses them with the
+    /// compression of the game.
+    fn extension(&self) -> Option<&str> {
+        None
+    }
+
+    /// Writes into `output` an archive of `save`, a file or a directory, stored as `prefix`, after
+    /// the `extra` files given by their path and contents.
+    fn create(
+        &self,
+        output: &mut dyn Write,
+        save: &Path,
+        prefix: &str,
+        extra: &[(&str, &[u8])],
+    ) -> Result<()>;
+
+    /// Calls `visit` with each entry of the archive read from `input`, in order, until it breaks.
+    fn extract(
+        &self,
+        input: &mut dyn Read,
+        visit: &mut dyn FnMut(&mut dyn ArchiveEntry) -> Result<ControlFlow<()>>,
+    ) -> Result<()>;
+
+    /// Returns the paths of the entries of the archive read from `input`.
+    fn list(&self, input: &mut dyn Read) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        self.extract(input, &mut |entry| {
+            paths.push(entry.path()?);
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(paths)
+    }
+
+    /// Reads the whole archive from `input`, failing if it is truncated or corrupted.
+    fn verify(&self, input: &mut dyn Read) -> Result<()> {
+        self.extract(input, &mut |entry| {
+            std::io::copy(entry, &mut std::io::sink())?;
+            Ok(ControlFlow::Continue(()))
+        })
+    }
+}
+
+/// The tar archives of gg, with the symlinks, permissions and modification times of the save.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Tar;
+
+impl Archiver for Tar {
+    fn create(
+        &self,
+        output: &mut dyn Write,
+        save: &Path,
+        prefix: &str,
+        extra: &[(&str, &[u8])],
+    ) -> Result<()> {
+        let mut builder = tar::Builder::new(output);
+        for (path, contents) in extra {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, path, *contents)
+                .context_with(|| format!("Could not archive {path}"))?;
+        }
+        if save.is_dir() {
+            builder
+                .append_dir_all(prefix, save)
+                .context_with(|| format!("Could not archive directory {}", save.display()))?;
+        } else {
+            builder
+                .append_file(prefix, &mut std::fs::File::open(save)?)
+                .context_with(|| format!("Could not archive file {}", save.display()))?;
+        }
+        builder.finish()?;
+        Ok(())
+    }
+
+    fn extract(
+        &self,
+        input: &mut dyn Read,
+        visit: &mut dyn FnMut(&mut dyn ArchiveEntry) -> Result<ControlFlow<()>>,
+    ) -> Result<()> {
+        let mut archive = tar::Archive::new(input);
+        for entry in archive.entries()? {
+            if visit(&mut entry?)?.is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> ArchiveEntry for tar::Entry<'_, R> {
+    fn path(&self) -> Result<PathBuf> {
+        Ok(tar::Entry::path(self)?.into_owned())
+    }
+
+    fn unpack(&mut self, dest: &Path) -> Result<()> {
+        tar::Entry::unpack(self, dest)?;
+        Ok(())
+    }
+
+    fn is_link(&self) -> bool {
+        let kind = self.header().entry_type();
+        kind.is_symlink() || kind.is_hard_link()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// Entry with the `contents`, a link if `link`.
+    struct Entry {
+        contents: &'static [u8],
+        link: bool,
+    }
+
+    impl Read for Entry {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.contents.read(buf)
+        }
+    }
+
+    impl ArchiveEntry for Entry {
+        fn path(&self) -> Result<PathBuf> {
+            Ok(PathBuf::from("entry"))
+        }
+
+        fn is_link(&self) -> bool {
+            self.link
+        }
+    }
+
+    fn file(contents: &'static [u8]) -> Entry {
+        Entry {
+            contents,
+            link: false,
+        }
+    }
+
+    #[test]
+    fn unpacks_inside_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save");
+        let dest = save.join("slots/1/data.sav");
+        file(b"save").unpack_in(&save, &dest).unwrap();
+        assert_eq!(std::fs::read(dest).unwrap(), b"save");
+    }
+
+    #[test]
+    fn refuses_paths_outside_the_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save");
+        for dest in [
+            save.join("../escaped"),
+            save.join("slots/../../escaped"),
+            dir.path().join("escaped"),
+            save.clone(),
+        ] {
+            assert!(file(b"evil").unpack_in(&save, &dest).is_err(), "{dest:?}");
+        }
+        assert!(!dir.path().join("escaped").exists());
+    }
+
+    #[test]
+    fn refuses_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = &mut Entry {
+            contents: b"",
+            link: true,
+        };
+        let dest = dir.path().join("link");
+        assert!(entry.unpack_in(dir.path(), &dest).is_err());
+        assert!(dest.symlink_metadata().is_err());
+    }
+
+    #[test]
+    fn refuses_symlinked_directories_pointing_outside() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save");
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&save).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        symlink(&outside, save.join("slots")).unwrap();
+
+        let dest = save.join("slots/data.sav");
+        assert!(file(b"evil").unpack_in(&save, &dest).is_err());
+        assert!(!outside.join("data.sav").exists());
+    }
+
+    #[test]
+    fn replaces_symlinked_files_instead_of_following_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save");
+        let outside = dir.path().join("outside.txt");
+        std::fs::create_dir_all(&save).unwrap();
+        std::fs::write(&outside, "untouched").unwrap();
+        let dest = save.join("data.sav");
+        symlink(&outside, &dest).unwrap();
+
+        file(b"save").unpack_in(&save, &dest).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"save");
+        assert!(!dest.symlink_metadata().unwrap().is_symlink());
+        assert_eq!(std::fs::read(&outside).unwrap(), b"untouched");
+    }
+
+    #[test]
+    fn refuses_the_links_of_tar_archives() {
+        let mut archive = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        archive
+            .append_link(&mut header, "@SAVE/passwd", "/etc/passwd")
+            .unwrap();
+        let archive = archive.into_inner().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("passwd");
+        let mut unpacked = None;
+        Tar.extract(&mut archive.as_slice(), &mut |entry| {
+            unpacked = Some(entry.unpack_in(dir.path(), &dest));
+            Ok(ControlFlow::Break(()))
+        })
+        .unwrap();
+        assert!(unpacked.unwrap().is_err());
+        assert!(dest.symlink_metadata().is_err());
+    }
+}
@@ -0,0 +1,1472 @@
+//! The backup engine: creating, restoring, verifying, repacking and pruning the backup archives
+//! of the games, and re-encrypting them with a new passphrase.
+//!
+//! A backup archive holds the files of the save under [`SAVE_PREFIX`], the [`MANIFEST_ENTRY`] with
+//! the hash of each one, checked after restoring them, and the [`INFO_ENTRY`] describing the
+//! backup. It is compressed and encrypted as configured for its game.
+
+use crate::archive::Archiver;
+use crate::cloud::CloudMode;
+use crate::compression::{self, Compressor};
+use crate::crypto;
+use crate::error::Error;
+use crate::events::{Event, Hook};
+use crate::games::{Backup, BackupMarker, Game, Games};
+use crate::units::{Duration, Size};
+use crate::{output, status};
+use age::secrecy::SecretString;
+use rootcause::Result;
+use rootcause::option_ext::OptionExt;
+use rootcause::prelude::*;
+use std::{
+    io::{Read, Write},
+    ops::ControlFlow,
+    path::{Component, Path, PathBuf},
+};
+
+/// Prefix under which the save files are stored inside a backup archive.
+///
+/// It stands for the game's save location and is replaced by it when restoring.
+pub const SAVE_PREFIX: &str = "@SAVE";
+/// Name of the archive entry that lists the checksum of every save file in the backup.
+pub const MANIFEST_ENTRY: &str = "gg-manifest.yaml";
+/// Name of the archive entry that describes the backup, so it can be imported without its game.
+pub const INFO_ENTRY: &str = "gg-metadata.json";
+
+/// Maps the archive path of every save file to its BLAKE3 hash.
+pub type Manifest = std::collections::BTreeMap<String, String>;
+
+/// What a backup archive records about itself in [`INFO_ENTRY`].
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct BackupInfo {
+    /// Name of the game the backup was made from.
+    pub game: String,
+    /// Player whose save it is, if any.
+    #[serde(default)]
+    pub player: Option<String>,
+    /// Root of the game on the machine that made the backup.
+    pub root: PathBuf,
+    /// Where the save was on the machine that made the backup.
+    pub save_location: PathBuf,
+    /// Seconds since the Unix epoch when the backup was made.
+    pub created: u64,
+    /// Version of gg that made the backup.
+    pub gg_version: String,
+    /// BLAKE3 hash of every save file, like in [`MANIFEST_ENTRY`].
+    pub files: Manifest,
+}
+
+/// A backup repacked by [`Games::gc`], or that would be in a dry run.
+#[derive(Debug)]
+pub struct Repack {
+    pub path: PathBuf,
+    /// Size of the backup before repacking it.
+    pub size: u64,
+    /// Size of the repacked backup, `None` in a dry run.
+    pub repacked: Option<u64>,
+}
+
+impl Games {
+    /// Backs up the save of the game, running its hooks and notifying the result.
+    ///
+    /// The backup is compressed and called "GAME-IDX" by default.
+    /// If a backup description is provided, the backup will be called "GAME-IDX-DESCRIPTION"
+    ///
+    /// The cloud commit is described by `message`, or by a default one that includes the length of
+    /// the play `session` that preceded the backup. With [`CloudMode::CommitOnly`] the backup is
+    /// only marked as pending upload after committing it.
+    ///
+    /// The backups past the retention of the game are then pruned if [`Games::auto_prune`].
+    ///
+    /// Returns the path of the backup.
+    pub fn backup(
+        &self,
+        game: &Game,
+        desc: Option<&str>,
+        message: Option<&str>,
+        session: Option<std::time::Duration>,
+        cloud: CloudMode,
+    ) -> Result<PathBuf> {
+        let start = std::time::Instant::now();
+        self.check_broken(game, Event::BackupCreated)?;
+        let _lock = status::lock(game.name(), "backup")?;
+        if let Some(pid) = game.running_pid() {
+            output::warning(format_args!(
+                "{} is running (PID {pid}), the backup may catch the saves mid-write",
+                game.name()
+            ));
+        }
+        self.run_hook(Hook::PreBackup, game, None)?;
+        let archive_path = match self.create_backup(game, desc) {
+            Ok(archive_path) => archive_path,
+            Err(e) => {
+                self.notify(Event::BackupCreated, game, None, start.elapsed(), Some(&e))?;
+                return Err(e);
+            }
+        };
+        self.notify(
+            Event::BackupCreated,
+            game,
+            Some(&archive_path),
+            start.elapsed(),
+            None,
+        )?;
+        self.run_hook(Hook::PostBackup, game, Some(&archive_path))?;
+        // Before the cloud commit, so the removed backups leave the cloud too
+        if self.auto_prune(game)?
+            && let Err(e) = self.prune(game, None, None, false)
+        {
+            output::warning(format_args!(
+                "Could not prune the backups of {}: {e}",
+                game.name()
+            ));
+        }
+
+        let message = message.map_or_else(
+            || game.commit_message(Some(&archive_path), session),
+            String::from,
+        );
+        if !cloud.commits() {
+            return Ok(archive_path);
+        }
+        let result = self
+            .cloud_commit(game, Some(&archive_path), Some(&message))
+            .and_then(|_| {
+                if cloud.transfers() {
+                    self.cloud_push(game, Some(&archive_path))
+                } else {
+                    game.mark_pending_upload(&archive_path)
+                }
+            });
+        if let Err(e) = result {
+            // The backup is already safe locally, so it is uploaded later instead of failing
+            game.mark_pending_upload(&archive_path)?;
+            tracing::warn!("Could not upload {}: {e}", archive_path.display());
+            output::warning(format_args!(
+                "Could not upload {}, 'gg sync' will retry it: {e}",
+                archive_path.display()
+            ));
+            let _ = status::record_failure(format!("gg backup {}", game.name()), e.to_string());
+            self.notify_desktop(
+                game,
+                &format!("Could not upload the backup of {}", game.name()),
+                "'gg sync' will retry it",
+                true,
+            )?;
+        }
+
+        Ok(archive_path)
+    }
+
+    /// Creates a new backup of the save of the game, returning its path.
+    ///
+    /// Unlike [`Games::backup`], it runs no hooks and leaves the cloud alone.
+    pub fn create_backup(&self, game: &Game, desc: Option<&str>) -> Result<PathBuf> {
+        let _activity = status::begin(game.name(), "backup");
+        let passphrase = self
+            .game_config(game)?
+            .backup
+            .encrypt
+            .then(|| self.passphrase())
+            .transpose()?;
+        let files = walkdir::WalkDir::new(game.save_location())
+            .follow_links(true)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| Some((entry.path().to_path_buf(), entry.metadata().ok()?)))
+            .filter(|(_, metadata)| metadata.is_file())
+            .map(|(path, metadata)| (path, metadata.len()))
+            .collect::<Vec<_>>();
+        let total = files.iter().map(|(_, size)| size).sum::<u64>();
+        self.check_save(game, &files, total)?;
+
+        let archive_path = self.next_backup_path(game, desc, passphrase.is_some())?;
+        tracing::debug!(
+            "Backing up {} into {}",
+            game.save_location().display(),
+            archive_path.display()
+        );
+        let archive = std::fs::File::create(&archive_path)
+            .context_with(|| format!("Could not create save backup {}", archive_path.display()))?;
+        let archive = crypto::MaybeEncrypted::new(archive, passphrase.as_ref())?;
+        let archiver = self.archiver(game)?;
+        let compressor = match self.compress_command(game)? {
+            _ if archiver.extension().is_some() => Compressor::Stored(archive),
+            Some(cmd) => Compressor::command(cmd, archive)?,
+            None => Compressor::zstd(archive)?,
+        };
+
+        let mut progress = BackupProgress {
+            inner: compressor,
+            archived: 0,
+            total,
+            game,
+            games: self,
+        };
+
+        let manifest = save_manifest(game.save_location())?;
+        let files = manifest.len();
+        let info = serde_json::to_string_pretty(&BackupInfo {
+            game: game.name().to_owned(),
+            player: game.player().map(str::to_owned),
+            root: game.root().to_path_buf(),
+            save_location: game.save_location().to_path_buf(),
+            created: crate::games::unix_time(),
+            gg_version: env!("CARGO_PKG_VERSION").to_owned(),
+            files: manifest.clone(),
+        })
+        .context("Could not serialize the backup metadata")?;
+        let manifest = serde_saphyr::to_string(&manifest)
+            .context("Could not serialize the backup manifest")?;
+        archiver.create(
+            &mut progress,
+            game.save_location(),
+            SAVE_PREFIX,
+            &[
+                (MANIFEST_ENTRY, manifest.as_bytes()),
+                (INFO_ENTRY, info.as_bytes()),
+            ],
+        )?;
+        progress
+            .inner
+            .finish()
+            .and_then(|encrypted| encrypted.finish())
+            .context_with(|| format!("Could not create backup {}", archive_path.display()))?;
+
+        if tracing::enabled!(tracing::Level::DEBUG) {
+            let archived = archive_path.metadata().map_or(0, |m| m.len());
+            tracing::debug!(
+                "Archived {files} files, {} compressed to {}",
+                Size(total),
+                Size(archived)
+            );
+        }
+        output::success(crate::tr!(
+            "backup-created",
+            path = archive_path.display().to_string()
+        ));
+        game.record_backup(&archive_path, total)?;
+        self.share_backups(game)?;
+        Ok(archive_path)
+    }
+
+    /// Warns about the signs of a corrupted or wiped save of the game, made of the `files` with
+    /// their sizes adding up to `total`, or fails if 'backup.checks.strict' is set.
+    fn check_save(&self, game: &Game, files: &[(PathBuf, u64)], total: u64) -> Result<()> {
+        let checks = &self.game_config(game)?.backup.checks;
+        let mut problems = Vec::new();
+        if checks.empty_save && files.is_empty() {
+            problems.push(String::from("it has no files"));
+        }
+        let empty = files
+            .iter()
+            .filter(|(_, size)| *size == 0)
+            .map(|(path, _)| {
+                let path = path.strip_prefix(game.save_location()).unwrap_or(path);
+                format!("{:?}", path.display().to_string())
+            })
+            .collect::<Vec<_>>();
+        if checks.empty_files && !empty.is_empty() {
+            problems.push(format!("these files are empty: {}", empty.join(", ")));
+        }
+        if checks.max_shrink > 0
+            && let Some(last) = game.last_save_size().filter(|last| *last > 0)
+        {
+            let shrunk = last.saturating_sub(total) * 100 / last;
+            if shrunk > u64::from(checks.max_shrink) {
+                problems.push(format!(
+                    "it is {shrunk}% smaller than the save of the last backup ({} instead of {})",
+                    Size(total),
+                    Size(last)
+                ));
+            }
+        }
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let problems = problems.join("; ");
+        if checks.strict {
+            bail!(
+                "Not backing up {}, its save may be corrupted: {problems}. Disable 'backup.checks.strict' to back it up anyway",
+                game.name()
+            )
+        }
+        output::warning(format_args!(
+            "The save of {} may be corrupted: {problems}",
+            game.name()
+        ));
+        Ok(())
+    }
+
+    /// Returns the path of the next backup of the game, with `desc` appended to its name.
+    pub fn next_backup_path(
+        &self,
+        game: &Game,
+        desc: Option<&str>,
+        encrypted: bool,
+    ) -> Result<PathBuf> {
+        let name = game.backup_prefix();
+        let idx = game.next_backup_index()?;
+        let desc = if let Some(desc) = desc {
+            format!("-{desc}")
+        } else {
+            String::new()
+        };
+        let backups_path = game.backups_path().join(format!("{name}-{idx:0>3}{desc}"));
+        let extension = match self.archiver(game)?.extension() {
+            Some(extension) => extension.to_owned(),
+            None => format!("tar.{}", self.compression_extension(game)?),
+        };
+        Ok(if encrypted {
+            backups_path.with_extension(format!("{extension}.{}", crypto::EXTENSION))
+        } else {
+            backups_path.with_extension(extension)
+        })
+    }
+
+    /// Restores the backup of the game called `target`, or only its `slot`, running the hooks and
+    /// notifying the result. The post-restore clean paths of the game are deleted if `clean`.
+    ///
+    /// The current save is backed up first, and the game must not be running.
+    pub fn restore(
+        &self,
+        game: &Game,
+        target: &str,
+        slot: Option<&str>,
+        cloud: CloudMode,
+        clean: bool,
+    ) -> Result<()> {
+        let start = std::time::Instant::now();
+        self.check_broken(game, Event::RestoreDone)?;
+        let target_path = game.backups_path().join(target);
+        self.run_hook(Hook::PreRestore, game, Some(&target_path))?;
+        let result = self
+            .restore_backup(game, target, slot, cloud)
+            .and_then(|()| match clean {
+                true => game.clean_after_restore(),
+                false => Ok(()),
+            })
+            .and_then(|()| self.run_hook(Hook::PostRestore, game, Some(&target_path)));
+        self.notify(
+            Event::RestoreDone,
+            game,
+            Some(target_path.as_path()).filter(|p| p.exists()),
+            start.elapsed(),
+            result.as_ref().err(),
+        )?;
+        result
+    }
+
+    /// Replaces the save of the game with the backup called `target`, or only its `slot`.
+    fn restore_backup(
+        &self,
+        game: &Game,
+        target: &str,
+        slot: Option<&str>,
+        cloud: CloudMode,
+    ) -> Result<()> {
+        let _activity = status::begin(game.name(), "restore");
+        if let Some(pid) = game.running_pid() {
+            bail!(Error::GameRunning {
+                game: game.name().to_owned(),
+                pid,
+            })
+        }
+        let _lock = status::lock(game.name(), "restore")?;
+        if cloud.transfers() {
+            self.cloud_fetch(game, target)?;
+            self.share_backups(game)?;
+        }
+        if let Some(marker) = game.newer_backup()
+            && marker.backup != target
+        {
+            warn_newer_backup(game, &marker);
+        }
+        let Some(target) = game.backups()?.into_iter().find(|b| b.name() == target) else {
+            bail!(Error::BackupNotFound {
+                path: game.backups_path().join(target),
+            })
+        };
+        // There is nothing to replace on a machine where the game has never been played
+        if game.save_location().exists() {
+            self.backup(
+                game,
+                Some(&format!("replaced-with-{:0>3}", target.index)),
+                None,
+                None,
+                cloud,
+            )?;
+        }
+
+        let save_location = game.save_location();
+        if let Some(slot) = slot {
+            self.restore_slot(game, &target.path, slot)?;
+        } else {
+            self.extract_backup(&target.path, game, save_location)?;
+        }
+
+        if cloud.commits() {
+            self.cloud_commit(game, None, None)?;
+        }
+        if cloud.transfers() {
+            self.cloud_push(game, None)?;
+        }
+
+        game.acknowledge_marker()?;
+        game.record_restore(&target.name())?;
+        output::success(format_args!(
+            "Successfully restored backup {} to {}",
+            target.path.display(),
+            save_location.display()
+        ));
+
+        Ok(())
+    }
+
+    /// Copies the save of the latest backup of `from` into the save location of `to`, returning
+    /// the path of that backup.
+    ///
+    /// A backup of the current save of `to` is created first.
+    pub fn transfer(&self, from: &Game, to: &Game, cloud: CloudMode) -> Result<PathBuf> {
+        if from.name() == to.name() {
+            bail!("Can't transfer the save of {} to itself", from.name())
+        }
+        let _activity = status::begin(to.name(), "transfer");
+        if let Some(pid) = to.running_pid() {
+            bail!(Error::GameRunning {
+                game: to.name().to_owned(),
+                pid,
+            })
+        }
+        let _lock = status::lock(to.name(), "transfer")?;
+        if cloud.transfers() {
+            self.cloud_pull(from)?;
+            self.share_backups(from)?;
+        }
+        let Some(source) = from.backups()?.pop() else {
+            bail!(
+                "{} has no backups, create one with 'gg backup' first",
+                from.name()
+            )
+        };
+
+        if to.save_location().exists() {
+            self.backup(
+                to,
+                Some(&format!("replaced-with-{}", slug::slugify(from.name()))),
+                None,
+                None,
+                cloud,
+            )?;
+        }
+        self.extract_backup(&source.path, from, to.save_location())?;
+        Ok(source.path)
+    }
+
+    /// Extracts the backup at `path` of `game` into `save_location`, verifying the restored files.
+    pub fn extract_backup(&self, path: &Path, game: &Game, save_location: &Path) -> Result<()> {
+        tracing::debug!(
+            "Extracting {} into {}",
+            path.display(),
+            save_location.display()
+        );
+        let manifest = self
+            .unpack_backup(path, game, save_location)
+            .context_with(|| {
+                format!(
+                    "Could not extract backup {} to {}",
+                    path.display(),
+                    save_location.display()
+                )
+            })?;
+        if let Some(manifest) = manifest {
+            verify_restore(&manifest, save_location)
+                .context_with(|| format!("Restore of {} is corrupted", path.display()))?;
+        } else {
+            output::progress("Backup has no manifest, skipping verification...");
+        }
+        Ok(())
+    }
+
+    /// Replaces a single slot of the game's save container with the one stored in the backup at
+    /// `path`.
+    pub fn restore_slot(&self, game: &Game, path: &Path, slot: &str) -> Result<()> {
+        let Some(container) = game.container_path() else {
+            bail!(
+                "The game {:?} has no save container, configure it with 'gg edit'",
+                game.name()
+            )
+        };
+
+        let dir = game.temp_dir()?;
+        let slot_path = dir.join("slot");
+        let result = self
+            .extract_slot_in(&dir, game, path, &container, slot, Some(&slot_path))
+            .and_then(|_| {
+                self.execute(
+                    self.slot_inject_command(game, &container, slot, &slot_path)?,
+                    "slot inject",
+                    game.root(),
+                )
+            });
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    /// Extracts `slot` of the container of the backup at `backup` that would be restored to
+    /// `container` into `output`, or prints it to stdout if `None`.
+    pub fn extract_slot(
+        &self,
+        game: &Game,
+        backup: &Path,
+        container: &Path,
+        slot: &str,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        if game.container().is_none() {
+            bail!(
+                "The game {:?} has no save container, configure it with 'gg edit'",
+                game.name()
+            )
+        }
+        let dir = game.temp_dir()?;
+        let result = self.extract_slot_in(&dir, game, backup, container, slot, output);
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    /// Like [`Games::extract_slot`], extracting the container into the temporary `dir`.
+    fn extract_slot_in(
+        &self,
+        dir: &Path,
+        game: &Game,
+        backup: &Path,
+        container: &Path,
+        slot: &str,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let backup_container = dir.join("container");
+        self.extract_to_file(backup, game, container, &backup_container)?;
+        self.execute(
+            self.slot_extract_command(game, &backup_container, slot, output)?,
+            "slot extract",
+            game.root(),
+        )
+    }
+
+    /// Opens the backup archive at `path` for reading, decrypting and decompressing it, returning
+    /// it along with the archiver that reads it.
+    ///
+    /// Archives not compressed with zstd are decompressed with the external command of the game.
+    pub fn open_backup(&self, path: &Path, game: &Game) -> Result<(Box<dyn Read>, &dyn Archiver)> {
+        let encrypted = crypto::is_encrypted(path);
+        let extension = if encrypted {
+            path.file_stem().map(Path::new).and_then(Path::extension)
+        } else {
+            path.extension()
+        };
+        let archiver = self.backup_archiver(path);
+        let decompress = match extension.and_then(|e| e.to_str()) {
+            _ if archiver.extension().is_some() => None,
+            Some(compression::ZSTD_EXTENSION) => None,
+            Some(e) if e == self.compression_extension(game)? => self.decompress_command(game)?,
+            _ => bail!(
+                "Unknown compression of backup {}, configure the commands that decompress it with 'gg edit'",
+                path.display()
+            ),
+        };
+
+        let file = std::fs::File::open(path)
+            .context_with(|| format!("Could not open backup {}", path.display()))?;
+        let file: Box<dyn Read + Send> = if encrypted {
+            Box::new(crypto::decrypt(
+                std::io::BufReader::new(file),
+                &self.passphrase()?,
+            )?)
+        } else {
+            Box::new(file)
+        };
+        if archiver.extension().is_some() {
+            return Ok((file, archiver));
+        }
+        Ok((compression::decompress(file, decompress)?, archiver))
+    }
+
+    /// Writes the contents of the file of the backup at `backup` that would be restored to `local`
+    /// into `output`.
+    pub fn extract_file(
+        &self,
+        backup: &Path,
+        game: &Game,
+        local: &Path,
+        output: &mut impl Write,
+    ) -> Result<()> {
+        let (mut archive, archiver) = self.open_backup(backup, game)?;
+        let mut found = false;
+        archiver.extract(&mut archive, &mut |entry| {
+            let path = entry.path()?;
+            if path == Path::new(MANIFEST_ENTRY)
+                || path == Path::new(INFO_ENTRY)
+                || restore_path(&path, game.save_location())? != local
+            {
+                return Ok(ControlFlow::Continue(()));
+            }
+            std::io::copy(entry, output)
+                .context_with(|| format!("Could not extract {}", path.display()))?;
+            found = true;
+            Ok(ControlFlow::Break(()))
+        })?;
+        if !found {
+            bail!("The backup does not contain {}", local.display())
+        }
+        Ok(())
+    }
+
+    /// Extracts the backed up file that would be restored to `local` into `dest`.
+    fn extract_to_file(&self, backup: &Path, game: &Game, local: &Path, dest: &Path) -> Result<()> {
+        let mut file = std::fs::File::create(dest)
+            .context_with(|| format!("Could not create {}", dest.display()))?;
+        self.extract_file(backup, game, local, &mut file)
+    }
+
+    /// Extracts the backup at `path` into `save_location`, returning its manifest if it has one.
+    ///
+    /// Entries under [`SAVE_PREFIX`] are remapped to the local save location, so a backup created
+    /// on a machine where the save lives somewhere else is restored to the right place.
+    pub fn unpack_backup(
+        &self,
+        path: &Path,
+        game: &Game,
+        save_location: &Path,
+    ) -> Result<Option<Manifest>> {
+        let mut manifest = None;
+        let (mut archive, archiver) = self.open_backup(path, game)?;
+        archiver.extract(&mut archive, &mut |entry| {
+            let path = entry.path()?;
+            if path == Path::new(MANIFEST_ENTRY) {
+                manifest = Some(
+                    serde_saphyr::from_reader(entry)
+                        .context("Could not parse the backup manifest")?,
+                );
+                return Ok(ControlFlow::Continue(()));
+            }
+            if path == Path::new(INFO_ENTRY) {
+                return Ok(ControlFlow::Continue(()));
+            }
+            let dest = restore_path(&path, save_location)?;
+            // Entries stay inside the save directory, or next to the save if it is a single file
+            let dir = if dest == save_location || save_location.is_file() {
+                save_location.parent().ok_or_report()?
+            } else {
+                save_location
+            };
+            entry
+                .unpack_in(dir, &dest)
+                .context_with(|| format!("Could not extract {}", dest.display()))?;
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(manifest)
+    }
+
+    /// Reads the manifest of the backup at `path` without extracting it, or `None` if it has none.
+    pub fn read_manifest(&self, path: &Path, game: &Game) -> Result<Option<Manifest>> {
+        let mut manifest = None;
+        let (mut archive, archiver) = self.open_backup(path, game)?;
+        archiver.extract(&mut archive, &mut |entry| {
+            if entry.path()? != Path::new(MANIFEST_ENTRY) {
+                return Ok(ControlFlow::Continue(()));
+            }
+            manifest = Some(
+                serde_saphyr::from_reader(entry).context("Could not parse the backup manifest")?,
+            );
+            Ok(ControlFlow::Break(()))
+        })?;
+        Ok(manifest)
+    }
+
+    /// Reads what the backup at `path` records about itself, or `None` if it was made before gg
+    /// recorded it.
+    pub fn read_backup_info(&self, path: &Path, game: &Game) -> Result<Option<BackupInfo>> {
+        let mut info = None;
+        let (mut archive, archiver) = self.open_backup(path, game)?;
+        archiver.extract(&mut archive, &mut |entry| {
+            if entry.path()? != Path::new(INFO_ENTRY) {
+                return Ok(ControlFlow::Continue(()));
+            }
+            info = Some(
+                serde_json::from_reader(entry).context("Could not parse the backup metadata")?,
+            );
+            Ok(ControlFlow::Break(()))
+        })?;
+        Ok(info)
+    }
+
+    /// Reads the whole backup archive at `path`, failing with [`Error::BackupCorrupt`] if it is
+    /// truncated or corrupted.
+    pub fn check_backup(&self, path: &Path, game: &Game) -> Result<()> {
+        let read = || -> Result<()> {
+            let (mut archive, archiver) = self.open_backup(path, game)?;
+            archiver.verify(&mut archive)
+        };
+        Ok(read().context_with(|| Error::BackupCorrupt {
+            path: path.to_owned(),
+        })?)
+    }
+
+    /// Repacks the backups of the game older than `older_than` with the zstd `level`, returning
+    /// the ones that got smaller, or the ones that would be repacked if `dry_run`.
+    ///
+    /// The age and level default to the 'gc' config of the game. Fails with [`Error::Locked`] if
+    /// another gg is busy with the game.
+    pub fn gc(
+        &self,
+        game: &Game,
+        older_than: Option<Duration>,
+        level: Option<u8>,
+        dry_run: bool,
+    ) -> Result<Vec<Repack>> {
+        if let Some(holder) = status::lock_holder(game.name()) {
+            bail!(Error::Locked {
+                game: game.name().to_owned(),
+                action: holder.action,
+                pid: holder.pid,
+            })
+        }
+        let config = &self.game_config(game)?.gc;
+        let older_than = older_than.unwrap_or(config.older_than);
+        let level = level.unwrap_or(config.level);
+        let _activity = status::begin(game.name(), "gc");
+        let cutoff = std::time::SystemTime::now()
+            .checked_sub(older_than.into())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        let mut metadata = game.backups_metadata();
+        let mut repacks = Vec::new();
+        for backup in game.all_backups()? {
+            let name = backup.name().into_owned();
+            let done = metadata
+                .get(&name)
+                .and_then(|m| m.repacked)
+                .is_some_and(|repacked| repacked >= level);
+            if backup.timestamp > cutoff || done || !self.is_zstd_backup(&backup.path) {
+                continue;
+            }
+            if dry_run {
+                repacks.push(Repack {
+                    path: backup.path,
+                    size: backup.size,
+                    repacked: None,
+                });
+                continue;
+            }
+            output::progress(format_args!("Repacking {}", backup.path.display()));
+            let repacked = self.repack_backup(&backup.path, level, game)?;
+            metadata.entry(name).or_default().repacked = Some(level);
+            game.set_backups_metadata(&metadata)?;
+            match repacked {
+                Some(size) => repacks.push(Repack {
+                    path: backup.path,
+                    size: backup.size,
+                    repacked: Some(size),
+                }),
+                None => output::progress(format_args!(
+                    "Kept {}, it would not get smaller",
+                    backup.path.display()
+                )),
+            }
+        }
+        if !repacks.is_empty() && !dry_run {
+            self.share_backups(game)?;
+        }
+        Ok(repacks)
+    }
+
+    /// Removes the backups of the game past its 'backup.retention', returning them, or the ones
+    /// that would be removed if `dry_run`.
+    ///
+    /// `keep` and `max_age` replace the ones of the retention. The backups of each player are
+    /// counted on their own, and every removed one is notified. Fails with [`Error::Locked`] if
+    /// another gg is busy with the game.
+    pub fn prune(
+        &self,
+        game: &Game,
+        keep: Option<usize>,
+        max_age: Option<Duration>,
+        dry_run: bool,
+    ) -> Result<Vec<Backup>> {
+        let retention = &self.game_config(game)?.backup.retention;
+        let keep = keep.or(retention.keep);
+        let max_age = max_age.or(retention.max_age);
+        if keep.is_none() && max_age.is_none() {
+            return Ok(Vec::new());
+        }
+        let _lock = status::lock(game.name(), "prune")?;
+        let cutoff = max_age.map(|max_age| {
+            std::time::SystemTime::now()
+                .checked_sub(max_age.into())
+                .unwrap_or(std::time::UNIX_EPOCH)
+        });
+        let mut metadata = game.backups_metadata();
+        let mut pruned = Vec::new();
+        for stream in game.streams()? {
+            let backups = stream.backups()?;
+            // The newest one is kept even with "keep: 0", so the save can always be restored
+            let old = backups.len().saturating_sub(keep.unwrap_or(0).max(1));
+            pruned.extend(backups.into_iter().take(old).filter(|backup| {
+                let pending = metadata
+                    .get(backup.name().as_ref())
+                    .is_some_and(|m| m.pending_upload);
+                !pending && cutoff.is_none_or(|cutoff| backup.timestamp < cutoff)
+            }));
+        }
+        if dry_run || pruned.is_empty() {
+            return Ok(pruned);
+        }
+        for backup in &pruned {
+            let start = std::time::Instant::now();
+            let removed = std::fs::remove_file(&backup.path)
+                .context_with(|| format!("Could not remove {}", backup.path.display()))
+                .map_err(Report::from);
+            if let Err(e) = removed {
+                game.set_backups_metadata(&metadata)?;
+                self.notify(
+                    Event::BackupPruned,
+                    game,
+                    Some(&backup.path),
+                    start.elapsed(),
+                    Some(&e),
+                )?;
+                return Err(e);
+            }
+            metadata.remove(backup.name().as_ref());
+            output::progress(format_args!("Removed {}", backup.path.display()));
+            self.notify(
+                Event::BackupPruned,
+                game,
+                Some(&backup.path),
+                start.elapsed(),
+                None,
+            )?;
+        }
+        game.set_backups_metadata(&metadata)?;
+        Ok(pruned)
+    }
+
+    /// Returns `true` if the backup at `path` is a tar archive compressed with the built-in zstd.
+    fn is_zstd_backup(&self, path: &Path) -> bool {
+        let path = if crypto::is_encrypted(path) {
+            Path::new(path.file_stem().unwrap_or_default())
+        } else {
+            path
+        };
+        self.backup_archiver(path).extension().is_none()
+            && path
+                .extension()
+                .is_some_and(|e| e == compression::ZSTD_EXTENSION)
+    }
+
+    /// Recompresses the backup at `path` with the zstd `level`, returning its new size, or `None`
+    /// if it would not get smaller and is kept as it was.
+    ///
+    /// The backup is repacked into a hidden file next to it, which replaces it with the same
+    /// modification time once it is checked to hold the same archive.
+    fn repack_backup(&self, path: &Path, level: u8, game: &Game) -> Result<Option<u64>> {
+        let name = path.file_name().ok_or_report()?.to_string_lossy();
+        // Same extensions, so it is opened like the backup
+        let repacked = path.with_file_name(format!(".gc-{name}"));
+        let result = self.repack_into(path, &repacked, level, game);
+        let replaced = result.and_then(|size| {
+            let original = path
+                .metadata()
+                .context_with(|| format!("Could not read {}", path.display()))?;
+            if size >= original.len() {
+                return Ok(None);
+            }
+            std::fs::File::options()
+                .write(true)
+                .open(&repacked)
+                .and_then(|file| file.set_modified(original.modified()?))
+                .and_then(|()| std::fs::set_permissions(&repacked, original.permissions()))
+                .and_then(|()| std::fs::rename(&repacked, path))
+                .context_with(|| format!("Could not replace {}", path.display()))?;
+            Ok(Some(size))
+        });
+        if !matches!(replaced, Ok(Some(_))) {
+            let _ = std::fs::remove_file(&repacked);
+        }
+        replaced
+    }
+
+    /// Writes the backup at `path` recompressed with the zstd `level` to `dest`, encrypted if it
+    /// is, failing if the result does not hold the same archive, and returns its size.
+    fn repack_into(&self, path: &Path, dest: &Path, level: u8, game: &Game) -> Result<u64> {
+        let passphrase = crypto::is_encrypted(path)
+            .then(|| self.passphrase())
+            .transpose()?;
+        let (mut archive, _) = self.open_backup(path, game)?;
+        let file = std::fs::File::create(dest)
+            .context_with(|| format!("Could not create {}", dest.display()))?;
+        let output =
+            crypto::MaybeEncrypted::new(std::io::BufWriter::new(file), passphrase.as_ref())?;
+        let mut compressor = Compressor::zstd_level(output, level.into())?;
+        let mut hasher = blake3::Hasher::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let read = archive
+                .read(&mut buf)
+                .context_with(|| format!("Could not read {}", path.display()))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buf[..read]);
+            compressor
+                .write_all(&buf[..read])
+                .context_with(|| format!("Could not write {}", dest.display()))?;
+        }
+        compressor
+            .finish()
+            .and_then(|encrypted| encrypted.finish())
+            .and_then(|mut file| file.flush())
+            .context_with(|| format!("Could not write {}", dest.display()))?;
+
+        let (mut written, _) = self.open_backup(dest, game)?;
+        let mut check = blake3::Hasher::new();
+        std::io::copy(&mut written, &mut check)
+            .context_with(|| format!("Could not read back {}", dest.display()))?;
+        if check.finalize() != hasher.finalize() {
+            bail!(
+                "The backup repacked into {} differs from {}",
+                dest.display(),
+                path.display()
+            )
+        }
+        Ok(dest.metadata()?.len())
+    }
+
+    /// Re-encrypts the encrypted backups of `targets` from the [passphrase](Games::passphrase) to
+    /// `new`, returning how many have been re-encrypted.
+    ///
+    /// Every backup is checked to open with the current passphrase first, and the re-encrypted
+    /// copies only replace them once all have been written, so a failure leaves every backup as
+    /// it was.
+    pub fn rotate_key(
+        &self,
+        targets: &[&Game],
+        new: &SecretString,
+        cloud: CloudMode,
+    ) -> Result<usize> {
+        let old = self.passphrase()?;
+        let encrypted = targets
+            .iter()
+            .map(|game| {
+                let backups = game.backups().unwrap_or_default();
+                (
+                    game,
+                    backups
+                        .into_iter()
+                        .filter(|b| b.is_encrypted())
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|(_, backups)| !backups.is_empty())
+            .collect::<Vec<_>>();
+        for backup in encrypted.iter().flat_map(|(_, backups)| backups) {
+            crypto::check_file(&backup.path, &old).context_with(|| {
+                format!(
+                    "{} does not open with the current passphrase, nothing has been re-encrypted",
+                    backup.path.display()
+                )
+            })?;
+        }
+
+        let rotated = encrypted
+            .iter()
+            .flat_map(|(_, backups)| backups)
+            .map(|backup| {
+                let temp = backup
+                    .path
+                    .with_file_name(format!(".rotate-{}", backup.name()));
+                (backup, temp)
+            })
+            .collect::<Vec<_>>();
+        let written = rotated
+            .iter()
+            .try_for_each(|(backup, temp)| crypto::reencrypt_file(&backup.path, temp, &old, new));
+        if written.is_err() {
+            for (_, temp) in &rotated {
+                let _ = std::fs::remove_file(temp);
+            }
+        }
+        written.context("Could not re-encrypt the backups, none has been replaced")?;
+        for (backup, temp) in &rotated {
+            std::fs::rename(temp, &backup.path)
+                .context_with(|| format!("Could not replace {}", backup.path.display()))?;
+            output::progress(format_args!("Re-encrypted {}", backup.path.display()));
+        }
+
+        // The copies in the cloud still have the old passphrase until they are uploaded again
+        for (game, backups) in &encrypted {
+            for backup in backups {
+                game.mark_pending_upload(&backup.path)?;
+            }
+            if cloud.transfers() {
+                self.cloud_sync(game)?;
+            } else if cloud.commits() {
+                self.cloud_commit(game, None, Some("Re-encrypt the backups"))?;
+                output::warning(format_args!(
+                    "The backups of {} in the cloud still use the old passphrase, run 'gg sync' to upload them",
+                    game.name()
+                ));
+            }
+        }
+        Ok(rotated.len())
+    }
+}
+
+impl Game {
+    /// Deletes the [post-restore clean paths](Game::post_restore_clean_paths) of the game.
+    pub fn clean_after_restore(&self) -> Result<()> {
+        let paths = self.post_restore_clean_paths();
+        if paths.is_empty() {
+            return Ok(());
+        }
+        for path in &paths {
+            let result = if path.is_dir() && !path.is_symlink() {
+                std::fs::remove_dir_all(path)
+            } else {
+                std::fs::remove_file(path)
+            };
+            result.context_with(|| format!("Could not delete {}", path.display()))?;
+        }
+        output::progress(format_args!("Deleted {} paths", paths.len()));
+        Ok(())
+    }
+
+    /// Creates a directory for the intermediate files of the game in the cache directory, only
+    /// accessible by the user and named after this process, which the caller removes when done.
+    fn temp_dir(&self) -> Result<PathBuf> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        let cache = crate::paths::cache_dir();
+        std::fs::create_dir_all(&cache)
+            .context_with(|| format!("Could not create {}", cache.display()))?;
+        let dir = cache.join(format!("{}{}", self.temp_prefix(), std::process::id()));
+        // Left behind by a killed process with the same id
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    /// Start of the names of the temporary directories of the game, followed by the process id.
+    fn temp_prefix(&self) -> String {
+        format!(".gg-{}-", slug::slugify(self.name()))
+    }
+
+    /// Returns the temporary files of the game left in the cache directory by processes that no
+    /// longer exist, the directories of `Game::temp_dir` and the files of older versions.
+    pub fn temp_leftovers(&self) -> Vec<PathBuf> {
+        let prefix = self.temp_prefix();
+        let Ok(entries) = std::fs::read_dir(crate::paths::cache_dir()) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                name.to_str()
+                    .and_then(|name| name.strip_prefix(&prefix))
+                    .is_some_and(|rest| {
+                        rest == "container"
+                            || rest == "slot"
+                            || (!rest.is_empty()
+                                && rest.bytes().all(|b| b.is_ascii_digit())
+                                && !Path::new("/proc").join(rest).exists())
+                    })
+            })
+            .map(|entry| entry.path())
+            .collect()
+    }
+}
+
+/// Warns that another machine made a backup that has not been restored in this one.
+pub fn warn_newer_backup(game: &Game, marker: &BackupMarker) {
+    output::warning(format_args!(
+        "{} was backed up on {} {} ({}), after the last backup known by this machine. \
+         Restore it with 'gg restore' to keep that progress",
+        game.name(),
+        marker.host,
+        marker.age(),
+        marker.backup
+    ));
+}
+
+/// Returns where the archive entry `path` is extracted to, failing if it is not a plain relative
+/// path, like one with `..` or a root.
+pub fn restore_path(path: &Path, save_location: &Path) -> Result<PathBuf> {
+    let rel = path.strip_prefix(SAVE_PREFIX).unwrap_or(path);
+    if rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+        bail!("The backup contains the invalid path {}", path.display());
+    }
+    let dest = match path.strip_prefix(SAVE_PREFIX) {
+        Ok(rel) if rel.as_os_str().is_empty() => save_location.to_path_buf(),
+        Ok(rel) => save_location.join(rel),
+        // Backups created before the prefix was introduced store their entries relative to the
+        // save directory, or just the file name if the save is a single file.
+        Err(_) if save_location.is_file() => save_location.parent().ok_or_report()?.join(path),
+        Err(_) => save_location.join(path),
+    };
+    Ok(dest)
+}
+
+/// Hashes every file of the save location, keyed by the path it will have inside the archive.
+pub fn save_manifest(save_location: &Path) -> Result<Manifest> {
+    if !save_location.is_dir() {
+        return Ok(Manifest::from([(
+            SAVE_PREFIX.to_owned(),
+            hash_file(save_location)?,
+        )]));
+    }
+
+    let mut manifest = Manifest::new();
+    for entry in walkdir::WalkDir::new(save_location).follow_links(true) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(save_location)?;
+        let key = Path::new(SAVE_PREFIX)
+            .join(rel)
+            .to_string_lossy()
+            .into_owned();
+        manifest.insert(key, hash_file(entry.path())?);
+    }
+    Ok(manifest)
+}
+
+/// Re-hashes the restored files, failing with [`Error::RestoreMismatch`] and every file that does
+/// not match the manifest.
+pub fn verify_restore(manifest: &Manifest, save_location: &Path) -> Result<()> {
+    let mut mismatches = Vec::new();
+    for (entry, expected) in manifest {
+        let path = restore_path(Path::new(entry), save_location)?;
+        match hash_file(&path) {
+            Ok(hash) if &hash == expected => {}
+            Ok(_) => mismatches.push(format!("{} (checksum mismatch)", path.display())),
+            Err(_) => mismatches.push(format!("{} (could not be read)", path.display())),
+        }
+    }
+    if !mismatches.is_empty() {
+        bail!(Error::RestoreMismatch { files: mismatches });
+    }
+    output::progress(format_args!("Verified {} restored files", manifest.len()));
+    Ok(())
+}
+
+/// Returns the BLAKE3 hash of the file at `path`, in hexadecimal.
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file =
+        std::fs::File::open(path).context_with(|| format!("Could not open {}", path.display()))?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher)
+        .context_with(|| format!("Could not read {}", path.display()))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Writer of a backup archive that reports to the observers of `games` how much of the save of
+/// `game` has been written.
+struct BackupProgress<'a, W> {
+    inner: W,
+    archived: u64,
+    /// Size of the files of the save.
+    total: u64,
+    game: &'a Game,
+    games: &'a Games,
+}
+
+impl<W: Write> Write for BackupProgress<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        // The archive also has the headers of the entries, so it ends up bigger than the save
+        self.archived = (self.archived + written as u64).min(self.total);
+        let (archived, total) = (self.archived, self.total);
+        self.games
+            .observe(|o| o.on_backup_progress(self.game, archived, total));
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Without zstd, the backups are compressed by a command.
+    const CONFIG: &str = if cfg!(feature = "zstd") {
+        ""
+    } else {
+        "backup:\n  compression:\n    compress: cat\n    decompress: cat\n    extension: raw\n"
+    };
+
+    /// Loads the games with the `config` and a game called `name` in `dir`, whose save has the
+    /// `files` with their contents.
+    fn games_with_save(
+        dir: &Path,
+        config: &str,
+        name: &str,
+        files: &[(&str, &str)],
+    ) -> (Games, Game) {
+        let path = dir.join("config.yaml");
+        std::fs::write(&path, format!("{CONFIG}{config}")).unwrap();
+        let games = Games::builder()
+            .config_path(path)
+            .in_memory()
+            .load()
+            .unwrap();
+        let game = Game::new(
+            name.to_owned(),
+            dir.join("game"),
+            dir.join("game/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        );
+        // Created by 'gg add'
+        std::fs::create_dir_all(game.backups_path()).unwrap();
+        write_save(&game, files);
+        (games, game)
+    }
+
+    fn write_save(game: &Game, files: &[(&str, &str)]) {
+        for (file, contents) in files {
+            let path = game.save_location().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn backs_up_and_restores_the_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let (games, game) = games_with_save(
+            dir.path(),
+            "",
+            "Hollow Knight",
+            &[("user1.dat", "first"), ("options/keys.ini", "jump=z")],
+        );
+        let first = games
+            .backup(&game, None, None, None, CloudMode::Off)
+            .unwrap();
+        write_save(&game, &[("user1.dat", "second")]);
+        std::fs::remove_file(game.save_location().join("options/keys.ini")).unwrap();
+
+        let name = first.file_name().unwrap().to_str().unwrap();
+        games
+            .restore(&game, name, None, CloudMode::Off, false)
+            .unwrap();
+
+        let save = game.save_location();
+        assert_eq!(
+            std::fs::read_to_string(save.join("user1.dat")).unwrap(),
+            "first"
+        );
+        assert_eq!(
+            std::fs::read_to_string(save.join("options/keys.ini")).unwrap(),
+            "jump=z"
+        );
+        // The replaced save is kept in a backup of its own
+        let backups = game.backups().unwrap();
+        assert_eq!(backups.len(), 2);
+        assert_eq!(backups[1].description.as_deref(), Some("replaced-with-000"));
+    }
+
+    #[test]
+    fn fails_to_restore_a_missing_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let (games, game) = games_with_save(dir.path(), "", "Hades", &[("Profile1.sav", "save")]);
+        let e = games
+            .restore(&game, "Hades-007.tar.zst", None, CloudMode::Off, false)
+            .unwrap_err();
+        assert!(matches!(
+            crate::error::kind(&e),
+            Some(Error::BackupNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn prunes_the_backups_past_the_retention_of_the_game() {
+        let dir = tempfile::tempdir().unwrap();
+        let (games, game) = games_with_save(
+            dir.path(),
+            "games:\n  Celeste:\n    backup:\n      retention:\n        keep: 2\n",
+            "Celeste",
+            &[("0.celeste", "chapter 1")],
+        );
+        let backups = (0..4)
+            .map(|_| {
+                games
+                    .backup(&game, None, None, None, CloudMode::Off)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        game.mark_pending_upload(&backups[1]).unwrap();
+
+        // Nothing is old enough
+        let max_age = Some(Duration::from_secs(60 * 60));
+        assert!(games.prune(&game, None, max_age, false).unwrap().is_empty());
+
+        let pruned = games.prune(&game, None, None, true).unwrap();
+        assert_eq!(
+            pruned.iter().map(|b| &b.path).collect::<Vec<_>>(),
+            [&backups[0]]
+        );
+        assert!(backups[0].exists());
+
+        games.prune(&game, None, None, false).unwrap();
+        let left = game.backups().unwrap();
+        assert_eq!(
+            left.into_iter().map(|b| b.path).collect::<Vec<_>>(),
+            &backups[1..]
+        );
+        // The newest one is kept whatever the retention
+        games.prune(&game, Some(0), None, false).unwrap();
+        assert_eq!(game.backups().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn prunes_after_backing_up_with_auto_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut games, game) = games_with_save(
+            dir.path(),
+            "games:\n  Celeste:\n    backup:\n      autoPrune: true\n      retention:\n        keep: 1\n",
+            "Celeste",
+            &[("0.celeste", "chapter 1")],
+        );
+        for _ in 0..3 {
+            games
+                .backup(&game, None, None, None, CloudMode::Off)
+                .unwrap();
+        }
+        assert_eq!(game.backups().unwrap().len(), 1);
+
+        games.set_auto_prune(false);
+        games
+            .backup(&game, None, None, None, CloudMode::Off)
+            .unwrap();
+        assert_eq!(game.backups().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn notifies_the_pruned_backups() {
+        #[derive(Debug, Default)]
+        struct Pruned(std::sync::Arc<std::sync::Mutex<Vec<PathBuf>>>);
+
+        impl crate::observer::Observer for Pruned {
+            fn on_event(&self, event: Event, _: &Game, archive: Option<&Path>, _: Option<&Report>) {
+                if event == Event::BackupPruned {
+                    self.0
+                        .lock()
+                        .unwrap()
+                        .extend(archive.map(Path::to_path_buf));
+                }
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let (mut games, game) =
+            games_with_save(dir.path(), "", "Hades", &[("Profile1.sav", "save")]);
+        let pruned = Pruned::default();
+        let notified = pruned.0.clone();
+        games.add_observer(pruned);
+        let old = games
+            .backup(&game, None, None, None, CloudMode::Off)
+            .unwrap();
+        games
+            .backup(&game, None, None, None, CloudMode::Off)
+            .unwrap();
+
+        games.prune(&game, Some(1), None, false).unwrap();
+        assert_eq!(*notified.lock().unwrap(), [old]);
+    }
+
+    #[test]
+    fn rotates_the_passphrase_of_the_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut games, game) = games_with_save(
+            dir.path(),
+            "games:\n  Celeste:\n    backup:\n      encrypt: true\n",
+            "Celeste",
+            &[("0.celeste", "chapter 1")],
+        );
+        games.set_passphrase(String::from("old").into());
+        let backup = games
+            .backup(&game, None, None, None, CloudMode::Off)
+            .unwrap();
+
+        let new = SecretString::from(String::from("new"));
+        assert_eq!(games.rotate_key(&[&game], &new, CloudMode::Off).unwrap(), 1);
+        crypto::check_file(&backup, &new).unwrap();
+
+        // The backup no longer opens with the passphrase of the games, so it is left as it is
+        let rotated = std::fs::read(&backup).unwrap();
+        assert!(games.rotate_key(&[&game], &new, CloudMode::Off).is_err());
+        assert_eq!(std::fs::read(&backup).unwrap(), rotated);
+        let leftovers = std::fs::read_dir(game.backups_path())
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(".rotate-"));
+        assert_eq!(leftovers.count(), 0);
+    }
+
+    #[test]
+    fn verifies_the_restored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("saves");
+        std::fs::create_dir_all(save.join("slots")).unwrap();
+        for (file, contents) in [
+            ("slots/1.sav", "one"),
+            ("slots/2.sav", "two"),
+            ("a.cfg", ""),
+        ] {
+            std::fs::write(save.join(file), contents).unwrap();
+        }
+        let manifest = save_manifest(&save).unwrap();
+        assert_eq!(
+            manifest.keys().collect::<Vec<_>>(),
+            ["@SAVE/a.cfg", "@SAVE/slots/1.sav", "@SAVE/slots/2.sav"]
+        );
+        verify_restore(&manifest, &save).unwrap();
+
+        std::fs::write(save.join("slots/1.sav"), "corrupted").unwrap();
+        std::fs::remove_file(save.join("slots/2.sav")).unwrap();
+        let e = verify_restore(&manifest, &save).unwrap_err();
+        let Some(Error::RestoreMismatch { files }) = crate::error::kind(&e) else {
+            panic!("{e}")
+        };
+        assert_eq!(
+            files,
+            &[
+                format!("{} (checksum mismatch)", save.join("slots/1.sav").display()),
+                format!("{} (could not be read)", save.join("slots/2.sav").display()),
+            ]
+        );
+    }
+
+    #[test]
+    fn verifies_a_single_file_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("game.sav");
+        std::fs::write(&save, "progress").unwrap();
+        let manifest = save_manifest(&save).unwrap();
+        assert_eq!(manifest.keys().collect::<Vec<_>>(), [SAVE_PREFIX]);
+        verify_restore(&manifest, &save).unwrap();
+
+        std::fs::write(&save, "lost").unwrap();
+        assert!(verify_restore(&manifest, &save).is_err());
+    }
+}
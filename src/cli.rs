@@ -5,7 +5,9 @@ use clap::{
     builder::{Styles, styling::AnsiColor},
 };
 use clap_complete::{ArgValueCandidates, ArgValueCompleter, CompletionCandidate};
-use goodgame::games::Games;
+use goodgame::cloud::CloudMode;
+use goodgame::games::{Games, Wrappers};
+use goodgame::units::Duration;
 
 const CLAP_STYLE: Styles = Styles::styled()
     .header(AnsiColor::Green.on_default().bold())
@@ -17,16 +19,54 @@ fn parse_env_vars(s: &str) -> Result<(String, String), std::convert::Infallible>
     Ok(s.split_once('=').unwrap_or((s, ""))).map(|(k, v)| (k.to_owned(), v.to_owned()))
 }
 
+/// Parses a path that can be empty, unlike the default parser of paths.
+fn parse_optional_path(s: &str) -> Result<PathBuf, std::convert::Infallible> {
+    Ok(PathBuf::from(s))
+}
+
 #[derive(clap::Parser)]
 #[clap(styles = CLAP_STYLE, version)]
-pub enum Cli {
+pub struct Cli {
+    /// Uses the default configuration instead of reading the system and user config files.
+    #[arg(long, global = true)]
+    pub ignore_config: bool,
+    /// Prints the result of list, status, backup, restore, config, stats, 'backups list' and
+    /// 'cloud status' as JSON, and every other message to stderr.
+    ///
+    /// The rest of the commands print their "operation", the "games" they worked on, the
+    /// "artifacts" they created and their "warnings".
+    ///
+    /// A failure prints its message as "error", along with its "kind", like "game-not-found", if
+    /// it is one that can be told apart, and what the command did before failing.
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Prints the log messages too, -vv adding the resolved paths and the archive statistics.
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+    /// Hides the commands run and the progress messages, printing only the results and the
+    /// warnings.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+    /// Prints the messages without colors, also disabled by setting the NO_COLOR environment
+    /// variable.
+    #[arg(long, global = true)]
+    pub no_color: bool,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(clap::Subcommand)]
+pub enum Command {
     /// Starts to manage the provided game.
     ///
     /// If the game is already being managed, the provided details will override the current ones.
+    ///
+    /// The paths are stored relative to the Steam library ($STEAM) or the home ($HOME) when inside
+    /// them, so the games can be shared between machines.
     #[clap(alias = "a", alias = "init")]
     Add {
         /// The path of the game executable.
-        #[arg(short, long="executable", value_hint = ValueHint::FilePath)]
+        #[arg(long = "executable", value_hint = ValueHint::FilePath)]
         executable: Option<PathBuf>,
         /// Arguments that will be passed to the main executable.
         #[arg(long = "args", alias = "executable-args")]
@@ -36,15 +76,43 @@ pub enum Cli {
         /// Should be given as "-e KEY=[VALUE]" or "-e KEY1=VALUE1:KEY2=VALUE2:..."
         #[arg(short, long = "env", alias = "environment", value_name = "KEY=[VALUE][:...]", value_delimiter = ':', value_parser = parse_env_vars)]
         environment_vars: Option<Vec<(String, String)>>,
+        /// Wine or Proton prefix of the game, exported as WINEPREFIX to its commands.
+        ///
+        /// Found in the Steam compatdata directory if the game is installed with Steam.
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::DirPath)]
+        prefix: Option<PathBuf>,
+        /// Proton that runs the executable of the game in its prefix, like "Proton 9.0".
+        ///
+        /// Without it, the Windows executables of a game with a prefix are run with wine.
+        #[arg(long, value_name = "VERSION", add = proton_candidates())]
+        proton: Option<String>,
+        /// Id of the game in Steam, which launches it as @EXE instead of running the executable.
+        ///
+        /// 'gg run' waits until every process of the game exits, as Steam returns right away.
+        #[arg(long, value_name = "ID")]
+        steam_appid: Option<u32>,
+        /// Directory 'gg run' starts the game in, relative to the root, like "bin".
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::DirPath)]
+        workdir: Option<PathBuf>,
+        /// Comma separated labels of the game, to select it along others with --tag.
+        #[arg(long, value_name = "TAG,...", value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+        /// Comma separated short names accepted instead of the name of the game, like "er".
+        #[arg(long, value_name = "ALIAS,...", value_delimiter = ',')]
+        aliases: Option<Vec<String>>,
         /// Comma separated list of the commands that will be used in 'gg run'.
         ///
         /// If not provided, the global one will be used, replacing @EXE with the above executable.
         ///
         /// Supported variables:
-        /// - @RUN: Global run command, similar to Steam's %command%.
-        /// - @NAME: Name of the game.
+        /// - @RUN or %command%: Global run commands, like Steam's %command% (e.g. "gamemoderun %command%").
+        /// - @ARGS: Arguments given to 'gg run' after "--", quoted.
+        /// - @NAME: Name of the game, will be enclosed in quotes.
         /// - @NAME-SLUG: Name of the game sanitized for use in URLs or repository names ('Cool náme!' => 'cool-name').
         /// - @EXE: Executable of the game, will be enclosed in quotes.
+        /// - @USER: Name of the current user, will be enclosed in quotes.
+        /// - @HOST: Name of the current machine, will be enclosed in quotes.
+        /// - @ROOT, @SAVE, @BACKUPS: Root, save location and backups directory of the game, will be enclosed in quotes.
         #[arg(short, long = "run")]
         run_commands: Option<Vec<String>>,
         /// Skips cloud saving features completely.
@@ -53,19 +121,51 @@ pub enum Cli {
         /// Skips cloud saving initialization.
         #[arg(long = "skip-init")]
         skip_cloud_init: bool,
+        /// Runs the game once and proposes the directories it wrote to as the save location.
+        #[arg(long, conflicts_with = "save_location")]
+        discover: bool,
+        /// Adds a game shared with 'gg share', importing the backup included in the bundle.
+        ///
+        /// The paths inside the shared root are moved to ROOT, the rest of the settings are kept.
+        #[arg(
+            long,
+            value_name = "BUNDLE",
+            value_hint = ValueHint::FilePath,
+            conflicts_with_all = ["executable", "executable_args", "environment_vars", "run_commands", "discover"]
+        )]
+        from_bundle: Option<PathBuf>,
+        /// Adds the game of the bundle without asking to accept the commands, environment
+        /// variables and post-restore clean patterns it brings.
+        #[arg(short, long, requires = "from_bundle")]
+        yes: bool,
+        /// Adds the game called TITLE in the manifest of Ludusavi, which gives its save location.
+        ///
+        /// The title is also the default name, and the root defaults to the directory the game
+        /// is installed in if Steam installed it. The manifest is downloaded and cached for a week.
+        #[arg(
+            long,
+            value_name = "TITLE",
+            conflicts_with_all = ["from_bundle", "save_location", "discover"]
+        )]
+        from_manifest: Option<String>,
         /// The name of the game to manage.
+        ///
+        /// Read from the gg-config.json of the root if not provided, which can also give its save
+        /// location, executable, executable arguments and run commands, like:
+        /// {"name": "My Game", "save_location": "saves", "executable": "bin/game"}
         #[arg(value_hint = ValueHint::AnyPath)]
-        game: String,
-        /// The root path of the game.
+        game: Option<String>,
+        /// The root path of the game, the current directory if not provided.
         #[arg(value_hint = ValueHint::DirPath)]
-        root: PathBuf,
+        root: Option<PathBuf>,
         /// The path where the game stores its save files.
         #[arg(value_hint = ValueHint::AnyPath)]
         save_location: Option<PathBuf>,
     },
     /// Edits the configuration of the specified game.
     ///
-    /// If no extra argument is provided, an editable JSON file will be opened.
+    /// If no extra argument is provided, an editable YAML file will be opened in the editor of the
+    /// config, $VISUAL, $EDITOR or vi.
     #[clap(alias = "e")]
     Edit {
         /// New game name.
@@ -89,78 +189,996 @@ pub enum Cli {
         /// New run commands.
         #[arg(long = "run")]
         run_commands: Option<Vec<String>>,
+        /// New wine or Proton prefix, an empty one removes it.
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::DirPath, value_parser = parse_optional_path)]
+        prefix: Option<PathBuf>,
+        /// New Proton version, an empty one runs the game with wine again.
+        #[arg(long, value_name = "VERSION", add = proton_candidates())]
+        proton: Option<String>,
+        /// New id of the game in Steam, 0 runs the executable again.
+        #[arg(long, value_name = "ID")]
+        steam_appid: Option<u32>,
+        /// New directory 'gg run' starts the game in, an empty one starts it in the root again.
+        #[arg(long, value_name = "PATH", value_hint = ValueHint::DirPath, value_parser = parse_optional_path)]
+        workdir: Option<PathBuf>,
+        #[command(flatten)]
+        wrappers: WrapperArgs,
+        /// Enables or disables the cloud features for the game.
+        ///
+        /// When disabled, backups are only stored locally, as if --skip-cloud was always given.
+        #[arg(long, value_name = "BOOL")]
+        cloud: Option<bool>,
+        /// Cloud backend of the game, "global" to use the configured one.
+        #[arg(long, value_parser = ["global", "git", "rclone", "rsync", "syncthing", "webdav"])]
+        backend: Option<String>,
+        /// Cloud remote of the game, overrides the one of the backend.
+        ///
+        /// An empty remote removes the override.
+        #[arg(long)]
+        remote: Option<String>,
+        /// Files or directories deleted after restoring a backup, like shader caches.
+        ///
+        /// Relative to the game root, with the `*` and `?` wildcards and `**` for any number of
+        /// directories.
+        #[arg(long = "clean", value_name = "PATTERN")]
+        post_restore_clean: Option<Vec<String>>,
+        /// New comma separated labels of the game, an empty list removes them.
+        #[arg(long, value_name = "TAG,...", value_delimiter = ',')]
+        tags: Option<Vec<String>>,
+        /// New comma separated short names of the game, an empty list removes them.
+        #[arg(long, value_name = "ALIAS,...", value_delimiter = ',')]
+        aliases: Option<Vec<String>>,
+        /// New notes about the game, like its mods or controller setup, empty ones remove them.
+        ///
+        /// Shown by 'gg list' and 'gg status'.
+        #[arg(long, value_name = "TEXT")]
+        notes: Option<String>,
         /// The name of the game to edit.
         #[arg(add = game_name_completer())]
         game: Option<String>,
     },
-    /// Removes the game from the managed list.
-    #[clap(alias = "rm", alias = "delete", alias = "del")]
-    Remove {
-        /// The name of the game to remove.
+    /// Renames a game along with its backups, their metadata and its cloud storage.
+    ///
+    /// The cloud storage is renamed by the configured 'backup.cloudRenameCommands', and the renamed
+    /// backups are pushed again.
+    Rename {
+        /// The name of the game to rename.
+        #[arg(add = game_name_completer())]
+        game: String,
+        /// The new name of the game.
+        new_name: String,
+        #[command(flatten)]
+        cloud: CloudArgs,
+    },
+    /// Moves a game to a new root or save location, like another drive.
+    ///
+    /// The files of the game must already be in the new place. The paths inside the old root are
+    /// moved to the new one, and so are the backups if they are still in the old root.
+    Move {
+        /// The name of the game to move.
         #[arg(add = game_name_completer())]
         game: String,
+        /// New root path.
+        #[arg(long, value_hint = ValueHint::DirPath, required_unless_present = "save_location")]
+        root: Option<PathBuf>,
+        /// New save location path, moved with the root by default if inside it.
+        #[arg(long, value_hint = ValueHint::AnyPath)]
+        save_location: Option<PathBuf>,
+    },
+    /// Removes the game from the managed list, asking first when run in a terminal.
+    ///
+    /// The gg-save-loc symlink in its root is removed too, while its backups are kept unless
+    /// --purge-backups is given. The backups in the cloud storage are never removed.
+    ///
+    /// Several games can be removed at once with wildcard patterns or --tag.
+    #[clap(alias = "rm", alias = "delete", alias = "del")]
+    Remove {
+        #[command(flatten)]
+        games: Selection,
+        /// Deletes the local backups directory of the game as well.
+        #[arg(long)]
+        purge_backups: bool,
+        /// Keeps the gg-save-loc symlink in the root of the game.
+        #[arg(long)]
+        keep_symlink: bool,
+    },
+    /// Archives games, which are kept with their backups but hidden from 'gg list', the
+    /// completions and the commands acting on every game, pattern or tag.
+    ///
+    /// Meant for uninstalled games that may be installed again, they are still used when given
+    /// by name and brought back with 'gg unarchive'.
+    Archive {
+        /// Names of the games.
+        #[arg(required = true, add = game_name_completer())]
+        games: Vec<String>,
+    },
+    /// Brings back archived games.
+    Unarchive {
+        /// Names of the games.
+        #[arg(required = true)]
+        games: Vec<String>,
     },
     /// Creates a backup of the current save.
     ///
     /// If no game name is provided, one will try to be selected based on the current directory.
     ///
+    /// Several games can be backed up at once with wildcard patterns like 'Dark*' or --tag, after
+    /// confirming them.
+    ///
     /// The backup is compressed and called "GAME-IDX" by default.
     /// If a backup description is provided, the backup will be called "GAME-IDX-DESCRIPTION"
     #[clap(alias = "b", alias = "bk")]
     Backup {
-        /// The name of the game to make the backup.
-        #[arg(add = game_name_completer())]
-        game: Option<String>,
+        #[command(flatten)]
+        games: Selection,
         /// Description that will be appended to the backup name.
         #[arg(long, short, value_hint = ValueHint::Other)]
         desc: Option<String>,
-        #[arg(short, long = "skip-cloud")]
-        skip_cloud: bool,
+        /// Message of the cloud commit, available to the commit commands as @MESSAGE.
+        ///
+        /// Defaults to one with the backup name, description and size, and the play time when the
+        /// backup follows 'gg run'.
+        #[arg(long, short, value_hint = ValueHint::Other)]
+        message: Option<String>,
+        /// Player whose backups and save are used, one of the 'players' of the game.
+        ///
+        /// The players are added to the game with 'gg edit'.
+        #[arg(long)]
+        player: Option<String>,
+        #[command(flatten)]
+        cloud: CloudArgs,
+        /// Prints the cloud commands the backup would run, without creating it or running them.
+        #[arg(long, conflicts_with = "skip_cloud")]
+        dry_run_cloud: bool,
+        /// Prunes the backups past the retention after backing up, the default if
+        /// 'backup.autoPrune' is true.
+        #[arg(long, overrides_with = "no_prune")]
+        prune: bool,
+        /// Doesn't prune the backups after backing up.
+        #[arg(long)]
+        no_prune: bool,
     },
     /// Restores the selected save backup.
     ///
     /// A backup of the current save will be created.
     #[clap()]
     Restore {
-        #[arg(short, long = "skip-cloud")]
-        skip_cloud: bool,
+        #[command(flatten)]
+        cloud: CloudArgs,
         /// Name of the game to restore the save backup.
         #[arg(add = game_name_completer())]
         game: String,
-        /// Name of the backup to restore.
+        /// Name of the backup to restore, chosen from a list if not provided.
+        ///
+        /// The list includes the backups only in the cloud storage, marked with ☁, which are
+        /// downloaded when chosen.
         #[arg(add = game_backup_candidates(), requires = "game")]
-        backup: String,
+        backup: Option<String>,
+        /// Only restore this slot of the game's save container.
+        #[arg(long)]
+        slot: Option<String>,
+        /// Player whose backups and save are used, one of the 'players' of the game.
+        ///
+        /// The players are added to the game with 'gg edit'.
+        #[arg(long)]
+        player: Option<String>,
+        /// Replaces the save, when 'confirmDestructiveOps' is enabled, and deletes the post-restore
+        /// clean paths of the game without asking.
+        #[arg(short, long)]
+        yes: bool,
+        /// Lists the post-restore clean paths of the game instead of deleting them.
+        #[arg(long, conflicts_with = "yes")]
+        dry_run_clean: bool,
+    },
+    /// Copies the save of the latest backup of a game into the save location of another one.
+    ///
+    /// Useful for remasters or regional versions sharing the save format.
+    /// A backup of the current save of the destination will be created.
+    Transfer {
+        #[command(flatten)]
+        cloud: CloudArgs,
+        /// Name of the game to copy the save from.
+        #[arg(add = game_name_completer())]
+        from: String,
+        /// Name of the game to copy the save to.
+        #[arg(add = game_name_completer())]
+        to: String,
     },
+    /// Uploads the backups missing in the cloud and downloads the ones missing locally.
+    ///
+    /// Backups that exist in both places with different contents are reported and left untouched.
+    Sync {
+        /// Name of the game to sync, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+    },
+    /// Sets up the games of another machine, downloading their backups from the cloud.
+    ///
+    /// Games that are already managed are skipped.
+    Clone {
+        /// Games database of the other machine, a copy of its ~/.local/share/goodgame/games.yaml,
+        /// or its games.sqlite if it was built with the "sqlite" feature.
+        #[arg(value_hint = ValueHint::FilePath)]
+        index: PathBuf,
+        /// Replaces the FROM prefix of the game paths with TO, for machines with another layout.
+        ///
+        /// Can be given multiple times, like "--map /home/me=/home/deck".
+        #[arg(long = "map", value_name = "FROM=TO", value_parser = parse_env_vars)]
+        path_maps: Vec<(String, String)>,
+        /// Restores the latest backup of every cloned game.
+        #[arg(long)]
+        restore: bool,
+    },
+    /// Writes the settings of the managed games to a file that 'gg import' reads.
+    ///
+    /// The paths start with $HOME or $STEAM where they can, so the file works in machines with
+    /// other users or Steam libraries, like the ones synced with the dotfiles.
+    Export {
+        /// File to write the games to.
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// Names of the games, or patterns with the `*` and `?` wildcards, all of them if not
+        /// provided.
+        #[arg(add = game_name_completer())]
+        games: Vec<String>,
+        /// Exports the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Includes the metadata of the backups, like the machine that made each one.
+        #[arg(long)]
+        backups: bool,
+    },
+    /// Adds the games of a file written by 'gg export' or of the games database of another
+    /// machine, or a backup archive made by gg.
+    ///
+    /// Managed games with other settings are reported as conflicts and kept, unless --replace is
+    /// given. The backups are not copied, 'gg sync' downloads them from the cloud.
+    ///
+    /// A backup archive is copied into the backups of the game it was made from, which is added
+    /// with the root and save location recorded in the archive if it is not managed, after asking.
+    Import {
+        /// File written by 'gg export', games database or backup archive.
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// Replaces the settings of the conflicting games with the imported ones.
+        #[arg(long)]
+        replace: bool,
+        /// Replaces the FROM prefix of the game paths with TO, for machines with another layout.
+        ///
+        /// Can be given multiple times, like "--map /home/me=/home/deck".
+        #[arg(long = "map", value_name = "FROM=TO", value_parser = parse_env_vars)]
+        path_maps: Vec<(String, String)>,
+        /// Adds the game of a backup archive that is not managed without asking.
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Bundles the settings and latest backup of a game into a single file.
+    ///
+    /// Other gg users can set the game up from it with 'gg add --from-bundle'.
+    /// The cloud settings of the game are not included.
+    Share {
+        /// Name of the game to share.
+        #[arg(add = game_name_completer())]
+        game: String,
+        /// Path of the bundle, "GAME-SLUG.gg.tar" in the current directory by default.
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+    /// Inspects the backups of a game.
+    #[clap(subcommand)]
+    Backups(Backups),
+    /// Inspects the cloud storage of the games.
+    #[clap(subcommand)]
+    Cloud(Cloud),
     /// Lists all managed games.
     #[clap(alias = "l", alias = "ls")]
-    List,
+    List {
+        /// Lists only the games with the tag, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Lists only the games whose name contains the text, ignoring the case.
+        #[arg(long, value_name = "TEXT")]
+        filter: Option<String>,
+        /// Order of the games, the most played, backed up or biggest ones first.
+        #[arg(long, value_enum, default_value_t = ListSort::Name)]
+        sort: ListSort,
+        /// Shows the number, size and age of the backups of every game too.
+        #[arg(short, long)]
+        long: bool,
+        /// Lists the archived games too.
+        #[arg(short, long)]
+        archived: bool,
+    },
     /// Opens the root directory of the game.
+    ///
+    /// The directory is opened with the 'opener' of the config, or with xdg-open if not set.
     #[clap(alias = "o")]
     Open {
         /// Open the save directory instead of the root.
         #[arg(long, short)]
         save: bool,
-        /// Name of the game to open the directory.
+        /// Open the backups directory instead of the root.
+        #[arg(long, short, conflicts_with = "save")]
+        backups: bool,
+        #[command(flatten)]
+        games: Selection,
+    },
+    /// Adds a launcher of the game to the application menu, which starts it with 'gg run'.
+    ///
+    /// The launcher is a .desktop file written to ~/.local/share/applications.
+    Desktop {
+        /// Icon of the launcher, a path or the name of an icon of the theme, the one fetched by
+        /// 'gg metadata' if not provided.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        icon: Option<String>,
+        /// Removes the launcher instead.
+        #[arg(long, conflicts_with = "icon")]
+        remove: bool,
+        /// Name of the game.
+        #[arg(add = game_name_completer())]
+        game: String,
+    },
+    /// Fetches the cover art, icon and release date of the games from SteamGridDB.
+    ///
+    /// They are kept in the data directory, and used by 'gg desktop', 'gg steam-shortcut' and
+    /// 'gg list --long'. The games are looked up by their Steam app id if they have one, and else
+    /// by their name. Needs an API key in 'metadata.steamgriddbKey'.
+    Metadata {
+        /// Names of the games, or patterns with the `*` and `?` wildcards, all of them if not
+        /// provided.
+        #[arg(add = game_name_completer())]
+        games: Vec<String>,
+        /// Fetches the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Fetches the metadata of the games that already have it again.
+        #[arg(long)]
+        refresh: bool,
+    },
+    /// Adds the game to Steam as a non-Steam shortcut, which starts it with 'gg run'.
+    ///
+    /// Lets Big Picture and the Game Mode of the Steam Deck launch the game with its saves backed
+    /// up. Steam must be closed, as it overwrites the shortcuts when it exits.
+    SteamShortcut {
+        /// Icon of the shortcut, the one fetched by 'gg metadata' or else the one of gg if not
+        /// provided.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        icon: Option<PathBuf>,
+        /// Removes the shortcut instead.
+        #[arg(long, conflicts_with = "icon")]
+        remove: bool,
+        /// Name of the game.
         #[arg(add = game_name_completer())]
         game: String,
     },
     /// Runs the selected game.
     #[clap(alias = "r")]
     Run {
-        /// Skip creating a backup of the saves when the game exits.
-        #[clap(short, long = "skip-cloud")]
-        skip_cloud: bool,
+        #[command(flatten)]
+        cloud: CloudArgs,
+        /// Backs up the save after the game closes, the default unless 'run.backupAfter' is false.
+        #[arg(long, overrides_with = "no_backup")]
+        backup: bool,
+        /// Doesn't back up the save after the game closes.
+        #[arg(long)]
+        no_backup: bool,
+        /// Backs up the save right before the game starts, the default if 'run.backupBefore' is
+        /// true.
+        #[arg(long, overrides_with = "no_pre_backup")]
+        pre_backup: bool,
+        /// Doesn't back up the save before the game starts.
+        #[arg(long)]
+        no_pre_backup: bool,
+        /// Backs up the save every this long while the game runs (e.g. "30m"), 0 disables it.
+        ///
+        /// Only the last 'run.autosaveKeep' of these backups are kept. Defaults to
+        /// 'run.autosaveInterval'.
+        #[arg(long, value_name = "DURATION", add = duration_candidates())]
+        autosave_interval: Option<Duration>,
+        /// Returns right away, leaving gg in the background to back up the save when the game
+        /// exits.
+        ///
+        /// Its output goes to a file in the state directory, and the passphrase of encrypted
+        /// backups must be in GG_PASSPHRASE, as there is no terminal to ask it in.
+        #[arg(long)]
+        detach: bool,
+        /// Launch profile of the game to use, the first one if not provided.
+        ///
+        /// The profiles are added to the 'launch_profiles' of the game with 'gg edit'.
+        #[arg(short, long)]
+        profile: Option<String>,
+        /// Player whose backups and save are used, one of the 'players' of the game.
+        ///
+        /// The players are added to the game with 'gg edit'.
+        #[arg(long)]
+        player: Option<String>,
+        /// Runs the game played most recently instead.
+        #[arg(short, long, conflicts_with = "game")]
+        last: bool,
         /// Name of the game to run.
+        ///
+        /// If not provided, it is inferred from the current directory, or is the game played
+        /// most recently.
         #[arg(add = game_name_completer())]
         game: Option<String>,
+        /// Arguments given to the game after "--", like 'gg run Celeste -- --windowed'.
+        ///
+        /// They replace @ARGS in the run commands, or go after @EXE if no command uses @ARGS.
+        #[arg(last = true)]
+        args: Vec<String>,
     },
-    /// Prints the current configuration.
+    /// Checks every game for missing roots, save locations and backups directories, dangling
+    /// gg-save-loc symlinks, truncated backups and stale temporary files.
     ///
-    /// Located on /etc/goodgame/config.json
-    Config,
+    /// When a save location is missing, like after a game update or a launcher migration, the
+    /// usual save directories are searched for the files of its last backup, proposing the place
+    /// they were moved to. When the root is missing, asks for the new one or to stop managing the
+    /// game.
+    Doctor {
+        /// Name of the game to check, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        /// Uses the best match of a missing save location without asking.
+        #[arg(short, long)]
+        yes: bool,
+        /// Repairs the safe problems: recreates the symlinks and backups directories, and removes
+        /// the temporary files.
+        #[arg(long)]
+        fix: bool,
+        /// Runs the cycle of 'gg sandbox' instead, checking that gg works on this machine.
+        #[arg(long, conflicts_with_all = ["game", "yes", "fix"])]
+        selftest: bool,
+    },
+    /// Tries gg on a throwaway game, without touching the managed games or the cloud.
+    ///
+    /// Creates a temporary data directory and a game with sample saves, then adds, backs up,
+    /// modifies, backs up and restores it, printing every command run.
+    Sandbox {
+        /// Keeps the sandbox afterwards, to try more commands on it.
+        #[arg(long)]
+        keep: bool,
+    },
+    /// Shows the time played, the stored backups and the activity of a game on this machine.
+    ///
+    /// Without a game or tag, also shows the totals of every game and the most played one. The
+    /// backups, restores and sessions are recorded in the history of each game, with which the
+    /// growth of its backups over the last year is drawn.
+    Stats {
+        /// Name of the game, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        /// Shows the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+    /// Shows the backups, restores and sessions recorded in the history of every game, or only of
+    /// one, oldest first.
+    ///
+    /// The parts of the history moved aside as it grew are read too.
+    History {
+        /// Name of the game, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        /// Shows the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Shows only what happened in the last DURATION (e.g. "7d").
+        #[arg(long, value_name = "DURATION")]
+        since: Option<Duration>,
+    },
+    /// Prints the log of gg, including the files moved aside by 'log.maxSize', oldest first.
+    Logs {
+        /// Prints only the messages of the last DURATION (e.g. "1h").
+        #[arg(long, value_name = "DURATION")]
+        since: Option<Duration>,
+    },
+    /// Shows the backups, the cloud state and the last session of every game, or only of one.
+    ///
+    /// The cloud storage of every game is listed, which may take a while with slow backends.
+    Status {
+        /// Name of the game, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        /// Shows the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+    },
+    /// Searches the games whose name, aliases, root, save location, executable or tags contain
+    /// the query, ignoring the case, printing the fields that match.
+    #[clap(alias = "find")]
+    Search {
+        /// Text to search for, like part of the path where a game is installed.
+        query: String,
+    },
+    /// Shows the space used by the backups of every game, or only of one, largest first, followed
+    /// by the total and the largest backups.
+    ///
+    /// The space on disk counts everything in the backups directory, like the history of the git
+    /// backend, while the size only counts the backup archives.
+    Du {
+        /// Name of the game, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        /// Shows the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Number of largest backups shown.
+        #[arg(long, value_name = "N", default_value_t = 5)]
+        top: usize,
+    },
+    /// Repacks the old backups of the games, recompressing them with a higher zstd level to take
+    /// less space, and prints the space reclaimed.
+    ///
+    /// Every repacked backup is checked to hold the same archive before it replaces the old one,
+    /// which is kept when it would not get smaller. Only the backups compressed with the built-in
+    /// zstd are repacked, each one once per level. The age and level default to the 'gc' section
+    /// of the config, whose schedule makes 'gg watch' run it too.
+    Gc {
+        /// Names of the games, or patterns with the `*` and `?` wildcards, all of them if not
+        /// provided.
+        #[arg(add = game_name_completer())]
+        games: Vec<String>,
+        /// Repacks the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Repacks only the backups older than this (e.g. "30d"), 'gc.olderThan' by default.
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<Duration>,
+        /// zstd level of the repacked backups, from 1 to 22, 'gc.level' by default.
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=22))]
+        level: Option<u8>,
+        /// Prints the backups that would be repacked, without repacking them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Removes the backups of the games past their 'backup.retention', and prints the space
+    /// reclaimed.
+    ///
+    /// A backup is removed when it is neither among the newest ones kept nor younger than the
+    /// maximum age. The newest backup and the ones not uploaded yet are always kept.
+    Prune {
+        /// Names of the games, or patterns with the `*` and `?` wildcards, all of them if not
+        /// provided.
+        #[arg(add = game_name_completer())]
+        games: Vec<String>,
+        /// Prunes the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Number of newest backups kept per game, 'backup.retention.keep' by default.
+        #[arg(long, value_name = "N")]
+        keep: Option<usize>,
+        /// Removes only the backups older than this (e.g. "90d"), 'backup.retention.maxAge' by
+        /// default.
+        #[arg(long, value_name = "DURATION")]
+        max_age: Option<Duration>,
+        /// Prints the backups that would be removed, without removing them.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Backs up the saves of the games when they change, until stopped with Ctrl-C.
+    ///
+    /// A save is backed up once it goes 'watch.settle' without changes, at most once every
+    /// 'watch.minInterval', keeping the last 'watch.keep' of these backups. The games with
+    /// 'watch.enabled' set to false are skipped, and so are the changes made while another gg
+    /// process runs, backs up or restores the game. The games with 'gc.schedule' set also have
+    /// their old backups repacked like with 'gg gc' at those times.
+    ///
+    /// Meant to run in the background, like in a systemd user service. Games added afterwards are
+    /// watched once it is started again.
+    Watch {
+        /// Names of the games, or patterns with the `*` and `?` wildcards, all of them if not
+        /// provided.
+        #[arg(add = game_name_completer())]
+        games: Vec<String>,
+        /// Watches the games with the tag too, can be repeated.
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        #[command(flatten)]
+        cloud: CloudArgs,
+    },
+    /// Answers the requests of frontends on a Unix socket, until stopped with Ctrl-C.
+    ///
+    /// The requests are JSON-RPC 2.0, one JSON object per line. The methods are "list",
+    /// "backup", "restore" and "run", with the params named like the arguments of their
+    /// commands, like {"game": "Celeste", "desc": "boss"} for "backup". "restore" needs the
+    /// "backup" to restore and doesn't ask before replacing the save, like with --yes. "status"
+    /// returns the PID of gg serve, when it started and the operations it has in progress.
+    Serve {
+        /// Path of the socket, gg.sock in $XDG_RUNTIME_DIR/goodgame by default.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        socket: Option<PathBuf>,
+    },
+    /// Shows the running games, the operations in progress and the recent failures of gg, and
+    /// the state of gg serve if it is listening.
+    Top {
+        /// Print the status once instead of refreshing it.
+        #[arg(long)]
+        once: bool,
+        /// Path of the socket of gg serve, gg.sock in $XDG_RUNTIME_DIR/goodgame by default.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        socket: Option<PathBuf>,
+    },
+    /// Prints the values set by the config files, with the file each one comes from.
+    ///
+    /// Read from /etc/goodgame/config.yaml and ~/.config/goodgame/config.yaml, the latter taking
+    /// precedence. config.yml and config.json are read too if there is no config.yaml, and
+    /// config.toml if gg is built with the "toml" feature.
+    ///
+    /// On macOS they are in /Library/Application Support/goodgame and
+    /// ~/Library/Application Support/goodgame instead.
+    Config {
+        #[command(subcommand)]
+        action: Option<Config>,
+    },
+    /// Manages the passphrase used to encrypt backups.
+    ///
+    /// The passphrase is asked interactively, or read from the GG_PASSPHRASE environment variable.
+    #[clap(subcommand)]
+    Key(Key),
+    /// Manages the credentials stored in the system keyring, used in the config with
+    /// `keyring: NAME` instead of writing them in the config files.
+    ///
+    /// Requires the "keyring" feature.
+    #[clap(subcommand)]
+    Secret(Secret),
+    /// Manages the shell completions.
+    #[clap(subcommand)]
+    Completions(Completions),
+    /// Writes the man pages of gg and of each of its commands, like gg-restore.1, into a
+    /// directory.
+    ///
+    /// The pages are always in English, whatever the language of the user.
+    Man {
+        /// Directory where the man pages are written, created if it doesn't exist.
+        #[arg(default_value = ".", value_hint = ValueHint::DirPath)]
+        dir: PathBuf,
+        /// Prints the help of every command instead, one after another.
+        #[arg(long)]
+        help_all: bool,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum Backups {
+    /// Lists the backups of a game with their size and cloud commit.
+    ///
+    /// The backups only in the cloud storage are listed too, marked with ☁.
+    #[clap(alias = "ls")]
+    List {
+        /// Name of the game.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        #[command(flatten)]
+        cloud: CloudArgs,
+    },
+    /// Gives a new index to the backups that share one, like the ones made on two machines.
+    ///
+    /// The oldest backup keeps the index, the rest are renamed after the last backup.
+    Dedupe {
+        /// Name of the game.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        #[command(flatten)]
+        cloud: CloudArgs,
+    },
+    /// Prints a file stored in a backup.
+    ///
+    /// By default the save container is printed, or the save file if the save location is a file.
+    Cat {
+        /// Print only this slot of the save container.
+        #[arg(long)]
+        slot: Option<String>,
+        /// Path of the file to print, relative to the save location.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        file: Option<PathBuf>,
+        /// Name of the game.
+        #[arg(add = game_name_completer())]
+        game: String,
+        /// Name of the backup.
+        #[arg(add = game_backup_candidates(), requires = "game")]
+        backup: String,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum Cloud {
+    /// Shows the backups of each game that haven't been pushed, and the newer ones in the cloud.
+    #[clap(alias = "st")]
+    Status {
+        /// Name of the game, all of them if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+    },
+    /// Lists the backups of a game that are in the cloud but not in this machine.
+    #[clap(alias = "list")]
+    Ls {
+        /// Name of the game, inferred from the current directory if not provided.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+    },
+    /// Prints the cloud commands of a game with its variables replaced, without running them.
+    ///
+    /// @BACKUP-FILE is replaced with the latest backup of the game. For the rclone and rsync
+    /// backends, prints the commands they would run instead.
+    #[clap(alias = "preview")]
+    DryRun {
+        /// Name of the game, inferred from the current directory if not provided.
+        #[arg(add = game_name_completer(), conflicts_with = "all")]
+        game: Option<String>,
+        /// Prints the commands of every game.
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum Config {
+    /// Opens the user config file in the editor, saving it only if the resulting config is valid.
+    ///
+    /// The editor is the 'editor' of the config, $VISUAL, $EDITOR or vi.
+    Edit {
+        /// Edit the system config file in /etc/goodgame instead, or in
+        /// /Library/Application Support/goodgame on macOS.
+        #[arg(long)]
+        system: bool,
+    },
+    /// Writes a commented starter config to the user config file.
+    Init {
+        /// Write the system config file in /etc/goodgame instead, or in
+        /// /Library/Application Support/goodgame on macOS.
+        #[arg(long)]
+        system: bool,
+        /// Overwrites the config file if it already exists.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Checks the config, failing if it has any problem.
+    ///
+    /// Besides parsing it, verifies that the shell exists and looks for unknown keys and for
+    /// variables the command templates can't use.
+    Check,
+    /// Prints the JSON Schema of the config files, for editors to validate and complete them.
+    Schema {
+        /// Prints the schema of the games database instead.
+        #[arg(long)]
+        games: bool,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum Key {
+    /// Re-encrypts the existing encrypted backups with a new passphrase.
+    ///
+    /// The new passphrase is asked interactively, or read from the GG_NEW_PASSPHRASE environment variable.
+    ///
+    /// Nothing is replaced unless every backup opens with the current passphrase. The re-encrypted
+    /// backups are uploaded again with 'gg sync', run right away unless the cloud is off.
+    Rotate {
+        /// Only rotate the backups of this game.
+        #[arg(add = game_name_completer())]
+        game: Option<String>,
+        #[command(flatten)]
+        cloud: CloudArgs,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum Secret {
+    /// Stores a credential in the system keyring, replacing the previous one.
+    ///
+    /// The credential is asked interactively, or read from stdin if it is not a terminal.
+    Set {
+        /// Name of the credential, the one given to `keyring` in the config.
+        name: String,
+    },
+    /// Removes a credential from the system keyring.
+    #[clap(alias = "rm")]
+    Remove {
+        /// Name of the credential, the one given to `keyring` in the config.
+        name: String,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum Completions {
+    /// Checks that the completions are set up for the shell, printing the line to add if not.
+    Doctor {
+        /// Shell to check, detected from $SHELL by default.
+        #[arg(long, value_enum)]
+        shell: Option<Shell>,
+    },
+}
+
+/// Programs that wrap the executable of a game in 'gg run'.
+#[derive(Clone, Debug, clap::Args)]
+pub struct WrapperArgs {
+    /// Runs the game inside gamescope.
+    #[arg(long, value_name = "BOOL")]
+    gamescope: Option<bool>,
+    /// Arguments of gamescope, like "-W 2560 -H 1440 -f".
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+    gamescope_args: Option<String>,
+    /// Shows the MangoHud overlay.
+    #[arg(long, value_name = "BOOL")]
+    mangohud: Option<bool>,
+    /// Other wrappers put before the rest, like "gamemoderun", an empty one removes them.
+    #[arg(long = "wrapper", value_name = "PROGRAM")]
+    extra: Option<Vec<String>>,
+}
+
+impl WrapperArgs {
+    /// Returns `wrappers` with the changes given, or `None` if there are none.
+    pub fn apply(self, wrappers: Option<&Wrappers>) -> Option<Wrappers> {
+        if self.gamescope.is_none()
+            && self.gamescope_args.is_none()
+            && self.mangohud.is_none()
+            && self.extra.is_none()
+        {
+            return None;
+        }
+        let mut wrappers = wrappers.cloned().unwrap_or_default();
+        if let Some(gamescope) = self.gamescope {
+            wrappers.gamescope = gamescope;
+        }
+        if let Some(args) = self.gamescope_args {
+            wrappers.gamescope_args = args.split_whitespace().map(String::from).collect();
+        }
+        if let Some(mangohud) = self.mangohud {
+            wrappers.mangohud = mangohud;
+        }
+        if let Some(extra) = self.extra {
+            wrappers.extra = extra.into_iter().filter(|w| !w.is_empty()).collect();
+        }
+        Some(wrappers)
+    }
+}
+
+/// Games chosen by name, by wildcard pattern or by tag.
+#[derive(Clone, Debug, clap::Args)]
+pub struct Selection {
+    /// Names of the games, or patterns with the `*` and `?` wildcards like 'Dark*'.
+    #[arg(add = game_name_completer())]
+    pub games: Vec<String>,
+    /// Selects the games with the tag too, can be repeated.
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// Doesn't ask before acting on the games matched by a pattern or tag.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+impl Command {
+    /// Returns whether the command changes the game database, holding its lock until it finishes.
+    pub fn changes_games(&self) -> bool {
+        matches!(
+            self,
+            Self::Add { .. }
+                | Self::Edit { .. }
+                | Self::Rename { .. }
+                | Self::Move { .. }
+                | Self::Remove { .. }
+                | Self::Archive { .. }
+                | Self::Unarchive { .. }
+                | Self::Clone { .. }
+                | Self::Import { .. }
+                | Self::Doctor {
+                    selftest: false,
+                    ..
+                }
+        )
+    }
 }
 
-static GAMES: std::sync::LazyLock<Games> = std::sync::LazyLock::new(|| Games::load().unwrap());
+impl Selection {
+    /// Returns whether games are chosen by a pattern or tag, instead of only by their names.
+    pub fn matches(&self) -> bool {
+        !self.tags.is_empty() || self.games.iter().any(|game| game.contains(['*', '?']))
+    }
+}
+
+/// How a command uses the cloud storage.
+#[derive(Clone, Copy, Debug, clap::Args)]
+pub struct CloudArgs {
+    /// How the cloud storage is used, the backup.cloud setting by default, or "push" if unset.
+    #[arg(long, value_enum, value_name = "MODE")]
+    cloud: Option<CloudMode>,
+    /// Skips cloud saving features completely, same as --cloud=off.
+    #[arg(short, long = "skip-cloud", conflicts_with = "cloud")]
+    skip_cloud: bool,
+}
+
+impl CloudArgs {
+    /// Returns the mode given, or `None` to use the configured one.
+    pub fn mode(self) -> Option<CloudMode> {
+        if self.skip_cloud {
+            Some(CloudMode::Off)
+        } else {
+            self.cloud
+        }
+    }
+}
+
+/// Order of the games in 'gg list'.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListSort {
+    Name,
+    LastPlayed,
+    BackupCount,
+    /// Total size of the backups.
+    Size,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Returns the command line of gg with its help translated to the language of the user, as far as
+/// it is translated.
+///
+/// The help of a command is the message named after its path, like "gg-backups-cat", and its long
+/// help the one ending in "-long", falling back to the short one so the help is not mixed with
+/// English. The help of an argument is the message of its command followed by "--" and its id,
+/// like "gg-restore--game".
+pub fn localized_command() -> clap::Command {
+    use clap::CommandFactory;
+
+    fn localize(mut command: clap::Command, id: &str) -> clap::Command {
+        use goodgame::i18n::translation;
+
+        if let Some(about) = translation(id) {
+            command = match translation(&format!("{id}-long")) {
+                Some(long) => command.about(about).long_about(long),
+                None => command.about(about).long_about(None),
+            };
+        }
+        if let Some(heading) = translation("help-commands") {
+            command = command.subcommand_help_heading(heading);
+        }
+        let args = command
+            .get_arguments()
+            .map(|arg| arg.get_id().to_string())
+            .collect::<Vec<_>>();
+        for arg in args {
+            if let Some(help) = translation(&format!("{id}--{arg}")) {
+                command = command.mut_arg(arg, |arg| arg.help(help).long_help(None));
+            }
+        }
+        let subcommands = command
+            .get_subcommands()
+            .map(|sub| sub.get_name().to_owned())
+            .collect::<Vec<_>>();
+        for name in subcommands {
+            command = command.mut_subcommand(&name, |sub| localize(sub, &format!("{id}-{name}")));
+        }
+        command
+    }
+    localize(Cli::command(), "gg")
+}
+
+/// Returns the games offered as completions, read the first time it is called, or `None` if they
+/// can't be read, like when the database is corrupt or newer than this gg, which the command itself
+/// reports.
+///
+/// The database is only read, never upgraded nor created, so completing doesn't print or write
+/// anything.
+fn completion_games() -> Option<&'static Games> {
+    static GAMES: std::sync::OnceLock<Option<Games>> = std::sync::OnceLock::new();
+    GAMES
+        .get_or_init(|| {
+            let path = goodgame::games::data_dir()
+                .ok()?
+                .join(Games::games_file_name());
+            let mut games = Games::builder().in_memory().load().ok()?;
+            for game in Games::read_games(&path).ok()? {
+                games.push(game);
+            }
+            Some(games)
+        })
+        .as_ref()
+}
 
 fn game_name_completer() -> ArgValueCompleter {
     fn inner(current: &std::ffi::OsStr) -> Vec<CompletionCandidate> {
@@ -169,9 +1187,12 @@ fn game_name_completer() -> ArgValueCompleter {
             return Vec::new();
         }
 
-        GAMES
-            .names()
+        completion_games()
             .into_iter()
+            .flat_map(|games| games.active())
+            .flat_map(|game| {
+                std::iter::once(game.name()).chain(game.aliases().iter().map(String::as_str))
+            })
             .filter(|c| {
                 current
                     .as_encoded_bytes()
@@ -186,24 +1207,91 @@ fn game_name_completer() -> ArgValueCompleter {
     ArgValueCompleter::new(inner)
 }
 
-fn game_backup_candidates() -> ArgValueCandidates {
-    if std::env::args().count() <= 2 {
-        return ArgValueCandidates::new(std::vec::Vec::new);
-    }
-    let Some(game) = std::env::args()
-        .rfind(|a| !a.is_empty())
-        .and_then(|chosen| GAMES.get_by_name(chosen).ok())
-    else {
-        return ArgValueCandidates::new(Vec::new);
-    };
+fn duration_candidates() -> ArgValueCandidates {
+    ArgValueCandidates::new(|| {
+        goodgame::units::DURATION_EXAMPLES
+            .iter()
+            .copied()
+            .map(CompletionCandidate::new)
+            .collect()
+    })
+}
 
+fn proton_candidates() -> ArgValueCandidates {
     ArgValueCandidates::new(|| {
-        game.backups_path()
-            .read_dir()
-            .unwrap()
-            .flatten()
-            .map(|f| f.file_name().to_string_lossy().into_owned())
+        goodgame::paths::proton_versions()
+            .into_iter()
             .map(CompletionCandidate::new)
             .collect()
     })
 }
+
+/// Returns the words typed before the one being completed, starting with the name of gg, from
+/// the arguments given by the shell after "--".
+fn completion_words() -> Vec<std::ffi::OsString> {
+    let mut words = std::env::args_os()
+        .skip_while(|a| a != "--")
+        .skip(1)
+        .collect::<Vec<_>>();
+    // Bash, zsh and elvish say which word is being completed, the rest complete the last one
+    let current = std::env::var("_CLAP_COMPLETE_INDEX")
+        .ok()
+        .and_then(|i| i.parse().ok())
+        .unwrap_or(words.len().saturating_sub(1));
+    words.truncate(current);
+    words
+}
+
+/// Returns the game chosen in the command being completed, for the player given with --player if
+/// any, found by parsing the words typed before the current one like the command would.
+fn completion_game() -> Option<goodgame::games::Game> {
+    use clap::CommandFactory;
+
+    let words = completion_words();
+    let mut matches = &Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(words)
+        .ok()?;
+    while let Some((_, sub)) = matches.subcommand() {
+        matches = sub;
+    }
+    let game = matches.try_get_one::<String>("game").ok()??;
+    let player = matches.try_get_one::<String>("player").ok().flatten();
+    completion_games()?
+        .get_by_name_or_alias(game)
+        .ok()?
+        .with_player(player.map(String::as_str))
+        .ok()
+}
+
+/// Offers the backups of the game chosen before, newest first, described by when and where they
+/// were made and their description, or nothing if it has none or there is no game.
+fn game_backup_candidates() -> ArgValueCandidates {
+    ArgValueCandidates::new(|| {
+        let Some(game) = completion_game() else {
+            return Vec::new();
+        };
+        let metadata = game.backups_metadata();
+        // Missing when the game has never been backed up
+        let mut backups = game.backups().unwrap_or_default();
+        backups.sort_by_key(|backup| std::cmp::Reverse(backup.index));
+        backups
+            .into_iter()
+            .map(|backup| {
+                let name = backup.name().into_owned();
+                let recorded = metadata.get(&name);
+                let time = recorded
+                    .and_then(|m| m.time)
+                    .unwrap_or_else(|| goodgame::games::unix_secs(backup.timestamp));
+                let mut help = goodgame::games::ago(time);
+                if let Some(host) = recorded.and_then(|m| m.host.as_deref()) {
+                    help += &format!(" on {host}");
+                }
+                if let Some(description) = &backup.description {
+                    help += &format!(", {description}");
+                }
+                CompletionCandidate::new(name).help(Some(help.into()))
+            })
+            .collect()
+    })
+}
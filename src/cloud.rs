@@ -0,0 +1,808 @@
+use crate::config::Backend;
+use crate::games::{Game, Games};
+use crate::runner::shell_quote;
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+/// Storage where the backups of the games are kept, like a git remote or a WebDAV server.
+///
+/// The built-in backends implement it, and a custom one can be used for every game with
+/// [`Games::set_cloud_backend`]. Every operation receives the [`Games`] it runs for, which gives
+/// access to its configuration and command runner, and the game whose backups are transferred.
+///
+/// Only the backends that can transfer single files, telling so with
+/// [`CloudBackend::copies_single_files`], can sync backups one by one and encrypt them.
+pub trait CloudBackend: std::fmt::Debug + Send + Sync {
+    /// Name of the backend, used in the messages for the user.
+    fn name(&self) -> &str;
+
+    /// Prepares the storage of a newly managed game.
+    fn init(&self, games: &Games, game: &Game) -> Result<()>;
+
+    /// Records the current backups of the game, for the backends that keep a history.
+    ///
+    /// `backup` is the archive that has just been created, if any, and `message` describes the
+    /// commit.
+    fn commit(
+        &self,
+        games: &Games,
+        game: &Game,
+        backup: Option<&Path>,
+        message: &str,
+    ) -> Result<()> {
+        let _ = (games, game, backup, message);
+        Ok(())
+    }
+
+    /// Uploads the backups of the game, `backup` being the archive that has just been created.
+    fn push(&self, games: &Games, game: &Game, backup: Option<&Path>) -> Result<()>;
+
+    /// Downloads the backups of the game that are only in the storage.
+    fn pull(&self, games: &Games, game: &Game) -> Result<()>;
+
+    /// Returns the names and sizes of the files of the game in the storage, or `None` if it can't
+    /// list them.
+    fn list(&self, games: &Games, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        let _ = (games, game);
+        Ok(None)
+    }
+
+    /// Whether the backend only works by running commands through the runner of the [`Games`], so
+    /// they can be recorded instead of run, like by 'gg cloud dry-run'.
+    fn runs_commands(&self) -> bool {
+        false
+    }
+
+    /// Whether the backend can upload, download and remove single files.
+    fn copies_single_files(&self) -> bool {
+        false
+    }
+
+    /// Uploads the file at `path` to the storage of the game.
+    fn upload(&self, games: &Games, game: &Game, path: &Path) -> Result<()> {
+        let _ = (games, game, path);
+        bail!("The {} backend can't upload single backups", self.name())
+    }
+
+    /// Downloads the file called `name` from the storage of the game into `dir`.
+    fn download(&self, games: &Games, game: &Game, name: &str, dir: &Path) -> Result<()> {
+        let _ = (games, game, name, dir);
+        bail!("The {} backend can't download single backups", self.name())
+    }
+
+    /// Removes the file called `name` from the storage of the game.
+    fn remove(&self, games: &Games, game: &Game, name: &str) -> Result<()> {
+        let _ = (games, game, name);
+        bail!("The {} backend can't remove single backups", self.name())
+    }
+}
+
+/// How the commands that change the backups of a game use the cloud storage.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum CloudMode {
+    /// Keep the backups only in this machine.
+    Off,
+    /// Commit the backups without reaching the cloud storage, like on a metered connection.
+    ///
+    /// The new backups are marked as pending upload, for 'gg sync' to upload them later.
+    CommitOnly,
+    /// Pull the backups before using them and commit and push the new ones.
+    #[default]
+    Push,
+}
+
+impl CloudMode {
+    /// Whether the backups are committed, like in the local repository of the git backend.
+    pub fn commits(self) -> bool {
+        self != Self::Off
+    }
+
+    /// Whether the cloud storage is reached, to pull, list or push the backups.
+    pub fn transfers(self) -> bool {
+        self == Self::Push
+    }
+}
+
+/// Returns the built-in implementation of `backend`, or the one that runs the configured cloud
+/// commands if `None`.
+pub(crate) fn built_in(backend: Option<&Backend>) -> Result<Arc<dyn CloudBackend>> {
+    Ok(match backend {
+        None => Arc::new(Commands),
+        #[cfg(feature = "git")]
+        Some(Backend::Git { remote }) => Arc::new(Git {
+            remote: remote.clone(),
+        }),
+        #[cfg(not(feature = "git"))]
+        Some(Backend::Git { .. }) => bail!(GIT_FEATURE_MISSING),
+        Some(Backend::Rclone { remote }) => Arc::new(Rclone {
+            remote: remote.clone(),
+        }),
+        Some(Backend::Rsync { remote, ssh }) => Arc::new(Rsync {
+            remote: remote.clone(),
+            ssh: ssh.clone(),
+        }),
+        #[cfg(feature = "webdav")]
+        Some(Backend::Webdav {
+            remote,
+            username,
+            password,
+        }) => Arc::new(Webdav {
+            remote: remote.clone(),
+            username: username.clone(),
+            password: password.clone(),
+        }),
+        #[cfg(not(feature = "webdav"))]
+        Some(Backend::Webdav { .. }) => bail!(WEBDAV_FEATURE_MISSING),
+        Some(Backend::Syncthing) => Arc::new(Syncthing),
+    })
+}
+
+/// Runs the cloud commands of the config file.
+#[derive(Debug)]
+struct Commands;
+
+impl CloudBackend for Commands {
+    fn name(&self) -> &str {
+        "commands"
+    }
+
+    fn runs_commands(&self) -> bool {
+        true
+    }
+
+    fn init(&self, games: &Games, game: &Game) -> Result<()> {
+        games.execute(games.cloud_init_command(game)?, "cloud init", game.root())
+    }
+
+    fn commit(
+        &self,
+        games: &Games,
+        game: &Game,
+        backup: Option<&Path>,
+        message: &str,
+    ) -> Result<()> {
+        games.execute(
+            games.cloud_commit_command(game, backup, message)?,
+            "cloud commit",
+            game.root(),
+        )
+    }
+
+    fn push(&self, games: &Games, game: &Game, backup: Option<&Path>) -> Result<()> {
+        games.execute(
+            games.cloud_push_command(game, backup)?,
+            "cloud push",
+            game.root(),
+        )
+    }
+
+    fn pull(&self, games: &Games, game: &Game) -> Result<()> {
+        games.execute(games.cloud_pull_command(game)?, "cloud pull", game.root())
+    }
+
+    fn remove(&self, _: &Games, _: &Game, _: &str) -> Result<()> {
+        bail!("The cloud commands can't remove single backups")
+    }
+}
+
+/// Keeps the backups directory of every game in its own git repository.
+#[cfg(feature = "git")]
+#[derive(Debug)]
+struct Git {
+    remote: Option<String>,
+}
+
+#[cfg(feature = "git")]
+impl CloudBackend for Git {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn init(&self, _: &Games, game: &Game) -> Result<()> {
+        crate::git::init(
+            &game.backups_path(),
+            game.cloud_remote(self.remote.as_deref()).as_deref(),
+        )
+    }
+
+    fn commit(&self, _: &Games, game: &Game, _: Option<&Path>, message: &str) -> Result<()> {
+        let commit = crate::git::commit(&game.backups_path(), message)?;
+        let Some(commit) = commit else {
+            return Ok(());
+        };
+        // Every backup without a commit yet has been first included in this one
+        let mut metadata = game.backups_metadata();
+        for backup in game.backups()? {
+            let entry = metadata.entry(backup.name().into_owned()).or_default();
+            entry.commit.get_or_insert_with(|| commit.clone());
+        }
+        game.set_backups_metadata(&metadata)
+    }
+
+    fn push(&self, _: &Games, game: &Game, _: Option<&Path>) -> Result<()> {
+        crate::git::push(&game.backups_path())
+    }
+
+    fn pull(&self, _: &Games, game: &Game) -> Result<()> {
+        crate::git::pull(&game.backups_path())
+    }
+
+    fn list(&self, _: &Games, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        crate::git::list(&game.backups_path())
+    }
+
+    /// The next commit records the removal.
+    fn remove(&self, _: &Games, _: &Game, _: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Copies the backups to an rclone remote.
+#[derive(Debug)]
+struct Rclone {
+    remote: Option<String>,
+}
+
+impl Rclone {
+    fn remote(&self, game: &Game) -> Result<String> {
+        let Some(remote) = game.cloud_remote(self.remote.as_deref()) else {
+            bail!(
+                "The rclone backend needs a remote, set it in the config or the game {:?}",
+                game.name()
+            )
+        };
+        Ok(remote)
+    }
+}
+
+impl CloudBackend for Rclone {
+    fn name(&self) -> &str {
+        "rclone"
+    }
+
+    fn runs_commands(&self) -> bool {
+        true
+    }
+
+    fn init(&self, games: &Games, game: &Game) -> Result<()> {
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.args(["mkdir", &self.remote(game)?]);
+        games.execute(Some(cmd), "cloud init", game.root())
+    }
+
+    fn push(&self, games: &Games, game: &Game, _: Option<&Path>) -> Result<()> {
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.args(["copy", "--progress", "--exclude", ".*"])
+            .arg(game.backups_path())
+            .arg(self.remote(game)?);
+        games.execute(Some(cmd), "cloud push", game.root())
+    }
+
+    fn pull(&self, games: &Games, game: &Game) -> Result<()> {
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.args([
+            "copy",
+            "--progress",
+            "--ignore-existing",
+            &self.remote(game)?,
+        ])
+        .arg(game.backups_path());
+        games.execute(Some(cmd), "cloud pull", game.root())?;
+        pull_changed(self, games, game)
+    }
+
+    fn list(&self, games: &Games, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.args(["lsjson", "--files-only", &self.remote(game)?]);
+        let out = games.output(cmd, "cloud list", game.root())?;
+        if out.is_empty() {
+            return Ok(Some(BTreeMap::new()));
+        }
+        Ok(Some(
+            serde_json::from_slice::<Vec<RcloneFile>>(&out)
+                .context("Could not parse the output of 'rclone lsjson'")?
+                .into_iter()
+                .map(|f| (f.name, f.size))
+                .collect(),
+        ))
+    }
+
+    fn copies_single_files(&self) -> bool {
+        true
+    }
+
+    fn upload(&self, games: &Games, game: &Game, path: &Path) -> Result<()> {
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.arg("copy").arg(path).arg(self.remote(game)?);
+        games.execute(Some(cmd), "cloud upload", game.root())
+    }
+
+    fn download(&self, games: &Games, game: &Game, name: &str, dir: &Path) -> Result<()> {
+        let remote = self.remote(game)?;
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.arg("copy")
+            .arg(format!("{}/{name}", remote.trim_end_matches('/')))
+            .arg(dir);
+        games.execute(Some(cmd), "cloud download", game.root())
+    }
+
+    fn remove(&self, games: &Games, game: &Game, name: &str) -> Result<()> {
+        let remote = self.remote(game)?;
+        let mut cmd = std::process::Command::new("rclone");
+        cmd.arg("deletefile")
+            .arg(format!("{}/{name}", remote.trim_end_matches('/')));
+        games.execute(Some(cmd), "cloud remove", game.root())
+    }
+}
+
+/// Downloads the marker of the last backup, which changes with every backup, if newer than the
+/// local one, and the backups that are both in the storage and in this machine with a different
+/// size, under a new name so neither copy is lost.
+///
+/// Used after copying the files missing locally, which never replaces the existing ones.
+fn pull_changed(backend: &dyn CloudBackend, games: &Games, game: &Game) -> Result<()> {
+    let Some(remote) = backend.list(games, game)? else {
+        return Ok(());
+    };
+    let backups_path = game.backups_path();
+    let dir = backups_path.join(crate::games::CLOUD_TEMP_DIR);
+    for (name, size) in remote {
+        if name == crate::games::MARKER_FILE {
+            pull_marker(backend, games, game, &dir)?;
+            continue;
+        }
+        let differs = backups_path
+            .join(&name)
+            .metadata()
+            .is_ok_and(|m| m.len() != size);
+        if name.starts_with('.') || !differs {
+            continue;
+        }
+        std::fs::create_dir_all(&dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+        backend.download(games, game, &name, &dir)?;
+        let new_name = game.adopted_backup_name(game, &name)?;
+        std::fs::rename(dir.join(&name), backups_path.join(&new_name)).context_with(|| {
+            format!("Could not write {}", backups_path.join(&new_name).display())
+        })?;
+        crate::output::warning(format_args!(
+            "{name} of {} differs from the one in the cloud, downloaded it as {new_name}",
+            game.name()
+        ));
+    }
+    Ok(())
+}
+
+/// Downloads the marker of the last backup into `dir`, keeping it only if newer than the local
+/// one, like one left by a backup made without reaching the cloud.
+fn pull_marker(backend: &dyn CloudBackend, games: &Games, game: &Game, dir: &Path) -> Result<()> {
+    use crate::games::{BackupMarker, MARKER_FILE};
+
+    std::fs::create_dir_all(dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    backend.download(games, game, MARKER_FILE, dir)?;
+    let downloaded = dir.join(MARKER_FILE);
+    let newer = BackupMarker::read(&downloaded)
+        .is_some_and(|cloud| game.marker().is_none_or(|local| cloud.time > local.time));
+    if newer {
+        let path = game.backups_path().join(MARKER_FILE);
+        std::fs::rename(&downloaded, &path)
+            .context_with(|| format!("Could not write {}", path.display()))?;
+    } else {
+        let _ = std::fs::remove_file(&downloaded);
+    }
+    Ok(())
+}
+
+/// Copies the backups to another machine through SSH.
+#[derive(Debug)]
+struct Rsync {
+    remote: Option<String>,
+    ssh: Option<String>,
+}
+
+impl Rsync {
+    fn remote(&self, game: &Game) -> Result<String> {
+        let Some(remote) = game.cloud_remote(self.remote.as_deref()) else {
+            bail!(
+                "The rsync backend needs a remote, set it in the config or the game {:?}",
+                game.name()
+            )
+        };
+        Ok(remote)
+    }
+
+    /// Runs `cmd` through SSH in the host of the remote, on the file called `name` of its directory,
+    /// or the directory itself if `None`.
+    fn run_remote(
+        &self,
+        games: &Games,
+        game: &Game,
+        cmd: &str,
+        name: Option<&str>,
+        desc: &str,
+    ) -> Result<()> {
+        let remote = self.remote(game)?;
+        let Some((host, path)) = remote
+            .split_once(':')
+            .filter(|(host, _)| !host.is_empty() && !host.starts_with('-'))
+        else {
+            bail!("The rsync remote {remote:?} must have the form \"user@host:path\"")
+        };
+        let path = match name {
+            Some(name) => format!("{}/{name}", path.trim_end_matches('/')),
+            None => path.to_owned(),
+        };
+        // Quoted once for the remote shell, and the whole command again for the local one
+        let remote_cmd = format!("{cmd} -- {}", shell_quote(&path));
+        let ssh = self.ssh.as_deref().unwrap_or("ssh");
+        let local_cmd = format!("{ssh} {} {}", shell_quote(host), shell_quote(&remote_cmd));
+        let cmd = games.commands_to_process(&[local_cmd], None)?;
+        games.execute(cmd, desc, game.root())
+    }
+
+    /// Base rsync invocation, connecting with the configured SSH command.
+    fn command(&self) -> std::process::Command {
+        let mut cmd = std::process::Command::new("rsync");
+        cmd.args(["--archive", "--progress"]);
+        if let Some(ssh) = &self.ssh {
+            cmd.args(["--rsh", ssh]);
+        }
+        cmd
+    }
+}
+
+impl CloudBackend for Rsync {
+    fn name(&self) -> &str {
+        "rsync"
+    }
+
+    fn runs_commands(&self) -> bool {
+        true
+    }
+
+    fn init(&self, games: &Games, game: &Game) -> Result<()> {
+        self.run_remote(games, game, "mkdir -p", None, "cloud init")
+    }
+
+    fn push(&self, games: &Games, game: &Game, _: Option<&Path>) -> Result<()> {
+        let mut cmd = self.command();
+        cmd.args(["--exclude", ".*"])
+            .arg(dir_contents(&game.backups_path()))
+            .arg(self.remote(game)?);
+        games.execute(Some(cmd), "cloud push", game.root())
+    }
+
+    fn pull(&self, games: &Games, game: &Game) -> Result<()> {
+        let remote = self.remote(game)?;
+        let mut cmd = self.command();
+        cmd.arg("--ignore-existing")
+            .arg(format!("{}/", remote.trim_end_matches('/')))
+            .arg(game.backups_path());
+        games.execute(Some(cmd), "cloud pull", game.root())?;
+        pull_changed(self, games, game)
+    }
+
+    fn list(&self, games: &Games, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        let remote = self.remote(game)?;
+        let mut cmd = self.command();
+        cmd.arg("--list-only")
+            .arg(format!("{}/", remote.trim_end_matches('/')));
+        let out = games.output(cmd, "cloud list", game.root())?;
+        Ok(Some(parse_rsync_list(&String::from_utf8_lossy(&out))))
+    }
+
+    fn copies_single_files(&self) -> bool {
+        true
+    }
+
+    fn upload(&self, games: &Games, game: &Game, path: &Path) -> Result<()> {
+        let remote = self.remote(game)?;
+        let mut cmd = self.command();
+        cmd.arg(path)
+            .arg(format!("{}/", remote.trim_end_matches('/')));
+        games.execute(Some(cmd), "cloud upload", game.root())
+    }
+
+    fn download(&self, games: &Games, game: &Game, name: &str, dir: &Path) -> Result<()> {
+        let remote = self.remote(game)?;
+        let mut cmd = self.command();
+        cmd.arg(format!("{}/{name}", remote.trim_end_matches('/')))
+            .arg(dir_contents(dir));
+        games.execute(Some(cmd), "cloud download", game.root())
+    }
+
+    fn remove(&self, games: &Games, game: &Game, name: &str) -> Result<()> {
+        self.run_remote(games, game, "rm -f", Some(name), "cloud remove")
+    }
+}
+
+/// Uploads the backups to a WebDAV server.
+#[cfg(feature = "webdav")]
+#[derive(Debug)]
+struct Webdav {
+    remote: Option<String>,
+    username: Option<String>,
+    password: Option<crate::secrets::Secret>,
+}
+
+#[cfg(feature = "webdav")]
+impl Webdav {
+    fn client(&self, games: &Games, game: &Game) -> Result<crate::webdav::Client> {
+        let Some(url) = game.cloud_remote(self.remote.as_deref()) else {
+            bail!(
+                "The WebDAV backend needs a remote URL, set it in the config or the game {:?}",
+                game.name()
+            )
+        };
+        let password = match &self.password {
+            Some(secret) => Some(secret.reveal(&games.game_config(game)?.shell)?),
+            None => std::env::var("GG_WEBDAV_PASSWORD").ok(),
+        };
+        Ok(crate::webdav::Client::new(
+            &url,
+            self.username.as_deref(),
+            password.as_deref(),
+        ))
+    }
+}
+
+#[cfg(feature = "webdav")]
+impl CloudBackend for Webdav {
+    fn name(&self) -> &str {
+        "WebDAV"
+    }
+
+    fn init(&self, games: &Games, game: &Game) -> Result<()> {
+        self.client(games, game)?.init()
+    }
+
+    fn push(&self, games: &Games, game: &Game, _: Option<&Path>) -> Result<()> {
+        let client = self.client(games, game)?;
+        crate::webdav::push(&client, &game.backups()?)?;
+        // The marker changes with every backup, so it is always uploaded
+        let marker = game.backups_path().join(crate::games::MARKER_FILE);
+        if marker.exists() {
+            client.upload(&marker)?;
+        }
+        Ok(())
+    }
+
+    fn pull(&self, games: &Games, game: &Game) -> Result<()> {
+        crate::webdav::pull(
+            &self.client(games, game)?,
+            &game.backups_path(),
+            &[crate::games::MARKER_FILE],
+        )
+    }
+
+    fn list(&self, games: &Games, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        Ok(Some(
+            self.client(games, game)?.list()?.into_iter().collect(),
+        ))
+    }
+
+    fn copies_single_files(&self) -> bool {
+        true
+    }
+
+    fn upload(&self, games: &Games, game: &Game, path: &Path) -> Result<()> {
+        self.client(games, game)?.upload(path)
+    }
+
+    fn download(&self, games: &Games, game: &Game, name: &str, dir: &Path) -> Result<()> {
+        self.client(games, game)?.download(name, dir)
+    }
+
+    fn remove(&self, games: &Games, game: &Game, name: &str) -> Result<()> {
+        self.client(games, game)?.delete(name)
+    }
+}
+
+/// Leaves the backups directory to Syncthing, which shares it on its own.
+#[derive(Debug)]
+struct Syncthing;
+
+impl CloudBackend for Syncthing {
+    fn name(&self) -> &str {
+        "Syncthing"
+    }
+
+    fn init(&self, _: &Games, game: &Game) -> Result<()> {
+        crate::syncthing::init(&game.backups_path())
+    }
+
+    /// Syncthing shares the new backups on its own.
+    fn push(&self, _: &Games, _: &Game, _: Option<&Path>) -> Result<()> {
+        Ok(())
+    }
+
+    fn pull(&self, _: &Games, game: &Game) -> Result<()> {
+        let arrived = game.index_synced_backups()?;
+        if !arrived.is_empty() {
+            crate::output::progress(format_args!(
+                "{} backups of {} arrived through Syncthing",
+                arrived.len(),
+                game.name()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Syncthing propagates the local removal.
+    fn remove(&self, _: &Games, _: &Game, _: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Entry of the output of 'rclone lsjson'.
+#[derive(serde::Deserialize)]
+struct RcloneFile {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Size")]
+    size: u64,
+}
+
+/// Parses the files listed by 'rsync --list-only', with lines like
+/// "-rw-r--r--          1,234 2024/01/01 12:00:00 name".
+fn parse_rsync_list(out: &str) -> BTreeMap<String, u64> {
+    out.lines()
+        .filter(|line| line.starts_with('-'))
+        .filter_map(|line| {
+            let mut rest = line;
+            let mut fields = [""; 4];
+            for field in &mut fields {
+                rest = rest.trim_start();
+                let end = rest.find(' ')?;
+                *field = &rest[..end];
+                rest = &rest[end..];
+            }
+            let name = rest.strip_prefix(' ')?;
+            Some((name.to_owned(), fields[1].replace(',', "").parse().ok()?))
+        })
+        .collect()
+}
+
+/// `dir` with a trailing slash, so rsync copies its contents instead of the directory itself.
+fn dir_contents(dir: &Path) -> std::ffi::OsString {
+    let mut dir = dir.as_os_str().to_owned();
+    dir.push("/");
+    dir
+}
+
+#[cfg(not(feature = "git"))]
+const GIT_FEATURE_MISSING: &str =
+    "The git backend is configured, but gg was built without the \"git\" feature";
+#[cfg(not(feature = "webdav"))]
+const WEBDAV_FEATURE_MISSING: &str =
+    "The WebDAV backend is configured, but gg was built without the \"webdav\" feature";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::{BackupMarker, MARKER_FILE};
+    use std::path::PathBuf;
+
+    /// Storage in a local directory.
+    #[derive(Debug)]
+    struct Folder(PathBuf);
+
+    impl CloudBackend for Folder {
+        fn name(&self) -> &str {
+            "folder"
+        }
+
+        fn init(&self, _: &Games, _: &Game) -> Result<()> {
+            Ok(())
+        }
+
+        fn push(&self, _: &Games, _: &Game, _: Option<&Path>) -> Result<()> {
+            Ok(())
+        }
+
+        fn pull(&self, _: &Games, _: &Game) -> Result<()> {
+            Ok(())
+        }
+
+        fn list(&self, _: &Games, _: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+            let files = std::fs::read_dir(&self.0)?
+                .flatten()
+                .map(|e| {
+                    let name = e.file_name().to_string_lossy().into_owned();
+                    (name, e.metadata().unwrap().len())
+                })
+                .collect();
+            Ok(Some(files))
+        }
+
+        fn copies_single_files(&self) -> bool {
+            true
+        }
+
+        fn download(&self, _: &Games, _: &Game, name: &str, dir: &Path) -> Result<()> {
+            std::fs::copy(self.0.join(name), dir.join(name))?;
+            Ok(())
+        }
+    }
+
+    fn marker(time: u64, backup: &str) -> BackupMarker {
+        BackupMarker {
+            machine: String::from("machine"),
+            host: String::from("host"),
+            time,
+            backup: backup.to_owned(),
+        }
+    }
+
+    fn write_marker(dir: &Path, marker: &BackupMarker) {
+        let contents = serde_saphyr::to_string(marker).unwrap();
+        std::fs::write(dir.join(MARKER_FILE), contents).unwrap();
+    }
+
+    #[test]
+    fn pulls_the_newer_marker_and_the_changed_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote");
+        std::fs::create_dir(&remote).unwrap();
+        let games = Games::builder().in_memory().load().unwrap();
+        let game = Game::new(
+            String::from("Celeste"),
+            dir.path().join("game"),
+            dir.path().join("game/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        );
+        let backups = game.backups_path();
+        std::fs::create_dir_all(&backups).unwrap();
+        let folder = Folder(remote.clone());
+
+        // Without a local marker, the one of the cloud is taken
+        write_marker(&remote, &marker(100, "Celeste-001.tar.zst"));
+        pull_changed(&folder, &games, &game).unwrap();
+        assert_eq!(game.marker(), Some(marker(100, "Celeste-001.tar.zst")));
+
+        // A newer local marker, like one of a backup made offline, is kept
+        write_marker(&backups, &marker(200, "Celeste-002.tar.zst"));
+        pull_changed(&folder, &games, &game).unwrap();
+        assert_eq!(game.marker(), Some(marker(200, "Celeste-002.tar.zst")));
+
+        write_marker(&remote, &marker(300, "Celeste-003.tar.zst"));
+        pull_changed(&folder, &games, &game).unwrap();
+        assert_eq!(game.marker(), Some(marker(300, "Celeste-003.tar.zst")));
+        assert!(
+            !backups
+                .join(crate::games::CLOUD_TEMP_DIR)
+                .join(MARKER_FILE)
+                .exists()
+        );
+
+        // A backup that differs from the local one is downloaded besides it
+        std::fs::write(backups.join("Celeste-003.tar.zst"), "local").unwrap();
+        std::fs::write(remote.join("Celeste-003.tar.zst"), "from the cloud").unwrap();
+        pull_changed(&folder, &games, &game).unwrap();
+        let names = game
+            .backups()
+            .unwrap()
+            .into_iter()
+            .map(|b| b.name().into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Celeste-003.tar.zst", "Celeste-004.tar.zst"]);
+        let downloaded = std::fs::read_to_string(backups.join("Celeste-004.tar.zst")).unwrap();
+        assert_eq!(downloaded, "from the cloud");
+    }
+}
@@ -0,0 +1,182 @@
+use rootcause::Result;
+use rootcause::option_ext::OptionExt;
+use rootcause::prelude::*;
+use std::{
+    io::{Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+    thread::JoinHandle,
+};
+
+/// Extension of the archives compressed with the built-in zstd.
+pub const ZSTD_EXTENSION: &str = "zst";
+/// Level of the built-in zstd of the new backups.
+pub const ZSTD_LEVEL: i32 = 9;
+
+/// Writer that compresses everything written to it into `W`.
+pub enum Compressor<W: Write> {
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+    /// The data is piped through a command, whose output is copied to `W` by another thread.
+    Command {
+        child: Child,
+        stdin: ChildStdin,
+        output: JoinHandle<std::io::Result<W>>,
+    },
+    /// Written as is, for the archive formats that compress by themselves.
+    Stored(W),
+}
+
+impl<W: Write + Send + 'static> Compressor<W> {
+    pub fn zstd(output: W) -> Result<Self> {
+        Self::zstd_level(output, ZSTD_LEVEL)
+    }
+
+    /// Compresses with zstd at `level`, from 1 to 22, higher being smaller and slower.
+    #[cfg(feature = "zstd")]
+    pub fn zstd_level(output: W, level: i32) -> Result<Self> {
+        Ok(Self::Zstd(
+            zstd::Encoder::new(output, level).context("Could not start compression")?,
+        ))
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    pub fn zstd_level(_output: W, _level: i32) -> Result<Self> {
+        bail!("gg was built without zstd, configure the commands that compress the backups")
+    }
+
+    /// Compresses through `cmd`, which must read from stdin and write to stdout.
+    pub fn command(mut cmd: Command, mut output: W) -> Result<Self> {
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("Could not start the compression command")?;
+        let stdin = child.stdin.take().ok_or_report()?;
+        let mut stdout = child.stdout.take().ok_or_report()?;
+        let output = std::thread::spawn(move || {
+            std::io::copy(&mut stdout, &mut output)?;
+            Ok(output)
+        });
+        Ok(Self::Command {
+            child,
+            stdin,
+            output,
+        })
+    }
+
+    /// Flushes the remaining data and returns the output.
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => w.finish(),
+            Self::Stored(w) => Ok(w),
+            Self::Command {
+                mut child,
+                stdin,
+                output,
+            } => {
+                // Closing stdin lets the command know there is nothing more to compress
+                drop(stdin);
+                let output = output
+                    .join()
+                    .map_err(|_| std::io::Error::other("The compression thread panicked"))??;
+                let status = child.wait()?;
+                if !status.success() {
+                    return Err(std::io::Error::other(format!(
+                        "The compression command exited with {status}"
+                    )));
+                }
+                Ok(output)
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Compressor<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => w.write(buf),
+            Self::Stored(w) => w.write(buf),
+            Self::Command { child, stdin, .. } => {
+                stdin.write(buf).map_err(|e| match child.try_wait() {
+                    Ok(Some(status)) => std::io::Error::other(format!(
+                        "The compression command exited with {status}"
+                    )),
+                    _ => e,
+                })
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            #[cfg(feature = "zstd")]
+            Self::Zstd(w) => w.flush(),
+            Self::Stored(w) => w.flush(),
+            Self::Command { stdin, .. } => stdin.flush(),
+        }
+    }
+}
+
+/// Returns a reader with the decompressed contents of `input`.
+///
+/// `cmd` is used to decompress it if given, zstd otherwise.
+pub fn decompress(
+    input: impl Read + Send + 'static,
+    cmd: Option<Command>,
+) -> Result<Box<dyn Read>> {
+    let Some(mut cmd) = cmd else {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(
+            zstd::Decoder::new(input).context("Could not start decompression")?,
+        ));
+        #[cfg(not(feature = "zstd"))]
+        bail!("gg was built without zstd, configure the commands that decompress the backups")
+    };
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Could not start the decompression command")?;
+    let mut stdin = child.stdin.take().ok_or_report()?;
+    let stdout = child.stdout.take().ok_or_report()?;
+    let mut input = input;
+    // The input is fed from another thread, as the command may not read it all before writing
+    let feeder = std::thread::spawn(move || std::io::copy(&mut input, &mut stdin).map(|_| ()));
+    Ok(Box::new(CommandReader {
+        child,
+        stdout,
+        feeder: Some(feeder),
+    }))
+}
+
+/// Reads the output of a decompression command, checking that it succeeded at the end.
+struct CommandReader {
+    child: Child,
+    stdout: ChildStdout,
+    feeder: Option<JoinHandle<std::io::Result<()>>>,
+}
+
+impl Read for CommandReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.stdout.read(buf)?;
+        if read == 0
+            && !buf.is_empty()
+            && let Some(feeder) = self.feeder.take()
+        {
+            // Checked first, as a failed command also makes the feeder fail with a broken pipe
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(std::io::Error::other(format!(
+                    "The decompression command exited with {status}"
+                )));
+            }
+            feeder
+                .join()
+                .map_err(|_| std::io::Error::other("The decompression thread panicked"))??;
+        }
+        Ok(read)
+    }
+}
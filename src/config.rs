@@ -1,37 +1,1120 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
+use rootcause::Result;
+use rootcause::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+use crate::cloud::CloudMode;
+use crate::events::Hook;
+use crate::units::{Duration, Size};
+
+/// Directory of the system config file.
+const CONFIG_DIR: &str = if cfg!(target_os = "macos") {
+    "/Library/Application Support/goodgame"
+} else {
+    "/etc/goodgame"
+};
+/// Commented starter config, written by 'gg config init'.
+pub const TEMPLATE: &str = include_str!("../config.yaml");
+/// Version of the layout of the config files, increased with every change that needs a migration.
+const CONFIG_VERSION: u32 = 1;
+
+/// Names of the config file in a config directory, the first existing one being read.
+///
+/// JSON is a subset of YAML, so the JSON files are read by the same parser.
+#[cfg(not(feature = "toml"))]
+const CONFIG_FILES: &[&str] = &["config.yaml", "config.yml", "config.json"];
+#[cfg(feature = "toml")]
+const CONFIG_FILES: &[&str] = &["config.yaml", "config.yml", "config.json", "config.toml"];
+
+#[derive(Debug, Default, Deserialize, Serialize, JsonSchema)]
 pub struct Config {
-    pub shell: String,
+    /// Version of the layout of the config, the current one if not set.
+    pub version: Option<u32>,
+    #[serde(default)]
+    pub shell: Shell,
+    /// Command that opens the files of 'gg edit' and 'gg config edit', falls back to $VISUAL,
+    /// $EDITOR and vi.
+    ///
+    /// Can have arguments, like "code --wait".
+    pub editor: Option<String>,
+    /// Command that opens the directories of 'gg open', falls back to xdg-open, or open on macOS.
+    ///
+    /// Can have arguments, and runs in the terminal until it exits, so terminal file managers
+    /// like "ranger" or "yazi" work.
+    pub opener: Option<String>,
+    #[serde(default)]
     pub run: Run,
+    #[serde(default)]
     pub backup: Backup,
+    #[serde(default)]
+    pub watch: Watch,
+    #[serde(default)]
+    pub gc: Gc,
+    #[serde(default)]
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub events: Events,
+    #[serde(default)]
+    pub log: Log,
+    #[serde(default)]
+    pub notifications: Notifications,
+    #[serde(default)]
+    pub metadata: Metadata,
+    /// URLs that receive an HTTP POST with a JSON payload for every backup, restore, run and
+    /// pruned backup of a game, including the failed ones.
+    ///
+    /// Requires the "webhooks" feature.
+    #[serde(default)]
+    pub webhooks: Vec<String>,
+    /// Ask before removing a game or replacing its save with a backup, unless given --yes.
+    #[serde(default, rename = "confirmDestructiveOps")]
+    pub confirm_destructive_ops: bool,
+    /// Names of the sections in `profiles`, each one merged over the rest of the config on the
+    /// machine with that hostname, or with that GG_PROFILE.
+    #[serde(skip)]
+    pub profiles: Vec<String>,
+    /// Name of the profile used in this machine, if any.
+    #[serde(skip)]
+    pub profile: Option<String>,
+    /// Effective config of the games with a section in `games`, keyed by their name.
+    ///
+    /// Each section is merged over the rest of the config, like a config file of its own.
+    #[serde(skip)]
+    pub games: BTreeMap<String, Config>,
+    /// Every value set by the config files, with the file it comes from.
+    #[serde(skip)]
+    pub settings: Vec<Setting>,
+}
+
+impl Config {
+    /// Reads the system config file and the one in the user config directory, the
+    /// values of the user taking precedence, using the defaults for the missing ones.
+    pub fn load() -> Result<Self> {
+        Self::merge_files(&Self::paths(), None)
+    }
+
+    /// Reads only the config file at `path`, using the defaults for the missing values.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        Self::merge_files(&[path.to_path_buf()], None)
+    }
+
+    /// Checks `contents` as the new contents of the config file at `path`, returning the config
+    /// that would be loaded with it.
+    ///
+    /// The file is parsed on its own first, so the errors point to its lines.
+    pub fn check(path: &Path, contents: &str) -> Result<Self> {
+        if parse_layer(path, contents)?.is_some() {
+            let checked = if is_toml(path) {
+                parse_toml::<Self>(contents).map(drop)
+            } else {
+                serde_saphyr::from_str_with_options::<Self>(contents, yaml_options())
+                    .map(drop)
+                    .map_err(Report::from)
+            };
+            checked.context_with(|| format!("Invalid config file {}", path.display()))?;
+        }
+        Self::merge_files(&Self::paths(), Some((path, contents)))
+    }
+
+    /// Merges the config files at `paths`, reading `replaced.1` instead of the file at
+    /// `replaced.0`.
+    fn merge_files(paths: &[PathBuf], replaced: Option<(&Path, &str)>) -> Result<Self> {
+        let mut merged = Value::Map(BTreeMap::new());
+        let mut sources = BTreeMap::new();
+        for path in paths {
+            let layer = match replaced {
+                Some((replaced, contents)) if replaced == path => parse_layer(path, contents)?,
+                _ => read_layer(path)?,
+            };
+            let Some(layer) = layer else {
+                continue;
+            };
+            merged.merge(layer, path, "", &mut sources);
+        }
+        if sources.is_empty() {
+            return Ok(Self::default());
+        }
+        let profiles = match &mut merged {
+            Value::Map(map) => map.remove("profiles"),
+            _ => None,
+        };
+        let profiles = match profiles {
+            None | Some(Value::Null) => BTreeMap::new(),
+            Some(Value::Map(profiles)) => profiles,
+            Some(_) => {
+                bail!("The profiles section of the config must map machine names to their config")
+            }
+        };
+        let name = std::env::var("GG_PROFILE").unwrap_or_else(|_| crate::games::hostname());
+        let profile = profiles.get(&name).map(|_| name);
+        let profile_names = profiles.keys().cloned().collect();
+        if let Some(section) = profile.as_ref().and_then(|name| profiles.get(name)) {
+            merged.merge(section.clone(), Path::new(""), "", &mut BTreeMap::new());
+        }
+        merged.expand_env();
+        let overrides = match &mut merged {
+            Value::Map(map) => map.remove("games"),
+            _ => None,
+        };
+        let mut config = Self::from_value(&merged, paths)?;
+        match overrides {
+            None | Some(Value::Null) => {}
+            Some(Value::Map(overrides)) => {
+                for (name, section) in overrides {
+                    let mut game = merged.clone();
+                    game.merge(section, Path::new(""), "", &mut BTreeMap::new());
+                    let game = Self::from_value(&game, paths)
+                        .context_with(|| format!("Invalid config of the game {name:?}"))?;
+                    config.games.insert(name, game);
+                }
+            }
+            Some(_) => bail!("The games section of the config must map game names to their config"),
+        }
+        config.profiles = profile_names;
+        config.profile = profile;
+        config.settings = sources
+            .into_iter()
+            .map(|(key, (value, file))| Setting { key, value, file })
+            .collect();
+        Ok(config)
+    }
+
+    fn from_value(value: &Value, paths: &[PathBuf]) -> Result<Self> {
+        let yaml = serde_saphyr::to_string(value).context("Could not merge the config files")?;
+        Ok(
+            serde_saphyr::from_str_with_options(&yaml, yaml_options()).context_with(|| {
+                format!(
+                    "Invalid config in {}, use --ignore-config to skip it",
+                    paths
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(" or ")
+                )
+            })?,
+        )
+    }
+
+    /// JSON Schema of the config files, for editors to validate and complete them.
+    pub fn schema() -> schemars::Schema {
+        let mut schema = schemars::schema_for!(Config);
+        // The games sections are merged by hand, so serde doesn't know them
+        if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+            properties.insert(
+                String::from("games"),
+                serde_json::json!({
+                    "description": "Settings of single games by name, merged over the rest of the config",
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#" },
+                }),
+            );
+            properties.insert(
+                String::from("profiles"),
+                serde_json::json!({
+                    "description": "Settings of single machines by hostname or GG_PROFILE, merged over the rest of the config",
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#" },
+                }),
+            );
+        }
+        schema
+    }
+
+    /// Returns the values set by the config files that gg doesn't use, like misspelled keys.
+    pub fn unknown_keys(&self) -> Result<Vec<&Setting>> {
+        let mut schema = Self::default();
+        // The backend and the permissions have no keys unless set
+        schema.backup.backend = self
+            .backup
+            .backend
+            .as_ref()
+            .map(|backend| Backend::from_type(backend.kind()))
+            .transpose()?;
+        schema.backup.permissions = Some(Permissions {
+            group: None,
+            mode: None,
+        });
+        let schema = serde_saphyr::to_string(&schema).context("Could not serialize the config")?;
+        let schema: Value =
+            serde_saphyr::from_str(&schema).context("Could not parse the serialized config")?;
+        // The keys of the profiles and games sections are checked without their "profiles.NAME."
+        // and "games.NAME." prefixes, the names can have dots
+        let games = self.games.keys().cloned().collect::<Vec<_>>();
+        let key = |setting: &'_ Setting| -> Option<String> {
+            let mut key = setting.key.as_str();
+            for (section, names) in [("profiles.", &self.profiles), ("games.", &games)] {
+                let Some(rest) = key.strip_prefix(section) else {
+                    continue;
+                };
+                key = names
+                    .iter()
+                    .find_map(|name| rest.strip_prefix(name.as_str())?.strip_prefix('.'))
+                    .or_else(|| Some(rest.split_once('.')?.1))?;
+            }
+            Some(key.to_owned())
+        };
+        Ok(self
+            .settings
+            .iter()
+            .filter(|setting| key(setting).is_none_or(|key| !schema.knows(&key)))
+            .collect())
+    }
+
+    /// The config files, from the lowest to the highest precedence.
+    pub fn paths() -> Vec<PathBuf> {
+        let user = crate::paths::config_dir();
+        std::iter::once(PathBuf::from(CONFIG_DIR))
+            .chain(user)
+            .map(|dir| config_file(&dir))
+            .collect()
+    }
+}
+
+/// Returns the config file of `dir`, config.yaml if there is none.
+fn config_file(dir: &Path) -> PathBuf {
+    CONFIG_FILES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(CONFIG_FILES[0]))
+}
+
+/// Keys of the config whose values are commands, or the programs that run them, left for the
+/// shell to expand their variables.
+const COMMAND_KEYS: [&str; 19] = [
+    "commands",
+    "cloudInitCommands",
+    "cloudCommitCommands",
+    "cloudPushCommands",
+    "cloudPullCommands",
+    "cloudRenameCommands",
+    "preRun",
+    "postRun",
+    "preBackup",
+    "postBackup",
+    "preRestore",
+    "postRestore",
+    "compress",
+    "decompress",
+    "command",
+    "editor",
+    "opener",
+    "shell",
+    "ssh",
+];
+
+/// Replaces the environment variables in `value`, like `$HOME` or `${XDG_DATA_HOME}`, with their
+/// values.
+///
+/// Variables that aren't set are kept as they are, so the commands can still use the ones of the
+/// shell (e.g. `$1` or the variables of a loop).
+pub fn expand_env(value: &str) -> String {
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after.find(|c| !is_name(c)).unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(is_name);
+        match std::env::var(name) {
+            Ok(var) if valid => expanded.push_str(&var),
+            _ => expanded.push_str(&rest[i..=i + len]),
+        }
+        rest = &rest[i + 1 + len..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Shell that runs the commands of the config, which are given to it as its last argument.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Shell {
+    /// Name or path of a shell that takes the commands after "-c", like "bash".
+    Name(String),
+    /// Program and arguments that take the commands after them, like ["pwsh", "-Command"].
+    Args(Vec<String>),
+}
+
+impl Shell {
+    /// Name or path of the program of the shell.
+    pub fn program(&self) -> &str {
+        match self {
+            Shell::Name(name) => name,
+            Shell::Args(args) => args.first().map_or("", String::as_str),
+        }
+    }
+
+    /// Arguments that run `cmds` with the shell, starting with its program.
+    pub fn args(&self, cmds: String) -> Result<Vec<String>> {
+        if self.program().trim().is_empty() {
+            bail!("The shell of the config is empty, set it to a shell like \"sh\" or remove it")
+        }
+        Ok(match self {
+            Shell::Name(name) => vec![name.clone(), String::from("-c"), cmds],
+            Shell::Args(args) => args.iter().cloned().chain([cmds]).collect(),
+        })
+    }
 }
 
-impl Default for Config {
+impl Default for Shell {
+    /// The shell of the user if it understands the POSIX syntax, sh otherwise.
+    #[cfg(unix)]
     fn default() -> Self {
-        Self {
-            shell: String::from("bash"),
-            run: Default::default(),
-            backup: Default::default(),
+        const POSIX_SHELLS: [&str; 5] = ["sh", "bash", "dash", "ksh", "zsh"];
+        let user = std::env::var("SHELL").ok().filter(|shell| {
+            Path::new(shell)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| POSIX_SHELLS.contains(&name))
+        });
+        Shell::Name(user.unwrap_or_else(|| String::from("sh")))
+    }
+
+    #[cfg(windows)]
+    fn default() -> Self {
+        Shell::Args(vec![String::from("powershell"), String::from("-Command")])
+    }
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shell::Name(name) => write!(f, "{name:?}"),
+            Shell::Args(args) => write!(f, "{args:?}"),
+        }
+    }
+}
+
+/// A command of the config, as written in the file.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum Command {
+    /// Command line run by the shell.
+    Line(String),
+    /// Program and arguments, quoted for the shell.
+    Args(Vec<String>),
+}
+
+/// Reads a list of commands, where a command can also be a list of arguments, quoted for the
+/// shell so they can contain spaces (e.g. ["cp", "@BACKUP-FILE", "/mnt/My Backups"]).
+fn commands<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Vec<String>, D::Error> {
+    Ok(Vec::<Command>::deserialize(d)?
+        .into_iter()
+        .map(|command| match command {
+            Command::Line(line) => line,
+            Command::Args(args) => args
+                .iter()
+                .map(|arg| crate::runner::shell_quote(arg))
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+        .collect())
+}
+
+/// A value set by a config file.
+#[derive(Debug, Serialize)]
+pub struct Setting {
+    /// Path of the value in the config, like "backup.retries".
+    pub key: String,
+    pub value: String,
+    /// Config file that sets it.
+    pub file: PathBuf,
+}
+
+/// Reads the config file at `path`, or returns `None` if it doesn't exist.
+fn read_layer(path: &Path) -> Result<Option<Value>> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).context_with(|| {
+                format!(
+                    "Could not read config file {}, use --ignore-config to skip it",
+                    path.display()
+                )
+            })?;
+        }
+    };
+    parse_layer(path, &contents)
+}
+
+/// Options of the config parser.
+///
+/// Only `true` and `false` are booleans, otherwise values like `cloud: off` would be read as
+/// `false` before knowing they are a cloud mode.
+pub(crate) fn yaml_options() -> serde_saphyr::Options {
+    serde_saphyr::Options {
+        strict_booleans: true,
+        ..Default::default()
+    }
+}
+
+/// Parses the `contents` of the config file at `path`, or returns `None` if it sets nothing.
+fn parse_layer(path: &Path, contents: &str) -> Result<Option<Value>> {
+    if contents.trim().is_empty() {
+        return Ok(None);
+    }
+    let layer = if is_toml(path) {
+        parse_toml(contents)
+    } else {
+        serde_saphyr::from_str_with_options(contents, yaml_options()).map_err(Report::from)
+    };
+    let layer = layer.context_with(|| {
+        format!(
+            "Invalid config file {}, use --ignore-config to skip it",
+            path.display()
+        )
+    })?;
+    if let Value::Map(map) = &layer
+        && let Some(Value::Int(version)) = map.get("version")
+        && *version > i64::from(CONFIG_VERSION)
+    {
+        bail!(
+            "The config file {} is version {version}, which is newer than this gg supports, update gg to use it",
+            path.display()
+        )
+    }
+    // A file with only comments
+    Ok(Some(layer).filter(|layer| !matches!(layer, Value::Null)))
+}
+
+/// Whether the config file at `path` is written in TOML instead of YAML, told by its extension.
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// Parses a config file written in TOML.
+#[cfg(feature = "toml")]
+fn parse_toml<T: serde::de::DeserializeOwned>(contents: &str) -> Result<T> {
+    Ok(toml::from_str(contents)?)
+}
+
+#[cfg(not(feature = "toml"))]
+fn parse_toml<T>(_: &str) -> Result<T> {
+    bail!("The config file is written in TOML, but gg was built without the \"toml\" feature")
+}
+
+/// Contents of a config file, merged key by key with the other ones.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Seq(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Whether `key`, like "backup.retries", is in this schema or inside one of its free-form values,
+    /// like the environment of the run commands.
+    fn knows(&self, key: &str) -> bool {
+        let mut value = self;
+        for name in key.split('.') {
+            match value {
+                Value::Map(map) if !map.is_empty() => match map.get(name) {
+                    Some(inner) => value = inner,
+                    None => return false,
+                },
+                _ => return true,
+            }
+        }
+        true
+    }
+
+    /// Expands the environment variables of every string, see [`expand_env`], except the ones of
+    /// the [`COMMAND_KEYS`], which the shell expands when they run.
+    fn expand_env(&mut self) {
+        match self {
+            Value::String(s) => *s = expand_env(s),
+            Value::Seq(seq) => seq.iter_mut().for_each(Value::expand_env),
+            Value::Map(map) => map
+                .iter_mut()
+                .filter(|(key, _)| !COMMAND_KEYS.contains(&key.as_str()))
+                .for_each(|(_, value)| value.expand_env()),
+            Value::Null | Value::Bool(_) | Value::Int(_) | Value::Float(_) => {}
+        }
+    }
+
+    /// Merges `other`, read from `file`, over the value at `key`.
+    ///
+    /// Maps are merged key by key, except the ones with a "type" like the backends, which are
+    /// replaced as any other value. The file of every replaced value is recorded in `sources`.
+    fn merge(
+        &mut self,
+        other: Value,
+        file: &Path,
+        key: &str,
+        sources: &mut BTreeMap<String, (String, PathBuf)>,
+    ) {
+        match (self, other) {
+            (Value::Map(map), Value::Map(other)) if !other.contains_key("type") => {
+                Value::merge_map(map, other, file, key, sources)
+            }
+            (this, other) => {
+                // The values nested in the replaced one came from another file
+                let prefix = format!("{key}.");
+                sources.retain(|k, _| !k.starts_with(&prefix));
+                if let Value::Map(other) = other {
+                    let mut map = BTreeMap::new();
+                    Value::merge_map(&mut map, other, file, key, sources);
+                    *this = Value::Map(map);
+                } else {
+                    sources.insert(key.to_owned(), (other.to_string(), file.to_owned()));
+                    *this = other;
+                }
+            }
+        }
+    }
+
+    fn merge_map(
+        map: &mut BTreeMap<String, Value>,
+        other: BTreeMap<String, Value>,
+        file: &Path,
+        key: &str,
+        sources: &mut BTreeMap<String, (String, PathBuf)>,
+    ) {
+        for (name, value) in other {
+            let key = if key.is_empty() {
+                name.clone()
+            } else {
+                format!("{key}.{name}")
+            };
+            map.entry(name)
+                .or_insert(Value::Map(BTreeMap::new()))
+                .merge(value, file, &key, sources);
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    /// Writes the value in the YAML flow style, like `[git push]` or `{type: git}`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => f.write_str("~"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Float(x) => write!(f, "{x}"),
+            Value::String(s) => write!(f, "{s:?}"),
+            Value::Seq(seq) => {
+                f.write_str("[")?;
+                for (i, value) in seq.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_str("]")
+            }
+            Value::Map(map) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                f.write_str("}")
+            }
         }
     }
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
 pub struct Run {
+    #[serde(deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
     pub commands: Vec<String>,
     pub environment: HashMap<String, String>,
+    /// Back up the save after the game closes, unless given --no-backup.
+    #[serde(rename = "backupAfter")]
+    pub backup_after: bool,
+    /// Back up the save right before the game starts, so a crash that corrupts it can be undone,
+    /// also enabled with --pre-backup.
+    #[serde(rename = "backupBefore")]
+    pub backup_before: bool,
+    /// Whether the save is backed up after the game exits with an error, as a crash may have
+    /// corrupted it.
+    #[serde(rename = "crashBackup")]
+    pub crash_backup: CrashBackup,
+    /// Backs up the save every this long while the game runs (e.g. "30m"), so a crash loses less
+    /// progress, also set with --autosave-interval.
+    #[serde(rename = "autosaveInterval")]
+    pub autosave_interval: Option<Duration>,
+    /// Number of those backups kept per game, the older ones being removed as new ones are made.
+    #[serde(rename = "autosaveKeep")]
+    pub autosave_keep: usize,
+}
+
+/// What 'gg run' does with the save of a game that exited with an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrashBackup {
+    /// Backs it up like after a clean exit, with "crash" in the name of the backup.
+    Always,
+    /// Doesn't back it up, keeping the last good backup as the latest one.
+    #[default]
+    OnlyOnCleanExit,
+    /// Asks whether to back it up, which doesn't happen without a terminal.
+    Ask,
+}
+
+impl Default for Run {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            environment: HashMap::new(),
+            backup_after: true,
+            backup_before: false,
+            crash_backup: CrashBackup::default(),
+            autosave_interval: None,
+            autosave_keep: 3,
+        }
+    }
+}
+
+/// Backups made by 'gg watch' when the save of a game changes outside 'gg run'.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Watch {
+    /// Whether 'gg watch' backs up the game, disabled in the section of a game to skip it.
+    pub enabled: bool,
+    /// Time the save must go without changes before it is backed up, so a save being written is
+    /// not caught halfway (e.g. "30s").
+    pub settle: Duration,
+    /// Minimum time between two of these backups of a game, the changes in between being backed
+    /// up once it passes (e.g. "10m").
+    #[serde(rename = "minInterval")]
+    pub min_interval: Duration,
+    /// Number of those backups kept per game, the older ones being removed as new ones are made.
+    pub keep: usize,
+}
+
+impl Default for Watch {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            settle: Duration::from_secs(30),
+            min_interval: Duration::from_secs(10 * 60),
+            keep: 5,
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Default)]
+/// Repacking of the old backups by 'gg gc', recompressing them with a higher zstd level.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Gc {
+    /// Age of the backups that are repacked, the newer ones being left as they are (e.g. "30d").
+    #[serde(rename = "olderThan")]
+    pub older_than: Duration,
+    /// zstd level of the repacked backups, from 1 to 22, higher being smaller and slower.
+    pub level: u8,
+    /// When 'gg watch' repacks the backups of the game: "hourly", "daily", "weekly", "monthly" or
+    /// a cron expression in local time, like "0 4 * * 0".
+    pub schedule: Option<crate::schedule::Schedule>,
+}
+
+impl Default for Gc {
+    fn default() -> Self {
+        Self {
+            older_than: Duration::from_secs(30 * 24 * 60 * 60),
+            level: 19,
+            schedule: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
 #[serde(default)]
 pub struct Backup {
-    #[serde(rename(deserialize = "cloudInitCommands"))]
+    #[serde(rename = "cloudInitCommands", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
     pub cloud_init_commands: Vec<String>,
-    #[serde(rename(deserialize = "cloudCommitCommands"))]
+    /// Commands that record the backups of a game after creating one.
+    ///
+    /// Besides the variables of the run commands, they can use @BACKUP-FILE, the path of the
+    /// backup just created (an empty string when there is none, like in 'gg sync'), and @MESSAGE,
+    /// the quoted commit message ('gg backup --message' or a default with the backup details).
+    #[serde(rename = "cloudCommitCommands", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
     pub cloud_commit_commands: Vec<String>,
-    #[serde(rename(deserialize = "cloudPushCommands"))]
+    /// Commands that upload the backups of a game, with the same variables as the commit ones.
+    #[serde(rename = "cloudPushCommands", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
     pub cloud_push_commands: Vec<String>,
+    /// Upload only the backup just created and the marker of the last backup, instead of every
+    /// backup missing in the cloud.
+    ///
+    /// Used by the rclone, rsync and WebDAV backends, the cloud commands can use @BACKUP-FILE.
+    #[serde(rename = "uploadNewOnly")]
+    pub upload_new_only: bool,
+    /// Commands that download the backups of a game before restoring.
+    #[serde(rename = "cloudPullCommands", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub cloud_pull_commands: Vec<String>,
+    /// Commands that rename the cloud storage of a game after 'gg rename', like its remote
+    /// repository.
+    ///
+    /// The variables of the run commands use the new name, the old one is @OLD-NAME and
+    /// @OLD-NAME-SLUG.
+    #[serde(rename = "cloudRenameCommands", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub cloud_rename_commands: Vec<String>,
+    /// Encrypt new backups with a passphrase.
+    pub encrypt: bool,
+    /// External compression of the backups of the games without their own one.
+    pub compression: Option<crate::games::Compression>,
+    /// Encrypt the backups right before uploading them and decrypt them after downloading them, so
+    /// the cloud never sees their contents.
+    ///
+    /// Works with the rclone, rsync and WebDAV backends.
+    #[serde(rename = "cloudEncryption")]
+    pub cloud_encryption: bool,
+    /// Passphrase of the cloud encryption, falls back to the GG_CLOUD_PASSPHRASE environment
+    /// variable.
+    #[serde(rename = "cloudPassphrase")]
+    pub cloud_passphrase: Option<crate::secrets::Secret>,
+    /// Built-in backend used instead of the cloud commands.
+    pub backend: Option<Backend>,
+    /// Ownership and mode of the backups, to share them between the accounts of the machine.
+    pub permissions: Option<Permissions>,
+    /// Number of games transferred to the cloud at the same time, 4 by default.
+    pub parallelism: Option<usize>,
+    /// Times a failed cloud operation is retried, 2 by default.
+    pub retries: Option<u32>,
+    /// Delay before retrying a failed cloud operation (e.g. "30s" or "1m", plain numbers are
+    /// seconds), doubled after every retry, 5 seconds by default.
+    #[serde(rename = "retryDelay")]
+    pub retry_delay: Option<Duration>,
+    /// When 'gg watch' backs up the games besides when their saves change: "hourly", "daily",
+    /// "weekly", "monthly" or a cron expression in local time, like "0 20 * * *".
+    ///
+    /// A time missed while the machine was off or 'gg watch' was stopped is caught up once it
+    /// runs again.
+    pub schedule: Option<crate::schedule::Schedule>,
+    pub checks: Checks,
+    /// Backups of each game removed by 'gg prune'.
+    pub retention: Retention,
+    /// Prune the backups of the game after backing it up, unless given --no-prune.
+    #[serde(rename = "autoPrune")]
+    pub auto_prune: bool,
+    /// How the backup, restore and run commands use the cloud storage when --cloud is not given:
+    /// "off", "commit-only" or "push" (the default).
+    pub cloud: Option<CloudMode>,
+}
+
+/// Checks of the save made before backing it up, so a corrupted or wiped save is noticed instead
+/// of silently becoming the newest backup.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(default)]
+pub struct Checks {
+    /// Warns when the save location has no files.
+    #[serde(rename = "emptySave")]
+    pub empty_save: bool,
+    /// Warns when files of the save are empty.
+    #[serde(rename = "emptyFiles")]
+    pub empty_files: bool,
+    /// Warns when the save is smaller than the one of the last backup by more than this
+    /// percentage, 0 disables it.
+    #[serde(rename = "maxShrink")]
+    pub max_shrink: u8,
+    /// Refuses to back up a save that fails a check, instead of only warning.
+    pub strict: bool,
+}
+
+impl Default for Checks {
+    fn default() -> Self {
+        Self {
+            empty_save: true,
+            empty_files: true,
+            max_shrink: 50,
+            strict: false,
+        }
+    }
+}
+
+/// Backups removed by 'gg prune', the ones that are neither among the newest `keep` nor younger
+/// than `maxAge`. Nothing is removed while neither is set.
+///
+/// The newest backup and the ones not uploaded yet are always kept.
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[serde(default)]
+pub struct Retention {
+    /// Number of newest backups kept per game, or per player.
+    pub keep: Option<usize>,
+    /// Age of the backups removed, unless they are among the newest `keep` (e.g. "90d").
+    #[serde(rename = "maxAge")]
+    pub max_age: Option<Duration>,
+}
+
+/// Commands run at the moments of the life of a game, in its root and with the variables of the
+/// run commands.
+///
+/// The hooks of the backups and restores can also use @BACKUP-FILE, the backup created or
+/// restored. A failed pre hook stops the operation, a failed post hook is only warned about.
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[serde(default)]
+pub struct Hooks {
+    /// Before starting the game with 'gg run'.
+    #[serde(rename = "preRun", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub pre_run: Vec<String>,
+    /// After the game closes, before backing it up.
+    #[serde(rename = "postRun", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub post_run: Vec<String>,
+    /// Before creating a backup.
+    #[serde(rename = "preBackup", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub pre_backup: Vec<String>,
+    /// After creating a backup, before sending it to the cloud.
+    #[serde(rename = "postBackup", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub post_backup: Vec<String>,
+    /// Before replacing the save with a backup.
+    #[serde(rename = "preRestore", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub pre_restore: Vec<String>,
+    /// After replacing the save with a backup.
+    #[serde(rename = "postRestore", deserialize_with = "commands")]
+    #[schemars(with = "Vec<Command>")]
+    pub post_restore: Vec<String>,
+}
+
+impl Hooks {
+    pub fn commands(&self, hook: Hook) -> &[String] {
+        match hook {
+            Hook::PreRun => &self.pre_run,
+            Hook::PostRun => &self.post_run,
+            Hook::PreBackup => &self.pre_backup,
+            Hook::PostBackup => &self.post_backup,
+            Hook::PreRestore => &self.pre_restore,
+            Hook::PostRestore => &self.post_restore,
+        }
+    }
+}
+
+/// Webhooks notified of the successful backup events, each one a list of URLs that receive an HTTP
+/// POST with a JSON payload (event, action, game, archive, size, duration and success).
+///
+/// Requires the "webhooks" feature.
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[serde(default)]
+pub struct Events {
+    /// Called after creating a backup.
+    #[serde(rename = "onBackupCreated")]
+    pub on_backup_created: Vec<String>,
+    /// Called after restoring a backup.
+    #[serde(rename = "onRestoreDone")]
+    pub on_restore_done: Vec<String>,
+    /// Called after 'gg prune' removes a backup.
+    #[serde(rename = "onPrune")]
+    pub on_prune: Vec<String>,
+}
+
+/// Log of the commands run and of the backups, restores and runs, to debug the failed ones.
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[serde(default)]
+pub struct Log {
+    /// Most detailed messages recorded: "off", "error", "warn", "info" (the default), "debug" or
+    /// "trace". Overridden by the GG_LOG environment variable.
+    pub level: Option<crate::logging::LogLevel>,
+    /// File the messages are appended to, gg.log in the state directory by default, like
+    /// ~/.local/state/goodgame.
+    pub file: Option<PathBuf>,
+    /// Size at which the file is moved aside and started again (e.g. "1M"), 10MiB by default.
+    #[serde(rename = "maxSize")]
+    pub max_size: Option<Size>,
+    /// Number of the files moved aside that are kept, 5 by default, the older ones being removed.
+    pub keep: Option<usize>,
+    /// Compress the files moved aside with zstd, when gg is built with it. True by default.
+    pub compress: Option<bool>,
+}
+
+/// Desktop notifications of the results of the backups, restores and uploads, useful after
+/// closing the terminal of 'gg run'.
+///
+/// Requires the "notifications" feature.
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[serde(default)]
+pub struct Notifications {
+    pub enabled: bool,
+    /// Only notifies the failures.
+    #[serde(rename = "onlyFailures")]
+    pub only_failures: bool,
+}
+
+/// Cover art and release dates of the games fetched by 'gg metadata' from SteamGridDB.
+///
+/// Requires the "metadata" feature.
+#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[serde(default)]
+pub struct Metadata {
+    /// API key of SteamGridDB, created in the preferences of an account of
+    /// https://www.steamgriddb.com.
+    #[serde(rename = "steamgriddbKey")]
+    pub steamgriddb_key: Option<crate::secrets::Secret>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct Permissions {
+    /// Group given to the backups and their directory (e.g. "games").
+    pub group: Option<String>,
+    /// Octal mode of the backups (e.g. "660"), directories also get the search bits.
+    pub mode: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Backend {
+    /// Keeps the backups directory of every game in its own git repository.
+    ///
+    /// Requires the "git" feature.
+    Git {
+        /// URL of the remote the backups are pushed to, supports the same variables as the cloud
+        /// commands (e.g. "git@gitlab.com:user/gg-@NAME-SLUG").
+        remote: Option<String>,
+    },
+    /// Copies the backups to an rclone remote, requires rclone to be installed.
+    Rclone {
+        /// The "remote:path" where the backups of the game are copied, supports the same variables
+        /// as the cloud commands (e.g. "gdrive:goodgame/@NAME-SLUG").
+        remote: Option<String>,
+    },
+    /// Copies the backups to another machine through SSH, requires rsync to be installed in both.
+    Rsync {
+        /// The "user@host:path" where the backups of the game are copied, supports the same
+        /// variables as the cloud commands (e.g. "me@nas.local:goodgame/@NAME-SLUG").
+        remote: Option<String>,
+        /// Command used to connect to the host, like "ssh -p 2222", defaults to "ssh".
+        ssh: Option<String>,
+    },
+    /// Leaves the backups directory of every game to Syncthing, which must share it between the
+    /// machines.
+    ///
+    /// gg keeps its local files out of the shared folder, and indexes the backups that arrive from
+    /// other machines before restoring.
+    Syncthing,
+    /// Uploads the backups to a WebDAV server, like Nextcloud.
+    ///
+    /// Requires the "webdav" feature.
+    Webdav {
+        /// URL of the directory where the backups of the game are stored, supports the same
+        /// variables as the cloud commands
+        /// (e.g. "https://cloud.example.com/remote.php/dav/files/user/goodgame/@NAME-SLUG").
+        remote: Option<String>,
+        username: Option<String>,
+        /// Password of the user, read from the GG_WEBDAV_PASSWORD environment variable if not set.
+        password: Option<crate::secrets::Secret>,
+    },
+}
+
+impl Backend {
+    /// Creates a backend of type `kind` ("git", "rclone", "rsync", "syncthing" or "webdav") with no
+    /// settings.
+    pub fn from_type(kind: &str) -> Result<Self> {
+        Ok(match kind {
+            "git" => Self::Git { remote: None },
+            "rclone" => Self::Rclone { remote: None },
+            "rsync" => Self::Rsync {
+                remote: None,
+                ssh: None,
+            },
+            "syncthing" => Self::Syncthing,
+            "webdav" => Self::Webdav {
+                remote: None,
+                username: None,
+                password: None,
+            },
+            _ => bail!("Unknown backend {kind:?}"),
+        })
+    }
+
+    /// Returns the type of the backend, as given to [`Backend::from_type`].
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Git { .. } => "git",
+            Self::Rclone { .. } => "rclone",
+            Self::Rsync { .. } => "rsync",
+            Self::Syncthing => "syncthing",
+            Self::Webdav { .. } => "webdav",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Never set, so it is kept as written.
+    const UNSET: &str = "GG_TEST_UNSET_VARIABLE";
+
+    #[test]
+    fn expands_environment_variables() {
+        let path = std::env::var("PATH").unwrap();
+        let cases = [
+            ("plain", String::from("plain")),
+            ("$PATH", path.clone()),
+            ("${PATH}", path.clone()),
+            ("$PATH/bin:${PATH}", format!("{path}/bin:{path}")),
+            ("a$PATH-b", format!("a{path}-b")),
+            ("$PATHS", String::from("$PATHS")),
+            (
+                &format!("${UNSET} ${{{UNSET}}}"),
+                format!("${UNSET} ${{{UNSET}}}"),
+            ),
+            ("$1 $@ $", String::from("$1 $@ $")),
+            ("${PATH", String::from("${PATH")),
+            ("${}", String::from("${}")),
+            ("${1A}", String::from("${1A}")),
+            ("ñ$PATH€", format!("ñ{path}€")),
+            ("$$PATH", format!("${path}")),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(expand_env(value), expected, "{value}");
+        }
+    }
+
+    #[test]
+    fn expands_environment_variables_except_in_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.yaml");
+        std::fs::write(&file, "editor: $PATH\nwebhooks: [\"${PATH}/hook\"]\n").unwrap();
+        let config = Config::load_from(&file).unwrap();
+        assert_eq!(config.editor.as_deref(), Some("$PATH"));
+        assert_eq!(
+            config.webhooks,
+            [format!("{}/hook", std::env::var("PATH").unwrap())]
+        );
+    }
+
+    #[test]
+    fn rejects_newer_config_versions() {
+        let path = Path::new("config.yaml");
+        let current = format!("version: {CONFIG_VERSION}\neditor: vi\n");
+        assert!(parse_layer(path, &current).unwrap().is_some());
+        assert!(parse_layer(path, "editor: vi\n").unwrap().is_some());
+        assert!(parse_layer(path, "# only a comment\n").unwrap().is_none());
+        let newer = format!("version: {}\n", CONFIG_VERSION + 1);
+        assert!(parse_layer(path, &newer).is_err());
+    }
 }
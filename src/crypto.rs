@@ -0,0 +1,212 @@
+use age::secrecy::SecretString;
+use age::stream::{StreamReader, StreamWriter};
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+/// Extension appended to the name of encrypted backups.
+pub const EXTENSION: &str = "age";
+
+/// Returns `true` if the backup at `path` is encrypted.
+pub fn is_encrypted(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == EXTENSION)
+}
+
+/// Writer that encrypts everything written to it if a passphrase is provided.
+pub enum MaybeEncrypted<W: Write> {
+    Plain(W),
+    Encrypted(StreamWriter<W>),
+}
+
+impl<W: Write> MaybeEncrypted<W> {
+    pub fn new(output: W, passphrase: Option<&SecretString>) -> Result<Self> {
+        match passphrase {
+            Some(passphrase) => encrypt(output, passphrase).map(Self::Encrypted),
+            None => Ok(Self::Plain(output)),
+        }
+    }
+
+    /// Flushes the remaining data, which must be done for the encrypted output to be valid.
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            Self::Plain(w) => Ok(w),
+            Self::Encrypted(w) => w.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for MaybeEncrypted<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(w) => w.write(buf),
+            Self::Encrypted(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(w) => w.flush(),
+            Self::Encrypted(w) => w.flush(),
+        }
+    }
+}
+
+pub fn encrypt<W: Write>(output: W, passphrase: &SecretString) -> Result<StreamWriter<W>> {
+    Ok(age::Encryptor::with_user_passphrase(passphrase.clone())
+        .wrap_output(output)
+        .context("Could not start encryption")?)
+}
+
+pub fn decrypt<R: Read>(input: R, passphrase: &SecretString) -> Result<StreamReader<R>> {
+    let decryptor = age::Decryptor::new(input).context("The file is not a valid age file")?;
+    let identity = age::scrypt::Identity::new(passphrase.clone());
+    Ok(decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .context("Could not decrypt, the passphrase may be wrong")?)
+}
+
+/// Writes the contents of the file at `input` encrypted with `passphrase` into `output`.
+pub fn encrypt_file(input: &Path, output: &Path, passphrase: &SecretString) -> Result<()> {
+    let mut reader = std::fs::File::open(input)
+        .context_with(|| format!("Could not open {}", input.display()))?;
+    let file = std::fs::File::create(output)
+        .context_with(|| format!("Could not create {}", output.display()))?;
+    let mut writer = encrypt(file, passphrase)?;
+    std::io::copy(&mut reader, &mut writer)
+        .and_then(|_| writer.finish())
+        .context_with(|| format!("Could not encrypt {}", input.display()))?;
+    Ok(())
+}
+
+/// Writes the contents of the file at `input` decrypted with `passphrase` into `output`.
+pub fn decrypt_file(input: &Path, output: &Path, passphrase: &SecretString) -> Result<()> {
+    let file = std::fs::File::open(input)
+        .context_with(|| format!("Could not open {}", input.display()))?;
+    let mut reader = decrypt(std::io::BufReader::new(file), passphrase)?;
+    let mut writer = std::fs::File::create(output)
+        .context_with(|| format!("Could not create {}", output.display()))?;
+    std::io::copy(&mut reader, &mut writer)
+        .context_with(|| format!("Could not decrypt {}", input.display()))?;
+    Ok(())
+}
+
+/// Fails if the file at `input` can't be decrypted with `passphrase`, reading it whole so a
+/// truncated file fails too.
+pub fn check_file(input: &Path, passphrase: &SecretString) -> Result<()> {
+    let file = std::fs::File::open(input)
+        .context_with(|| format!("Could not open {}", input.display()))?;
+    let mut reader = decrypt(std::io::BufReader::new(file), passphrase)?;
+    std::io::copy(&mut reader, &mut std::io::sink())
+        .context_with(|| format!("Could not decrypt {}", input.display()))?;
+    Ok(())
+}
+
+/// Writes the contents of the file at `input`, encrypted with `old`, encrypted with `new` into
+/// `output`.
+///
+/// The contents are streamed from one to the other, so the plaintext never touches the disk.
+pub fn reencrypt_file(
+    input: &Path,
+    output: &Path,
+    old: &SecretString,
+    new: &SecretString,
+) -> Result<()> {
+    let file = std::fs::File::open(input)
+        .context_with(|| format!("Could not open {}", input.display()))?;
+    let mut reader = decrypt(std::io::BufReader::new(file), old)?;
+    let file = std::fs::File::create(output)
+        .context_with(|| format!("Could not create {}", output.display()))?;
+    let mut writer = encrypt(std::io::BufWriter::new(file), new)?;
+    std::io::copy(&mut reader, &mut writer)
+        .and_then(|_| writer.finish())
+        .and_then(|mut w| w.flush())
+        .context_with(|| format!("Could not re-encrypt {}", input.display()))?;
+    Ok(())
+}
+
+/// Reads a passphrase from the environment variable `var`, or asks it interactively.
+#[cfg(feature = "cli")]
+pub fn read_passphrase(var: &str, prompt: &str) -> Result<SecretString> {
+    if let Ok(passphrase) = std::env::var(var) {
+        return Ok(passphrase.into());
+    }
+    let passphrase = inquire::Password::new(prompt)
+        .with_display_mode(inquire::PasswordDisplayMode::Masked)
+        .prompt()
+        .context("Could not read the passphrase")?;
+    Ok(passphrase.into())
+}
+
+/// Reads a passphrase from the environment variable `var`, as the prompts are part of the "cli"
+/// feature.
+#[cfg(not(feature = "cli"))]
+pub fn read_passphrase(var: &str, _: &str) -> Result<SecretString> {
+    match std::env::var(var) {
+        Ok(passphrase) => Ok(passphrase.into()),
+        Err(_) => bail!("Set the passphrase in the environment variable {var}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passphrase(passphrase: &str) -> SecretString {
+        passphrase.to_owned().into()
+    }
+
+    #[test]
+    fn encrypts_and_decrypts_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plain, encrypted, decrypted) = (
+            dir.path().join("save.tar"),
+            dir.path().join("save.tar.age"),
+            dir.path().join("decrypted.tar"),
+        );
+        std::fs::write(&plain, "the save").unwrap();
+        encrypt_file(&plain, &encrypted, &passphrase("hunter2")).unwrap();
+        assert_ne!(std::fs::read(&encrypted).unwrap(), b"the save");
+
+        decrypt_file(&encrypted, &decrypted, &passphrase("hunter2")).unwrap();
+        assert_eq!(std::fs::read(&decrypted).unwrap(), b"the save");
+    }
+
+    #[test]
+    fn fails_with_the_wrong_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plain, encrypted) = (dir.path().join("save.tar"), dir.path().join("save.tar.age"));
+        std::fs::write(&plain, "the save").unwrap();
+        encrypt_file(&plain, &encrypted, &passphrase("hunter2")).unwrap();
+
+        assert!(check_file(&encrypted, &passphrase("hunter3")).is_err());
+        // A truncated file fails even with the right one
+        let contents = std::fs::read(&encrypted).unwrap();
+        std::fs::write(&encrypted, &contents[..contents.len() - 8]).unwrap();
+        assert!(check_file(&encrypted, &passphrase("hunter2")).is_err());
+    }
+
+    #[test]
+    fn reencrypts_files_with_a_new_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let (plain, old, new) = (
+            dir.path().join("save.tar"),
+            dir.path().join("old.tar.age"),
+            dir.path().join("new.tar.age"),
+        );
+        std::fs::write(&plain, "the save").unwrap();
+        encrypt_file(&plain, &old, &passphrase("hunter2")).unwrap();
+
+        reencrypt_file(
+            &old,
+            &new,
+            &passphrase("hunter2"),
+            &passphrase("correct horse"),
+        )
+        .unwrap();
+        decrypt_file(&new, &plain, &passphrase("correct horse")).unwrap();
+        assert_eq!(std::fs::read(&plain).unwrap(), b"the save");
+    }
+}
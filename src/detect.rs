@@ -0,0 +1,170 @@
+//! Guesses of the save location of a game, from the engine files in its root and the directories
+//! named like it where games usually store their saves.
+
+use std::path::{Path, PathBuf};
+
+/// A directory that may be the save location of a game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub path: PathBuf,
+    /// Why it was suggested, like "RenPy save directory".
+    pub reason: String,
+    /// How likely it is the save location, the higher the better.
+    pub score: u32,
+}
+
+impl std::fmt::Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.path.display(), self.reason)
+    }
+}
+
+/// Save locations of game engines, as the files that identify the engine in the root and the
+/// save directory relative to it.
+const ENGINES: [(&str, &[&str], &str); 2] = [
+    ("RenPy", &["renpy"], "game/saves"),
+    ("RPG Maker MV", &["nw.dll"], "www/save"),
+];
+
+/// Names of the directories of the root that usually hold the saves.
+const SAVE_DIRS: [&str; 7] = [
+    "save",
+    "saves",
+    "savegames",
+    "savedata",
+    "saved",
+    "savefiles",
+    "userdata",
+];
+
+/// Directories of the Windows users of a wine prefix where games store their saves, with how deep
+/// the directory of the game is, as some are grouped by their publisher.
+const WINE_USER_DIRS: [(&str, usize); 6] = [
+    ("AppData/Roaming", 2),
+    ("AppData/Local", 2),
+    ("AppData/LocalLow", 2),
+    ("Documents", 1),
+    ("Documents/My Games", 1),
+    ("Saved Games", 1),
+];
+
+/// Returns the directories that may be the save location of the game called `name` installed in
+/// `root`, with its wine `prefix` if it has one, the most likely first.
+///
+/// Only existing directories are returned, and the root itself is never one of them.
+pub fn save_candidates(name: &str, root: &Path, prefix: Option<&Path>) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for (engine, markers, saves) in ENGINES {
+        if markers.iter().all(|marker| root.join(marker).exists()) {
+            candidates.push(Candidate {
+                path: root.join(saves),
+                reason: format!("{engine} save directory"),
+                score: 4,
+            });
+        }
+    }
+
+    let in_root = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(3)
+        .into_iter()
+        .filter_entry(|e| e.file_name() != "gg-saves" && e.file_name() != ".git")
+        .flatten()
+        .filter(|e| e.file_type().is_dir());
+    for entry in in_root {
+        let dir = entry.file_name().to_string_lossy().to_lowercase();
+        if SAVE_DIRS.contains(&dir.as_str()) {
+            candidates.push(Candidate {
+                path: entry.into_path(),
+                reason: String::from("save directory in the root"),
+                score: 2,
+            });
+        }
+    }
+
+    let name = normalized(name);
+    if !name.is_empty() {
+        for (base, depth) in search_dirs(prefix) {
+            candidates.extend(named_like(&name, &base, depth));
+        }
+    }
+
+    candidates.retain(|c| c.path.is_dir() && c.path != root);
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    let mut seen = std::collections::HashSet::new();
+    candidates.retain(|c| seen.insert(c.path.canonicalize().unwrap_or_else(|_| c.path.clone())));
+    candidates
+}
+
+/// Returns the directories where games store their saves, with how deep the directory of a game
+/// can be inside them.
+fn search_dirs(prefix: Option<&Path>) -> Vec<(PathBuf, usize)> {
+    let home = crate::paths::home().unwrap_or_default();
+    let mut dirs = vec![
+        (home.join(".local/share"), 1),
+        (home.join(".config"), 1),
+        (home.join(".local/share/godot/app_userdata"), 1),
+        // Grouped by the company, like ~/.config/unity3d/COMPANY/GAME
+        (home.join(".config/unity3d"), 2),
+        (home.join("Documents/My Games"), 1),
+        (home.join("Documents"), 1),
+    ];
+    dirs.extend(crate::paths::data_home().map(|dir| (dir, 1)));
+    dirs.extend(crate::paths::config_home().map(|dir| (dir, 1)));
+
+    let prefixes = prefix
+        .map(Path::to_path_buf)
+        .into_iter()
+        .chain(crate::paths::compatdata_prefixes());
+    for prefix in prefixes {
+        let Ok(users) = std::fs::read_dir(prefix.join("drive_c/users")) else {
+            continue;
+        };
+        for user in users.flatten() {
+            dirs.extend(
+                WINE_USER_DIRS
+                    .iter()
+                    .map(|(dir, depth)| (user.path().join(dir), *depth)),
+            );
+        }
+    }
+    dirs.retain(|(dir, _)| dir.is_dir());
+    dirs
+}
+
+/// Returns the directories up to `depth` levels inside `base` named like the game, whose name is
+/// already `normalized`.
+fn named_like(name: &str, base: &Path, depth: usize) -> Vec<Candidate> {
+    walkdir::WalkDir::new(base)
+        .min_depth(1)
+        .max_depth(depth)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_dir())
+        .filter_map(|entry| {
+            let dir = normalized(&entry.file_name().to_string_lossy());
+            let score = if dir == name {
+                3
+            } else if dir.len() >= 4 && (dir.contains(name) || name.contains(&dir)) {
+                1
+            } else {
+                return None;
+            };
+            Some(Candidate {
+                reason: format!("named like the game in {}", base.display()),
+                path: entry.into_path(),
+                score,
+            })
+        })
+        .collect()
+}
+
+/// Returns `name` in lowercase without spaces nor punctuation, so "Hollow Knight" and
+/// "hollow_knight" are the same.
+fn normalized(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,91 @@
+//! Kinds of the errors of gg that the programs using this library can tell apart, found in the
+//! causes of a report with [`kind`].
+//!
+//! The rest of the errors only describe what went wrong, as they are meant to be read.
+
+use rootcause::Report;
+use std::path::PathBuf;
+
+/// Serialized with its kind in kebab-case, like `{"kind": "game-not-found", "name": ...}`.
+#[derive(thiserror::Error, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Error {
+    /// No game has the name, nor an alias or a name that contains it.
+    #[error("The game {name:?} does not exist{}", did_you_mean(suggestions))]
+    GameNotFound {
+        name: String,
+        /// Names of the games with a similar name, closest first.
+        suggestions: Vec<String>,
+    },
+    /// The name is part of the names of several games.
+    #[error("{name:?} matches several games: {}", quoted(matches))]
+    AmbiguousName { name: String, matches: Vec<String> },
+    /// A game with the name is already managed.
+    #[error("The game {name:?} already exists")]
+    GameExists { name: String },
+    /// Another gg process is running, backing up or restoring the game.
+    #[error("{game} is locked by the {action} of another gg (PID {pid}), wait for it to finish")]
+    Locked {
+        game: String,
+        action: String,
+        pid: u32,
+    },
+    /// A path of the game does not exist anymore, found by
+    /// [`Game::validate`](crate::games::Game::validate).
+    #[error("{name} can't be used. {problem}, repair it with 'gg doctor {name}'")]
+    BrokenGame { name: String, problem: String },
+    /// The game is running, so its save can't be replaced.
+    #[error("{game} is running (PID {pid}), close it before replacing its save")]
+    GameRunning { game: String, pid: u32 },
+    /// The game has no backup with the name.
+    #[error("The backup {} does not exist", path.display())]
+    BackupNotFound { path: PathBuf },
+    /// The backup archive could not be read to the end.
+    #[error("The backup {} is truncated or corrupted", path.display())]
+    BackupCorrupt { path: PathBuf },
+    /// The restored files do not have the checksums recorded in the manifest of the backup.
+    #[error("{} restored files do not match the backup:\n{}", files.len(), files.join("\n"))]
+    RestoreMismatch {
+        /// Every file that does not match, with why.
+        files: Vec<String>,
+    },
+    /// An operation on the cloud storage failed after every retry.
+    #[error("The {operation} failed")]
+    CloudFailed {
+        /// The operation, like "cloud push" or "cloud upload".
+        operation: String,
+    },
+}
+
+// Shown in the reports like the rest of their messages
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.to_string())
+    }
+}
+
+/// Returns the kind of the error of `report`, the outermost one found in its causes.
+pub fn kind(report: &Report) -> Option<&Error> {
+    report
+        .iter_reports()
+        .find_map(|report| report.downcast_current_context::<Error>())
+}
+
+fn quoted(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|name| format!("{name:?}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let suggestions = suggestions
+        .iter()
+        .map(|name| format!("{name:?}"))
+        .collect::<Vec<_>>();
+    format!(", did you mean {}?", suggestions.join(" or "))
+}
@@ -0,0 +1,140 @@
+#[cfg(any(feature = "webhooks", feature = "notifications"))]
+use rootcause::Result;
+#[cfg(any(feature = "webhooks", feature = "notifications"))]
+use rootcause::prelude::*;
+use serde::Serialize;
+
+/// Events that notify the configured webhooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    BackupCreated,
+    RestoreDone,
+    /// The game has been closed after 'gg run'.
+    RunDone,
+    /// A backup past the retention of the game has been removed by 'gg prune'.
+    BackupPruned,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BackupCreated => "backup_created",
+            Self::RestoreDone => "restore_done",
+            Self::RunDone => "run_done",
+            Self::BackupPruned => "backup_pruned",
+        }
+    }
+
+    /// Name of the event when the operation failed.
+    pub fn failed_name(self) -> &'static str {
+        match self {
+            Self::BackupCreated => "backup_failed",
+            Self::RestoreDone => "restore_failed",
+            Self::RunDone => "run_failed",
+            Self::BackupPruned => "prune_failed",
+        }
+    }
+
+    /// The operation that triggers the event, "backup", "restore", "run" or "prune".
+    pub fn action(self) -> &'static str {
+        match self {
+            Self::BackupCreated => "backup",
+            Self::RestoreDone => "restore",
+            Self::RunDone => "run",
+            Self::BackupPruned => "prune",
+        }
+    }
+}
+
+/// Moments of the life of a game that run the configured hooks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    PreRun,
+    PostRun,
+    PreBackup,
+    PostBackup,
+    PreRestore,
+    PostRestore,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 6] = [
+        Self::PreRun,
+        Self::PostRun,
+        Self::PreBackup,
+        Self::PostBackup,
+        Self::PreRestore,
+        Self::PostRestore,
+    ];
+
+    /// Name of the hook in the config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::PreRun => "preRun",
+            Self::PostRun => "postRun",
+            Self::PreBackup => "preBackup",
+            Self::PostBackup => "postBackup",
+            Self::PreRestore => "preRestore",
+            Self::PostRestore => "postRestore",
+        }
+    }
+
+    /// Whether the hook runs before the operation, which doesn't happen if the hook fails.
+    pub fn is_pre(self) -> bool {
+        matches!(self, Self::PreRun | Self::PreBackup | Self::PreRestore)
+    }
+}
+
+/// JSON body sent to the webhooks.
+#[derive(Debug, Serialize)]
+pub struct Payload<'a> {
+    pub event: &'static str,
+    pub action: &'static str,
+    pub game: &'a str,
+    /// Name of the backup archive, if the operation got to have one.
+    pub archive: Option<&'a str>,
+    /// Size of the archive in bytes.
+    pub size: Option<u64>,
+    /// Seconds the operation took.
+    pub duration: f64,
+    pub success: bool,
+    /// Why the operation failed.
+    pub error: Option<&'a str>,
+}
+
+/// Seconds before giving up on a webhook, so a slow server doesn't hold gg.
+#[cfg(feature = "webhooks")]
+const TIMEOUT: u64 = 10;
+
+/// POSTs `payload` as JSON to `url`.
+#[cfg(feature = "webhooks")]
+pub fn post(url: &str, payload: &Payload) -> Result<()> {
+    let body = serde_json::to_vec(payload).context("Could not serialize the event")?;
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(std::time::Duration::from_secs(TIMEOUT)))
+        .build()
+        .into();
+    agent
+        .post(url)
+        .header("Content-Type", "application/json")
+        .send(&body[..])
+        .context_with(|| format!("Could not send the event to {url}"))?;
+    Ok(())
+}
+
+/// Shows a desktop notification with `summary` and `body`, with an error icon if `failed`.
+#[cfg(feature = "notifications")]
+pub fn show(summary: &str, body: &str, failed: bool) -> Result<()> {
+    notify_rust::Notification::new()
+        .appname("gg")
+        .summary(summary)
+        .body(body)
+        .icon(if failed {
+            "dialog-error"
+        } else {
+            "document-save"
+        })
+        .show()
+        .context("Could not show the desktop notification")?;
+    Ok(())
+}
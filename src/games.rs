@@ -1,9 +1,17 @@
-use crate::config::Config;
+use crate::archive::Archiver;
+use crate::cloud::{CloudBackend, CloudMode};
+use crate::config::{Backend, Config, Value};
+use crate::error::Error;
+use crate::events::{Event, Hook};
+use crate::observer::Observer;
+use crate::runner::{CommandRunner, SystemRunner};
+use crate::units::Duration;
+use age::secrecy::SecretString;
 use rootcause::Result;
+use rootcause::option_ext::OptionExt;
 use rootcause::prelude::*;
 use std::{
-    collections::HashMap,
-    io::Seek,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
 };
 
@@ -11,61 +19,81 @@ use std::{
 pub struct Games {
     inner: Vec<Game>,
     data_dir: PathBuf,
-    games_file: std::fs::File,
-    config: Config,
+    /// Lock of the game database, held until dropped if loaded with [`Games::load_locked`].
+    lock: Option<std::fs::File>,
+    /// Parsed on first use, as most commands don't need it.
+    config: std::sync::OnceLock<Config>,
+    /// Only config file read, instead of the system and user ones, see
+    /// [`GamesBuilder::config_path`].
+    config_path: Option<PathBuf>,
+    /// The games are never written, see [`GamesBuilder::in_memory`].
+    in_memory: bool,
+    runner: Box<dyn CommandRunner>,
+    /// Replaces the configured backends when set, see [`Games::set_cloud_backend`].
+    cloud_backend: Option<std::sync::Arc<dyn CloudBackend>>,
+    observers: Vec<Box<dyn Observer>>,
+    /// Formats of the backups by their name, see [`Games::register_archiver`].
+    archivers: BTreeMap<String, Box<dyn Archiver>>,
+    /// Problems of the games by their name, found when loading them or marked with
+    /// [`Games::mark_broken`].
+    broken: HashMap<String, Vec<Broken>>,
+    /// Replaces the one of the environment or asked, see [`Games::set_passphrase`].
+    passphrase: Option<SecretString>,
+    /// Replaces 'backup.autoPrune' when set, see [`Games::set_auto_prune`].
+    auto_prune: Option<bool>,
 }
 
 impl Games {
+    /// Loads the games of the data directory of gg, with the system and user config.
     pub fn load() -> Result<Games> {
-        let config = std::fs::File::open("/etc/goodgame/config.yaml")
-            .context("Could not open config file /etc/goodgame/config.yaml")
-            .and_then(|config| {
-                serde_saphyr::from_reader::<_, Config>(config)
-                    .context("Could not parse config file /etc/goodgame/config.yaml")
-            })
-            .unwrap_or_default();
+        Self::builder().load()
+    }
 
-        let data_dir = std::env::var("XDG_DATA_HOME")
-            .or_else(|_| std::env::var("HOME").map(|h| h + "/.local/share"))
-            .map(|s| PathBuf::from(s + "/goodgame"))
-            .context("Could not obtain data directory")?;
-        std::fs::create_dir_all(&data_dir)?;
-
-        let games_path = data_dir.join(Self::games_file_name());
-        let games_file = std::fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .truncate(false)
-            .create(true)
-            .open(&games_path)
-            .context_with(|| format!("Could not read {}", games_path.display()))?;
-        let games = if games_file.metadata()?.len() == 0 {
-            Vec::new()
-        } else {
-            serde_saphyr::from_reader::<_, Vec<Game>>(&games_file)
-                .context_with(|| format!("Could not parse {}", games_path.display()))?
-        };
+    /// Loads the games like [`Games::load`], holding the lock of the database until dropped so no
+    /// other gg process changes it in the meantime, as its changes would be lost when storing.
+    ///
+    /// Waits for the process that holds it, if any.
+    pub fn load_locked() -> Result<Games> {
+        Self::builder().locked().load()
+    }
 
-        Ok(Games {
-            inner: games,
-            config,
-            data_dir,
-            games_file,
-        })
+    /// Returns a builder to load the games from other places than the ones of gg, like the
+    /// temporary directories of tests.
+    pub fn builder() -> GamesBuilder {
+        GamesBuilder::default()
     }
 
-    /// Saves the in-memory game database into disk.
+    /// JSON Schema of the game database, for editors to validate and complete it.
+    pub fn schema() -> schemars::Schema {
+        schemars::schema_for!(GamesFile<Vec<Game>>)
+    }
+
+    /// Reads the games of the database at `path`, like the one of another machine.
+    pub fn read_games(path: &Path) -> Result<Vec<Game>> {
+        #[cfg(feature = "sqlite")]
+        if crate::sqlite::is_database(path) {
+            return crate::sqlite::read_games(path);
+        }
+        let contents = std::fs::read_to_string(path)
+            .context_with(|| format!("Could not open {}", path.display()))?;
+        parse_games(&contents, games_version(&contents), path)
+    }
+
+    /// Saves the in-memory game database into disk, unless it was loaded
+    /// [`in_memory`](GamesBuilder::in_memory).
     pub fn store(&mut self) -> Result<()> {
-        self.games_file.set_len(0)?;
-        if self.inner.is_empty() {
+        self.inner.sort_unstable(); // TODO: Unnecessary in theory, but good for migration
+        if self.in_memory {
             return Ok(());
         }
-        self.games_file.rewind()?;
-        self.inner.sort_unstable(); // TODO: Unnecessary in theory, but good for migration
-        serde_saphyr::to_io_writer(&mut self.games_file, &self.inner)
-            .context_with(|| format!("Could not save to {}", self.games_path().display()))?;
-
-        Ok(())
+        // Through the link, if the database is one, so it is not replaced by a regular file
+        let path = self.games_path();
+        let path = path.canonicalize().unwrap_or(path);
+        #[cfg(not(feature = "sqlite"))]
+        let write = write_games_file;
+        #[cfg(feature = "sqlite")]
+        let write = crate::sqlite::store;
+        write(&path, &self.inner)
     }
 
     /// Pushes or updates the provided game.
@@ -80,49 +108,516 @@ impl Games {
                 i
             }
         };
-        &self.inner[i]
+        let game = &self.inner[i];
+        self.broken.remove(game.name());
+        let problems = game.validate();
+        if !problems.is_empty() {
+            self.broken.insert(game.name().to_owned(), problems);
+        }
+        game
     }
 
     pub fn delete(&mut self, name: impl AsRef<str>) -> Result<Game> {
-        let (_, i) = self.get_idx_by_name(name)?;
-        Ok(self.inner.remove(i))
+        let (_, i) = self.find_by_name(name.as_ref(), false)?;
+        let game = self.inner.remove(i);
+        self.broken.remove(game.name());
+        Ok(game)
+    }
+
+    /// Adds the games of `export`, keeping the managed games that have other settings unless
+    /// `replace`.
+    ///
+    /// The metadata of their backups is added to the one of this machine, which is kept for the
+    /// backups that have both.
+    pub fn import(&mut self, export: Export, replace: bool) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut backups = export.backups;
+        for game in export.games {
+            let name = game.name.clone();
+            match self.get_exact(&name) {
+                None => report.added.push(name.clone()),
+                Some(managed) if *managed == game => {
+                    report.unchanged.push(name);
+                    continue;
+                }
+                Some(managed) => {
+                    let fields = differing_fields(managed, &game)?;
+                    if !replace {
+                        report.conflicts.push(ImportConflict { game: name, fields });
+                        continue;
+                    }
+                    self.delete(&name)?;
+                    report.replaced.push(name.clone());
+                }
+            }
+            crate::outcome::record_game(&name);
+            let backups_path = game.backups_path();
+            std::fs::create_dir_all(&backups_path)
+                .context_with(|| format!("Could not create {}", backups_path.display()))?;
+            if let Some(exported) = backups.remove(&name) {
+                let mut metadata = game.backups_metadata();
+                for (backup, exported) in exported {
+                    metadata.entry(backup).or_insert(exported);
+                }
+                game.set_backups_metadata(&metadata)?;
+            }
+            self.push(game);
+        }
+        Ok(report)
+    }
+
+    /// Checks the paths of every game again, replacing the problems found when they were loaded
+    /// and the marked ones.
+    pub fn validate(&mut self) {
+        self.broken = self
+            .inner
+            .iter()
+            .map(|game| (game.name().to_owned(), game.validate()))
+            .filter(|(_, problems)| !problems.is_empty())
+            .collect();
+    }
+
+    /// Returns the problems of `game`, empty if it can be used.
+    pub fn broken(&self, game: &Game) -> &[Broken] {
+        self.broken.get(game.name()).map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the games with problems, along with them.
+    pub fn broken_games(&self) -> impl Iterator<Item = (&Game, &[Broken])> {
+        self.inner
+            .iter()
+            .map(|game| (game, self.broken(game)))
+            .filter(|(_, problems)| !problems.is_empty())
+    }
+
+    /// Marks the game called `name` as broken by `problem`, found outside of [`Game::validate`],
+    /// until it is validated or updated again.
+    pub fn mark_broken(&mut self, name: impl AsRef<str>, problem: Broken) -> Result<()> {
+        let name = self.get_by_name(name)?.name().to_owned();
+        let problems = self.broken.entry(name).or_default();
+        if !problems.contains(&problem) {
+            problems.push(problem);
+        }
+        Ok(())
+    }
+
+    /// Fails if `game` has a problem that prevents the `operation`, instead of letting it fail
+    /// later with a less clear error.
+    pub fn check_broken(&self, game: &Game, operation: Event) -> Result<()> {
+        // It may have been fixed since, like the save location created by the game
+        let broken = |p: &&Broken| p.prevents(operation) && !p.path().exists();
+        if let Some(problem) = self.broken(game).iter().find(broken) {
+            bail!(Error::BrokenGame {
+                name: game.name().to_owned(),
+                problem: problem.to_string(),
+            })
+        }
+        Ok(())
     }
 
     pub fn games(&self) -> &[Game] {
         &self.inner
     }
 
-    pub fn config(&self) -> &Config {
-        &self.config
+    /// Returns the games that are not archived, the ones acted on when no game is given.
+    pub fn active(&self) -> impl Iterator<Item = &Game> {
+        self.inner.iter().filter(|game| !game.archived)
+    }
+
+    pub fn config(&self) -> Result<&Config> {
+        if let Some(config) = self.config.get() {
+            return Ok(config);
+        }
+        let config = match &self.config_path {
+            Some(path) => Config::load_from(path)?,
+            None => Config::load()?,
+        };
+        Ok(self.config.get_or_init(|| config))
+    }
+
+    /// Returns the config of the game, with its section in `games` merged over the global one.
+    pub fn game_config(&self, game: &Game) -> Result<&Config> {
+        let config = self.config()?;
+        Ok(config.games.get(game.name()).unwrap_or(config))
+    }
+
+    /// Returns the external compression of the game, its own one taking precedence over the
+    /// configured one, or `None` if it uses the built-in zstd.
+    pub fn compression<'a>(&'a self, game: &'a Game) -> Result<Option<&'a Compression>> {
+        let configured = self.game_config(game)?.backup.compression.as_ref();
+        Ok(game.compression.as_ref().or(configured))
+    }
+
+    /// Extension of the compressed tar archives of the game, without the dot.
+    pub fn compression_extension<'a>(&'a self, game: &'a Game) -> Result<&'a str> {
+        Ok(self
+            .compression(game)?
+            .map_or(crate::compression::ZSTD_EXTENSION, |c| &c.extension))
+    }
+
+    /// Adds a format for the backups, used by the games whose archiver is `name`, replacing the
+    /// one with the same name.
+    pub fn register_archiver(
+        &mut self,
+        name: impl Into<String>,
+        archiver: impl Archiver + 'static,
+    ) {
+        self.archivers.insert(name.into(), Box::new(archiver));
+    }
+
+    /// Returns the archiver of the new backups of the game, the built-in tar unless it chooses
+    /// another.
+    pub fn archiver(&self, game: &Game) -> Result<&dyn Archiver> {
+        let name = game.archiver.as_deref().unwrap_or(crate::archive::TAR);
+        let Some(archiver) = self.archivers.get(name) else {
+            bail!(
+                "The archiver {name:?} of {} is not available, the available ones are {}",
+                game.name(),
+                self.archivers
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        };
+        Ok(archiver.as_ref())
+    }
+
+    /// Returns the archiver that reads the backup at `path`, found by its extension, or the
+    /// built-in tar if no other one uses it.
+    pub fn backup_archiver(&self, path: &Path) -> &dyn Archiver {
+        let path = if crate::crypto::is_encrypted(path) {
+            Path::new(path.file_stem().unwrap_or_default())
+        } else {
+            path
+        };
+        let extension = path.extension().and_then(|e| e.to_str());
+        self.archivers
+            .values()
+            .find(|archiver| archiver.extension().is_some() && archiver.extension() == extension)
+            .map_or(&crate::archive::Tar, |archiver| archiver.as_ref())
+    }
+
+    /// Returns `mode`, or the configured cloud mode if `None`.
+    pub fn cloud_mode(&self, mode: Option<CloudMode>) -> Result<CloudMode> {
+        Ok(mode.or(self.config()?.backup.cloud).unwrap_or_default())
+    }
+
+    /// Uses the default configuration instead of reading the config file.
+    pub fn ignore_config(&mut self) {
+        self.config = std::sync::OnceLock::from(Config::default());
+    }
+
+    /// Replaces the runner used to execute the configured commands.
+    pub fn set_runner(&mut self, runner: impl CommandRunner + 'static) {
+        self.runner = Box::new(runner);
+    }
+
+    /// Uses `passphrase` to encrypt and decrypt the backups, instead of reading it from the
+    /// environment or asking it.
+    pub fn set_passphrase(&mut self, passphrase: SecretString) {
+        self.passphrase = Some(passphrase);
+    }
+
+    /// Prunes the backups of every game after backing it up if `auto_prune`, whatever their
+    /// 'backup.autoPrune'.
+    pub fn set_auto_prune(&mut self, auto_prune: bool) {
+        self.auto_prune = Some(auto_prune);
+    }
+
+    /// Returns whether the backups of the game are pruned after backing it up.
+    pub fn auto_prune(&self, game: &Game) -> Result<bool> {
+        match self.auto_prune {
+            Some(auto_prune) => Ok(auto_prune),
+            None => Ok(self.game_config(game)?.backup.auto_prune),
+        }
+    }
+
+    /// Returns the passphrase used to encrypt and decrypt the backups, the one of
+    /// [`Games::set_passphrase`] or else read from `GG_PASSPHRASE`, which is only asked once.
+    pub fn passphrase(&self) -> Result<SecretString> {
+        static READ: std::sync::OnceLock<SecretString> = std::sync::OnceLock::new();
+        if let Some(passphrase) = self.passphrase.as_ref().or_else(|| READ.get()) {
+            return Ok(passphrase.clone());
+        }
+        let passphrase = crate::crypto::read_passphrase("GG_PASSPHRASE", "Backup passphrase:")?;
+        Ok(READ.get_or_init(|| passphrase).clone())
+    }
+
+    /// Registers an observer that receives the progress of what gg does from now on.
+    pub fn add_observer(&mut self, observer: impl Observer + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Calls `f` with every registered [`Observer`], in the order they were added.
+    pub fn observe(&self, f: impl Fn(&dyn Observer)) {
+        self.observers
+            .iter()
+            .for_each(|observer| f(observer.as_ref()));
+    }
+
+    /// Executes `cmd` in `cwd` through the configured [`CommandRunner`].
+    ///
+    /// A missing command is not an error, as it means the user has not configured it.
+    pub fn execute(
+        &self,
+        cmd: Option<std::process::Command>,
+        desc: &str,
+        cwd: &Path,
+    ) -> Result<()> {
+        let Some(cmd) = cmd else {
+            crate::output::progress(format_args!("Command {desc} not configured, skipping..."));
+            return Ok(());
+        };
+        self.observe(|o| o.on_command_start(desc, &cmd));
+        let result = self.runner.run(cmd, desc, cwd);
+        self.observe(|o| o.on_command_finish(desc, result.as_ref().err()));
+        result
+    }
+
+    /// Executes `cmd` in `cwd` like [`execute`](Self::execute), but leaving it the terminal even
+    /// when the output of the commands is logged, as needed by the games and the editors.
+    pub fn execute_interactive(
+        &self,
+        cmd: Option<std::process::Command>,
+        desc: &str,
+        cwd: &Path,
+    ) -> Result<()> {
+        let Some(cmd) = cmd else {
+            crate::output::progress(format_args!("Command {desc} not configured, skipping..."));
+            return Ok(());
+        };
+        self.observe(|o| o.on_command_start(desc, &cmd));
+        let result = self.runner.run_interactive(cmd, desc, cwd);
+        self.observe(|o| o.on_command_finish(desc, result.as_ref().err()));
+        result
+    }
+
+    /// Executes `cmd` in `cwd` through the configured [`CommandRunner`], returning its output.
+    pub fn output(&self, cmd: std::process::Command, desc: &str, cwd: &Path) -> Result<Vec<u8>> {
+        self.observe(|o| o.on_command_start(desc, &cmd));
+        let result = self.runner.output(cmd, desc, cwd);
+        self.observe(|o| o.on_command_finish(desc, result.as_ref().err()));
+        result
     }
 
     pub fn names(&self) -> impl IntoIterator<Item = &str> {
         self.inner.iter().map(|g| g.name.as_str())
     }
 
+    /// Returns the aliases of every game.
+    pub fn aliases(&self) -> impl IntoIterator<Item = &str> {
+        self.inner
+            .iter()
+            .flat_map(|g| g.aliases().iter().map(String::as_str))
+    }
+
+    /// Fails if the name or an alias of `game` is already the name or an alias of another game.
+    pub fn check_aliases(&self, game: &Game) -> Result<()> {
+        let others = self
+            .inner
+            .iter()
+            .filter(|other| slug::slugify(&other.name) != slug::slugify(&game.name));
+        for other in others {
+            if let Some(alias) = game.aliases().iter().find(|a| other.is_called(a)) {
+                bail!("The alias {alias:?} is already used by {:?}", other.name)
+            }
+            if let Some(alias) = other.aliases().iter().find(|a| game.is_called(a)) {
+                bail!("{:?} is already an alias of {:?}", alias, other.name)
+            }
+        }
+        Ok(())
+    }
+
     pub fn games_file_name() -> &'static str {
-        "games.yaml"
+        if cfg!(feature = "sqlite") {
+            "games.sqlite"
+        } else {
+            GAMES_FILE
+        }
     }
 
     pub fn games_path(&self) -> PathBuf {
         self.data_dir.join(Self::games_file_name())
     }
 
+    /// Returns the game called `name`, ignoring the case and the punctuation, or the only one
+    /// whose name starts with or contains it, printing the name it resolved to then.
+    ///
+    /// Fails suggesting the closest names if no game matches, or listing them if several do.
     pub fn get_by_name(&self, name: impl AsRef<str>) -> Result<&Game> {
         self.get_idx_by_name(name).map(|g| g.0)
     }
 
-    pub fn get_idx_by_name(&self, name: impl AsRef<str>) -> Result<(&Game, usize)> {
-        let name = name.as_ref();
-        let name = slug::slugify(name);
+    /// Returns the game called `name` or with the alias `name`, ignoring the case and the
+    /// punctuation, without guessing it from part of the name.
+    ///
+    /// Used by the commands that replace or delete something, where acting on the wrong game
+    /// can't be undone. Fails suggesting the games that [`Games::get_by_name`] would guess.
+    pub fn get_by_name_or_alias(&self, name: impl AsRef<str>) -> Result<&Game> {
+        self.find_by_name(name.as_ref(), false).map(|g| g.0)
+    }
+
+    /// Returns the games called `names`, or matching them if they have the `*` and `?` wildcards,
+    /// and the ones with any of the `tags`, in the order of the list.
+    ///
+    /// Fails if a name, pattern or tag matches no game.
+    pub fn select(
+        &self,
+        names: &[impl AsRef<str>],
+        tags: &[impl AsRef<str>],
+    ) -> Result<Vec<&Game>> {
+        self.select_names(names, tags, true)
+    }
+
+    /// Like [`Games::select`], but with the names resolved by [`Games::get_by_name_or_alias`],
+    /// for the commands that delete something.
+    pub fn select_exact(
+        &self,
+        names: &[impl AsRef<str>],
+        tags: &[impl AsRef<str>],
+    ) -> Result<Vec<&Game>> {
+        self.select_names(names, tags, false)
+    }
 
-        let Ok(i) = self
+    fn select_names(
+        &self,
+        names: &[impl AsRef<str>],
+        tags: &[impl AsRef<str>],
+        guess: bool,
+    ) -> Result<Vec<&Game>> {
+        let mut selected = vec![false; self.inner.len()];
+        for name in names {
+            let name = name.as_ref();
+            if !name.contains(['*', '?']) {
+                selected[self.find_by_name(name, guess)?.1] = true;
+                continue;
+            }
+            let pattern = name.to_lowercase();
+            let mut found = false;
+            for (i, game) in self.inner.iter().enumerate() {
+                if !game.archived && wildcard_match(&pattern, &game.name.to_lowercase()) {
+                    selected[i] = true;
+                    found = true;
+                }
+            }
+            if !found {
+                bail!("No game matches {name:?}")
+            }
+        }
+        for tag in tags {
+            let tag = tag.as_ref();
+            let mut found = false;
+            for (i, game) in self.inner.iter().enumerate() {
+                if !game.archived && game.has_tag(tag) {
+                    selected[i] = true;
+                    found = true;
+                }
+            }
+            if !found {
+                bail!("No game has the tag {tag:?}")
+            }
+        }
+        Ok(self
             .inner
+            .iter()
+            .zip(selected)
+            .filter_map(|(game, selected)| selected.then_some(game))
+            .collect())
+    }
+
+    /// Returns the game called `name`, ignoring the case and the punctuation only.
+    ///
+    /// Used to check whether a game is managed, unlike [`Games::get_by_name`] which resolves the
+    /// names typed by the user.
+    pub fn get_exact(&self, name: impl AsRef<str>) -> Option<&Game> {
+        let name = slug::slugify(name.as_ref());
+        self.inner
             .binary_search_by(|g| slug::slugify(&g.name).cmp(&name))
-        else {
-            bail!("The game {name:?} does not exist")
-        };
-        Ok((&self.inner[i], i))
+            .ok()
+            .map(|i| &self.inner[i])
+    }
+
+    pub fn get_idx_by_name(&self, name: impl AsRef<str>) -> Result<(&Game, usize)> {
+        self.find_by_name(name.as_ref(), true)
+    }
+
+    /// Returns the game called `name` or with the alias `name`, or if `guess`, the only one whose
+    /// name starts with or contains it.
+    fn find_by_name(&self, name: &str, guess: bool) -> Result<(&Game, usize)> {
+        let slug = slug::slugify(name);
+        if let Ok(i) = self
+            .inner
+            .binary_search_by(|g| slug::slugify(&g.name).cmp(&slug))
+        {
+            return Ok((&self.inner[i], i));
+        }
+        if let Some(i) = self
+            .inner
+            .iter()
+            .position(|g| g.aliases().iter().any(|a| slug::slugify(a) == slug))
+        {
+            return Ok((&self.inner[i], i));
+        }
+
+        // "eldenring" is "Elden Ring", and "elden" is guessed to be it
+        let key = slug.replace('-', "");
+        let keys = self
+            .inner
+            .iter()
+            .map(|g| slug::slugify(&g.name).replace('-', ""))
+            .collect::<Vec<_>>();
+        if let Some(i) = keys.iter().position(|k| !key.is_empty() && *k == key) {
+            return Ok((&self.inner[i], i));
+        }
+        let matchers: [fn(&str, &str) -> bool; 2] =
+            [|k, key| k.starts_with(key), |k, key| k.contains(key)];
+        let mut guessed = Vec::new();
+        for matcher in matchers {
+            let found = keys
+                .iter()
+                .enumerate()
+                .filter(|(_, k)| !key.is_empty() && matcher(k, &key))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>();
+            match found.as_slice() {
+                [] => continue,
+                [i] if guess => {
+                    crate::output::progress(format_args!(
+                        "Using {:?} for {name:?}",
+                        self.inner[*i].name
+                    ));
+                    return Ok((&self.inner[*i], *i));
+                }
+                found if guess => bail!(Error::AmbiguousName {
+                    name: name.to_owned(),
+                    matches: found.iter().map(|i| self.inner[*i].name.clone()).collect(),
+                }),
+                found => {
+                    guessed = found.to_vec();
+                    break;
+                }
+            }
+        }
+
+        let mut suggestions = keys
+            .iter()
+            .enumerate()
+            .map(|(i, k)| (edit_distance(k, &key), i))
+            .filter(|(distance, i)| {
+                *distance <= (key.chars().count() / 3).max(2) && !guessed.contains(i)
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort();
+        bail!(Error::GameNotFound {
+            name: name.to_owned(),
+            suggestions: guessed
+                .into_iter()
+                .chain(suggestions.into_iter().map(|(_, i)| i))
+                .take(3)
+                .map(|i| self.inner[i].name.clone())
+                .collect(),
+        })
     }
 
     pub fn get_by_root(&self, path: impl AsRef<Path>) -> Option<&Game> {
@@ -135,18 +630,55 @@ impl Games {
         self.inner.iter().find(|g| g.save_location == path)
     }
 
+    /// Returns the game whose root or save location contains the current directory, the one with
+    /// the deepest of them if several do, like a game installed inside another one's root.
     pub fn get_by_current_dir(&self) -> Option<&Game> {
         let curr = std::env::current_dir().ok()?;
+        // The paths of the games are canonical, while the current one may go through a link
+        let curr = curr.canonicalize().unwrap_or(curr);
         self.inner
             .iter()
-            .find(|g| g.root == curr || g.save_location == curr)
+            .flat_map(|g| [(g, &g.root), (g, &g.save_location)])
+            .filter(|(_, path)| curr.starts_with(path))
+            .max_by_key(|(_, path)| path.components().count())
+            .map(|(g, _)| g)
+    }
+
+    /// Returns the game played most recently on this machine with 'gg run'.
+    pub fn last_played(&self) -> Option<&Game> {
+        self.active()
+            .filter_map(|g| Some((g.playtime().last_played?, g)))
+            .max_by_key(|(last_played, _)| *last_played)
+            .map(|(_, g)| g)
+    }
+
+    /// Asks which game to use, starting at `default`, or returns None if there is no terminal to
+    /// ask in.
+    pub fn pick(&self, default: Option<&str>) -> Result<Option<&Game>> {
+        use std::io::IsTerminal;
+
+        if self.inner.is_empty() || !std::io::stdin().is_terminal() {
+            return Ok(None);
+        }
+        let names = self.active().map(Game::name).collect::<Vec<_>>();
+        let cursor = default
+            .and_then(|default| names.iter().position(|name| *name == default))
+            .unwrap_or_default();
+        match select_name(names, cursor)? {
+            Some(name) => Ok(Some(self.get_by_name(name)?)),
+            None => Ok(None),
+        }
     }
 
+    /// Returns the game called `game`, or the one of the current directory if not given, asking
+    /// which one if the directory doesn't belong to any.
     pub fn try_get(&self, game: Option<impl AsRef<str>>) -> Result<&Game> {
         if let Some(game) = game {
             self.get_by_name(game)
         } else if let Some(game) = self.get_by_current_dir() {
             Ok(game)
+        } else if let Some(game) = self.pick(None)? {
+            Ok(game)
         } else {
             bail!(
                 "Could not infer game by the current directory {}",
@@ -159,114 +691,2905 @@ impl Games {
         &self,
         cmds: &[String],
         game: Option<&Game>,
-    ) -> Option<std::process::Command> {
+    ) -> Result<Option<std::process::Command>> {
         if cmds.is_empty() {
-            return None;
+            return Ok(None);
         }
-        let mut cmds = cmds.join("&&");
-        let mut p = std::process::Command::new("/usr/bin/env");
+        let mut cmds = cmds.join(" && ");
         if let Some(game) = game {
             cmds = game.replace_vars(cmds);
+        }
+        self.shell_command(cmds, game).map(Some)
+    }
+
+    /// Command that runs `cmds` as they are with the shell, in the environment of the `game`.
+    fn shell_command(&self, cmds: String, game: Option<&Game>) -> Result<std::process::Command> {
+        let mut p = std::process::Command::new("/usr/bin/env");
+        let mut config = self.config()?;
+        if let Some(game) = game {
+            p.envs(game.wine_env());
             if let Some(vars) = &game.environment_vars {
                 p.envs(vars);
             }
+            config = self.game_config(game)?;
         }
-        p.args([self.config.shell.clone(), String::from("-c"), cmds]);
-        Some(p)
+        p.args(config.shell.args(cmds)?);
+        Ok(p)
     }
-    pub fn cloud_init_command(&self, game: &Game) -> Option<std::process::Command> {
-        self.commands_to_process(&self.config.backup.cloud_init_commands, Some(game))
+
+    /// Command that opens `path` in the configured editor, or in $VISUAL, $EDITOR or vi.
+    ///
+    /// Doesn't fail if the config is invalid, so it can be used to fix it.
+    pub fn editor_command(&self, path: &Path) -> Result<std::process::Command> {
+        let config = self.config().ok();
+        let editor = config
+            .and_then(|c| c.editor.clone())
+            .or_else(|| std::env::var("VISUAL").ok())
+            .or_else(|| std::env::var("EDITOR").ok())
+            .filter(|editor| !editor.trim().is_empty())
+            .unwrap_or_else(|| String::from("vi"));
+        let cmd = format!(
+            "{editor} {}",
+            crate::runner::shell_quote(&path.to_string_lossy())
+        );
+        let shell = config.map_or_else(Default::default, |c| c.shell.clone());
+        let mut p = std::process::Command::new("/usr/bin/env");
+        p.args(shell.args(cmd)?);
+        Ok(p)
     }
-    pub fn cloud_commit_command(&self, game: &Game) -> Option<std::process::Command> {
-        self.commands_to_process(&self.config.backup.cloud_commit_commands, Some(game))
+
+    /// Command that opens `path` with the configured opener, or `None` if there is none.
+    pub fn opener_command(&self, path: &Path) -> Result<Option<std::process::Command>> {
+        let config = self.config()?;
+        let Some(opener) = config.opener.as_ref().filter(|o| !o.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let cmd = format!(
+            "{opener} {}",
+            crate::runner::shell_quote(&path.to_string_lossy())
+        );
+        let mut p = std::process::Command::new("/usr/bin/env");
+        p.args(config.shell.args(cmd)?);
+        Ok(Some(p))
     }
-    pub fn cloud_push_command(&self, game: &Game) -> Option<std::process::Command> {
-        self.commands_to_process(&self.config.backup.cloud_push_commands, Some(game))
+
+    pub fn cloud_init_command(&self, game: &Game) -> Result<Option<std::process::Command>> {
+        self.commands_to_process(
+            &self.game_config(game)?.backup.cloud_init_commands,
+            Some(game),
+        )
     }
-    pub fn run_command(&self, game: &Game) -> Option<std::process::Command> {
-        let cmds: std::borrow::Cow<[String]> = game
-            .run_commands
-            .clone()
-            .map(|mut cmds| {
-                let global_run = self.config.run.commands.join("&&");
-                for cmd in cmds.iter_mut() {
-                    if let Some(i) = cmd.find("@RUN") {
-                        cmd.replace_range(i..(i + "@RUN".len()), &global_run);
-                    }
-                }
-                cmds.into()
-            })
-            .unwrap_or(self.config.run.commands.as_slice().into());
+    pub fn cloud_commit_command(
+        &self,
+        game: &Game,
+        backup: Option<&Path>,
+        message: &str,
+    ) -> Result<Option<std::process::Command>> {
+        let message = crate::runner::shell_quote(message);
+        let cmds = with_backup_file(
+            &self.game_config(game)?.backup.cloud_commit_commands,
+            backup,
+        )
+        .into_iter()
+        .map(|cmd| cmd.replace("@MESSAGE", &message))
+        .collect::<Vec<_>>();
         self.commands_to_process(&cmds, Some(game))
     }
-}
-
-impl std::fmt::Display for Games {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        serde_saphyr::to_fmt_writer(f, &self.games()).map_err(|_| std::fmt::Error)
+    pub fn cloud_push_command(
+        &self,
+        game: &Game,
+        backup: Option<&Path>,
+    ) -> Result<Option<std::process::Command>> {
+        let cmds = with_backup_file(&self.game_config(game)?.backup.cloud_push_commands, backup);
+        self.commands_to_process(&cmds, Some(game))
     }
-}
 
-#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
-pub struct Game {
-    name: String,
-    root: PathBuf,
-    save_location: PathBuf,
-    executable: Option<PathBuf>,
-    executable_args: Option<Vec<String>>,
-    environment_vars: Option<HashMap<String, String>>,
-    run_commands: Option<Vec<String>>,
-}
+    pub fn cloud_pull_command(&self, game: &Game) -> Result<Option<std::process::Command>> {
+        self.commands_to_process(
+            &self.game_config(game)?.backup.cloud_pull_commands,
+            Some(game),
+        )
+    }
+    pub fn cloud_rename_command(
+        &self,
+        game: &Game,
+        old_name: &str,
+    ) -> Result<Option<std::process::Command>> {
+        let cmds = self
+            .game_config(game)?
+            .backup
+            .cloud_rename_commands
+            .iter()
+            .map(|cmd| {
+                cmd.replace("@OLD-NAME-SLUG", &slug::slugify(old_name))
+                    .replace("@OLD-NAME", &crate::runner::shell_quote(old_name))
+            })
+            .collect::<Vec<_>>();
+        self.commands_to_process(&cmds, Some(game))
+    }
 
-impl Game {
-    pub fn new(
-        name: String,
-        root: PathBuf,
-        save_location: PathBuf,
-        executable: Option<PathBuf>,
-        executable_args: Option<Vec<String>>,
-        environment_vars: Option<impl IntoIterator<Item = (String, String)>>,
-        run_commands: Option<Vec<String>>,
-    ) -> Self {
-        Self {
-            name,
-            root,
-            save_location,
-            executable,
-            executable_args,
-            environment_vars: environment_vars.map(HashMap::from_iter),
-            run_commands,
+    /// Prepares the cloud storage of a newly managed game.
+    pub fn cloud_init(&self, game: &Game) -> Result<()> {
+        if !self.check_cloud_enabled(game) {
+            return Ok(());
         }
+        self.backend(game)?.init(self, game)
     }
 
-    pub fn name(&self) -> &str {
-        &self.name
+    /// Runs the cloud rename commands of the game, which was called `old_name`, if configured.
+    pub fn cloud_rename(&self, game: &Game, old_name: &str) -> Result<()> {
+        let cmd = self.cloud_rename_command(game, old_name)?;
+        if cmd.is_none() || !self.check_cloud_enabled(game) {
+            return Ok(());
+        }
+        self.execute(cmd, "cloud rename", game.root())
     }
 
-    pub fn root(&self) -> &Path {
-        &self.root
+    /// Records the current backups of the game in the cloud storage.
+    ///
+    /// `backup` is the archive that has just been created, if any. The commit is described by
+    /// `message`, or by [`Game::commit_message`] if `None`.
+    pub fn cloud_commit(
+        &self,
+        game: &Game,
+        backup: Option<&Path>,
+        message: Option<&str>,
+    ) -> Result<()> {
+        if !self.check_cloud_enabled(game) {
+            return Ok(());
+        }
+        let message = message.map_or_else(|| game.commit_message(backup, None), String::from);
+        self.backend(game)?.commit(self, game, backup, &message)
     }
 
-    pub fn save_location(&self) -> &Path {
-        &self.save_location
+    /// Uploads the recorded backups of the game.
+    ///
+    /// `backup` is the archive that has just been created, if any. With `uploadNewOnly` it is the
+    /// only backup uploaded by the backends that copy single files.
+    pub fn cloud_push(&self, game: &Game, backup: Option<&Path>) -> Result<()> {
+        if !self.check_cloud_enabled(game) {
+            return Ok(());
+        }
+        let _activity = crate::status::begin(game.name(), "cloud push");
+        self.observe(|o| o.on_cloud_push(game));
+        let backend = self.backend(game)?;
+        if let Some(backup) = backup
+            && self.game_config(game)?.backup.upload_new_only
+            && backend.copies_single_files()
+        {
+            self.retry("cloud upload", || self.cloud_upload(game, backup))?;
+            let marker = game.backups_path().join(MARKER_FILE);
+            if marker.exists() {
+                self.retry("cloud upload", || self.cloud_upload(game, &marker))?;
+            }
+            return game.clear_pending_uploads(Some(backup));
+        }
+        if self.cloud_passphrase()?.is_some() {
+            self.transfer_encrypted(game, true)?;
+            return game.clear_pending_uploads(None);
+        }
+        self.retry("cloud push", || backend.push(self, game, backup))?;
+        // Everything in the backups directory has been uploaded
+        game.clear_pending_uploads(None)
     }
 
-    pub fn backups_path(&self) -> PathBuf {
-        self.root.join("gg-saves")
+    /// Downloads the backups of the game that are only in the cloud storage.
+    pub fn cloud_pull(&self, game: &Game) -> Result<()> {
+        if !self.check_cloud_enabled(game) {
+            return Ok(());
+        }
+        let _activity = crate::status::begin(game.name(), "cloud pull");
+        self.observe(|o| o.on_cloud_pull(game));
+        if self.cloud_passphrase()?.is_some() {
+            return self.transfer_encrypted(game, false);
+        }
+        let backend = self.backend(game)?;
+        self.retry("cloud pull", || backend.pull(self, game))
     }
 
-    pub fn executable(&self) -> Option<&PathBuf> {
-        self.executable.as_ref()
-    }
+    /// Reconciles the local backups of the game with the ones in the cloud storage.
+    ///
+    /// Uploads the backups missing in the cloud and downloads the ones missing locally, leaving
+    /// untouched the ones that exist in both with a different size. Backends that can't list their
+    /// contents reconcile on their own by pulling and pushing, returning `None`.
+    pub fn cloud_sync(&self, game: &Game) -> Result<Option<SyncReport>> {
+        if !self.check_cloud_enabled(game) {
+            return Ok(None);
+        }
+        let _activity = crate::status::begin(game.name(), "cloud sync");
+        let Some(mut remote) = self.cloud_list(game)? else {
+            self.cloud_pull(game)?;
+            self.cloud_commit(game, None, None)?;
+            self.cloud_push(game, None)?;
+            return Ok(None);
+        };
 
-    pub fn executable_args(&self) -> Option<&[String]> {
-        self.executable_args.as_deref()
-    }
+        let remote_marker = remote.remove(MARKER_FILE).is_some();
+
+        let backups_path = game.backups_path();
+        std::fs::create_dir_all(&backups_path)
+            .context_with(|| format!("Could not create {}", backups_path.display()))?;
+        let status = compare_backups(game, &remote, self.cloud_passphrase()?.is_none())?;
+        let report = SyncReport {
+            uploaded: status.local_only,
+            downloaded: status.remote_only,
+            conflicts: status.conflicts,
+        };
+
+        for name in &report.uploaded {
+            self.retry("cloud upload", || {
+                self.cloud_upload(game, &backups_path.join(name))
+            })?;
+        }
+        for name in &report.downloaded {
+            self.retry("cloud download", || self.cloud_download(game, name))?;
+        }
+        self.sync_marker(game, remote_marker)?;
+        game.clear_pending_uploads(None)?;
+        Ok(Some(report))
+    }
+
+    /// Uploads the backups missing in the cloud storage if `upload` is `true`, or downloads the ones
+    /// missing locally otherwise, along with the marker of the last backup.
+    ///
+    /// Used with the cloud encryption, as the backends can't tell which encrypted files are new.
+    fn transfer_encrypted(&self, game: &Game, upload: bool) -> Result<()> {
+        let Some(mut remote) = self.retry("cloud list", || self.cloud_list(game))? else {
+            bail!("The cloud encryption requires the rclone, rsync or WebDAV backend")
+        };
+        let remote_marker = remote.remove(MARKER_FILE).is_some();
+        let backups_path = game.backups_path();
+        std::fs::create_dir_all(&backups_path)
+            .context_with(|| format!("Could not create {}", backups_path.display()))?;
+        let status = compare_backups(game, &remote, false)?;
+        let marker = backups_path.join(MARKER_FILE);
+        if upload {
+            for name in &status.local_only {
+                self.retry("cloud upload", || {
+                    self.cloud_upload(game, &backups_path.join(name))
+                })?;
+            }
+            // The marker changes with every backup, so it is always uploaded
+            if marker.exists() {
+                self.retry("cloud upload", || self.cloud_upload(game, &marker))?;
+            }
+        } else {
+            for name in &status.remote_only {
+                self.retry("cloud download", || self.cloud_download(game, name))?;
+            }
+            if remote_marker {
+                self.retry("cloud download", || self.cloud_download(game, MARKER_FILE))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the passphrase of the cloud encryption, or `None` if it is disabled.
+    fn cloud_passphrase(&self) -> Result<Option<SecretString>> {
+        let config = self.config()?;
+        if !config.backup.cloud_encryption {
+            return Ok(None);
+        }
+        let passphrase = match &config.backup.cloud_passphrase {
+            Some(secret) => Some(secret.reveal(&config.shell)?),
+            None => std::env::var("GG_CLOUD_PASSPHRASE").ok(),
+        };
+        match passphrase {
+            Some(passphrase) => Ok(Some(passphrase.into())),
+            None => bail!(
+                "The cloud encryption is enabled, but neither cloudPassphrase nor GG_CLOUD_PASSPHRASE are set"
+            ),
+        }
+    }
+
+    /// Calls `f` until it succeeds, up to the configured number of retries.
+    ///
+    /// The delay between attempts doubles after every failure, up to [`MAX_RETRY_DELAY`].
+    fn retry<T>(&self, desc: &str, mut f: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut delay = self
+            .config()?
+            .backup
+            .retry_delay
+            .unwrap_or(DEFAULT_RETRY_DELAY);
+        for _ in 0..self.config()?.backup.retries.unwrap_or(DEFAULT_RETRIES) {
+            match f() {
+                Ok(value) => return Ok(value),
+                Err(e) => {
+                    tracing::warn!("{desc} failed, retrying in {delay}: {e}");
+                    crate::output::warning(format_args!("{desc} failed, retrying in {delay}: {e}"))
+                }
+            }
+            std::thread::sleep(delay.0);
+            delay.0 = delay.0.saturating_mul(2).min(MAX_RETRY_DELAY);
+        }
+        Ok(f().context_with(|| Error::CloudFailed {
+            operation: desc.to_owned(),
+        })?)
+    }
+
+    /// Calls `f` with every game, running up to the configured parallelism at the same time.
+    ///
+    /// The results are returned in the same order as `games`.
+    pub fn for_each_parallel<T: Send>(
+        &self,
+        games: &[&Game],
+        f: impl Fn(&Game) -> T + Sync,
+    ) -> Result<Vec<T>> {
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let results = std::sync::Mutex::new(Vec::from_iter(games.iter().map(|_| None)));
+        let workers = self
+            .config()?
+            .backup
+            .parallelism
+            .unwrap_or(DEFAULT_PARALLELISM)
+            .clamp(1, games.len().max(1));
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    loop {
+                        let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        let Some(game) = games.get(i) else {
+                            break;
+                        };
+                        let result = f(game);
+                        results.lock().unwrap()[i] = Some(result);
+                    }
+                });
+            }
+        });
+        Ok(results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|r| r.expect("every game is processed by a worker"))
+            .collect())
+    }
+
+    /// Compares the local backups of the game with the ones in the cloud storage, like the files
+    /// of the remote branch for the git backend.
+    ///
+    /// Returns `None` if the cloud is disabled for the game or its backend can't list its contents.
+    pub fn cloud_status(&self, game: &Game) -> Result<Option<CloudStatus>> {
+        if !game.cloud_enabled() {
+            return Ok(None);
+        }
+        let Some(remote) = self.cloud_backups(game)? else {
+            return Ok(None);
+        };
+        Ok(Some(compare_backups(
+            game,
+            &remote,
+            self.cloud_passphrase()?.is_none(),
+        )?))
+    }
+
+    /// Keeps the newest of the local and cloud markers in both places.
+    fn sync_marker(&self, game: &Game, in_cloud: bool) -> Result<()> {
+        let path = game.backups_path().join(MARKER_FILE);
+        let local = game.marker();
+        if in_cloud {
+            self.cloud_download(game, MARKER_FILE)?;
+            let Some(local) = local else {
+                return Ok(());
+            };
+            if game.marker().is_some_and(|cloud| cloud.time >= local.time) {
+                return Ok(());
+            }
+            local.write(&path)?;
+        } else if local.is_none() {
+            return Ok(());
+        }
+        self.cloud_upload(game, &path)
+    }
+
+    /// Returns the names and sizes of the backups in the cloud storage, without the marker of the
+    /// last backup, or `None` if the backend can't list them.
+    ///
+    /// Unlike the listing used to sync single backups, it also lists the backends that can't copy
+    /// single files, like the remote of the git backend.
+    pub fn cloud_backups(&self, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        let backend = self.backend(game)?;
+        let remote = if backend.copies_single_files() {
+            self.cloud_list(game)?
+        } else {
+            backend.list(self, game)?
+        };
+        Ok(remote.map(|mut remote| {
+            remote.remove(MARKER_FILE);
+            remote
+        }))
+    }
+
+    /// Returns the names and sizes of the backups that are only in the cloud storage, which can be
+    /// downloaded one by one with [`Games::cloud_fetch`].
+    ///
+    /// Empty if the cloud is disabled for the game or its backend can't copy single files.
+    pub fn cloud_only_backups(&self, game: &Game) -> Result<BTreeMap<String, u64>> {
+        if !game.cloud_enabled() {
+            return Ok(BTreeMap::new());
+        }
+        let Some(mut remote) = self.retry("cloud list", || self.cloud_list(game))? else {
+            return Ok(BTreeMap::new());
+        };
+        remote.remove(MARKER_FILE);
+        let backups_path = game.backups_path();
+        remote.retain(|name, _| !backups_path.join(name).exists());
+        Ok(remote)
+    }
+
+    /// Downloads the backup called `name` from the cloud storage if it is not in this machine,
+    /// along with the marker of the last backup.
+    ///
+    /// Backends that can't copy single files pull every backup instead.
+    pub fn cloud_fetch(&self, game: &Game, name: &str) -> Result<()> {
+        if !self.check_cloud_enabled(game) {
+            return Ok(());
+        }
+        let Some(remote) = self.retry("cloud list", || self.cloud_list(game))? else {
+            return self.cloud_pull(game);
+        };
+        let _activity = crate::status::begin(game.name(), "cloud fetch");
+        let backups_path = game.backups_path();
+        std::fs::create_dir_all(&backups_path)
+            .context_with(|| format!("Could not create {}", backups_path.display()))?;
+        if !backups_path.join(name).exists() && remote.contains_key(name) {
+            self.retry("cloud download", || self.cloud_download(game, name))?;
+            crate::outcome::record_artifact(backups_path.join(name));
+        }
+        if remote.contains_key(MARKER_FILE) {
+            self.retry("cloud download", || self.cloud_download(game, MARKER_FILE))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the names and sizes of the backups in the cloud storage, or `None` if the backend
+    /// can't list them or copy single files.
+    fn cloud_list(&self, game: &Game) -> Result<Option<BTreeMap<String, u64>>> {
+        let backend = self.backend(game)?;
+        if !backend.copies_single_files() {
+            return Ok(None);
+        }
+        let Some(files) = backend.list(self, game)? else {
+            return Ok(None);
+        };
+        let files = files.into_iter().filter(|(name, _)| !name.starts_with('.'));
+        if self.cloud_passphrase()?.is_none() {
+            return Ok(Some(files.collect()));
+        }
+        // Only the encrypted files are backups, listed by the name they are downloaded as
+        let suffix = format!(".{}", crate::crypto::EXTENSION);
+        Ok(Some(
+            files
+                .filter_map(|(name, size)| Some((name.strip_suffix(&suffix)?.to_owned(), size)))
+                .collect(),
+        ))
+    }
+
+    /// Uploads the single backup at `path` to the cloud storage, encrypting it first with the cloud
+    /// encryption.
+    fn cloud_upload(&self, game: &Game, path: &Path) -> Result<()> {
+        let Some(passphrase) = self.cloud_passphrase()? else {
+            return self.upload_file(game, path);
+        };
+        let dir = game.backups_path().join(CLOUD_TEMP_DIR);
+        std::fs::create_dir_all(&dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+        let mut name = path.file_name().ok_or_report()?.to_owned();
+        name.push(format!(".{}", crate::crypto::EXTENSION));
+        let encrypted = dir.join(name);
+        let result = crate::crypto::encrypt_file(path, &encrypted, &passphrase)
+            .and_then(|_| self.upload_file(game, &encrypted));
+        let _ = std::fs::remove_file(&encrypted);
+        result
+    }
+
+    /// Downloads the backup called `name` from the cloud storage into the backups directory,
+    /// decrypting it with the cloud encryption.
+    fn cloud_download(&self, game: &Game, name: &str) -> Result<()> {
+        let Some(passphrase) = self.cloud_passphrase()? else {
+            return self.download_file(game, name, &game.backups_path());
+        };
+        let dir = game.backups_path().join(CLOUD_TEMP_DIR);
+        std::fs::create_dir_all(&dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+        let encrypted_name = format!("{name}.{}", crate::crypto::EXTENSION);
+        let encrypted = dir.join(&encrypted_name);
+        // Decrypted next to the destination, so a failure never leaves a partial backup
+        let partial = game.backups_path().join(format!(".{name}.part"));
+        let result = self
+            .download_file(game, &encrypted_name, &dir)
+            .and_then(|_| crate::crypto::decrypt_file(&encrypted, &partial, &passphrase))
+            .and_then(|_| {
+                let path = game.backups_path().join(name);
+                Ok(std::fs::rename(&partial, &path)
+                    .context_with(|| format!("Could not write {}", path.display()))?)
+            });
+        let _ = std::fs::remove_file(&encrypted);
+        let _ = std::fs::remove_file(&partial);
+        result
+    }
+
+    /// Uploads the file at `path` to the cloud storage as is.
+    fn upload_file(&self, game: &Game, path: &Path) -> Result<()> {
+        self.backend(game)?.upload(self, game, path)
+    }
+
+    /// Downloads the file called `name` from the cloud storage into `dir` as is.
+    fn download_file(&self, game: &Game, name: &str, dir: &Path) -> Result<()> {
+        self.backend(game)?.download(self, game, name, dir)
+    }
+
+    /// Removes the backup called `name` from the cloud storage, after renaming or deleting it.
+    pub fn cloud_remove(&self, game: &Game, name: &str) -> Result<()> {
+        if !game.cloud_enabled() {
+            return Ok(());
+        }
+        let name = match self.cloud_passphrase()? {
+            Some(_) => &format!("{name}.{}", crate::crypto::EXTENSION),
+            None => name,
+        };
+        self.backend(game)?.remove(self, game, name)
+    }
+
+    /// Returns the type of the backend of the game, or `None` if it uses the cloud commands.
+    ///
+    /// A backend set with [`Games::set_cloud_backend`] is reported by its name.
+    pub fn backend_type(&self, game: &Game) -> Result<Option<String>> {
+        if let Some(backend) = &self.cloud_backend {
+            return Ok(Some(backend.name().to_owned()));
+        }
+        Ok(self.configured_backend(game)?.map(|b| b.kind().to_owned()))
+    }
+
+    /// Returns the commands that the cloud backend of the game would run to init, commit, push and
+    /// pull its backups, recorded with a [`RecordingRunner`](crate::runner::RecordingRunner)
+    /// instead of run, or `None` if the backend doesn't work through commands, like git or WebDAV.
+    ///
+    /// `backup` and `message` are passed to the commit and the push, like after a backup.
+    pub fn cloud_backend_dry_run(
+        &mut self,
+        game: &Game,
+        backup: Option<&Path>,
+        message: &str,
+    ) -> Result<Option<Vec<crate::runner::RecordedCommand>>> {
+        let backend = self.backend(game)?;
+        if !backend.runs_commands() {
+            return Ok(None);
+        }
+        let recorder = crate::runner::RecordingRunner::new();
+        let runner = std::mem::replace(&mut self.runner, Box::new(recorder.clone()));
+        let result = (|| {
+            backend.init(self, game)?;
+            backend.commit(self, game, backup, message)?;
+            backend.push(self, game, backup)?;
+            backend.pull(self, game)
+        })();
+        self.runner = runner;
+        result?;
+        Ok(Some(recorder.commands()))
+    }
+
+    /// Replaces the configured cloud backend of every game with `backend`.
+    pub fn set_cloud_backend(&mut self, backend: impl CloudBackend + 'static) {
+        self.cloud_backend = Some(std::sync::Arc::new(backend));
+    }
+
+    /// Returns the cloud backend of the game.
+    fn backend(&self, game: &Game) -> Result<std::sync::Arc<dyn CloudBackend>> {
+        match &self.cloud_backend {
+            Some(backend) => Ok(backend.clone()),
+            None => crate::cloud::built_in(self.configured_backend(game)?),
+        }
+    }
+
+    /// Returns the configured backend of the game, its own one taking precedence over the global
+    /// one, or `None` if it uses the cloud commands.
+    fn configured_backend<'a>(&'a self, game: &'a Game) -> Result<Option<&'a Backend>> {
+        let own = game.cloud.as_ref().and_then(|c| c.backend.as_ref());
+        Ok(own.or(self.game_config(game)?.backup.backend.as_ref()))
+    }
+
+    /// Returns `false` and tells the user if the cloud is disabled for the game.
+    fn check_cloud_enabled(&self, game: &Game) -> bool {
+        if !game.cloud_enabled() {
+            tracing::info!("Cloud is disabled for {}, skipping", game.name());
+            crate::output::progress(format_args!(
+                "Cloud is disabled for {}, skipping...",
+                game.name()
+            ));
+        }
+        game.cloud_enabled()
+    }
+
+    /// Runs the commands of the `hook` of the game, with `backup` as @BACKUP-FILE.
+    ///
+    /// A failed post hook is only warned about, as the operation has already happened.
+    pub fn run_hook(&self, hook: Hook, game: &Game, backup: Option<&Path>) -> Result<()> {
+        let cmds = with_backup_file(self.game_config(game)?.hooks.commands(hook), backup);
+        if cmds.is_empty() {
+            return Ok(());
+        }
+        let desc = format!("{} hook", hook.name());
+        let result = self
+            .commands_to_process(&cmds, Some(game))
+            .and_then(|cmd| self.execute(cmd, &desc, game.root()));
+        match result {
+            Err(e) if !hook.is_pre() => {
+                crate::output::warning(format_args!("The {desc} of {} failed: {e}", game.name()));
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Notifies the configured webhooks that `event` happened to the backup at `archive`, or that
+    /// it failed with `error`.
+    ///
+    /// The event has already happened, so the webhooks that fail are only warned about.
+    pub fn notify(
+        &self,
+        event: Event,
+        game: &Game,
+        archive: Option<&Path>,
+        duration: std::time::Duration,
+        error: Option<&Report>,
+    ) -> Result<()> {
+        let archive_name = archive.map(|a| a.display().to_string()).unwrap_or_default();
+        match error {
+            None => tracing::info!(
+                "{} {} {archive_name} in {duration:?}",
+                event.name(),
+                game.name()
+            ),
+            Some(e) => tracing::error!("{} {}: {e}", event.failed_name(), game.name()),
+        }
+        self.observe(|o| o.on_event(event, game, archive, error));
+        let config = self.game_config(game)?;
+        let summary = match (event, error) {
+            // The result of the run itself is already in the terminal of the game
+            (Event::RunDone, _) => None,
+            // Only the failures are worth a notification
+            (Event::BackupPruned, None) => None,
+            (Event::BackupCreated, None) => Some(crate::tr!("backup-done", game = game.name())),
+            (Event::RestoreDone, None) => Some(crate::tr!("restore-done", game = game.name())),
+            (_, Some(_)) => Some(format!("Could not {} {}", event.action(), game.name())),
+        };
+        if let Some(summary) = summary {
+            let body = match error {
+                Some(e) => e.to_string(),
+                None => archive_name.clone(),
+            };
+            self.notify_desktop(game, &summary, body.trim(), error.is_some())?;
+        }
+        let events = match (event, error) {
+            (_, Some(_)) | (Event::RunDone, _) => &[][..],
+            (Event::BackupCreated, None) => &config.events.on_backup_created,
+            (Event::RestoreDone, None) => &config.events.on_restore_done,
+            (Event::BackupPruned, None) => &config.events.on_prune,
+        };
+        let urls = events.iter().chain(&config.webhooks).collect::<Vec<_>>();
+        if urls.is_empty() {
+            return Ok(());
+        }
+        #[cfg(not(feature = "webhooks"))]
+        {
+            let _ = (game, archive, duration, error);
+            crate::output::warning(format_args!("{WEBHOOKS_FEATURE_MISSING}"));
+        }
+        #[cfg(feature = "webhooks")]
+        {
+            let name = match archive {
+                Some(archive) => Some(archive.file_name().ok_or_report()?.to_string_lossy()),
+                None => None,
+            };
+            let error = error.map(|e| e.to_string());
+            let payload = crate::events::Payload {
+                event: match error {
+                    Some(_) => event.failed_name(),
+                    None => event.name(),
+                },
+                action: event.action(),
+                game: game.name(),
+                archive: name.as_deref(),
+                size: archive.and_then(|a| a.metadata().ok()).map(|m| m.len()),
+                duration: duration.as_secs_f64(),
+                success: error.is_none(),
+                error: error.as_deref().map(str::trim),
+            };
+            for url in urls {
+                if let Err(e) = crate::events::post(url, &payload) {
+                    crate::output::warning(format_args!(
+                        "The {} webhook failed: {e}",
+                        event.name()
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Shows a desktop notification of a result of the game if configured, only warning if it
+    /// can't be shown.
+    pub fn notify_desktop(
+        &self,
+        game: &Game,
+        summary: &str,
+        body: &str,
+        failed: bool,
+    ) -> Result<()> {
+        let notifications = &self.game_config(game)?.notifications;
+        if !notifications.enabled || (notifications.only_failures && !failed) {
+            return Ok(());
+        }
+        #[cfg(not(feature = "notifications"))]
+        {
+            let _ = (summary, body);
+            crate::output::warning(format_args!("{NOTIFICATIONS_FEATURE_MISSING}"));
+        }
+        #[cfg(feature = "notifications")]
+        if let Err(e) = crate::events::show(summary, body, failed) {
+            crate::output::warning(format_args!("{e}"));
+        }
+        Ok(())
+    }
+
+    /// Applies the configured [`Permissions`](crate::config::Permissions) to the backups directory
+    /// of the game and the backups in it.
+    ///
+    /// Only the entries owned by the current user are changed, as those are the only ones it can.
+    pub fn share_backups(&self, game: &Game) -> Result<()> {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let Some(permissions) = &self.config()?.backup.permissions else {
+            return Ok(());
+        };
+        let gid = permissions.group.as_deref().map(group_id).transpose()?;
+        let mode = match &permissions.mode {
+            Some(mode) => Some(
+                u32::from_str_radix(mode, 8)
+                    .context_with(|| format!("Invalid backup permissions mode {mode:?}"))?,
+            ),
+            None => None,
+        };
+        let uid = rustix::process::getuid().as_raw();
+
+        let dir = game.backups_path();
+        let backups = game.backups()?.into_iter().map(|backup| backup.path);
+        for path in std::iter::once(dir.clone()).chain(backups) {
+            let metadata = path.metadata()?;
+            if metadata.uid() != uid {
+                continue;
+            }
+            if gid.is_some() {
+                std::os::unix::fs::chown(&path, None, gid)
+                    .context_with(|| format!("Could not change the group of {}", path.display()))?;
+            }
+            let mode = match mode {
+                // Directories can be entered by whoever can read them, and their new files inherit
+                // the group
+                Some(mode) if path == dir => mode | (mode & 0o444) >> 2 | 0o2000,
+                Some(mode) => mode,
+                None if path == dir && gid.is_some() => metadata.mode() | 0o2000,
+                None => continue,
+            };
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                .context_with(|| format!("Could not change the mode of {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Command that extracts `slot` of the container at `container` into `output`.
+    ///
+    /// If no output is given, the slot is printed to stdout.
+    pub fn slot_extract_command(
+        &self,
+        game: &Game,
+        container: &Path,
+        slot: &str,
+        output: Option<&Path>,
+    ) -> Result<Option<std::process::Command>> {
+        let Some(container_cfg) = &game.container else {
+            return Ok(None);
+        };
+        let mut cmd = container_cfg.extract.clone();
+        if let Some(output) = output {
+            cmd = format!(
+                "({cmd}) > {}",
+                crate::runner::shell_quote(&output.to_string_lossy())
+            );
+        }
+        self.commands_to_process(&[Container::replace_vars(cmd, container, slot)], Some(game))
+    }
+
+    /// Command that replaces `slot` of the container at `container` with the contents of `input`.
+    pub fn slot_inject_command(
+        &self,
+        game: &Game,
+        container: &Path,
+        slot: &str,
+        input: &Path,
+    ) -> Result<Option<std::process::Command>> {
+        let Some(container_cfg) = &game.container else {
+            return Ok(None);
+        };
+        let cmd = format!(
+            "({}) < {}",
+            container_cfg.inject,
+            crate::runner::shell_quote(&input.to_string_lossy())
+        );
+        self.commands_to_process(&[Container::replace_vars(cmd, container, slot)], Some(game))
+    }
+
+    /// Command that compresses its stdin, if the game uses an external compressor.
+    pub fn compress_command(&self, game: &Game) -> Result<Option<std::process::Command>> {
+        let Some(compression) = self.compression(game)? else {
+            return Ok(None);
+        };
+        self.commands_to_process(std::slice::from_ref(&compression.compress), Some(game))
+    }
+
+    /// Command that decompresses its stdin, if the game uses an external compressor.
+    pub fn decompress_command(&self, game: &Game) -> Result<Option<std::process::Command>> {
+        let Some(compression) = self.compression(game)? else {
+            return Ok(None);
+        };
+        self.commands_to_process(std::slice::from_ref(&compression.decompress), Some(game))
+    }
+
+    /// Command that runs the game, with `args` in place of @ARGS, or after @EXE if no command
+    /// uses @ARGS, or at the end if none uses @EXE either.
+    ///
+    /// The wrappers of the game go before @EXE, or at the start of the last command.
+    pub fn run_command(
+        &self,
+        game: &Game,
+        args: &[String],
+    ) -> Result<Option<std::process::Command>> {
+        if let Some(version) = game.proton_version() {
+            if game.wine_prefix().is_none() {
+                bail!(
+                    "{} uses {version}, which needs a prefix, set it with 'gg edit --prefix'",
+                    game.name()
+                )
+            }
+            if crate::paths::proton(version).is_none() {
+                bail!(
+                    "{version} is not installed, the installed versions are {:?}",
+                    crate::paths::proton_versions()
+                )
+            }
+        }
+        let config = self.game_config(game)?;
+        // Like in the launch options of Steam, %command% is the command of the game
+        let mut global_run = config
+            .run
+            .commands
+            .iter()
+            .map(|cmd| cmd.replace("%command%", "@EXE"))
+            .collect::<Vec<_>>();
+        let mut cmds = game.run_commands.clone().unwrap_or_default();
+        let splices = cmds
+            .iter()
+            .any(|cmd| cmd.contains("@RUN") || cmd.contains("%command%"));
+        let args_placed = cmds.iter().any(|cmd| cmd.contains("@ARGS"));
+        let placeholder = if args.is_empty() {
+            ""
+        } else {
+            ARGS_PLACEHOLDER
+        };
+        let wrappers = game.wrappers().map(Wrappers::prefix).unwrap_or_default();
+        if game.run_commands.is_none() || (splices && !args_placed) {
+            add_args_and_wrappers(&mut global_run, placeholder, &wrappers);
+        } else {
+            add_args_and_wrappers(&mut cmds, placeholder, &wrappers);
+        }
+        // The arguments and the global run commands are put in once the variables are replaced, so
+        // nothing in them is taken for a variable
+        let args = crate::runner::shell_join(args);
+        let finish = |cmd: String, run: &str| {
+            game.replace_vars(cmd)
+                .replace(ARGS_PLACEHOLDER, &args)
+                .replace(RUN_PLACEHOLDER, run)
+        };
+        let global_run = global_run
+            .into_iter()
+            .map(|cmd| finish(cmd, ""))
+            .collect::<Vec<_>>();
+        let cmds = if game.run_commands.is_none() {
+            global_run
+        } else if splices {
+            // Several commands are run by a shell of their own, so a wrapper like
+            // "gamemoderun @RUN" wraps all of them
+            let global_run = match global_run.as_slice() {
+                [cmd] => cmd.clone(),
+                cmds => crate::runner::shell_join(&config.shell.args(cmds.join(" && "))?),
+            };
+            cmds.into_iter()
+                .map(|cmd| {
+                    let cmd = cmd
+                        .replace("@RUN", RUN_PLACEHOLDER)
+                        .replace("%command%", RUN_PLACEHOLDER);
+                    finish(cmd, &global_run)
+                })
+                .collect()
+        } else {
+            cmds.into_iter().map(|cmd| finish(cmd, "")).collect()
+        };
+        if cmds.is_empty() {
+            return Ok(None);
+        }
+        self.shell_command(cmds.join(" && "), Some(game)).map(Some)
+    }
+}
+
+/// Stand-ins for the arguments given to 'gg run' and the global run commands while the variables
+/// of the run commands are replaced. Commands can't have NUL bytes, so they never clash with them.
+const ARGS_PLACEHOLDER: &str = "\0ARGS\0";
+const RUN_PLACEHOLDER: &str = "\0RUN\0";
+
+/// Adds the arguments given to 'gg run' to `cmds`, replacing @ARGS or after @EXE, and puts the
+/// wrappers before @EXE, both in the last command if there is no @EXE.
+fn add_args_and_wrappers(cmds: &mut [String], args: &str, wrappers: &str) {
+    if cmds.iter().any(|cmd| cmd.contains("@ARGS")) {
+        for cmd in cmds.iter_mut() {
+            *cmd = cmd.replace("@ARGS", args);
+        }
+    } else if !args.is_empty() {
+        match cmds.iter_mut().find(|cmd| cmd.contains("@EXE")) {
+            Some(cmd) => *cmd = cmd.replace("@EXE", &format!("@EXE {args}")),
+            None => {
+                if let Some(last) = cmds.last_mut() {
+                    last.push(' ');
+                    last.push_str(args);
+                }
+            }
+        }
+    }
+    if !wrappers.is_empty() {
+        match cmds.iter_mut().find(|cmd| cmd.contains("@EXE")) {
+            Some(cmd) => *cmd = cmd.replacen("@EXE", &format!("{wrappers} @EXE"), 1),
+            None => {
+                if let Some(last) = cmds.last_mut() {
+                    *last = format!("{wrappers} {last}");
+                }
+            }
+        }
+    }
+}
+
+/// Times a failed cloud operation is retried if not configured.
+const DEFAULT_RETRIES: u32 = 2;
+/// Delay before the first retry of a cloud operation if not configured.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(5);
+/// Longest delay between two retries of a cloud operation, however many have failed.
+const MAX_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Version of the layout of the games database, increased with every change that needs a migration.
+pub(crate) const GAMES_VERSION: u32 = 2;
+
+/// Games database of the builds without the "sqlite" feature, which the others migrate from.
+pub(crate) const GAMES_FILE: &str = "games.yaml";
+
+/// Upgrades of the games database, the first one from version 1 to 2.
+const GAMES_MIGRATIONS: [fn(Value) -> Value; GAMES_VERSION as usize - 1] = [
+    // Version 1 was the bare list of games
+    |games| {
+        Value::Map(BTreeMap::from([
+            (String::from("version"), Value::Int(2)),
+            (String::from("games"), games),
+        ]))
+    },
+];
+
+/// Layout of the games database.
+#[derive(serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+struct GamesFile<G> {
+    version: u32,
+    games: G,
+}
+
+/// Games written by 'gg export' for 'gg import' to set them up in another machine.
+///
+/// Laid out like the games database with the metadata of the backups added, so the database of
+/// another machine can be imported too. The paths start with variables like `$HOME` where they
+/// can, which are resolved when read.
+#[derive(Debug, serde::Serialize)]
+pub struct Export {
+    version: u32,
+    pub games: Vec<Game>,
+    /// Metadata of the backups of every game by its name, if exported with them.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub backups: BTreeMap<String, BTreeMap<String, BackupMetadata>>,
+}
+
+impl Export {
+    /// Exports `games`, with the metadata of their backups if `with_backups`.
+    pub fn new<'a>(games: impl IntoIterator<Item = &'a Game>, with_backups: bool) -> Export {
+        let mut export = Export {
+            version: GAMES_VERSION,
+            games: Vec::new(),
+            backups: BTreeMap::new(),
+        };
+        for game in games {
+            if with_backups {
+                let metadata = game.backups_metadata();
+                if !metadata.is_empty() {
+                    export.backups.insert(game.name.clone(), metadata);
+                }
+            }
+            export.games.push(game.clone());
+        }
+        export
+    }
+
+    /// Reads the export at `path`, or the games database of another machine.
+    pub fn read(path: &Path) -> Result<Export> {
+        #[derive(serde::Deserialize)]
+        struct Backups {
+            #[serde(default)]
+            backups: BTreeMap<String, BTreeMap<String, BackupMetadata>>,
+        }
+        let games = Games::read_games(path)?;
+        // Only the exports have them, and the old databases are bare lists
+        let backups = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_saphyr::from_str::<Backups>(&contents).ok())
+            .map(|b| b.backups)
+            .unwrap_or_default();
+        Ok(Export {
+            version: GAMES_VERSION,
+            games,
+            backups,
+        })
+    }
+
+    /// Writes the export to `path`.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let contents = serde_saphyr::to_string(self)
+            .context_with(|| format!("Could not export to {}", path.display()))?;
+        std::fs::write(path, contents)
+            .context_with(|| format!("Could not write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// What [`Games::import`] did with every imported game, by name.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct ImportReport {
+    /// Games that were not managed.
+    pub added: Vec<String>,
+    /// Managed games with other settings, whose settings were replaced by the imported ones.
+    pub replaced: Vec<String>,
+    /// Managed games with the same settings.
+    pub unchanged: Vec<String>,
+    /// Managed games with other settings, kept as they were.
+    pub conflicts: Vec<ImportConflict>,
+}
+
+/// Managed game whose settings differ from the imported ones.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ImportConflict {
+    pub game: String,
+    /// Settings that differ, like "root" or "executable".
+    pub fields: Vec<String>,
+}
+
+/// Returns the names of the settings of `a` and `b` that differ.
+fn differing_fields(a: &Game, b: &Game) -> Result<Vec<String>> {
+    let (serde_json::Value::Object(a), serde_json::Value::Object(b)) =
+        (serde_json::to_value(a)?, serde_json::to_value(b)?)
+    else {
+        bail!("Games are not serialized as maps")
+    };
+    let mut fields = a
+        .iter()
+        .filter(|(field, value)| b.get(*field) != Some(value))
+        .map(|(field, _)| field.clone())
+        .chain(b.keys().filter(|field| !a.contains_key(*field)).cloned())
+        .collect::<Vec<_>>();
+    fields.sort_unstable();
+    Ok(fields)
+}
+
+/// Reads the games database at `games_path`, creating it if it doesn't exist.
+///
+/// Returns whether it was upgraded from an older version, after keeping a copy of the old one.
+#[cfg(not(feature = "sqlite"))]
+fn load_games_file(games_path: &Path) -> Result<(Vec<Game>, bool)> {
+    let games_file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .create(true)
+        .open(games_path)
+        .context_with(|| format!("Could not read {}", games_path.display()))?;
+    // Read at once, as parsing from the file makes a syscall for every few bytes
+    let contents = std::io::read_to_string(games_file)
+        .context_with(|| format!("Could not read {}", games_path.display()))?;
+    let version = games_version(&contents);
+    if version < GAMES_VERSION {
+        let old_path = games_path.with_extension(format!("v{version}.yaml"));
+        std::fs::write(&old_path, &contents)
+            .context_with(|| format!("Could not back up {}", games_path.display()))?;
+        eprintln!(
+            "Upgrading {} to version {GAMES_VERSION}, the old one is kept in {}",
+            games_path.display(),
+            old_path.display()
+        );
+    }
+    let games = parse_games(&contents, version, games_path)?;
+    Ok((games, version < GAMES_VERSION))
+}
+
+/// Writes `games` to the games database at `path`.
+///
+/// Written to a temporary file that then replaces the database, so a crash never leaves it
+/// half-written.
+#[cfg(not(feature = "sqlite"))]
+fn write_games_file(path: &Path, games: &[Game]) -> Result<()> {
+    use std::io::Write;
+
+    let contents = if games.is_empty() {
+        String::new()
+    } else {
+        let file = GamesFile {
+            version: GAMES_VERSION,
+            games,
+        };
+        serde_saphyr::to_string(&file)
+            .context_with(|| format!("Could not save to {}", path.display()))?
+    };
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+    let write = || -> std::io::Result<()> {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temp, path)
+    };
+    if let Err(e) = write() {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e).context_with(|| format!("Could not save to {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Returns the version of the games database in `contents`, 1 if it has none.
+fn games_version(contents: &str) -> u32 {
+    #[derive(serde::Deserialize)]
+    struct Version {
+        version: u32,
+    }
+    if contents.trim().is_empty() {
+        return GAMES_VERSION;
+    }
+    serde_saphyr::from_str::<Version>(contents).map_or(1, |v| v.version)
+}
+
+/// Parses the games database in `contents`, read from `path`, upgrading it from `version` if it
+/// is an older one.
+fn parse_games(contents: &str, version: u32, path: &Path) -> Result<Vec<Game>> {
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    if version > GAMES_VERSION {
+        bail!(
+            "{} is version {version}, which is newer than this gg supports, update gg to use it",
+            path.display()
+        )
+    }
+    // The versions start at 1, so 0 was written by hand or by something else
+    if version == 0 {
+        bail!(
+            "{} is version 0, which no gg writes, set it to the version the file was written with",
+            path.display()
+        )
+    }
+    let upgraded;
+    let contents = if version < GAMES_VERSION {
+        let mut value =
+            serde_saphyr::from_str_with_options(contents, crate::config::yaml_options())
+                .context_with(|| format!("Could not parse {}", path.display()))?;
+        for migration in &GAMES_MIGRATIONS[version as usize - 1..] {
+            value = migration(value);
+        }
+        upgraded = serde_saphyr::to_string(&value)
+            .context_with(|| format!("Could not upgrade {}", path.display()))?;
+        &upgraded
+    } else {
+        contents
+    };
+    Ok(serde_saphyr::from_str::<GamesFile<Vec<Game>>>(contents)
+        .context_with(|| format!("Could not parse {}", path.display()))?
+        .games)
+}
+
+/// Returns the directory where gg stores its data, resolved once per process.
+pub fn data_dir() -> Result<&'static Path> {
+    static DATA_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+    if let Some(dir) = DATA_DIR.get() {
+        return Ok(dir);
+    }
+    let dir = crate::paths::data_dir().context("Could not obtain data directory")?;
+    Ok(DATA_DIR.get_or_init(|| dir))
+}
+
+/// File locked in the data directory by the processes that change the game database.
+const LOCK_FILE: &str = "games.lock";
+
+/// Where [`Games`] are loaded from, created with [`Games::builder`].
+///
+/// By default they are the ones of gg, like [`Games::load`] does.
+#[derive(Debug, Default)]
+pub struct GamesBuilder {
+    config_path: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    in_memory: bool,
+    locked: bool,
+}
+
+impl GamesBuilder {
+    /// Reads the config only from the file at `path`, instead of the system and user ones.
+    pub fn config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Keeps the games database in `dir` instead of the data directory of gg.
+    pub fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(dir.into());
+        self
+    }
+
+    /// Starts with no games and never writes them to disk, using the default config unless
+    /// [`config_path`](Self::config_path) is given.
+    pub fn in_memory(mut self) -> Self {
+        self.in_memory = true;
+        self
+    }
+
+    /// Holds the lock of the database until the games are dropped, like [`Games::load_locked`].
+    pub fn locked(mut self) -> Self {
+        self.locked = true;
+        self
+    }
+
+    /// Loads the games with these options.
+    pub fn load(self) -> Result<Games> {
+        let data_dir = match self.data_dir {
+            Some(dir) => dir,
+            None => data_dir()?.to_path_buf(),
+        };
+        let mut games = Games {
+            inner: Vec::new(),
+            config: std::sync::OnceLock::new(),
+            config_path: self.config_path,
+            in_memory: self.in_memory,
+            data_dir,
+            lock: None,
+            runner: Box::new(SystemRunner),
+            cloud_backend: None,
+            observers: Vec::new(),
+            archivers: BTreeMap::from([(
+                String::from(crate::archive::TAR),
+                Box::new(crate::archive::Tar) as Box<dyn Archiver>,
+            )]),
+            broken: HashMap::new(),
+            passphrase: None,
+            auto_prune: None,
+        };
+        if self.in_memory {
+            if games.config_path.is_none() {
+                games.ignore_config();
+            }
+            return Ok(games);
+        }
+
+        std::fs::create_dir_all(&games.data_dir)
+            .context_with(|| format!("Could not create {}", games.data_dir.display()))?;
+        if self.locked {
+            games.lock = Some(lock_database(&games.data_dir)?);
+        }
+        #[cfg(not(feature = "sqlite"))]
+        let (inner, upgraded) = load_games_file(&games.data_dir.join(GAMES_FILE))?;
+        #[cfg(feature = "sqlite")]
+        let (inner, upgraded) = (crate::sqlite::load(&games.data_dir)?, false);
+        games.inner = inner;
+        if upgraded {
+            games.store()?;
+        }
+        games.validate();
+        Ok(games)
+    }
+}
+
+/// Asks which of the `names` to use, starting at the one in `cursor`.
+#[cfg(feature = "cli")]
+fn select_name(names: Vec<&str>, cursor: usize) -> Result<Option<&str>> {
+    let name = inquire::Select::new(&crate::tr!("prompt-game"), names)
+        .with_starting_cursor(cursor)
+        .prompt()
+        .context("Could not read the game")?;
+    Ok(Some(name))
+}
+
+/// Never asks, as the prompts are part of the "cli" feature.
+#[cfg(not(feature = "cli"))]
+fn select_name(_: Vec<&str>, _: usize) -> Result<Option<&str>> {
+    Ok(None)
+}
+
+/// Locks the games database in `data_dir`, waiting for the process that holds it, if any.
+fn lock_database(data_dir: &Path) -> Result<std::fs::File> {
+    let path = data_dir.join(LOCK_FILE);
+    let lock = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(false)
+        .create(true)
+        .open(&path)
+        .context_with(|| format!("Could not open {}", path.display()))?;
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(std::fs::TryLockError::WouldBlock) => {
+            crate::output::progress("Waiting for another gg to finish changing the games...");
+            lock.lock()
+                .context_with(|| format!("Could not lock {}", path.display()))?;
+        }
+        Err(std::fs::TryLockError::Error(e)) => {
+            return Err(e).context_with(|| format!("Could not lock {}", path.display()))?;
+        }
+    }
+    Ok(lock)
+}
+
+/// Games transferred to the cloud at the same time if not configured.
+const DEFAULT_PARALLELISM: usize = 4;
+
+/// Outcome of [`Games::cloud_sync`], with the names of the affected backups.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub uploaded: Vec<String>,
+    pub downloaded: Vec<String>,
+    /// Backups that exist both locally and in the cloud with a different size.
+    pub conflicts: Vec<String>,
+}
+
+/// Differences between the local backups of a game and the ones in the cloud storage.
+#[derive(Debug, Default)]
+pub struct CloudStatus {
+    /// Backups that haven't been pushed to the cloud.
+    pub local_only: Vec<String>,
+    /// Backups in the cloud that haven't been pulled, made on other machines.
+    pub remote_only: Vec<String>,
+    /// Backups that exist both locally and in the cloud with a different size.
+    pub conflicts: Vec<String>,
+}
+
+/// Compares the local backups of the game with the `remote` ones, given by name and size.
+///
+/// The sizes are ignored if `compare_sizes` is `false`, as encrypted backups never match.
+fn compare_backups(
+    game: &Game,
+    remote: &BTreeMap<String, u64>,
+    compare_sizes: bool,
+) -> Result<CloudStatus> {
+    let mut local = BTreeMap::new();
+    if game.backups_path().exists() {
+        for backup in game.backups()? {
+            local.insert(backup.name().into_owned(), backup.size);
+        }
+    }
+
+    let pending = game.pending_uploads();
+    let mut status = CloudStatus::default();
+    for (name, size) in &local {
+        match remote.get(name) {
+            // Pending ones changed since they were uploaded, like when re-encrypted
+            None => status.local_only.push(name.clone()),
+            Some(_) if pending.contains(name) => status.local_only.push(name.clone()),
+            Some(remote_size) if compare_sizes && remote_size != size => {
+                status.conflicts.push(name.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    status.remote_only = remote
+        .keys()
+        .filter(|name| !local.contains_key(*name))
+        .cloned()
+        .collect();
+    Ok(status)
+}
+
+/// Returns the existing paths matched by `pattern`, whose components can have the `*` and `?`
+/// wildcards or be `**` to match any number of directories.
+pub(crate) fn expand_glob(pattern: &Path) -> Vec<PathBuf> {
+    let mut matches = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let component = component.as_os_str().to_string_lossy();
+        let mut next = Vec::new();
+        for base in matches {
+            if component == "**" {
+                next.extend(
+                    walkdir::WalkDir::new(&base)
+                        .into_iter()
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| entry.file_type().is_dir())
+                        .map(|entry| entry.into_path()),
+                );
+            } else if component.contains(['*', '?']) {
+                let Ok(entries) = std::fs::read_dir(&base) else {
+                    continue;
+                };
+                next.extend(
+                    entries
+                        .flatten()
+                        .filter(|e| wildcard_match(&component, &e.file_name().to_string_lossy()))
+                        .map(|e| e.path()),
+                );
+            } else {
+                let path = base.join(&*component);
+                if path.symlink_metadata().is_ok() {
+                    next.push(path);
+                }
+            }
+        }
+        matches = next;
+    }
+    matches
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any characters and `?` a single one.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the character of the name it is matched up to
+    let mut star = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            p += 1;
+            star = Some((p, n));
+        } else if let Some((after_star, matched)) = star {
+            p = after_star;
+            n = matched + 1;
+            star = Some((after_star, n));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Number of characters to insert, delete or replace to turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let replaced = previous[j] + usize::from(a != *b);
+            current.push(replaced.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns the id of the group called `name`.
+fn group_id(name: &str) -> Result<u32> {
+    let group = nix::unistd::Group::from_name(name)
+        .context_with(|| format!("Could not look up the group {name:?}"))?;
+    let Some(group) = group else {
+        bail!("The group {name:?} does not exist")
+    };
+    Ok(group.gid.as_raw())
+}
+
+/// Replaces @BACKUP-FILE in `cmds` with the path of `backup`, or an empty string if missing.
+fn with_backup_file(cmds: &[String], backup: Option<&Path>) -> Vec<String> {
+    let backup = backup.map_or(String::from("''"), |b| {
+        crate::runner::shell_quote(&b.to_string_lossy())
+    });
+    cmds.iter()
+        .map(|cmd| cmd.replace("@BACKUP-FILE", &backup))
+        .collect()
+}
+
+/// Description of the backups made while a game is running.
+pub const AUTOSAVE_DESCRIPTION: &str = "autosave";
+/// Description of the backups made by 'gg watch' when a save changes.
+pub const WATCH_DESCRIPTION: &str = "watch";
+/// Description of the backups made by 'gg watch' at the times of 'backup.schedule'.
+pub const SCHEDULED_DESCRIPTION: &str = "scheduled";
+
+#[cfg(not(feature = "webhooks"))]
+const WEBHOOKS_FEATURE_MISSING: &str =
+    "Webhooks are configured, but gg was built without the \"webhooks\" feature";
+
+#[cfg(not(feature = "notifications"))]
+const NOTIFICATIONS_FEATURE_MISSING: &str =
+    "Notifications are enabled, but gg was built without the \"notifications\" feature";
+
+impl std::fmt::Display for Games {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        serde_saphyr::to_fmt_writer(f, &self.games()).map_err(|_| std::fmt::Error)
+    }
+}
+
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Game {
+    name: String,
+    /// The paths are stored with the variables of [`crate::paths`], like `$STEAM`, and can use
+    /// environment variables, both resolved when loading the games.
+    #[serde(serialize_with = "contracted_path", deserialize_with = "resolved_path")]
+    root: PathBuf,
+    #[serde(serialize_with = "contracted_path", deserialize_with = "resolved_path")]
+    save_location: PathBuf,
+    #[serde(
+        default,
+        serialize_with = "contracted_optional_path",
+        deserialize_with = "resolved_optional_path"
+    )]
+    executable: Option<PathBuf>,
+    executable_args: Option<Vec<String>>,
+    environment_vars: Option<HashMap<String, String>>,
+    /// Wine or Proton prefix of the game, exported as WINEPREFIX to its commands.
+    #[serde(
+        default,
+        serialize_with = "contracted_optional_path",
+        deserialize_with = "resolved_optional_path"
+    )]
+    wine_prefix: Option<PathBuf>,
+    /// Proton that runs the executable, like "Proton 9.0" or "GE-Proton9-20".
+    #[serde(default)]
+    proton_version: Option<String>,
+    /// Id of the game in Steam, which launches it instead of running the executable.
+    #[serde(default)]
+    steam_appid: Option<u32>,
+    run_commands: Option<Vec<String>>,
+    /// Other ways of launching the game, like its launcher or a modded executable, selected with
+    /// 'gg run --profile'. The first one is used by default.
+    #[serde(default)]
+    launch_profiles: Option<Vec<LaunchProfile>>,
+    /// Directory 'gg run' starts the game in, relative to the root, for games that only work
+    /// when launched from a subdirectory like "bin".
+    #[serde(
+        default,
+        serialize_with = "contracted_optional_path",
+        deserialize_with = "resolved_optional_path"
+    )]
+    workdir: Option<PathBuf>,
+    /// Programs that wrap the executable in 'gg run', like gamescope or MangoHud.
+    #[serde(default)]
+    wrappers: Option<Wrappers>,
+    container: Option<Container>,
+    compression: Option<Compression>,
+    /// Format of the backups, the name of an archiver registered with
+    /// [`Games::register_archiver`], or "tar" by default.
+    #[serde(default)]
+    archiver: Option<String>,
+    cloud: Option<GameCloud>,
+    /// Files or directories deleted after restoring a backup, like shader caches that don't match
+    /// an older save.
+    ///
+    /// Relative to the root of the game, with the `*` and `?` wildcards and `**` for any number
+    /// of directories (e.g. "cache/shaders/**/*.bin").
+    post_restore_clean: Option<Vec<String>>,
+    /// Labels to select several games at once with --tag, like "emulated" or "multiplayer".
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+    /// Short names accepted wherever the name of the game is, like "er" for "Elden Ring".
+    #[serde(default)]
+    aliases: Option<Vec<String>>,
+    /// Free text about the game, like its mods, the account it uses or the controller setup.
+    #[serde(default)]
+    notes: Option<String>,
+    /// People sharing the game with their own saves, each one with its backups kept apart from
+    /// the rest and selected with --player.
+    #[serde(default)]
+    players: Option<Vec<Player>>,
+    /// Hidden from the lists, the completions and the commands acting on every game, while its
+    /// backups are kept, like for an uninstalled game that may be installed again.
+    #[serde(default)]
+    archived: bool,
+    /// Player whose backups and save are used, set with [`Game::with_player`].
+    #[serde(skip)]
+    player: Option<String>,
+}
+
+fn contracted_path<S: serde::Serializer>(
+    path: &Path,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(&crate::paths::contract(path), s)
+}
+
+fn contracted_optional_path<S: serde::Serializer>(
+    path: &Option<PathBuf>,
+    s: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(&path.as_deref().map(crate::paths::contract), s)
+}
+
+fn resolved_path<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<PathBuf, D::Error> {
+    <PathBuf as serde::Deserialize>::deserialize(d).map(|path| crate::paths::resolve(&path))
+}
+
+fn resolved_optional_path<'de, D: serde::Deserializer<'de>>(
+    d: D,
+) -> std::result::Result<Option<PathBuf>, D::Error> {
+    <Option<PathBuf> as serde::Deserialize>::deserialize(d)
+        .map(|path| path.as_deref().map(crate::paths::resolve))
+}
+
+/// Name of the file shipped by game packagers in the root of a game, read by 'gg add'.
+pub const DROP_IN_FILE: &str = "gg-config.json";
+
+/// Settings of a game described by its packager in the [`DROP_IN_FILE`] of its root, used by
+/// 'gg add' for the arguments that are not given.
+///
+/// Relative paths are relative to the root, and environment variables like `$HOME` are expanded.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DropIn {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "resolved_optional_path")]
+    pub save_location: Option<PathBuf>,
+    #[serde(default, deserialize_with = "resolved_optional_path")]
+    pub executable: Option<PathBuf>,
+    pub executable_args: Option<Vec<String>>,
+    pub run_commands: Option<Vec<String>>,
+}
+
+/// Problem of a loaded game that prevents using it, found by [`Game::validate`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "kind", content = "path", rename_all = "kebab-case")]
+pub enum Broken {
+    /// The root was moved or deleted, like when the game was uninstalled.
+    MissingRoot(PathBuf),
+    /// The save location does not exist, although the root does.
+    MissingSaveLocation(PathBuf),
+    /// The absolute path of the executable does not exist.
+    MissingExecutable(PathBuf),
+    MissingWinePrefix(PathBuf),
+}
+
+impl Broken {
+    /// Returns the path that does not exist.
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::MissingRoot(path)
+            | Self::MissingSaveLocation(path)
+            | Self::MissingExecutable(path)
+            | Self::MissingWinePrefix(path) => path,
+        }
+    }
+
+    /// Returns whether the problem makes the `operation` fail.
+    ///
+    /// A missing save location only prevents backups, as it is created when restoring and by the
+    /// game when run.
+    pub fn prevents(&self, operation: Event) -> bool {
+        match self {
+            Self::MissingRoot(_) => true,
+            Self::MissingSaveLocation(_) => operation == Event::BackupCreated,
+            Self::MissingExecutable(_) | Self::MissingWinePrefix(_) => operation == Event::RunDone,
+        }
+    }
+}
+
+impl std::fmt::Display for Broken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let what = match self {
+            Self::MissingRoot(_) => "root",
+            Self::MissingSaveLocation(_) => "save location",
+            Self::MissingExecutable(_) => "executable",
+            Self::MissingWinePrefix(_) => "wine prefix",
+        };
+        write!(f, "The {what} does not exist: {}", self.path().display())
+    }
+}
+
+impl DropIn {
+    /// Reads the drop-in file in `root`, if it has one, with its paths joined to `root`.
+    pub fn read(root: &Path) -> Result<Option<DropIn>> {
+        let path = root.join(DROP_IN_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&path)
+            .context_with(|| format!("Could not read {}", path.display()))?;
+        let mut drop_in = serde_json::from_str::<DropIn>(&contents)
+            .context_with(|| format!("Invalid game settings in {}", path.display()))?;
+        for path in [&mut drop_in.save_location, &mut drop_in.executable]
+            .into_iter()
+            .flatten()
+        {
+            *path = root.join(&*path);
+        }
+        Ok(Some(drop_in))
+    }
+}
+
+/// Cloud settings of a single game, overriding the global ones.
+#[derive(
+    Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct GameCloud {
+    /// Whether the backups of the game are sent to the cloud at all.
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+    /// Backend used instead of the configured one.
+    pub backend: Option<Backend>,
+    /// Cloud remote of the game, overrides the one of the backend.
+    pub remote: Option<String>,
+}
+
+impl Default for GameCloud {
+    fn default() -> Self {
+        Self {
+            enabled: enabled_default(),
+            backend: None,
+            remote: None,
+        }
+    }
+}
+
+impl GameCloud {
+    /// Uses a backend of type `kind` ("git", "rclone", "rsync" or "webdav") without settings, or the
+    /// configured one if `None`.
+    pub fn set_backend(&mut self, kind: Option<&str>) -> Result<()> {
+        self.backend = kind.map(Backend::from_type).transpose()?;
+        Ok(())
+    }
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+/// A way of launching a game, replacing its executable or run commands.
+#[derive(
+    Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct LaunchProfile {
+    pub name: String,
+    /// Executable launched instead of the one of the game, relative to its root.
+    #[serde(
+        default,
+        serialize_with = "contracted_optional_path",
+        deserialize_with = "resolved_optional_path"
+    )]
+    pub executable: Option<PathBuf>,
+    #[serde(default)]
+    pub executable_args: Option<Vec<String>>,
+    /// Run commands used instead of the ones of the game.
+    #[serde(default)]
+    pub run_commands: Option<Vec<String>>,
+}
+
+/// Person sharing a game, whose backups are named after them, like "Celeste@alice-003.tar.zst",
+/// so they are numbered and restored apart from the ones of the other players.
+#[derive(
+    Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Player {
+    pub name: String,
+    /// Save location used instead of the one of the game, relative to its root, for games that
+    /// keep every profile in its own directory.
+    #[serde(
+        default,
+        serialize_with = "contracted_optional_path",
+        deserialize_with = "resolved_optional_path"
+    )]
+    pub save_location: Option<PathBuf>,
+}
+
+/// Programs that wrap the executable of a game in 'gg run', put right before @EXE.
+#[derive(
+    Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Wrappers {
+    /// Runs the game inside gamescope.
+    #[serde(default)]
+    pub gamescope: bool,
+    /// Arguments of gamescope, like ["-W", "2560", "-H", "1440", "-f"].
+    #[serde(default)]
+    pub gamescope_args: Vec<String>,
+    /// Shows the MangoHud overlay.
+    #[serde(default)]
+    pub mangohud: bool,
+    /// Other wrappers put before the rest, like "gamemoderun" or "prime-run".
+    #[serde(default)]
+    pub extra: Vec<String>,
+}
+
+impl Wrappers {
+    /// Returns the wrappers in the order they run, like "gamemoderun gamescope -f -- mangohud", or
+    /// an empty string if there are none.
+    pub fn prefix(&self) -> String {
+        let mut prefix = self.extra.clone();
+        if self.gamescope {
+            prefix.push(String::from("gamescope"));
+            prefix.extend(
+                self.gamescope_args
+                    .iter()
+                    .map(|arg| crate::runner::shell_quote(arg)),
+            );
+            prefix.push(String::from("--"));
+        }
+        if self.mangohud {
+            prefix.push(String::from("mangohud"));
+        }
+        prefix.join(" ")
+    }
+}
+
+/// A single file holding every save slot of a game.
+///
+/// Slots are read and written through user provided commands, which can use the variables
+/// @CONTAINER (path of the container file) and @SLOT (name of the slot).
+#[derive(
+    Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Container {
+    /// Path of the container relative to the save location, or the save location itself if missing.
+    pub path: Option<PathBuf>,
+    /// Command that prints the contents of a slot to stdout.
+    pub extract: String,
+    /// Command that replaces the contents of a slot with its stdin.
+    pub inject: String,
+}
+
+/// External commands used to compress the backups of a game instead of the built-in zstd.
+///
+/// The tar archive is piped through them, so both must read from stdin and write to stdout.
+#[derive(
+    Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+pub struct Compression {
+    /// Command that compresses its stdin (e.g. "lz4 -9").
+    pub compress: String,
+    /// Command that decompresses its stdin (e.g. "lz4 -d").
+    pub decompress: String,
+    /// Extension of the compressed archives, without the dot (e.g. "lz4").
+    pub extension: String,
+}
+
+impl Container {
+    fn replace_vars(template: String, container: &Path, slot: &str) -> String {
+        template
+            .replace(
+                "@CONTAINER",
+                &crate::runner::shell_quote(&container.to_string_lossy()),
+            )
+            .replace("@SLOT", &crate::runner::shell_quote(slot))
+    }
+}
+
+impl Game {
+    pub fn new(
+        name: String,
+        root: PathBuf,
+        save_location: PathBuf,
+        executable: Option<PathBuf>,
+        executable_args: Option<Vec<String>>,
+        environment_vars: Option<impl IntoIterator<Item = (String, String)>>,
+        run_commands: Option<Vec<String>>,
+    ) -> Self {
+        Self {
+            name,
+            root,
+            save_location,
+            executable,
+            executable_args,
+            environment_vars: environment_vars.map(HashMap::from_iter),
+            wine_prefix: None,
+            proton_version: None,
+            steam_appid: None,
+            workdir: None,
+            run_commands,
+            launch_profiles: None,
+            wrappers: None,
+            container: None,
+            compression: None,
+            archiver: None,
+            cloud: None,
+            post_restore_clean: None,
+            tags: None,
+            aliases: None,
+            notes: None,
+            players: None,
+            archived: false,
+            player: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn save_location(&self) -> &Path {
+        &self.save_location
+    }
+
+    pub fn backups_path(&self) -> PathBuf {
+        self.root.join("gg-saves")
+    }
+
+    /// Returns what the names of the backups of the game start with, its name followed by the
+    /// selected player if any, like "Celeste@alice".
+    pub fn backup_prefix(&self) -> String {
+        match &self.player {
+            Some(player) => format!("{}@{player}", self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Returns the paths of the game that don't exist anymore.
+    ///
+    /// Only the missing root is returned if it is missing, as the rest are usually inside it.
+    pub fn validate(&self) -> Vec<Broken> {
+        if !self.root.exists() {
+            return vec![Broken::MissingRoot(self.root.clone())];
+        }
+        let mut problems = Vec::new();
+        if !self.save_location.exists() {
+            problems.push(Broken::MissingSaveLocation(self.save_location.clone()));
+        }
+        // Relative executables are searched in the PATH
+        let missing = |exe: &&PathBuf| exe.is_absolute() && !exe.exists();
+        if let Some(exe) = self.executable.as_ref().filter(missing) {
+            problems.push(Broken::MissingExecutable(exe.clone()));
+        }
+        if let Some(prefix) = self.wine_prefix.as_ref().filter(|prefix| !prefix.is_dir()) {
+            problems.push(Broken::MissingWinePrefix(prefix.clone()));
+        }
+        problems
+    }
+
+    /// Returns the backups of the game, or of its selected player, oldest first.
+    ///
+    /// Only the files named like a backup of the game are included, so hidden entries like the
+    /// repository of the git backend and the [`MARKER_FILE`] are skipped.
+    pub fn backups(&self) -> Result<Vec<Backup>> {
+        let path = self.backups_path();
+        let mut backups = path
+            .read_dir()
+            .context_with(|| format!("Could not read backups directory {}", path.display()))?
+            .flatten()
+            .filter(|e| !e.file_name().as_encoded_bytes().starts_with(b"."))
+            .filter_map(|e| Backup::new(self, e.path()))
+            .collect::<Vec<_>>();
+        backups.sort_unstable_by(|a, b| (a.index, &a.path).cmp(&(b.index, &b.path)));
+        Ok(backups)
+    }
+
+    /// Returns the files left in the backups directory by interrupted cloud downloads.
+    pub fn download_leftovers(&self) -> Vec<PathBuf> {
+        let path = self.backups_path();
+        let partial = path
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| {
+                let name = e.file_name();
+                let name = name.as_encoded_bytes();
+                name.starts_with(b".") && name.ends_with(b".part")
+            })
+            .map(|e| e.path());
+        let downloads = path
+            .join(CLOUD_TEMP_DIR)
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path());
+        let mut leftovers = partial.chain(downloads).collect::<Vec<_>>();
+        leftovers.sort_unstable();
+        leftovers
+    }
+
+    pub fn executable(&self) -> Option<&PathBuf> {
+        self.executable.as_ref()
+    }
+
+    pub fn steam_appid(&self) -> Option<u32> {
+        self.steam_appid
+    }
+
+    /// Replaces the tags of the game, empty ones are skipped and no tags remove them.
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        let tags = tags
+            .into_iter()
+            .map(|tag| tag.trim().to_owned())
+            .filter(|tag| !tag.is_empty())
+            .collect::<Vec<_>>();
+        self.tags = (!tags.is_empty()).then_some(tags);
+        self
+    }
+
+    /// Replaces the aliases of the game, empty ones are skipped and no aliases remove them.
+    pub fn with_aliases(mut self, aliases: Vec<String>) -> Self {
+        let aliases = aliases
+            .into_iter()
+            .map(|alias| alias.trim().to_owned())
+            .filter(|alias| !alias.is_empty())
+            .collect::<Vec<_>>();
+        self.aliases = (!aliases.is_empty()).then_some(aliases);
+        self
+    }
+
+    /// Replaces the notes of the game, blank ones remove them.
+    pub fn with_notes(mut self, notes: String) -> Self {
+        self.notes = (!notes.trim().is_empty()).then_some(notes);
+        self
+    }
+
+    /// Gives the game a new name, without renaming its backups, see [`Game::rename_backups`].
+    pub fn with_name(mut self, name: String) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Launches the game with Steam, or runs its executable again if 0.
+    pub fn with_steam_appid(mut self, appid: u32) -> Self {
+        self.steam_appid = Some(appid).filter(|&id| id != 0);
+        self
+    }
+
+    /// Returns the processes Steam started for the game, found by the SteamAppId variable Steam
+    /// gives them.
+    pub fn steam_pids(&self) -> Vec<u32> {
+        let Some(appid) = self.steam_appid else {
+            return Vec::new();
+        };
+        let var = format!("SteamAppId={appid}");
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|pid| {
+                std::fs::read(format!("/proc/{pid}/environ"))
+                    .is_ok_and(|env| env.split(|&b| b == 0).any(|v| v == var.as_bytes()))
+            })
+            .collect()
+    }
+
+    pub fn wrappers(&self) -> Option<&Wrappers> {
+        self.wrappers.as_ref()
+    }
+
+    pub fn with_wrappers(mut self, wrappers: Wrappers) -> Self {
+        self.wrappers = Some(wrappers);
+        self
+    }
+
+    pub fn players(&self) -> &[Player] {
+        self.players.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the player selected with [`Game::with_player`], if any.
+    pub fn player(&self) -> Option<&str> {
+        self.player.as_deref()
+    }
+
+    /// Returns the game with the backups and the save of its player called `name`, or the game
+    /// itself if not given.
+    pub fn with_player(&self, name: Option<&str>) -> Result<Game> {
+        let Some(name) = name else {
+            return Ok(self.clone());
+        };
+        let players = self.players();
+        let Some(player) = players.iter().find(|p| p.name == name) else {
+            bail!(
+                "{} has no player {name:?}, its players are {:?}",
+                self.name,
+                players.iter().map(|p| &p.name).collect::<Vec<_>>()
+            )
+        };
+        let mut game = self.clone();
+        game.player = Some(player.name.clone());
+        if let Some(save_location) = &player.save_location {
+            game.save_location = self.root.join(save_location);
+        }
+        Ok(game)
+    }
+
+    pub fn launch_profiles(&self) -> &[LaunchProfile] {
+        self.launch_profiles.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the game launched with its profile called `name`, or with the first one if not
+    /// given and it has any.
+    pub fn with_launch_profile(&self, name: Option<&str>) -> Result<Game> {
+        let profiles = self.launch_profiles();
+        let profile = match name {
+            Some(name) => match profiles.iter().find(|p| p.name == name) {
+                Some(profile) => profile,
+                None => bail!(
+                    "{} has no launch profile {name:?}, its profiles are {:?}",
+                    self.name,
+                    profiles.iter().map(|p| &p.name).collect::<Vec<_>>()
+                ),
+            },
+            None => match profiles.first() {
+                Some(profile) => profile,
+                None => return Ok(self.clone()),
+            },
+        };
+        let mut game = self.clone();
+        if let Some(executable) = &profile.executable {
+            game.executable = Some(self.root.join(executable));
+        }
+        if profile.executable_args.is_some() {
+            game.executable_args = profile.executable_args.clone();
+        }
+        if profile.run_commands.is_some() {
+            game.run_commands = profile.run_commands.clone();
+        }
+        Ok(game)
+    }
+
+    pub fn workdir(&self) -> Option<&Path> {
+        self.workdir.as_deref()
+    }
+
+    /// Returns the directory 'gg run' starts the game in, the root if it has no working directory.
+    pub fn run_dir(&self) -> PathBuf {
+        match &self.workdir {
+            Some(workdir) => self.root.join(workdir),
+            None => self.root.clone(),
+        }
+    }
+
+    /// Replaces the working directory of the game, an empty one starting it in the root again.
+    pub fn with_workdir(mut self, workdir: PathBuf) -> Self {
+        self.workdir = (!workdir.as_os_str().is_empty()).then_some(workdir);
+        self
+    }
+
+    pub fn wine_prefix(&self) -> Option<&Path> {
+        self.wine_prefix.as_deref()
+    }
+
+    pub fn proton_version(&self) -> Option<&str> {
+        self.proton_version.as_deref()
+    }
+
+    /// Replaces the prefix and the Proton version of the game that are given, an empty one
+    /// removing it.
+    pub fn with_wine(mut self, prefix: Option<PathBuf>, proton: Option<String>) -> Self {
+        if let Some(prefix) = prefix {
+            self.wine_prefix = (!prefix.as_os_str().is_empty()).then_some(prefix);
+        }
+        if let Some(proton) = proton {
+            self.proton_version = (!proton.is_empty()).then_some(proton);
+        }
+        self
+    }
+
+    /// Environment variables that run the game in its prefix, empty if it has none.
+    ///
+    /// Proton also needs the directory above the prefix, where it keeps its own files, and the
+    /// Steam installation.
+    pub fn wine_env(&self) -> Vec<(&'static str, PathBuf)> {
+        let Some(prefix) = &self.wine_prefix else {
+            return Vec::new();
+        };
+        let mut env = vec![("WINEPREFIX", prefix.clone())];
+        if self.proton_version.is_some() {
+            let data = if prefix.ends_with("pfx") {
+                prefix.parent().unwrap_or(prefix)
+            } else {
+                prefix
+            };
+            env.push(("STEAM_COMPAT_DATA_PATH", data.to_path_buf()));
+            if let Some(steam) = crate::paths::steam_root() {
+                env.push(("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam.to_path_buf()));
+            }
+        }
+        env
+    }
+
+    pub fn executable_args(&self) -> Option<&[String]> {
+        self.executable_args.as_deref()
+    }
+
+    pub fn environment_vars(&self) -> Option<&HashMap<String, String>> {
+        self.environment_vars.as_ref()
+    }
 
     pub fn run_commands(&self) -> Option<&[String]> {
         self.run_commands.as_deref()
     }
 
+    pub fn post_restore_clean(&self) -> Option<&[String]> {
+        self.post_restore_clean.as_deref()
+    }
+
+    pub fn tags(&self) -> &[String] {
+        self.tags.as_deref().unwrap_or_default()
+    }
+
+    /// Returns whether the game has the `tag`, ignoring the case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn aliases(&self) -> &[String] {
+        self.aliases.as_deref().unwrap_or_default()
+    }
+
+    pub fn notes(&self) -> Option<&str> {
+        self.notes.as_deref()
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived
+    }
+
+    /// Archives the game, or brings it back if not `archived`.
+    pub fn with_archived(mut self, archived: bool) -> Self {
+        self.archived = archived;
+        self
+    }
+
+    /// Returns whether the name or one of the aliases of the game is `name`, compared by slug.
+    fn is_called(&self, name: &str) -> bool {
+        let slug = slug::slugify(name);
+        slug::slugify(&self.name) == slug || self.aliases().iter().any(|a| slug::slugify(a) == slug)
+    }
+
+    /// Returns the existing paths matched by the post-restore clean patterns of the game.
+    ///
+    /// The save location, its parents and the files inside it are never included, as the save has
+    /// just been restored. Patterns that are absolute or go up with `..` are ignored, and so are the
+    /// matches that a symlink takes outside of the root.
+    pub fn post_restore_clean_paths(&self) -> Vec<PathBuf> {
+        use std::path::Component;
+
+        let Ok(root) = self.root.canonicalize() else {
+            return Vec::new();
+        };
+        let save_location = self
+            .save_location
+            .canonicalize()
+            .unwrap_or_else(|_| self.save_location.clone());
+        let mut paths = self
+            .post_restore_clean
+            .iter()
+            .flatten()
+            .filter(|pattern| {
+                let relative = Path::new(pattern).components().all(|c| {
+                    !matches!(c, Component::RootDir | Component::Prefix(_) | Component::ParentDir)
+                });
+                if !relative {
+                    tracing::warn!("Ignoring the post-restore clean pattern {pattern:?} of {}, it must be relative to the root", self.name);
+                }
+                relative
+            })
+            .flat_map(|pattern| expand_glob(&root.join(pattern)))
+            .filter(|path| {
+                // A symlink is deleted itself, so only its parent is resolved
+                let resolved = match (path.parent(), path.file_name()) {
+                    (Some(parent), Some(name)) => parent.canonicalize().map(|p| p.join(name)),
+                    _ => path.canonicalize(),
+                };
+                resolved.is_ok_and(|path| {
+                    path.starts_with(&root)
+                        && !save_location.starts_with(&path)
+                        && !path.starts_with(&save_location)
+                })
+            })
+            .collect::<Vec<_>>();
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+
+    pub fn cloud(&self) -> Option<&GameCloud> {
+        self.cloud.as_ref()
+    }
+
+    /// Returns `false` if the cloud features have been disabled for the game.
+    pub fn cloud_enabled(&self) -> bool {
+        self.cloud.as_ref().is_none_or(|c| c.enabled)
+    }
+
+    /// Returns the cloud remote of the game with its variables replaced, its own one taking
+    /// precedence over `default`.
+    pub fn cloud_remote(&self, default: Option<&str>) -> Option<String> {
+        self.cloud
+            .as_ref()
+            .and_then(|c| c.remote.clone())
+            .or_else(|| default.map(String::from))
+            .map(|r| self.replace_values(r, str::to_owned))
+    }
+
+    pub fn container(&self) -> Option<&Container> {
+        self.container.as_ref()
+    }
+
+    pub fn compression(&self) -> Option<&Compression> {
+        self.compression.as_ref()
+    }
+
+    /// Returns the local path of the save slots container, if the game has one.
+    pub fn container_path(&self) -> Option<PathBuf> {
+        let container = self.container.as_ref()?;
+        Some(match &container.path {
+            Some(path) => self.save_location.join(path),
+            None => self.save_location.clone(),
+        })
+    }
+
+    /// Returns the PID of the process running the game, if it is running.
+    ///
+    /// Detects games started with 'gg run' through the lock of the game, and the rest by searching
+    /// for a process of the executable.
+    pub fn running_pid(&self) -> Option<u32> {
+        if let Some(holder) = crate::status::lock_holder(&self.name)
+            && holder.action == "run"
+        {
+            return Some(holder.pid);
+        }
+        if let Some(&pid) = self.steam_pids().first() {
+            return Some(pid);
+        }
+
+        let exe = self.executable.as_ref()?;
+        let exe_name = exe.file_name()?.to_str()?;
+        // Games running under Wine or Proton see their executable as a Windows path
+        let windows_suffix = format!("\\{exe_name}");
+        let matches = |arg: &[u8]| {
+            arg == exe.as_os_str().as_encoded_bytes() || arg.ends_with(windows_suffix.as_bytes())
+        };
+
+        let own_pid = std::process::id();
+        std::fs::read_dir("/proc")
+            .ok()?
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|&pid| pid != own_pid)
+            .find(|pid| {
+                let proc = Path::new("/proc").join(pid.to_string());
+                std::fs::read_link(proc.join("exe")).is_ok_and(|e| e == *exe)
+                    || std::fs::read(proc.join("cmdline"))
+                        .is_ok_and(|cmdline| cmdline.split(|&b| b == 0).any(matches))
+            })
+    }
+
+    /// Returns the marker of the last backup made on any machine, as far as the local copy knows.
+    pub fn marker(&self) -> Option<BackupMarker> {
+        BackupMarker::read(&self.backups_path().join(MARKER_FILE))
+    }
+
+    /// Returns the local metadata of the backups, keyed by their name.
+    pub fn backups_metadata(&self) -> BTreeMap<String, BackupMetadata> {
+        // Games whose metadata has not been changed since the database was created still have it
+        // in the file
+        #[cfg(feature = "sqlite")]
+        if let Ok(Some(metadata)) = crate::sqlite::backups_metadata(&self.name) {
+            return metadata;
+        }
+        std::fs::read_to_string(self.backups_path().join(METADATA_FILE))
+            .ok()
+            .and_then(|m| serde_saphyr::from_str(&m).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_backups_metadata(&self, metadata: &BTreeMap<String, BackupMetadata>) -> Result<()> {
+        let path = self.backups_path().join(METADATA_FILE);
+        #[cfg(not(feature = "sqlite"))]
+        {
+            let contents =
+                serde_saphyr::to_string(metadata).context("Could not serialize the metadata")?;
+            std::fs::write(&path, contents)
+                .context_with(|| format!("Could not write {}", path.display()))?;
+        }
+        #[cfg(feature = "sqlite")]
+        {
+            crate::sqlite::set_backups_metadata(&self.name, metadata)?;
+            // Moved into the database
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .context_with(|| format!("Could not remove {}", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Records that `backup` could not be uploaded to the cloud.
+    pub fn mark_pending_upload(&self, backup: &Path) -> Result<()> {
+        let name = backup.file_name().ok_or_report()?.to_string_lossy();
+        let mut metadata = self.backups_metadata();
+        metadata
+            .entry(name.into_owned())
+            .or_default()
+            .pending_upload = true;
+        self.set_backups_metadata(&metadata)
+    }
+
+    /// Returns the names of the backups that could not be uploaded to the cloud yet.
+    pub fn pending_uploads(&self) -> Vec<String> {
+        self.backups_metadata()
+            .into_iter()
+            .filter(|(_, m)| m.pending_upload)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Marks `backup` as uploaded, or every backup if `None`.
+    fn clear_pending_uploads(&self, backup: Option<&Path>) -> Result<()> {
+        let name = backup
+            .and_then(|b| b.file_name())
+            .map(|n| n.to_string_lossy());
+        let mut metadata = self.backups_metadata();
+        let mut changed = false;
+        for (_, m) in metadata
+            .iter_mut()
+            .filter(|(n, _)| name.as_deref().is_none_or(|name| name == n.as_str()))
+        {
+            changed |= std::mem::take(&mut m.pending_upload);
+        }
+        if !changed {
+            return Ok(());
+        }
+        self.set_backups_metadata(&metadata)
+    }
+
+    /// Returns the marker this machine last wrote or restored over.
+    pub fn seen_marker(&self) -> Option<BackupMarker> {
+        BackupMarker::read(&self.backups_path().join(SEEN_MARKER_FILE))
+    }
+
+    /// Returns the time the game has been played on this machine with 'gg run'.
+    pub fn playtime(&self) -> Playtime {
+        std::fs::read_to_string(self.backups_path().join(PLAYTIME_FILE))
+            .ok()
+            .and_then(|p| serde_saphyr::from_str(&p).ok())
+            .unwrap_or_default()
+    }
+
+    /// Adds a session of `duration` that has just ended to the playtime of the game.
+    pub fn record_session(&self, duration: std::time::Duration) -> Result<()> {
+        let mut playtime = self.playtime();
+        playtime.total += duration.as_secs();
+        playtime.sessions += 1;
+        playtime.last_played = Some(unix_time());
+        playtime.last_session = duration.as_secs();
+        let path = self.backups_path().join(PLAYTIME_FILE);
+        std::fs::create_dir_all(self.backups_path())?;
+        let contents =
+            serde_saphyr::to_string(&playtime).context("Could not serialize the playtime")?;
+        std::fs::write(&path, contents)
+            .context_with(|| format!("Could not write {}", path.display()))?;
+        self.record_history(HistoryEvent::Session {
+            time: unix_time(),
+            duration: duration.as_secs(),
+        })
+    }
+
+    /// Returns the backups, restores and sessions of the game on this machine, oldest first.
+    ///
+    /// The parts of the history moved aside as it grew are read too.
+    pub fn history(&self) -> Vec<HistoryEvent> {
+        let path = self.backups_path().join(HISTORY_FILE);
+        crate::rotation::segments(&path)
+            .iter()
+            .chain([&path])
+            .filter_map(|part| crate::rotation::read_segment(part).ok())
+            .flat_map(|h| serde_saphyr::from_str::<Vec<HistoryEvent>>(&h).unwrap_or_default())
+            .collect()
+    }
+
+    /// Appends `event` to the history, moving it aside once it reaches [`HISTORY_MAX_SIZE`].
+    fn record_history(&self, event: HistoryEvent) -> Result<()> {
+        let path = self.backups_path().join(HISTORY_FILE);
+        let mut history = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|h| serde_saphyr::from_str::<Vec<HistoryEvent>>(&h).ok())
+            .unwrap_or_default();
+        history.push(event);
+        std::fs::create_dir_all(self.backups_path())?;
+        let contents =
+            serde_saphyr::to_string(&history).context("Could not serialize the history")?;
+        std::fs::write(&path, contents)
+            .context_with(|| format!("Could not write {}", path.display()))?;
+        crate::rotation::rotate(&path, HISTORY_MAX_SIZE, true, HISTORY_KEEP)?;
+        Ok(())
+    }
+
+    /// Records that the backup called `backup` has just been restored.
+    pub fn record_restore(&self, backup: &str) -> Result<()> {
+        self.record_history(HistoryEvent::Restore {
+            time: unix_time(),
+            backup: backup.to_owned(),
+        })
+    }
+
+    /// Returns the backups of the game and of all its players, oldest first.
+    pub fn all_backups(&self) -> Result<Vec<Backup>> {
+        let mut backups = Vec::new();
+        for stream in self.streams()? {
+            backups.extend(stream.backups()?);
+        }
+        backups.sort_unstable_by_key(|backup| backup.timestamp);
+        Ok(backups)
+    }
+
+    /// Returns the game without a player followed by the game with each of its players.
+    pub(crate) fn streams(&self) -> Result<Vec<Game>> {
+        let players = self.players().iter().map(|p| Some(p.name.as_str()));
+        std::iter::once(None)
+            .chain(players)
+            .map(|player| {
+                Game {
+                    player: None,
+                    ..self.clone()
+                }
+                .with_player(player)
+            })
+            .collect()
+    }
+
+    /// Records `backup` as the last backup of the game, made by this machine from a save of
+    /// `save_size` bytes.
+    pub fn record_backup(&self, backup: &Path, save_size: u64) -> Result<()> {
+        crate::outcome::record_artifact(backup);
+        let marker = BackupMarker {
+            machine: machine_id(),
+            host: hostname(),
+            time: unix_time(),
+            backup: backup
+                .file_name()
+                .ok_or_report()?
+                .to_string_lossy()
+                .into_owned(),
+        };
+        marker.write(&self.backups_path().join(MARKER_FILE))?;
+        marker.write(&self.backups_path().join(SEEN_MARKER_FILE))?;
+        let mut metadata = self.backups_metadata();
+        let entry = metadata.entry(marker.backup).or_default();
+        entry.host = Some(marker.host);
+        entry.time = Some(marker.time);
+        entry.save_size = Some(save_size);
+        self.set_backups_metadata(&metadata)?;
+        let backups = self.all_backups()?;
+        self.record_history(HistoryEvent::Backup {
+            time: marker.time,
+            size: backup.metadata().map_or(0, |m| m.len()),
+            backups: backups.len(),
+            stored: backups.iter().map(|backup| backup.size).sum(),
+        })
+    }
+
+    /// Returns the size in bytes of the save the latest backup of the game was made from, if it
+    /// was recorded.
+    pub fn last_save_size(&self) -> Option<u64> {
+        let latest = self.backups().ok()?.pop()?;
+        self.backups_metadata().get(&*latest.name())?.save_size
+    }
+
+    /// Marks the current marker as seen, so it is not reported as newer anymore.
+    pub fn acknowledge_marker(&self) -> Result<()> {
+        match self.marker() {
+            Some(marker) => marker.write(&self.backups_path().join(SEEN_MARKER_FILE)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the marker of a backup made on another machine after the last one seen by this one.
+    ///
+    /// Must be called after pulling the backups, as it only looks at the local copy of the marker.
+    pub fn newer_backup(&self) -> Option<BackupMarker> {
+        let marker = self.marker()?;
+        let seen_time = self.seen_marker().map_or(0, |m| m.time);
+        (marker.machine != machine_id() && marker.time > seen_time).then_some(marker)
+    }
+
+    /// Returns the default message of the cloud commit after creating `backup`, with its name,
+    /// description and size, and the length of the play `session` that preceded it, if any.
+    pub fn commit_message(
+        &self,
+        backup: Option<&Path>,
+        session: Option<std::time::Duration>,
+    ) -> String {
+        let Some(name) = backup.and_then(|b| b.file_name()) else {
+            return format!("Backup {}", self.name);
+        };
+        let name = name.to_string_lossy();
+        // "GAME-IDX[-DESCRIPTION]", without the extensions
+        let stem = name.split(".tar.").next().unwrap_or(&name);
+        let mut message = match self.split_backup_name(stem) {
+            Some((idx, desc)) if !desc.is_empty() => {
+                format!(
+                    "Backup {}-{idx:0>3}: {}",
+                    self.backup_prefix(),
+                    desc.trim_start_matches('-')
+                )
+            }
+            _ => format!("Backup {stem}"),
+        };
+        message.push_str(&format!("\n\nGame: {}", self.name));
+        if let Some(player) = &self.player {
+            message.push_str(&format!("\nPlayer: {player}"));
+        }
+        if let Some(size) = backup.and_then(|b| b.metadata().ok()) {
+            message.push_str(&format!("\nSize: {}", crate::units::Size(size.len())));
+        }
+        if let Some(session) = session {
+            let session = std::time::Duration::from_secs(session.as_secs());
+            message.push_str(&format!("\nSession: {}", Duration(session)));
+        }
+        message
+    }
+
+    /// Returns the index and the description of the backup called `name`, or `None` if it is not
+    /// named like a backup of the game.
+    pub fn parse_backup_name<'a>(&self, name: &'a str) -> Option<(usize, Option<&'a str>)> {
+        let (idx, rest) = self.split_backup_name(name)?;
+        // "-DESCRIPTION.tar.EXT", ".tar.EXT", the same with the extension of another archiver, or
+        // nothing
+        let rest = rest
+            .strip_suffix(crate::crypto::EXTENSION)
+            .and_then(|rest| rest.strip_suffix('.'))
+            .unwrap_or(rest);
+        let rest = match rest.split_once(".tar.") {
+            Some((rest, _)) => rest,
+            None => rest.rsplit_once('.').map_or(rest, |(rest, _)| rest),
+        };
+        match rest.strip_prefix('-') {
+            Some(desc) => Some((idx, Some(desc))),
+            None if rest.is_empty() => Some((idx, None)),
+            None => None,
+        }
+    }
+
+    /// Splits the name of a backup into its index and what follows it.
+    fn split_backup_name<'a>(&self, name: &'a str) -> Option<(usize, &'a str)> {
+        let rest = name
+            .strip_prefix(&self.backup_prefix())?
+            .strip_prefix('-')?;
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        Some((rest[..end].parse().ok()?, &rest[end..]))
+    }
+
+    /// Returns the backups made while the game was running, oldest first.
+    pub fn autosaves(&self) -> Result<Vec<Backup>> {
+        let mut autosaves = self.backups()?;
+        autosaves.retain(|backup| backup.description.as_deref() == Some(AUTOSAVE_DESCRIPTION));
+        Ok(autosaves)
+    }
+
+    /// Returns the index for a new backup, after the highest existing one.
+    ///
+    /// Counting the backups instead would reuse indices after removing or pulling some.
+    pub fn next_backup_index(&self) -> Result<usize> {
+        let last = self.backups()?.last().map(|backup| backup.index);
+        Ok(last.map_or(0, |i| i + 1))
+    }
+
+    /// Gives a new index to the backups sharing one with an older backup, oldest first.
+    ///
+    /// Returns the old and new names of the renamed backups.
+    pub fn dedupe_backups(&self) -> Result<Vec<(String, String)>> {
+        let mut by_index = BTreeMap::<usize, Vec<(std::time::SystemTime, String)>>::new();
+        for backup in self.backups()? {
+            by_index
+                .entry(backup.index)
+                .or_default()
+                .push((backup.timestamp, backup.name().into_owned()));
+        }
+
+        let mut next = by_index.last_key_value().map_or(0, |(i, _)| i + 1);
+        let mut renamed = Vec::new();
+        for mut backups in by_index.into_values() {
+            backups.sort_unstable();
+            for (_, name) in backups.into_iter().skip(1) {
+                let (_, rest) = self.split_backup_name(&name).ok_or_report()?;
+                let new_name = format!("{}-{next:0>3}{rest}", self.backup_prefix());
+                next += 1;
+                let (from, to) = (
+                    self.backups_path().join(&name),
+                    self.backups_path().join(&new_name),
+                );
+                std::fs::rename(&from, &to)
+                    .context_with(|| format!("Could not rename {}", from.display()))?;
+                renamed.push((name, new_name));
+            }
+        }
+        self.record_renames(&renamed)?;
+        Ok(renamed)
+    }
+
+    /// Renames the backups of the game and of its players to start with `name` instead of its
+    /// current name.
+    ///
+    /// Returns the old and new names of the renamed backups.
+    pub fn rename_backups(&self, name: &str) -> Result<Vec<(String, String)>> {
+        let mut renamed = Vec::new();
+        for stream in self.streams()? {
+            let prefix = match stream.player() {
+                Some(player) => format!("{name}@{player}"),
+                None => name.to_owned(),
+            };
+            for backup in stream.backups()? {
+                let old_name = backup.name().into_owned();
+                let (idx, rest) = stream.split_backup_name(&old_name).ok_or_report()?;
+                let new_name = format!("{prefix}-{idx:0>3}{rest}");
+                let to = self.backups_path().join(&new_name);
+                std::fs::rename(&backup.path, &to)
+                    .context_with(|| format!("Could not rename {}", backup.path.display()))?;
+                renamed.push((old_name, new_name));
+            }
+        }
+        self.record_renames(&renamed)?;
+        #[cfg(feature = "sqlite")]
+        crate::sqlite::rename_game(&self.name, name)?;
+        Ok(renamed)
+    }
+
+    /// Moves the metadata and the markers of the `renamed` backups to their new names.
+    fn record_renames(&self, renamed: &[(String, String)]) -> Result<()> {
+        if renamed.is_empty() {
+            return Ok(());
+        }
+        let mut metadata = self.backups_metadata();
+        for (from, to) in renamed {
+            if let Some(m) = metadata.remove(from) {
+                metadata.insert(to.clone(), m);
+            }
+        }
+        self.set_backups_metadata(&metadata)?;
+        for (file, marker) in [
+            (MARKER_FILE, self.marker()),
+            (SEEN_MARKER_FILE, self.seen_marker()),
+        ] {
+            let Some(mut marker) = marker else {
+                continue;
+            };
+            if let Some((_, to)) = renamed.iter().find(|(from, _)| *from == marker.backup) {
+                marker.backup = to.clone();
+                marker.write(&self.backups_path().join(file))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the backups that arrived from other machines through Syncthing to the metadata, after
+    /// renaming the conflict copies it made of the backups and keeping the newest marker.
+    ///
+    /// Returns the names of the added backups.
+    pub fn index_synced_backups(&self) -> Result<Vec<String>> {
+        let backups_path = self.backups_path();
+        if !backups_path.exists() {
+            return Ok(Vec::new());
+        }
+        for entry in backups_path
+            .read_dir()
+            .context_with(|| {
+                format!(
+                    "Could not read backups directory {}",
+                    backups_path.display()
+                )
+            })?
+            .flatten()
+        {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Some(original) = crate::syncthing::conflict_original(&name) else {
+                continue;
+            };
+            let path = entry.path();
+            if original == MARKER_FILE {
+                if let Some(copy) = BackupMarker::read(&path)
+                    && self.marker().is_none_or(|marker| marker.time < copy.time)
+                {
+                    copy.write(&backups_path.join(MARKER_FILE))?;
+                }
+                std::fs::remove_file(&path)
+                    .context_with(|| format!("Could not remove {}", path.display()))?;
+            } else if self.split_backup_name(&original).is_some() {
+                // Both machines made a backup with the same index, so one of them gets the next
+                let new_path = backups_path.join(self.adopted_backup_name(self, &original)?);
+                std::fs::rename(&path, &new_path)
+                    .context_with(|| format!("Could not rename {}", path.display()))?;
+            }
+        }
+
+        let marker = self.marker();
+        let mut metadata = self.backups_metadata();
+        let mut arrived = Vec::new();
+        for backup in self.backups()? {
+            let name = backup.name().into_owned();
+            if metadata.contains_key(&name) {
+                continue;
+            }
+            let entry = metadata.entry(name.clone()).or_default();
+            match &marker {
+                Some(marker) if marker.backup == name => {
+                    entry.host = Some(marker.host.clone());
+                    entry.time = Some(marker.time);
+                }
+                _ => {
+                    entry.time = backup
+                        .timestamp
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .ok()
+                        .map(|d| d.as_secs());
+                }
+            }
+            arrived.push(name);
+        }
+        if !arrived.is_empty() {
+            self.set_backups_metadata(&metadata)?;
+        }
+        Ok(arrived)
+    }
+
+    /// Returns the settings of the game without the ones specific to this user, for 'gg share'.
+    pub fn shared(&self) -> Game {
+        Game {
+            cloud: None,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the name that the backup `name` of `other` gets when copied into the backups of the
+    /// game, keeping its description and extension.
+    pub fn adopted_backup_name(&self, other: &Game, name: &str) -> Result<String> {
+        let idx = self.next_backup_index()?;
+        let rest = match other.split_backup_name(name) {
+            Some((_, rest)) => rest.to_owned(),
+            None => format!("-{name}"),
+        };
+        Ok(format!("{}-{idx:0>3}{rest}", self.backup_prefix()))
+    }
+
+    /// Replaces the `from` prefix of the paths of the game with `to`.
+    pub fn remap(&mut self, from: &Path, to: &Path) {
+        let remap = |path: &mut PathBuf| {
+            if let Ok(rest) = path.strip_prefix(from) {
+                *path = to.join(rest);
+            }
+        };
+        remap(&mut self.root);
+        remap(&mut self.save_location);
+        if let Some(executable) = &mut self.executable {
+            remap(executable);
+        }
+        if let Some(workdir) = &mut self.workdir {
+            remap(workdir);
+        }
+        for profile in self.launch_profiles.iter_mut().flatten() {
+            if let Some(executable) = &mut profile.executable {
+                remap(executable);
+            }
+        }
+        for player in self.players.iter_mut().flatten() {
+            if let Some(save_location) = &mut player.save_location {
+                remap(save_location);
+            }
+        }
+    }
+
     pub fn merge(&mut self, game: Game) {
         self.root = game.root;
         self.save_location = game.save_location;
@@ -279,9 +3602,42 @@ impl Game {
         if game.environment_vars.is_some() {
             self.environment_vars = game.environment_vars;
         }
+        // Always complete in `game`, so 'gg edit' can remove them
+        self.wine_prefix = game.wine_prefix;
+        self.proton_version = game.proton_version;
+        self.steam_appid = game.steam_appid;
+        self.workdir = game.workdir;
+        self.tags = game.tags;
+        self.aliases = game.aliases;
+        self.notes = game.notes;
+        self.archived = game.archived;
         if game.run_commands.is_some() {
             self.run_commands = game.run_commands;
         }
+        if game.launch_profiles.is_some() {
+            self.launch_profiles = game.launch_profiles;
+        }
+        if game.players.is_some() {
+            self.players = game.players;
+        }
+        if game.wrappers.is_some() {
+            self.wrappers = game.wrappers;
+        }
+        if game.container.is_some() {
+            self.container = game.container;
+        }
+        if game.compression.is_some() {
+            self.compression = game.compression;
+        }
+        if game.archiver.is_some() {
+            self.archiver = game.archiver;
+        }
+        if game.cloud.is_some() {
+            self.cloud = game.cloud;
+        }
+        if game.post_restore_clean.is_some() {
+            self.post_restore_clean = game.post_restore_clean;
+        }
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -294,6 +3650,8 @@ impl Game {
         executable_args: Option<Vec<String>>,
         environment_vars: Option<Vec<(String, String)>>,
         run_commands: Option<Vec<String>>,
+        cloud: Option<GameCloud>,
+        post_restore_clean: Option<Vec<String>>,
     ) -> Game {
         Game {
             name: name.unwrap_or(self.name),
@@ -304,26 +3662,353 @@ impl Game {
             environment_vars: environment_vars
                 .map(HashMap::from_iter)
                 .or(self.environment_vars),
+            wine_prefix: self.wine_prefix,
+            proton_version: self.proton_version,
+            steam_appid: self.steam_appid,
+            workdir: self.workdir,
+            launch_profiles: self.launch_profiles,
             run_commands: run_commands.or(self.run_commands),
+            wrappers: self.wrappers,
+            container: self.container,
+            compression: self.compression,
+            archiver: self.archiver,
+            cloud: cloud.or(self.cloud),
+            post_restore_clean: post_restore_clean.or(self.post_restore_clean),
+            tags: self.tags,
+            aliases: self.aliases,
+            notes: self.notes,
+            players: self.players,
+            archived: self.archived,
+            player: self.player,
         }
     }
 
+    /// Returns the arguments that launch the game, through Steam, Proton or wine if it uses them,
+    /// or None if it has no executable.
+    pub fn launch_args(&self) -> Option<Vec<String>> {
+        let executable_args = self.executable_args().unwrap_or_default();
+        if let Some(appid) = self.steam_appid {
+            return Some(steam_launch(appid, executable_args));
+        }
+        let exe = self.executable.as_ref()?;
+        let mut args = match (&self.proton_version, &self.wine_prefix) {
+            (Some(version), _) => crate::paths::proton(version)
+                .map(|dir| {
+                    let proton = dir.join("proton").to_string_lossy().into_owned();
+                    vec![proton, String::from("run")]
+                })
+                .unwrap_or_default(),
+            (None, Some(_)) if exe_has_windows_extension(exe) => vec![String::from("wine")],
+            _ => Vec::new(),
+        };
+        args.push(exe.to_string_lossy().into_owned());
+        args.extend_from_slice(executable_args);
+        Some(args)
+    }
+
+    /// Replaces the variables in the command `template`, quoted for the shell.
     fn replace_vars(&self, mut template: String) -> String {
-        if let Some(exe) = &self.executable {
-            let exe = exe.display();
-            let executable_args = self
-                .executable_args
-                .as_deref()
-                .unwrap_or_default()
-                .join(" ");
-            template = template.replace("@EXE", &format!("'{exe}' {executable_args}"));
+        use crate::runner::{shell_join, shell_quote};
+
+        if let Some(args) = self.launch_args() {
+            template = template.replace("@EXE", &shell_join(&args));
         }
+        self.replace_values(template, shell_quote)
+    }
+
+    /// Replaces the variables in `template` other than @EXE with their values passed through
+    /// `quote`.
+    fn replace_values(&self, template: String, quote: impl Fn(&str) -> String) -> String {
+        let path = |path: &Path| quote(&path.to_string_lossy());
         template
+            .replace("@ROOT", &path(&self.root))
+            .replace("@SAVE", &path(&self.save_location))
+            .replace("@BACKUPS", &path(&self.backups_path()))
             .replace("@NAME-SLUG", &slug::slugify(&self.name))
-            .replace("@NAME", &self.name)
+            .replace("@NAME", &quote(&self.name))
+            .replace("@USER", &quote(&username()))
+            .replace("@HOST", &quote(&hostname()))
+    }
+}
+
+/// Returns the arguments that launch the Steam game `appid` with `args`.
+///
+/// Uses `steam -applaunch` if Steam is in the PATH, or its URL otherwise, like with the flatpak,
+/// which can't pass arguments.
+fn steam_launch(appid: u32, args: &[String]) -> Vec<String> {
+    let in_path = std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join("steam").is_file()));
+    if in_path {
+        [
+            String::from("steam"),
+            String::from("-applaunch"),
+            appid.to_string(),
+        ]
+        .into_iter()
+        .chain(args.iter().cloned())
+        .collect()
+    } else {
+        vec![
+            String::from(crate::paths::OPEN_COMMAND),
+            format!("steam://rungameid/{appid}"),
+        ]
+    }
+}
+
+/// Whether `exe` is a Windows program, run with wine when the game has a prefix.
+fn exe_has_windows_extension(exe: &Path) -> bool {
+    exe.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ["exe", "bat", "msi"].contains(&ext.to_lowercase().as_str()))
+}
+
+/// Variables replaced in the commands of every game, like "@NAME".
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "@EXE",
+    "@ROOT",
+    "@SAVE",
+    "@BACKUPS",
+    "@NAME-SLUG",
+    "@NAME",
+    "@USER",
+    "@HOST",
+];
+/// Variables replaced in the run commands besides [`TEMPLATE_VARIABLES`].
+pub const RUN_TEMPLATE_VARIABLES: &[&str] = &["@ARGS"];
+/// Variables replaced in the cloud commit and push commands besides [`TEMPLATE_VARIABLES`].
+pub const CLOUD_TEMPLATE_VARIABLES: &[&str] = &["@BACKUP-FILE", "@MESSAGE"];
+/// Variables replaced in the cloud rename commands besides [`TEMPLATE_VARIABLES`].
+pub const RENAME_TEMPLATE_VARIABLES: &[&str] = &["@OLD-NAME", "@OLD-NAME-SLUG"];
+
+/// Name of the file that records the last backup of a game, synced with the backups.
+pub const MARKER_FILE: &str = "gg-last-backup.yaml";
+/// Copy of the marker this machine last knew about, never synced.
+const SEEN_MARKER_FILE: &str = ".gg-last-seen.yaml";
+
+/// Local metadata of the backups of a game, never synced.
+const METADATA_FILE: &str = ".gg-backups.yaml";
+/// Time played on this machine, never synced.
+const PLAYTIME_FILE: &str = ".gg-playtime.yaml";
+/// Hidden file in the backups directory with the [`HistoryEvent`]s of the game.
+const HISTORY_FILE: &str = ".gg-history.yaml";
+/// Size of the history of a game at which it is moved aside, compressed, and started again.
+const HISTORY_MAX_SIZE: u64 = 256 * 1024;
+/// Number of the parts of the history moved aside that are kept, the oldest being removed.
+const HISTORY_KEEP: usize = 20;
+/// Hidden directory where the backups are encrypted and decrypted during cloud transfers.
+pub(crate) const CLOUD_TEMP_DIR: &str = ".gg-cloud";
+
+/// A backup archive of a game, named "GAME-IDX[-DESCRIPTION].tar.EXT", or with the extension of
+/// its [`Archiver`], with ".age" appended if it is encrypted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Backup {
+    /// Name of the game the backup belongs to.
+    pub game: String,
+    /// Position of the backup among the ones of the game, increasing with each new backup.
+    pub index: usize,
+    /// When the archive was last modified, which is when it was made unless it was copied.
+    pub timestamp: std::time::SystemTime,
+    /// Description given when the backup was made, like "autosave".
+    pub description: Option<String>,
+    pub path: PathBuf,
+    /// Size of the archive in bytes.
+    pub size: u64,
+}
+
+impl Backup {
+    /// Returns the backup of `game` at `path`, or `None` if the file is not named like one or
+    /// could not be read.
+    fn new(game: &Game, path: PathBuf) -> Option<Backup> {
+        let name = path.file_name()?.to_string_lossy();
+        let (index, description) = game.parse_backup_name(&name)?;
+        let description = description.map(str::to_owned);
+        let metadata = path.metadata().ok()?;
+        Some(Backup {
+            game: game.name.clone(),
+            index,
+            timestamp: metadata.modified().ok()?,
+            description,
+            size: metadata.len(),
+            path,
+        })
+    }
+
+    /// Returns the file name of the backup, which identifies it in the commands.
+    pub fn name(&self) -> std::borrow::Cow<'_, str> {
+        self.path.file_name().unwrap_or_default().to_string_lossy()
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        crate::crypto::is_encrypted(&self.path)
+    }
+}
+
+/// Information about a backup that is not stored in the archive itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BackupMetadata {
+    /// Commit of the git backend that first included the backup.
+    pub commit: Option<String>,
+    /// Name of the machine that made the backup, if known.
+    #[serde(default)]
+    pub host: Option<String>,
+    /// Seconds since the Unix epoch when the backup was made, or arrived if the machine is unknown.
+    #[serde(default)]
+    pub time: Option<u64>,
+    /// The backup could not be uploaded when it was made, 'gg sync' uploads it.
+    #[serde(default)]
+    pub pending_upload: bool,
+    /// Size in bytes of the save the backup was made from, before compressing it.
+    #[serde(default)]
+    pub save_size: Option<u64>,
+    /// zstd level 'gg gc' repacked the backup with, if it did.
+    #[serde(default)]
+    pub repacked: Option<u8>,
+}
+
+/// Something that happened to a game, kept in its history for 'gg stats'.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum HistoryEvent {
+    /// A backup of `size` bytes was made, leaving `backups` backups of `stored` bytes in total.
+    Backup {
+        time: u64,
+        size: u64,
+        backups: usize,
+        stored: u64,
+    },
+    /// The `backup` was restored.
+    Restore { time: u64, backup: String },
+    /// The game was played with 'gg run' for `duration` seconds.
+    Session { time: u64, duration: u64 },
+}
+
+impl HistoryEvent {
+    /// Returns when it happened, in seconds since the Unix epoch.
+    pub fn time(&self) -> u64 {
+        match self {
+            HistoryEvent::Backup { time, .. }
+            | HistoryEvent::Restore { time, .. }
+            | HistoryEvent::Session { time, .. } => *time,
+        }
+    }
+}
+
+/// Time a game has been played on this machine, recorded by 'gg run'.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Playtime {
+    /// Seconds played in every session.
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub sessions: u64,
+    /// Seconds since the Unix epoch when the last session ended.
+    #[serde(default)]
+    pub last_played: Option<u64>,
+    /// Seconds played in the last session.
+    #[serde(default)]
+    pub last_session: u64,
+}
+
+/// Records who made the last backup of a game and when.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BackupMarker {
+    /// Id of the machine that made the backup.
+    pub machine: String,
+    /// Name of the machine that made the backup.
+    pub host: String,
+    /// Seconds since the Unix epoch when the backup was made.
+    pub time: u64,
+    /// Name of the backup archive.
+    pub backup: String,
+}
+
+impl BackupMarker {
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        serde_saphyr::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        let contents = serde_saphyr::to_string(self).context("Could not serialize the marker")?;
+        std::fs::write(path, contents)
+            .context_with(|| format!("Could not write {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Describes how long ago the backup was made, like "3 hours ago".
+    pub fn age(&self) -> String {
+        ago(self.time)
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_time() -> u64 {
+    unix_secs(std::time::SystemTime::now())
+}
+
+/// Returns `time` in seconds since the Unix epoch.
+pub fn unix_secs(time: std::time::SystemTime) -> u64 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Describes how long ago `time` (in seconds since the Unix epoch) was, like "3 hours ago".
+pub fn ago(time: u64) -> String {
+    let secs = unix_time().saturating_sub(time);
+    match secs {
+        0..60 => String::from("just now"),
+        60..3600 => format!("{} minutes ago", secs / 60),
+        3600..86400 => format!("{} hours ago", secs / 3600),
+        _ => format!("{} days ago", secs / 86400),
     }
 }
 
+/// Returns the year, month and day of `time` in UTC, in seconds since the Unix epoch.
+pub fn date(time: i64) -> (i64, u32, u32) {
+    // Days to civil date, by Howard Hinnant
+    let days = time.div_euclid(24 * 60 * 60) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    (
+        year_of_era + era * 400 + i64::from(month <= 2),
+        month as u32,
+        day as u32,
+    )
+}
+
+/// Unique id of the current machine, or its hostname if it has none.
+pub fn machine_id() -> String {
+    std::fs::read_to_string("/etc/machine-id")
+        .map(|id| id.trim().to_owned())
+        .ok()
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(hostname)
+}
+
+/// Name of the user running gg.
+pub fn username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("LOGNAME"))
+        .unwrap_or_else(|_| String::from("user"))
+}
+
+/// Name of the current machine.
+pub fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|h| h.trim().to_owned())
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .unwrap_or_else(|_| String::from("localhost"))
+}
+
 impl PartialOrd for Game {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -341,3 +4026,417 @@ impl std::fmt::Display for Game {
         serde_saphyr::to_fmt_writer(f, &self).map_err(|_| std::fmt::Error)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_wildcards() {
+        let cases = [
+            ("*", "", true),
+            ("*", "save.dat", true),
+            ("*.dat", "save.dat", true),
+            ("*.dat", "save.dat.bak", false),
+            ("save?.dat", "save1.dat", true),
+            ("save?.dat", "save.dat", false),
+            ("s*e*.dat", "slot-save.dat", true),
+            ("*a*b", "aaab", true),
+            ("*a*b", "aaba", false),
+            ("profile", "profile", true),
+            ("profile", "Profile", false),
+            ("??", "ñé", true),
+            ("**", "anything", true),
+        ];
+        for (pattern, name, matches) in cases {
+            assert_eq!(wildcard_match(pattern, name), matches, "{pattern} {name}");
+        }
+    }
+
+    #[test]
+    fn expands_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["a/x.sav", "a/y.txt", "b/c/z.sav", "b/w.sav"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        let expand = |pattern: &str| {
+            let mut matches = expand_glob(&dir.path().join(pattern))
+                .into_iter()
+                .map(|p| p.strip_prefix(dir.path()).unwrap().to_owned())
+                .collect::<Vec<_>>();
+            matches.sort();
+            matches
+        };
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+
+        assert_eq!(expand("a/*.sav"), paths(&["a/x.sav"]));
+        assert_eq!(expand("*/*.sav"), paths(&["a/x.sav", "b/w.sav"]));
+        assert_eq!(expand("?/y.*"), paths(&["a/y.txt"]));
+        assert_eq!(expand("b/**/*.sav"), paths(&["b/c/z.sav", "b/w.sav"]));
+        assert_eq!(expand("b/c"), paths(&["b/c"]));
+        assert_eq!(expand("b/missing"), paths(&[]));
+        assert_eq!(expand("*/missing/*"), paths(&[]));
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        let cases = [
+            ("", "", 0),
+            ("", "abc", 3),
+            ("abc", "", 3),
+            ("celeste", "celeste", 0),
+            ("celeste", "celest", 1),
+            ("celeste", "celestr", 1),
+            ("celeste", "cleeste", 2),
+            ("kitten", "sitting", 3),
+            ("hollowknight", "holowknigth", 3),
+            ("ñandú", "nandu", 2),
+        ];
+        for (a, b, distance) in cases {
+            assert_eq!(edit_distance(a, b), distance, "{a} {b}");
+            assert_eq!(edit_distance(b, a), distance, "{b} {a}");
+        }
+    }
+
+    #[test]
+    fn suggests_similar_names() {
+        let mut games = Games::builder().in_memory().load().unwrap();
+        for name in ["Celeste", "Hollow Knight", "Hades"] {
+            games.push(Game::new(
+                name.to_owned(),
+                PathBuf::from("/games").join(name),
+                PathBuf::from("/saves").join(name),
+                None,
+                None,
+                None::<[(String, String); 0]>,
+                None,
+            ));
+        }
+        let suggestions = |name: &str| {
+            let report = games.get_idx_by_name(name).unwrap_err();
+            match crate::error::kind(&report) {
+                Some(Error::GameNotFound { suggestions, .. }) => suggestions.clone(),
+                kind => panic!("{name}: {kind:?}"),
+            }
+        };
+
+        assert_eq!(suggestions("Celest3"), ["Celeste"]);
+        assert_eq!(suggestions("hollow-knigth"), ["Hollow Knight"]);
+        assert_eq!(suggestions("Hadez"), ["Hades"]);
+        assert!(suggestions("Portal").is_empty());
+    }
+
+    /// Loads no games, with the config file written from `config` in `dir`.
+    fn games_with_config(dir: &Path, config: &str) -> (Games, crate::runner::RecordingRunner) {
+        let path = dir.join("config.yaml");
+        std::fs::write(&path, config).unwrap();
+        let mut games = Games::builder()
+            .config_path(path)
+            .in_memory()
+            .load()
+            .unwrap();
+        let recorder = crate::runner::RecordingRunner::new();
+        games.set_runner(recorder.clone());
+        (games, recorder)
+    }
+
+    #[test]
+    fn shares_the_backups_with_a_group() {
+        use std::os::unix::fs::MetadataExt;
+
+        let gid = rustix::process::getgid();
+        let group = nix::unistd::Group::from_gid(gid.as_raw().into())
+            .unwrap()
+            .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let (games, _) = games_with_config(
+            dir.path(),
+            &format!(
+                "backup:\n  permissions:\n    group: {:?}\n    mode: \"640\"\n",
+                group.name
+            ),
+        );
+        let game = Game::new(
+            String::from("Celeste"),
+            dir.path().join("game"),
+            dir.path().join("game/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        );
+        let backups = game.backups_path();
+        std::fs::create_dir_all(&backups).unwrap();
+        std::fs::write(backups.join("Celeste-1.tar.zst"), "").unwrap();
+        games.share_backups(&game).unwrap();
+
+        let backup = backups.join("Celeste-1.tar.zst").metadata().unwrap();
+        assert_eq!(backup.mode() & 0o7777, 0o640);
+        assert_eq!(backup.gid(), gid.as_raw());
+        let directory = backups.metadata().unwrap();
+        assert_eq!(directory.mode() & 0o7777, 0o2750);
+        assert_eq!(directory.gid(), gid.as_raw());
+
+        let (games, _) = games_with_config(
+            dir.path(),
+            "backup:\n  permissions:\n    group: gg-test-missing-group\n",
+        );
+        assert!(games.share_backups(&game).is_err());
+    }
+
+    /// The commands given to the shell by every recorded command, with their descriptions.
+    fn shell_commands(recorder: &crate::runner::RecordingRunner) -> Vec<(String, String)> {
+        recorder
+            .commands()
+            .into_iter()
+            .map(|cmd| {
+                let script = cmd.args.last().unwrap().to_string_lossy().into_owned();
+                (cmd.desc, script)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runs_the_cloud_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut games, recorder) = games_with_config(
+            dir.path(),
+            r#"
+shell: ["sh", "-c"]
+backup:
+  cloudInitCommands: ["git init", "git remote add origin me@host:gg-@NAME-SLUG"]
+  cloudCommitCommands: ["git add @BACKUP-FILE", "git commit -m @MESSAGE"]
+  cloudPushCommands: ["git push"]
+  cloudPullCommands: ["git pull"]
+  cloudRenameCommands: ["mv @OLD-NAME-SLUG @NAME-SLUG"]
+"#,
+        );
+        let game = Game::new(
+            String::from("Baldur's Gate"),
+            dir.path().join("game"),
+            dir.path().join("game/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        );
+        let backup = game.backups_path().join("Baldur's Gate 1.tar.zst");
+        let commands = games
+            .cloud_backend_dry_run(&game, Some(&backup), "Back up $HOME")
+            .unwrap()
+            .unwrap();
+        let scripts = commands
+            .iter()
+            .map(|cmd| {
+                (
+                    cmd.desc.as_str(),
+                    cmd.args.last().unwrap().to_str().unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let commit = format!(
+            "git add {} && git commit -m 'Back up $HOME'",
+            crate::runner::shell_quote(&backup.to_string_lossy())
+        );
+        assert_eq!(
+            scripts,
+            [
+                (
+                    "cloud init",
+                    "git init && git remote add origin me@host:gg-baldur-s-gate"
+                ),
+                ("cloud commit", commit.as_str()),
+                ("cloud push", "git push"),
+                ("cloud pull", "git pull"),
+            ]
+        );
+        assert!(commands.iter().all(|cmd| cmd.cwd == game.root()));
+        // The dry run puts the runner of the games back
+        assert!(recorder.commands().is_empty());
+
+        games.cloud_rename(&game, "Baldurs Gate").unwrap();
+        assert_eq!(
+            shell_commands(&recorder),
+            [(
+                String::from("cloud rename"),
+                String::from("mv baldurs-gate baldur-s-gate")
+            )]
+        );
+    }
+
+    #[test]
+    fn runs_the_commands_of_the_rclone_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut games, _) = games_with_config(
+            dir.path(),
+            "backup:\n  backend:\n    type: rclone\n    remote: \"gdrive:gg/@NAME-SLUG\"\n",
+        );
+        let game = Game::new(
+            String::from("Hollow Knight"),
+            dir.path().join("game"),
+            dir.path().join("game/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        );
+        let commands = games
+            .cloud_backend_dry_run(&game, None, "Back up")
+            .unwrap()
+            .unwrap();
+        let backups = game.backups_path().to_string_lossy().into_owned();
+        let remote = "gdrive:gg/hollow-knight";
+        let args = commands
+            .iter()
+            .map(|cmd| {
+                assert_eq!(cmd.program, "rclone");
+                let args = cmd.args.iter().map(|arg| arg.to_str().unwrap());
+                (cmd.desc.as_str(), args.collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            args,
+            [
+                ("cloud init", vec!["mkdir", remote]),
+                (
+                    "cloud push",
+                    vec!["copy", "--progress", "--exclude", ".*", &backups, remote]
+                ),
+                (
+                    "cloud pull",
+                    vec!["copy", "--progress", "--ignore-existing", remote, &backups]
+                ),
+                ("cloud list", vec!["lsjson", "--files-only", remote]),
+            ]
+        );
+    }
+
+    #[test]
+    fn runs_the_game_with_its_arguments() {
+        let dir = tempfile::tempdir().unwrap();
+        let (games, recorder) = games_with_config(
+            dir.path(),
+            "shell: [\"sh\", \"-c\"]\nrun:\n  commands: [\"gamemoderun %command%\"]\n",
+        );
+        let root = dir.path().join("game");
+        let mut game = Game::new(
+            String::from("Baldur's Gate"),
+            root.clone(),
+            root.join("saves"),
+            Some(root.join("Celeste.bin")),
+            Some(vec![String::from("--fullscreen")]),
+            Some([(String::from("LANG"), String::from("C"))]),
+            None,
+        );
+        let args = [String::from("--level"), String::from("@SAVE $(id)")];
+        games
+            .execute_interactive(games.run_command(&game, &args).unwrap(), "run", game.root())
+            .unwrap();
+        game.run_commands = Some(vec![
+            String::from("echo @NAME"),
+            String::from("mangohud @RUN"),
+        ]);
+        games
+            .execute_interactive(games.run_command(&game, &args).unwrap(), "run", game.root())
+            .unwrap();
+        let exe = crate::runner::shell_quote(&root.join("Celeste.bin").to_string_lossy());
+        // The arguments are quoted and kept as given, without replacing the variables in them
+        let run = format!("gamemoderun {exe} --fullscreen --level '@SAVE $(id)'");
+        assert_eq!(
+            shell_commands(&recorder),
+            [
+                (String::from("run"), run.clone()),
+                (
+                    String::from("run"),
+                    format!(r"echo 'Baldur'\''s Gate' && mangohud {run}")
+                ),
+            ]
+        );
+        let recorded = recorder.commands();
+        assert_eq!(recorded[0].program, "/usr/bin/env");
+        assert_eq!(recorded[0].args[..2], ["sh", "-c"]);
+        assert_eq!(
+            recorded[0].envs,
+            [(std::ffi::OsString::from("LANG"), Some("C".into()))]
+        );
+        assert_eq!(recorded[0].cwd, root);
+    }
+
+    /// Games database of version 1, a bare list of games.
+    const GAMES_V1: &str = "- name: Celeste\n  root: /games/Celeste\n  save_location: /saves/Celeste\n- name: Hades\n  root: /games/Hades\n  save_location: /saves/Hades\n";
+
+    #[test]
+    fn reads_the_version_of_the_games_database() {
+        assert_eq!(games_version(""), GAMES_VERSION);
+        assert_eq!(games_version(GAMES_V1), 1);
+        assert_eq!(games_version("version: 2\ngames: []\n"), 2);
+        assert_eq!(games_version("version: 7\ngames: []\n"), 7);
+    }
+
+    #[test]
+    fn migrates_the_games_database() {
+        let path = Path::new("games.yaml");
+        let upgraded = parse_games(GAMES_V1, 1, path).unwrap();
+        let names = upgraded.iter().map(Game::name).collect::<Vec<_>>();
+        assert_eq!(names, ["Celeste", "Hades"]);
+        assert_eq!(upgraded[1].save_location(), Path::new("/saves/Hades"));
+
+        // Writing the upgraded games gives the current layout, which reads the same games back
+        let current = serde_saphyr::to_string(&GamesFile {
+            version: GAMES_VERSION,
+            games: &upgraded,
+        })
+        .unwrap();
+        assert_eq!(games_version(&current), GAMES_VERSION);
+        let reread = parse_games(&current, GAMES_VERSION, path).unwrap();
+        assert_eq!(reread, upgraded);
+
+        assert!(parse_games("", 1, path).unwrap().is_empty());
+        assert!(parse_games(&current, 0, path).is_err());
+        assert!(parse_games(&current, GAMES_VERSION + 1, path).is_err());
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    #[test]
+    fn upgrades_the_games_database_when_loading() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(GAMES_FILE), GAMES_V1).unwrap();
+        let games = Games::builder().data_dir(dir.path()).load().unwrap();
+        assert!(games.get_exact("Celeste").is_some());
+
+        let stored = std::fs::read_to_string(dir.path().join(GAMES_FILE)).unwrap();
+        assert_eq!(games_version(&stored), GAMES_VERSION);
+        let kept = std::fs::read_to_string(dir.path().join("games.v1.yaml")).unwrap();
+        assert_eq!(kept, GAMES_V1);
+    }
+
+    #[test]
+    fn reads_the_history_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let game = Game::new(
+            String::from("Celeste"),
+            dir.path().to_owned(),
+            dir.path().join("saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        );
+        game.record_session(std::time::Duration::from_secs(60))
+            .unwrap();
+        let path = game.backups_path().join(HISTORY_FILE);
+        assert!(crate::rotation::rotate(&path, 0, true, HISTORY_KEEP).unwrap());
+        game.record_restore("Celeste-000.tar.zst").unwrap();
+
+        let history = game.history();
+        assert!(matches!(
+            history.as_slice(),
+            [
+                HistoryEvent::Session { duration: 60, .. },
+                HistoryEvent::Restore { .. }
+            ]
+        ));
+    }
+}
@@ -0,0 +1,205 @@
+use git2::{
+    Cred, CredentialType, FetchOptions, IndexAddOption, ObjectType, Oid, PushOptions,
+    RemoteCallbacks, Repository,
+};
+use rootcause::Result;
+use rootcause::option_ext::OptionExt;
+use rootcause::prelude::*;
+use std::{collections::BTreeMap, path::Path};
+
+const REMOTE: &str = "origin";
+
+/// Initializes a repository in `dir` if there is none, adding `remote` as its origin.
+pub fn init(dir: &Path, remote: Option<&str>) -> Result<()> {
+    let repo = match Repository::open(dir) {
+        Ok(repo) => repo,
+        Err(_) => Repository::init(dir)
+            .context_with(|| format!("Could not create git repository in {}", dir.display()))?,
+    };
+    if let Some(remote) = remote
+        && repo.find_remote(REMOTE).is_err()
+    {
+        repo.remote(REMOTE, remote)
+            .context_with(|| format!("Could not add remote {remote}"))?;
+    }
+    Ok(())
+}
+
+/// Commits every file in `dir`, doing nothing if there are no changes.
+///
+/// The files of gg starting with ".gg-" are local to the machine, so they are never committed.
+/// Returns the id of the created commit, if any.
+pub fn commit(dir: &Path, message: &str) -> Result<Option<String>> {
+    let repo = open(dir)?;
+    let mut index = repo.index()?;
+    index.add_all(
+        ["*"],
+        IndexAddOption::DEFAULT,
+        Some(&mut |path: &Path, _: &[u8]| {
+            i32::from(path.as_os_str().as_encoded_bytes().starts_with(b".gg-"))
+        }),
+    )?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree.id()) {
+        println!("Nothing to commit in {}, skipping...", dir.display());
+        return Ok(None);
+    }
+
+    let signature = repo
+        .signature()
+        .or_else(|_| git2::Signature::now("goodgame", "goodgame@localhost"))?;
+    let parents: Vec<_> = parent.iter().collect();
+    let id = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .context_with(|| format!("Could not commit in {}", dir.display()))?;
+    crate::output::progress(format_args!("Committed {id} in {}", dir.display()));
+    Ok(Some(id.to_string()))
+}
+
+/// Pushes the current branch of the repository in `dir` to its origin.
+pub fn push(dir: &Path) -> Result<()> {
+    let repo = open(dir)?;
+    let Ok(mut remote) = repo.find_remote(REMOTE) else {
+        println!(
+            "No remote configured for {}, skipping push...",
+            dir.display()
+        );
+        return Ok(());
+    };
+    let head = repo.head().context("Nothing has been committed yet")?;
+    let branch = head.shorthand().ok_or_report()?;
+
+    let config = repo.config()?;
+    let mut callbacks = callbacks(&config);
+    callbacks.push_update_reference(|reference, status| match status {
+        Some(msg) => Err(git2::Error::from_str(&format!(
+            "Remote rejected {reference}: {msg}"
+        ))),
+        None => Ok(()),
+    });
+
+    crate::output::progress(format_args!(
+        "Pushing {branch} to {}",
+        remote.url().unwrap_or(REMOTE)
+    ));
+    remote
+        .push(
+            &[format!("refs/heads/{branch}:refs/heads/{branch}")],
+            Some(PushOptions::new().remote_callbacks(callbacks)),
+        )
+        .context_with(|| format!("Could not push {}", dir.display()))?;
+    Ok(())
+}
+
+/// Fetches the origin and fast-forwards the current branch to it.
+pub fn pull(dir: &Path) -> Result<()> {
+    let repo = open(dir)?;
+    let Ok(mut remote) = repo.find_remote(REMOTE) else {
+        println!(
+            "No remote configured for {}, skipping pull...",
+            dir.display()
+        );
+        return Ok(());
+    };
+    crate::output::progress(format_args!(
+        "Pulling from {}",
+        remote.url().unwrap_or(REMOTE)
+    ));
+    let Some(fetched) = fetch(&repo, &mut remote, dir)? else {
+        return Ok(());
+    };
+    let fetched = repo.find_annotated_commit(fetched)?;
+    let (analysis, _) = repo.merge_analysis(&[&fetched])?;
+    if analysis.is_up_to_date() {
+        return Ok(());
+    }
+    if !analysis.is_fast_forward() && !analysis.is_unborn() {
+        bail!(
+            "The backups in {} diverged from the remote, merge them manually",
+            dir.display()
+        )
+    }
+
+    let head = repo.find_reference("HEAD")?;
+    let branch = head.symbolic_target().ok_or_report()?.to_owned();
+    repo.reference(&branch, fetched.id(), true, "gg: fast-forward")?;
+    repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+    Ok(())
+}
+
+/// Returns the names and sizes of the files in the origin of the repository in `dir`, or `None`
+/// if it has no origin.
+///
+/// Hidden files, like the ones of git, are skipped.
+pub fn list(dir: &Path) -> Result<Option<BTreeMap<String, u64>>> {
+    let repo = open(dir)?;
+    let Ok(mut remote) = repo.find_remote(REMOTE) else {
+        return Ok(None);
+    };
+    let Some(fetched) = fetch(&repo, &mut remote, dir)? else {
+        return Ok(Some(BTreeMap::new()));
+    };
+    let tree = repo.find_commit(fetched)?.tree()?;
+    let mut files = BTreeMap::new();
+    for entry in tree.iter() {
+        let Some(name) = entry.name() else {
+            continue;
+        };
+        if name.starts_with('.') || entry.kind() != Some(ObjectType::Blob) {
+            continue;
+        }
+        let size = repo.find_blob(entry.id())?.size();
+        files.insert(name.to_owned(), size as u64);
+    }
+    Ok(Some(files))
+}
+
+/// Fetches `remote`, returning the fetched commit, or `None` if the remote is empty.
+fn fetch(repo: &Repository, remote: &mut git2::Remote, dir: &Path) -> Result<Option<Oid>> {
+    let config = repo.config()?;
+    remote
+        .fetch(
+            &[] as &[&str],
+            Some(FetchOptions::new().remote_callbacks(callbacks(&config))),
+            None,
+        )
+        .context_with(|| format!("Could not fetch {}", dir.display()))?;
+    let Ok(fetch_head) = repo.find_reference("FETCH_HEAD") else {
+        return Ok(None);
+    };
+    Ok(Some(fetch_head.peel_to_commit()?.id()))
+}
+
+/// Callbacks that take the credentials from the SSH agent or the configured git credential helpers.
+fn callbacks(config: &git2::Config) -> RemoteCallbacks<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(|url, username, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) {
+            Cred::ssh_key_from_agent(username.unwrap_or("git"))
+        } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            Cred::credential_helper(config, url, username)
+        } else {
+            Cred::default()
+        }
+    });
+    callbacks
+}
+
+fn open(dir: &Path) -> Result<Repository> {
+    Ok(Repository::open(dir).context_with(|| {
+        format!(
+            "{} is not a git repository, add the game again to initialize it",
+            dir.display()
+        )
+    })?)
+}
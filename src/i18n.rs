@@ -0,0 +1,115 @@
+//! Translations of the messages, prompts and help of gg, in the language of the user.
+//!
+//! The messages are Fluent files in the locales directory, embedded in the binary. The language
+//! is the first one set in LC_ALL, LC_MESSAGES or LANG, falling back to English for the languages
+//! and messages that are not translated.
+
+use fluent::concurrent::FluentBundle;
+use fluent::{FluentArgs, FluentResource};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+pub use fluent::FluentValue;
+
+/// Messages of every language, by their language tag, English being the fallback.
+const LOCALES: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en/gg.ftl")),
+    ("es", include_str!("../locales/es/gg.ftl")),
+];
+
+/// Bundles of the language of the user, if translated, and of English.
+struct Bundles {
+    user: Option<FluentBundle<FluentResource>>,
+    english: FluentBundle<FluentResource>,
+}
+
+/// Returns the language of the user, read from LC_ALL, LC_MESSAGES and LANG, or `None` if none
+/// sets one, like when they are "C" or "POSIX".
+///
+/// Values like "es_ES.UTF-8" or "ca_ES@valencia" are read as "es-ES" and "ca-ES".
+pub fn locale() -> Option<LanguageIdentifier> {
+    let value = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())?;
+    let tag = value.split(['.', '@']).next()?.replace('_', "-");
+    if tag == "C" || tag == "POSIX" {
+        return None;
+    }
+    tag.parse().ok()
+}
+
+fn bundle(tag: &str, source: &str) -> FluentBundle<FluentResource> {
+    let langid = tag.parse::<LanguageIdentifier>().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // The marks that isolate the arguments show up as garbage in most terminals
+    bundle.set_use_isolating(false);
+    let resource =
+        FluentResource::try_new(source.to_owned()).unwrap_or_else(|(resource, _)| resource);
+    let _ = bundle.add_resource(resource);
+    bundle
+}
+
+fn bundles() -> &'static Bundles {
+    static BUNDLES: OnceLock<Bundles> = OnceLock::new();
+    BUNDLES.get_or_init(|| {
+        let user = locale().and_then(|locale| {
+            LOCALES
+                .iter()
+                .find(|(tag, _)| *tag != "en" && *tag == locale.language.as_str())
+                .map(|(tag, source)| bundle(tag, source))
+        });
+        Bundles {
+            user,
+            english: bundle(LOCALES[0].0, LOCALES[0].1),
+        }
+    })
+}
+
+fn format(
+    bundle: &FluentBundle<FluentResource>,
+    id: &str,
+    args: Option<&FluentArgs>,
+) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let message = bundle.format_pattern(pattern, args, &mut errors);
+    errors.is_empty().then(|| message.into_owned())
+}
+
+/// Returns the message `id` translated to the language of the user, or `None` if it is not
+/// translated to it, so the caller keeps its own English text, like the help of the commands.
+pub fn translation(id: &str) -> Option<String> {
+    format(bundles().user.as_ref()?, id, None)
+}
+
+/// Returns the message `id` in the language of the user with the `args` replaced, in English if
+/// it is not translated, or `id` itself if it doesn't exist.
+pub fn message(id: &str, args: &[(&str, FluentValue<'_>)]) -> String {
+    let args = (!args.is_empty()).then(|| {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        fluent_args
+    });
+    let bundles = bundles();
+    bundles
+        .user
+        .as_ref()
+        .and_then(|user| format(user, id, args.as_ref()))
+        .or_else(|| format(&bundles.english, id, args.as_ref()))
+        .unwrap_or_else(|| id.to_owned())
+}
+
+/// Returns the message with the given id in the language of the user, with the arguments given
+/// as `name = value`, like `tr!("backup-created", path = path.display().to_string())`.
+#[macro_export]
+macro_rules! tr {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::message(
+            $id,
+            &[$((stringify!($name), $crate::i18n::FluentValue::from($value))),*],
+        )
+    };
+}
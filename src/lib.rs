@@ -1,5 +1,40 @@
-mod config;
+pub mod archive;
+pub mod backup;
+pub mod cloud;
+pub mod compression;
+pub mod config;
+pub mod crypto;
+pub mod detect;
+pub mod error;
+pub mod events;
 pub mod games;
+#[cfg(feature = "git")]
+mod git;
+pub mod i18n;
+pub mod logging;
+pub mod manifest;
+pub mod metadata;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod observer;
+pub mod outcome;
+pub mod output;
+pub mod paths;
+mod rotation;
+pub mod rpc;
+pub mod runner;
+pub mod schedule;
+pub mod secrets;
+pub mod shortcuts;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+pub mod status;
+mod syncthing;
+pub mod units;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "webdav")]
+mod webdav;
 
 // TODO: Add MelonLoader installer
 // TODO: Add CreamAPI installer
@@ -0,0 +1,264 @@
+//! Log of the commands run and of the backups, restores and runs, appended to a file to debug the
+//! operations that failed, and shown in the terminal with -v.
+
+use crate::config::Log;
+use crate::units::Size;
+use rootcause::Result;
+use rootcause::option_ext::OptionExt;
+use rootcause::prelude::*;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Name of the log file in the state directory if not configured.
+const DEFAULT_FILE: &str = "gg.log";
+/// Size of the log file at which it is moved aside if not configured.
+const DEFAULT_MAX_SIZE: Size = Size(10 * 1024 * 1024);
+/// Number of the log files moved aside that are kept if not configured.
+const DEFAULT_KEEP: usize = 5;
+/// Target of the lines printed by the commands, only recorded in the log file as the terminal
+/// already shows them.
+pub const COMMAND_OUTPUT: &str = "command_output";
+
+static VERBOSITY: OnceLock<Verbosity> = OnceLock::new();
+
+/// How much gg prints to the terminal besides the results, set with -q, -v and -vv.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only the results, the warnings and the errors.
+    Quiet,
+    /// Also the commands run and the progress of the transfers.
+    #[default]
+    Normal,
+    /// Also the messages recorded in the log at the info level.
+    Verbose,
+    /// Also the resolved paths and the statistics of the archives.
+    Debug,
+    Trace,
+}
+
+impl Verbosity {
+    /// Returns the verbosity of `-q` if `quiet`, or of as many `-v` as `verbose`.
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (_, 0) => Self::Normal,
+            (_, 1) => Self::Verbose,
+            (_, 2) => Self::Debug,
+            _ => Self::Trace,
+        }
+    }
+
+    fn filter(self) -> tracing::level_filters::LevelFilter {
+        use tracing::level_filters::LevelFilter;
+        match self {
+            Self::Quiet | Self::Normal => LevelFilter::OFF,
+            Self::Verbose => LevelFilter::INFO,
+            Self::Debug => LevelFilter::DEBUG,
+            Self::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Returns the verbosity given to [`init`], the normal one if not started.
+pub fn verbosity() -> Verbosity {
+    VERBOSITY.get().copied().unwrap_or_default()
+}
+
+/// Returns whether gg was run with -q, hiding the commands run and the progress messages.
+pub fn quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+/// Most detailed messages recorded in the log.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Deserialize,
+    serde::Serialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    /// Nothing is recorded.
+    Off,
+    Error,
+    Warn,
+    /// The commands run and the backups, restores and runs of the games.
+    #[default]
+    Info,
+    /// Also the output of the commands, which then see pipes instead of the terminal.
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn filter(self) -> tracing::level_filters::LevelFilter {
+        use tracing::level_filters::LevelFilter;
+        match self {
+            Self::Off => LevelFilter::OFF,
+            Self::Error => LevelFilter::ERROR,
+            Self::Warn => LevelFilter::WARN,
+            Self::Info => LevelFilter::INFO,
+            Self::Debug => LevelFilter::DEBUG,
+            Self::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Starts appending the messages of the configured level to the log file, and printing the ones
+/// of the `verbosity` to stderr.
+///
+/// The GG_LOG environment variable takes precedence over the configured level. A file bigger than
+/// the configured size is first moved aside, compressed unless disabled.
+pub fn init(config: &Log, verbosity: Verbosity) -> Result<()> {
+    use tracing_subscriber::prelude::*;
+
+    let _ = VERBOSITY.set(verbosity);
+    let terminal = (verbosity > Verbosity::Normal).then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_ansi(crate::output::color())
+            .without_time()
+            .with_target(false)
+            .with_filter(verbosity.filter())
+            .with_filter(tracing_subscriber::filter::filter_fn(|metadata| {
+                metadata.target() != COMMAND_OUTPUT
+            }))
+    });
+    // The terminal still shows the messages if the log file can't be opened
+    let (file, error) = match open_file(config) {
+        Ok(file) => (file, None),
+        Err(e) => (None, Some(e)),
+    };
+    let file = file.map(|(file, level)| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::sync::Mutex::new(file))
+            .with_ansi(false)
+            .with_target(false)
+            .with_filter(level.filter())
+    });
+    if let Err(e) = tracing_subscriber::registry()
+        .with(file)
+        .with(terminal)
+        .try_init()
+    {
+        bail!("Could not start the log: {e}")
+    }
+    error.map_or(Ok(()), Err)
+}
+
+/// Opens the log file with the level of its messages, `None` if the level is off.
+fn open_file(config: &Log) -> Result<Option<(std::fs::File, LogLevel)>> {
+    let level = match std::env::var("GG_LOG") {
+        Ok(level) => serde_saphyr::from_str(&level).context_with(|| {
+            format!("GG_LOG must be off, error, warn, info, debug or trace, not {level:?}")
+        })?,
+        Err(_) => config.level.unwrap_or_default(),
+    };
+    if level == LogLevel::Off {
+        return Ok(None);
+    }
+    let path = file_path(config)?;
+    crate::rotation::rotate(
+        &path,
+        config.max_size.unwrap_or(DEFAULT_MAX_SIZE).0,
+        config.compress.unwrap_or(true),
+        config.keep.unwrap_or(DEFAULT_KEEP),
+    )
+    .context("Could not rotate the log file")?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context_with(|| format!("Could not open the log file {}", path.display()))?;
+    Ok(Some((file, level)))
+}
+
+/// Returns the path of the log file, the configured one or gg.log in the state directory.
+pub fn file_path(config: &Log) -> Result<PathBuf> {
+    match &config.file {
+        Some(file) => Ok(file.clone()),
+        None => Ok(crate::paths::state_dir()
+            .context("Could not obtain state directory")?
+            .join(DEFAULT_FILE)),
+    }
+}
+
+/// Returns the lines of the log file and of the files moved aside, oldest first, only the ones
+/// recorded in the last `since` if given.
+pub fn read(config: &Log, since: Option<std::time::Duration>) -> Result<Vec<String>> {
+    let contents = crate::rotation::read(&file_path(config)?)?;
+    let Some(since) = since else {
+        return Ok(contents.lines().map(str::to_owned).collect());
+    };
+    let cutoff = timestamp(crate::games::unix_time().saturating_sub(since.as_secs()));
+    let mut recent = false;
+    let mut lines = Vec::new();
+    for line in contents.lines() {
+        // The lines of a message without a timestamp go with the line before
+        if line.starts_with(|c: char| c.is_ascii_digit())
+            && let Some(time) = line.get(..cutoff.len())
+        {
+            recent = time >= cutoff.as_str();
+        }
+        if recent {
+            lines.push(line.to_owned());
+        }
+    }
+    Ok(lines)
+}
+
+/// Formats `time`, in seconds since the Unix epoch, like the timestamps of the log, which are in
+/// UTC and can be compared as text.
+fn timestamp(time: u64) -> String {
+    let (year, month, day) = crate::games::date(time as i64);
+    let secs = time % (24 * 60 * 60);
+    format!(
+        "{year}-{month:0>2}-{day:0>2}T{:0>2}:{:0>2}:{:0>2}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_recent_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = Log {
+            file: Some(dir.path().join("gg.log")),
+            ..Log::default()
+        };
+        let now = timestamp(crate::games::unix_time());
+        std::fs::write(
+            dir.path().join("gg.log.1"),
+            "2020-01-01T00:00:00.000000Z  INFO Started gg list\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("gg.log"),
+            format!(
+                "2020-01-02T00:00:00.000000Z ERROR Failed\n  old cause\n\
+                 {now}.000000Z ERROR Failed\n  new cause\n"
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(read(&config, None).unwrap().len(), 5);
+        let recent = read(&config, Some(std::time::Duration::from_secs(3600))).unwrap();
+        assert_eq!(
+            recent,
+            [format!("{now}.000000Z ERROR Failed"), "  new cause".into()]
+        );
+    }
+}
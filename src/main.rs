@@ -1,28 +1,94 @@
 mod cli;
 
-use clap::{CommandFactory, Parser};
-use goodgame::games::{Game, Games};
+use clap::FromArgMatches;
+use goodgame::archive::ArchiveEntry;
+use goodgame::cloud::CloudMode;
+use goodgame::config::{Config, CrashBackup};
+use goodgame::error::Error;
+use goodgame::events::{Event, Hook};
+use goodgame::games::{Broken, Game, Games};
+use goodgame::tr;
+use goodgame::units::Size;
+use goodgame::{backup, crypto, outcome, output, runner, secrets, status};
 use rootcause::Result;
 use rootcause::option_ext::OptionExt;
 use rootcause::prelude::*;
 use std::{
-    io::Seek,
+    io::Read,
     os::unix::ffi::OsStrExt,
     path::{Path, PathBuf},
-    process::Command,
+    process::{Command, Stdio},
 };
 
-fn main() -> Result<()> {
-    // echo "source (COMPLETE=fish your_program | psub)" >> ~/.config/fish/config.fish
-    clap_complete::CompleteEnv::with_factory(cli::Cli::command)
+fn main() -> std::process::ExitCode {
+    match try_main() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(report) => {
+            output::error(format_args!("{report:?}"));
+            if json_output() {
+                // The fields of the kind, if known, along with the message
+                let mut error = serde_json::to_value(goodgame::error::kind(&report))
+                    .ok()
+                    .filter(serde_json::Value::is_object)
+                    .unwrap_or_else(|| serde_json::json!({}));
+                error["error"] = report.format_current_context().to_string().into();
+                // What it did before failing, like the backups created
+                if let (Some(error), Ok(serde_json::Value::Object(outcome))) =
+                    (error.as_object_mut(), serde_json::to_value(outcome::take()))
+                {
+                    error.extend(outcome);
+                }
+                let _ = print_json(&error);
+            }
+            std::process::ExitCode::FAILURE
+        }
+    }
+}
+
+/// Runs the command given in the arguments.
+fn try_main() -> Result<()> {
+    // Run 'gg completions doctor' to get the line that enables them in the current shell
+    clap_complete::CompleteEnv::with_factory(cli::localized_command)
         .bin("gg")
         .complete();
 
-    let games = Games::load()?;
-    let cli = cli::Cli::parse();
+    // Checked before parsing, so the help and the usage errors are not colored either
+    let mut command = cli::localized_command();
+    if std::env::args_os().any(|arg| arg == "--no-color") {
+        output::disable_color();
+        command = command.color(clap::ColorChoice::Never);
+    }
+    let matches = command.get_matches();
+    let cli = cli::Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // The names of the subcommands, like "cloud push"
+    let operation = std::iter::successors(matches.subcommand(), |(_, m)| m.subcommand())
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    outcome::begin(operation.join(" "));
+    if cli.json {
+        start_json_output()?;
+    }
+    let mut games = if cli.command.changes_games() {
+        Games::load_locked()?
+    } else {
+        Games::load()?
+    };
+    if cli.ignore_config {
+        games.ignore_config();
+    }
+    // An invalid config is reported by the command itself, and the log is not worth failing it
+    let verbosity = goodgame::logging::Verbosity::from_flags(cli.quiet, cli.verbose);
+    let default_log = goodgame::config::Log::default();
+    let log = games.config().map_or(&default_log, |config| &config.log);
+    if let Err(e) = goodgame::logging::init(log, verbosity) {
+        output::warning(format_args!("Could not start the log: {e}"));
+    }
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = format!("gg {}", args.join(" "));
+    tracing::info!("Started {command}");
 
-    match cli {
-        cli::Cli::Add {
+    let result = match cli.command {
+        cli::Command::Add {
             game,
             root,
             save_location,
@@ -31,20 +97,40 @@ fn main() -> Result<()> {
             executable,
             executable_args,
             environment_vars,
+            prefix,
+            proton,
+            steam_appid,
+            workdir,
+            tags,
+            aliases,
             run_commands,
+            discover,
+            from_bundle,
+            yes,
+            from_manifest,
         } => add(
             game,
             root,
             save_location,
             skip_cloud,
             skip_cloud_init,
+            discover,
+            from_bundle,
+            yes,
+            from_manifest,
             executable,
             executable_args,
             environment_vars,
+            prefix,
+            proton,
+            steam_appid,
+            workdir,
+            tags,
+            aliases,
             run_commands,
             games,
         ),
-        cli::Cli::Edit {
+        cli::Command::Edit {
             name,
             root,
             save_location,
@@ -52,6 +138,18 @@ fn main() -> Result<()> {
             executable_args,
             environment_vars,
             run_commands,
+            prefix,
+            proton,
+            steam_appid,
+            workdir,
+            wrappers,
+            cloud,
+            backend,
+            remote,
+            post_restore_clean,
+            tags,
+            aliases,
+            notes,
             game,
         } => edit(
             name,
@@ -61,66 +159,556 @@ fn main() -> Result<()> {
             executable_args,
             environment_vars,
             run_commands,
+            prefix,
+            proton,
+            steam_appid,
+            workdir,
+            wrappers,
+            cloud,
+            backend,
+            remote,
+            post_restore_clean,
+            tags,
+            aliases,
+            notes,
             game,
             games,
         ),
-        cli::Cli::Remove { game } => remove(game, games),
-        cli::Cli::List => list(games),
-        cli::Cli::Backup {
+        cli::Command::Rename {
+            game,
+            new_name,
+            cloud,
+        } => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| rename(game, new_name, cloud, games)),
+        cli::Command::Move {
             game,
+            root,
+            save_location,
+        } => move_game(game, root, save_location, games),
+        cli::Command::Remove {
+            games: selection,
+            purge_backups,
+            keep_symlink,
+        } => remove(selection, purge_backups, keep_symlink, games),
+        cli::Command::List {
+            tags,
+            filter,
+            sort,
+            long,
+            archived,
+        } => list(&tags, filter.as_deref(), sort, long, archived, games),
+        cli::Command::Archive { games: names } => archive(&names, true, games),
+        cli::Command::Unarchive { games: names } => archive(&names, false, games),
+        cli::Command::Backup {
+            games: selection,
             desc,
-            skip_cloud,
-        } => backup(game.as_deref(), desc.as_deref(), skip_cloud, &games),
-        cli::Cli::Restore {
+            message,
+            dry_run_cloud: true,
+            ..
+        } => select_games(&selection, &games).and_then(|selected| {
+            selected.into_iter().try_for_each(|game| {
+                backup_dry_run(
+                    Some(game.name()),
+                    desc.as_deref(),
+                    message.as_deref(),
+                    &games,
+                )
+            })
+        }),
+        cli::Command::Backup {
+            games: selection,
+            desc,
+            message,
+            player,
+            cloud,
+            prune,
+            no_prune,
+            ..
+        } => {
+            if prune || no_prune {
+                games.set_auto_prune(prune);
+            }
+            games.cloud_mode(cloud.mode()).and_then(|cloud| {
+                backup_selected(
+                    &selection,
+                    desc.as_deref(),
+                    message.as_deref(),
+                    player.as_deref(),
+                    cloud,
+                    &games,
+                )
+            })
+        }
+        cli::Command::Restore {
             game,
             backup,
-            skip_cloud,
-        } => restore(game, backup, skip_cloud, games),
-        cli::Cli::Open { game, save } => open(game, save, games),
-        cli::Cli::Run { game, skip_cloud } => run(game, skip_cloud, games),
-        cli::Cli::Config => print_config(games),
+            slot,
+            player,
+            cloud,
+            yes,
+            dry_run_clean,
+        } => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| {
+                restore(
+                    game.clone(),
+                    backup,
+                    slot.clone(),
+                    player.as_deref(),
+                    cloud,
+                    yes,
+                    dry_run_clean,
+                    &games,
+                )
+            })
+            .and_then(|backup| {
+                print_json(&serde_json::json!({
+                    "game": game,
+                    "player": player,
+                    "backup": backup,
+                    "slot": slot,
+                }))
+            }),
+        cli::Command::Transfer { from, to, cloud } => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| transfer(from, to, cloud, &games)),
+        cli::Command::Share { game, output } => share(game, output, games),
+        cli::Command::Backups(cli::Backups::List { game, cloud }) => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| backups_list(game, cloud, games)),
+        cli::Command::Backups(cli::Backups::Dedupe { game, cloud }) => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| backups_dedupe(game, cloud, games)),
+        cli::Command::Backups(cli::Backups::Cat {
+            game,
+            backup,
+            file,
+            slot,
+        }) => backups_cat(game, backup, file, slot, games),
+        cli::Command::Sync { game } => sync(game, games),
+        cli::Command::Cloud(cli::Cloud::Status { game }) => cloud_status(game, games),
+        cli::Command::Cloud(cli::Cloud::Ls { game }) => cloud_ls(game, games),
+        cli::Command::Cloud(cli::Cloud::DryRun { game, all }) => cloud_dry_run(game, all, games),
+        cli::Command::Clone {
+            index,
+            path_maps,
+            restore,
+        } => clone(index, path_maps, restore, games),
+        cli::Command::Export {
+            file,
+            games: names,
+            tags,
+            backups,
+        } => export(&file, &names, &tags, backups, &games),
+        cli::Command::Import {
+            file,
+            replace,
+            path_maps,
+            yes,
+        } => import(&file, replace, path_maps, yes, games),
+        cli::Command::Open {
+            games: selection,
+            save,
+            backups,
+        } => open(selection, save, backups, games),
+        cli::Command::Desktop { game, icon, remove } => desktop(game, icon, remove, games),
+        cli::Command::SteamShortcut { game, icon, remove } => {
+            steam_shortcut(game, icon, remove, games)
+        }
+        cli::Command::Metadata {
+            games: names,
+            tags,
+            refresh,
+        } => metadata(&names, &tags, refresh, &games),
+        cli::Command::Run {
+            detach: true,
+            game,
+            last,
+            ..
+        } => run_detached(game, last, &games),
+        cli::Command::Run {
+            game,
+            cloud,
+            backup,
+            no_backup,
+            pre_backup,
+            no_pre_backup,
+            autosave_interval,
+            last,
+            profile,
+            player,
+            args,
+            ..
+        } => games.cloud_mode(cloud.mode()).and_then(|cloud| {
+            let backup_after = match (backup, no_backup) {
+                (true, _) => true,
+                (_, true) => false,
+                _ => games.config()?.run.backup_after,
+            };
+            let backup_before = match (pre_backup, no_pre_backup) {
+                (true, _) => true,
+                (_, true) => false,
+                _ => games.config()?.run.backup_before,
+            };
+            let autosave_interval = autosave_interval
+                .or(games.config()?.run.autosave_interval)
+                .map(std::time::Duration::from)
+                .filter(|interval| !interval.is_zero());
+            run(
+                game,
+                last,
+                profile.as_deref(),
+                player.as_deref(),
+                &args,
+                cloud,
+                backup_before,
+                backup_after,
+                autosave_interval,
+                games,
+            )
+        }),
+        cli::Command::Config { action: None } => print_config(games),
+        cli::Command::Config {
+            action: Some(cli::Config::Edit { system }),
+        } => edit_config(system, games),
+        cli::Command::Config {
+            action: Some(cli::Config::Check),
+        } => check_config(games),
+        cli::Command::Config {
+            action: Some(cli::Config::Schema { games }),
+        } => print_schema(games),
+        cli::Command::Config {
+            action: Some(cli::Config::Init { system, force }),
+        } => init_config(system, force),
+        cli::Command::Key(cli::Key::Rotate { game, cloud }) => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| rotate_key(game, cloud, games)),
+        cli::Command::Secret(cli::Secret::Set { name }) => set_secret(&name),
+        cli::Command::Secret(cli::Secret::Remove { name }) => {
+            secrets::remove(&name).map(|()| println!("Removed the secret {name:?}"))
+        }
+        cli::Command::Completions(cli::Completions::Doctor { shell }) => completions_doctor(shell),
+        cli::Command::Man { dir, help_all } => man(&dir, help_all),
+        cli::Command::Stats { game, tags } => stats(game, &tags, games),
+        cli::Command::History { game, tags, since } => history(game, &tags, since, games),
+        cli::Command::Logs { since } => logs(since, &games),
+        cli::Command::Status { game, tags } => game_status(game, &tags, games),
+        cli::Command::Search { query } => search(&query, games),
+        cli::Command::Du { game, tags, top } => disk_usage(game, &tags, top, games),
+        cli::Command::Gc {
+            games: names,
+            tags,
+            older_than,
+            level,
+            dry_run,
+        } => gc(&names, &tags, older_than, level, dry_run, &games),
+        cli::Command::Prune {
+            games: names,
+            tags,
+            keep,
+            max_age,
+            dry_run,
+        } => prune(&names, &tags, keep, max_age, dry_run, &games),
+        cli::Command::Top { once, socket } => top(once, socket, games),
+        cli::Command::Serve { socket } => serve(socket, cli.ignore_config),
+        cli::Command::Watch {
+            games: names,
+            tags,
+            cloud,
+        } => games
+            .cloud_mode(cloud.mode())
+            .and_then(|cloud| watch(&names, &tags, cloud, &games)),
+        cli::Command::Doctor { selftest: true, .. } => selftest(),
+        cli::Command::Doctor { game, yes, fix, .. } => doctor(game, yes, fix, games),
+        cli::Command::Sandbox { keep } => sandbox(keep),
+    };
+
+    if let Err(report) = &result {
+        tracing::error!("{command} failed: {report}");
+        let _ = status::record_failure(command, report.to_string());
+    }
+    // The commands with their own JSON output already describe what they did
+    if result.is_ok() && !JSON_PRINTED.load(std::sync::atomic::Ordering::Relaxed) {
+        print_json(&outcome::take())?;
     }
+    result
+}
+
+/// Original stdout when run with --json, every other message going to stderr.
+static JSON_OUTPUT: std::sync::OnceLock<std::fs::File> = std::sync::OnceLock::new();
+
+/// Whether the command printed its own JSON, instead of the [`outcome::Outcome`] of it.
+static JSON_PRINTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Points stdout to stderr, for gg and the commands it runs, keeping the original one for the
+/// JSON printed by [`print_json`].
+fn start_json_output() -> Result<()> {
+    use std::os::fd::AsFd;
+
+    let stdout = std::io::stdout()
+        .as_fd()
+        .try_clone_to_owned()
+        .context("Could not duplicate stdout")?;
+    rustix::stdio::dup2_stdout(std::io::stderr()).context("Could not point stdout to stderr")?;
+    let _ = JSON_OUTPUT.set(stdout.into());
+    Ok(())
+}
+
+/// Returns whether gg was run with --json.
+fn json_output() -> bool {
+    JSON_OUTPUT.get().is_some()
+}
+
+/// Prints `value` as JSON to stdout if run with --json, does nothing otherwise.
+fn print_json(value: &impl serde::Serialize) -> Result<()> {
+    use std::io::Write;
+
+    let Some(mut out) = JSON_OUTPUT.get() else {
+        return Ok(());
+    };
+    serde_json::to_writer_pretty(&mut out, value).context("Could not print the JSON output")?;
+    writeln!(out)?;
+    JSON_PRINTED.store(true, std::sync::atomic::Ordering::Relaxed);
+    Ok(())
+}
+
+/// Returns the game, name, path and size of the backup at `archive_path` as JSON.
+fn backup_json(archive_path: &Path, games: &Games) -> serde_json::Value {
+    let game = games
+        .games()
+        .iter()
+        .find(|game| archive_path.parent() == Some(game.backups_path().as_path()));
+    serde_json::json!({
+        "game": game.map(Game::name),
+        "backup": archive_path.file_name().map(|name| name.to_string_lossy()),
+        "path": archive_path,
+        "size": archive_path.metadata().map_or(0, |m| m.len()),
+    })
+}
+
+/// Returns the games of the `selection`, or the one of the current directory if it is empty.
+///
+/// The games matched by a pattern or tag are listed and confirmed first, unless --yes or there is
+/// no terminal to ask in.
+fn select_games<'a>(selection: &cli::Selection, games: &'a Games) -> Result<Vec<&'a Game>> {
+    use std::io::IsTerminal;
+
+    if selection.games.is_empty() && selection.tags.is_empty() {
+        return Ok(vec![games.try_get(None::<&str>)?]);
+    }
+    let selected = games.select(&selection.games, &selection.tags)?;
+    if selection.matches() && !selection.yes && std::io::stdin().is_terminal() {
+        let names = selected.iter().map(|game| game.name()).collect::<Vec<_>>();
+        let confirmed = inquire::Confirm::new(&tr!("prompt-continue", games = names.join(", ")))
+            .with_default(true)
+            .prompt()
+            .context("Could not read the confirmation")?;
+        if !confirmed {
+            bail!("{}", tr!("cancelled"))
+        }
+    }
+    Ok(selected)
+}
+
+/// Backs up the games of the `selection`, going on with the rest when one fails.
+fn backup_selected(
+    selection: &cli::Selection,
+    desc: Option<&str>,
+    message: Option<&str>,
+    player: Option<&str>,
+    cloud: CloudMode,
+    games: &Games,
+) -> Result<()> {
+    let selected = select_games(selection, games)?;
+    let mut backups = Vec::new();
+    let mut failed = Vec::new();
+    for game in &selected {
+        let result = game
+            .with_player(player)
+            .and_then(|game| games.backup(&game, desc, message, None, cloud));
+        match result {
+            Ok(archive_path) => backups.push(backup_json(&archive_path, games)),
+            Err(e) if selected.len() > 1 => {
+                output::error(format_args!("Could not back up {}: {e}", game.name()));
+                failed.push(game.name());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    // A single named game keeps printing a single object
+    match backups.as_slice() {
+        [backup] if selected.len() == 1 && !selection.matches() => print_json(backup)?,
+        backups => print_json(&backups)?,
+    }
+    if !failed.is_empty() {
+        bail!("Could not back up {}", failed.join(", "))
+    }
+    Ok(())
 }
 
 #[allow(clippy::too_many_arguments)]
 fn add(
-    game: String,
-    root: PathBuf,
+    game: Option<String>,
+    root: Option<PathBuf>,
     save_location: Option<PathBuf>,
     skip_cloud: bool,
     skip_cloud_init: bool,
+    discover: bool,
+    from_bundle: Option<PathBuf>,
+    yes: bool,
+    from_manifest: Option<String>,
     mut executable: Option<PathBuf>,
-    executable_args: Option<Vec<String>>,
+    mut executable_args: Option<Vec<String>>,
     environment_vars: Option<Vec<(String, String)>>,
-    run_commands: Option<Vec<String>>,
+    prefix: Option<PathBuf>,
+    proton: Option<String>,
+    steam_appid: Option<u32>,
+    workdir: Option<PathBuf>,
+    tags: Option<Vec<String>>,
+    aliases: Option<Vec<String>>,
+    mut run_commands: Option<Vec<String>>,
     mut games: Games,
 ) -> Result<()> {
+    use goodgame::games::{DROP_IN_FILE, DropIn};
+
+    let manifest = from_manifest
+        .is_some()
+        .then(goodgame::manifest::Manifest::load)
+        .transpose()?;
+    let listed = match (&manifest, &from_manifest) {
+        (Some(manifest), Some(title)) => Some(manifest.get(title)?),
+        _ => None,
+    };
+
+    let root = match root {
+        Some(root) => root,
+        None => match listed.and_then(|(_, listed)| {
+            listed
+                .install_dirs()
+                .find_map(goodgame::paths::steam_game_dir)
+        }) {
+            Some(installed) => installed,
+            None => std::env::current_dir().context("Could not get the current directory")?,
+        },
+    };
     let root = root
         .canonicalize()
         .context_with(|| format!("Failed to get root {}", root.display()))?;
 
-    let original_game = games.get_by_name(&game).ok();
-
-    let Some(save_location) = save_location
-        .or_else(|| original_game.map(|g| g.save_location().to_path_buf()))
-        .or_else(|| try_get_save_location(&root))
+    // The arguments take precedence over the settings of the packager
+    let drop_in = DropIn::read(&root)?.unwrap_or_default();
+    let Some(game) = game
+        .or(drop_in.name)
+        .or_else(|| listed.map(|(title, _)| title.to_owned()))
     else {
-        bail!("Save location could not be found automatically, please provide it")
+        bail!(
+            "No game name given, and {} has no {DROP_IN_FILE} with one",
+            root.display()
+        )
+    };
+    let save_location = save_location.or(drop_in.save_location);
+    executable = executable.or(drop_in.executable);
+    executable_args = executable_args.or(drop_in.executable_args);
+    run_commands = run_commands.or(drop_in.run_commands);
+
+    let original_game = games.get_exact(&game);
+
+    let shared = match &from_bundle {
+        Some(bundle) => {
+            let mut shared = read_bundle_game(bundle)?;
+            let shared_root = shared.root().to_path_buf();
+            shared.remap(&shared_root, &root);
+            confirm_bundle(&shared, yes)?;
+            Some(shared)
+        }
+        None => None,
     };
-    let save_location = save_location
-        .canonicalize()
-        .context_with(|| format!("Failed to get save location {}", save_location.display()))?;
 
     if let Some(exe) = &mut executable {
         *exe = exe
             .canonicalize()
             .context_with(|| format!("Failed to get executable {}", exe.display()))?;
     } else {
-        executable = original_game
+        executable = shared
+            .as_ref()
             .and_then(|g| g.executable().cloned())
+            .or_else(|| original_game.and_then(|g| g.executable().cloned()))
             .or_else(|| try_get_executable_location(&root));
     };
 
+    let wine_prefix = match prefix {
+        Some(prefix) => Some(
+            prefix
+                .canonicalize()
+                .context_with(|| format!("Failed to get prefix {}", prefix.display()))?,
+        ),
+        None => shared
+            .as_ref()
+            .and_then(|g| g.wine_prefix())
+            .or_else(|| original_game.and_then(|g| g.wine_prefix()))
+            .filter(|prefix| prefix.is_dir())
+            .map(Path::to_path_buf)
+            .or_else(|| goodgame::paths::compatdata_prefix(&root)),
+    };
+    let proton = proton.or_else(|| {
+        shared
+            .as_ref()
+            .and_then(|g| g.proton_version())
+            .or_else(|| original_game.and_then(|g| g.proton_version()))
+            .map(String::from)
+    });
+
+    let listed_save = listed.and_then(|(title, listed)| {
+        manifest_save_location(title, listed, &root, wine_prefix.as_deref())
+    });
+
+    let discovered = if discover && save_location.is_none() {
+        let probe = Game::new(
+            game.clone(),
+            root.clone(),
+            root.clone(),
+            executable.clone(),
+            executable_args.clone(),
+            environment_vars.clone(),
+            run_commands.clone(),
+        )
+        .with_wine(wine_prefix.clone(), proton.clone());
+        discover_save_location(&probe, &games)?
+    } else {
+        None
+    };
+
+    if save_location.is_none()
+        && let Some(shared) = &shared
+        && !shared.save_location().exists()
+    {
+        let shared_save = shared.save_location();
+        // The game creates it on its first run anyway
+        if !shared_save.starts_with(&root) {
+            bail!(
+                "The shared save location {} doesn't exist in this machine, please provide it",
+                shared_save.display()
+            )
+        }
+        std::fs::create_dir_all(shared_save)
+            .context_with(|| format!("Could not create {}", shared_save.display()))?;
+    }
+    let Some(save_location) = save_location
+        .or(discovered)
+        .or(listed_save)
+        .or_else(|| shared.as_ref().map(|g| g.save_location().to_path_buf()))
+        .or_else(|| original_game.map(|g| g.save_location().to_path_buf()))
+        .or_else(|| suggest_save_location(&game, &root, wine_prefix.as_deref()))
+        .or_else(|| wine_prefix.as_deref().and_then(prefix_save_location))
+        .or_else(|| try_get_save_location(&root))
+    else {
+        bail!("Save location could not be found automatically, please provide it")
+    };
+    let save_location = save_location
+        .canonicalize()
+        .context_with(|| format!("Failed to get save location {}", save_location.display()))?;
+
     if !root.is_dir() {
         bail!("The root must be a directory");
     }
@@ -140,15 +728,54 @@ fn add(
         })?;
     }
 
-    let game = Game::new(
-        game,
-        root,
-        save_location,
-        executable,
-        executable_args,
-        environment_vars,
-        run_commands,
-    );
+    let game = match shared.clone() {
+        // Keeps the settings that can't be given as arguments, like the compression
+        Some(shared) => shared.merged_with(
+            Some(game),
+            Some(root),
+            Some(save_location),
+            executable,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ),
+        None => Game::new(
+            game,
+            root,
+            save_location,
+            executable,
+            executable_args,
+            environment_vars,
+            run_commands,
+        ),
+    }
+    .with_wine(wine_prefix, proton);
+    let game = match steam_appid
+        .or_else(|| listed.and_then(|(_, listed)| listed.steam_appid()))
+        .or_else(|| original_game.and_then(Game::steam_appid))
+    {
+        Some(appid) => game.with_steam_appid(appid),
+        None => game,
+    };
+    let game = match workdir.or_else(|| original_game?.workdir().map(Path::to_path_buf)) {
+        Some(workdir) => game.with_workdir(workdir),
+        None => game,
+    };
+    let game = match tags.or_else(|| Some(original_game?.tags().to_vec())) {
+        Some(tags) => game.with_tags(tags),
+        None => game,
+    };
+    let game = match aliases.or_else(|| Some(original_game?.aliases().to_vec())) {
+        Some(aliases) => game.with_aliases(aliases),
+        None => game,
+    };
+    let game = match original_game.and_then(Game::notes) {
+        Some(notes) => game.with_notes(notes.to_owned()),
+        None => game,
+    };
+    games.check_aliases(&game)?;
 
     let backups_location = game.backups_path();
     if !backups_location.exists() {
@@ -160,15 +787,249 @@ fn add(
         })?;
     }
 
-    if !skip_cloud && !skip_cloud_init && games.get_by_name(game.name()).is_err() {
-        run_command(games.cloud_init_command(&game), "cloud init", game.root())?;
+    // With the cloud off by default there is nothing to initialize
+    let skip_cloud = skip_cloud || !games.cloud_mode(None)?.commits();
+    if !skip_cloud && !skip_cloud_init && games.get_exact(game.name()).is_none() {
+        games.cloud_init(&game)?;
+    }
+
+    let imported = match (&from_bundle, &shared) {
+        (Some(bundle), Some(shared)) => import_bundle_backup(bundle, shared, &game)?,
+        _ => None,
+    };
+    if imported.is_some() {
+        games.share_backups(&game)?;
     }
 
     let game_s = format!("{game:#?}");
+    let name = game.name().to_owned();
     games.push(game);
     games.store()?;
     println!("Now managing {game_s}");
+    if let Some(backup) = imported {
+        println!("Imported the shared backup {backup}, run 'gg restore {name} {backup}' to use it");
+    }
+
+    Ok(())
+}
+
+fn cloud_ls(game: Option<String>, games: Games) -> Result<()> {
+    let game = games.try_get(game)?;
+    if !game.cloud_enabled() {
+        bail!("Cloud is disabled for {}", game.name())
+    }
+    let Some(remote) = games.cloud_backups(game)? else {
+        bail!(
+            "The cloud backend of {} can't list its backups",
+            game.name()
+        )
+    };
+    let backups_path = game.backups_path();
+    let missing = remote
+        .iter()
+        .filter(|(name, _)| !backups_path.join(name).exists())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        println!(
+            "Every backup of {} in the cloud is in this machine",
+            game.name()
+        );
+        return Ok(());
+    }
+    for (name, size) in &missing {
+        println!("{name:<50} {:>10}", Size(**size));
+    }
+    println!(
+        "{} backups only in the cloud ({})",
+        missing.len(),
+        Size(missing.iter().map(|(_, size)| **size).sum())
+    );
+    Ok(())
+}
+
+fn cloud_dry_run(game: Option<String>, all: bool, mut games: Games) -> Result<()> {
+    let targets = if all {
+        games.active().cloned().collect()
+    } else {
+        vec![games.try_get(game)?.clone()]
+    };
+
+    for game in &targets {
+        if !game.cloud_enabled() {
+            println!("{}: cloud disabled", game.name());
+            continue;
+        }
+        let latest = if game.backups_path().exists() {
+            game.backups()?.pop().map(|backup| backup.path)
+        } else {
+            None
+        };
+        let message = game.commit_message(latest.as_deref(), None);
+        if let Some(kind) = games.backend_type(game)? {
+            let Some(cmds) = games.cloud_backend_dry_run(game, latest.as_deref(), &message)? else {
+                println!(
+                    "{}: uses the built-in {kind} backend, which runs no commands",
+                    game.name()
+                );
+                continue;
+            };
+            println!("{} ({kind} backend):", game.name());
+            for cmd in cmds {
+                let stage = cmd.desc.strip_prefix("cloud ").unwrap_or(&cmd.desc);
+                println!("  {stage:<6} {}", cmd.shell_line());
+            }
+            continue;
+        }
+        println!("{}:", game.name());
+        print_cloud_commands(&[
+            ("init", games.cloud_init_command(game)?),
+            (
+                "commit",
+                games.cloud_commit_command(game, latest.as_deref(), &message)?,
+            ),
+            ("push", games.cloud_push_command(game, latest.as_deref())?),
+            ("pull", games.cloud_pull_command(game)?),
+        ]);
+    }
+    Ok(())
+}
+
+/// Prints the shell line of the command of every cloud stage.
+fn print_cloud_commands(stages: &[(&str, Option<Command>)]) {
+    for (stage, cmd) in stages {
+        match cmd {
+            Some(cmd) => println!("  {stage:<6} {}", runner::shell_line(cmd)),
+            None => println!("  {stage:<6} (no commands)"),
+        }
+    }
+}
+
+/// Name of the game settings inside the bundles created by 'gg share'.
+const BUNDLE_GAME_ENTRY: &str = "gg.yaml";
+
+/// Reads the settings of the game shared in `bundle`.
+fn read_bundle_game(bundle: &Path) -> Result<Game> {
+    let file = std::fs::File::open(bundle)
+        .context_with(|| format!("Could not open bundle {}", bundle.display()))?;
+    let mut archive = tar::Archive::new(file);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.path()? == Path::new(BUNDLE_GAME_ENTRY) {
+            return Ok(serde_saphyr::from_reader(entry)
+                .context_with(|| format!("Invalid game settings in {}", bundle.display()))?);
+        }
+    }
+    bail!(
+        "{} is not a gg bundle, it has no {BUNDLE_GAME_ENTRY}",
+        bundle.display()
+    )
+}
+
+/// Prints the commands, environment variables and post-restore clean patterns that the game of a
+/// bundle brings, which come from whoever shared it, asking to accept them unless `yes`.
+fn confirm_bundle(shared: &Game, yes: bool) -> Result<()> {
+    let run_commands = shared.run_commands().unwrap_or_default();
+    let mut environment_vars = shared
+        .environment_vars()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    environment_vars.sort_unstable();
+    let post_restore_clean = shared.post_restore_clean().unwrap_or_default();
+    if run_commands.is_empty() && environment_vars.is_empty() && post_restore_clean.is_empty() {
+        return Ok(());
+    }
+    println!("The bundle of {} brings:", shared.name());
+    for command in run_commands {
+        println!("  run command: {command}");
+    }
+    for (key, value) in environment_vars {
+        println!("  environment variable: {key}={value}");
+    }
+    for pattern in post_restore_clean {
+        println!("  post-restore clean: {pattern}");
+    }
+    if yes {
+        return Ok(());
+    }
+    confirm(&tr!("prompt-add-game", game = shared.name()))
+}
+
+/// Copies the backup shared in `bundle` into the backups of `game`, returning its new name.
+fn import_bundle_backup(bundle: &Path, shared: &Game, game: &Game) -> Result<Option<String>> {
+    let file = std::fs::File::open(bundle)
+        .context_with(|| format!("Could not open bundle {}", bundle.display()))?;
+    let mut archive = tar::Archive::new(file);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path == Path::new(BUNDLE_GAME_ENTRY) {
+            continue;
+        }
+        let Some(name) = path.file_name() else {
+            continue;
+        };
+        let name = game.adopted_backup_name(shared, &name.to_string_lossy())?;
+        let dest = game.backups_path().join(&name);
+        ArchiveEntry::unpack_in(&mut entry, &game.backups_path(), &dest).context_with(|| {
+            format!("Could not import the shared backup into {}", dest.display())
+        })?;
+        return Ok(Some(name));
+    }
+    Ok(None)
+}
+
+fn share(game: String, output: Option<PathBuf>, games: Games) -> Result<()> {
+    let game = games.get_by_name(&game)?;
+    let output =
+        output.unwrap_or_else(|| PathBuf::from(format!("{}.gg.tar", slug::slugify(game.name()))));
+    let file = std::fs::File::create(&output)
+        .context_with(|| format!("Could not create bundle {}", output.display()))?;
+    let mut bundle = tar::Builder::new(file);
+
+    let settings = game.shared().to_string();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(settings.len() as u64);
+    header.set_mode(0o644);
+    bundle
+        .append_data(&mut header, BUNDLE_GAME_ENTRY, settings.as_bytes())
+        .context("Could not bundle the game settings")?;
+
+    let latest = if game.backups_path().exists() {
+        game.backups()?.pop()
+    } else {
+        None
+    };
+    if let Some(latest) = &latest {
+        if latest.is_encrypted() {
+            output::warning(format_args!(
+                "{} is encrypted, it can only be restored with its passphrase",
+                latest.path.display()
+            ));
+        }
+        bundle
+            .append_path_with_name(&latest.path, &*latest.name())
+            .context_with(|| format!("Could not bundle backup {}", latest.path.display()))?;
+    }
+    bundle
+        .into_inner()
+        .context_with(|| format!("Could not create bundle {}", output.display()))?;
+    outcome::record_game(game.name());
+    outcome::record_artifact(&output);
 
+    match latest {
+        Some(latest) => println!(
+            "Shared {} with backup {} in {}",
+            game.name(),
+            latest.name(),
+            output.display()
+        ),
+        None => println!(
+            "Shared {} without backups in {}",
+            game.name(),
+            output.display()
+        ),
+    }
     Ok(())
 }
 
@@ -181,23 +1042,79 @@ fn edit(
     executable_args: Option<Vec<String>>,
     environment_vars: Option<Vec<(String, String)>>,
     run_commands: Option<Vec<String>>,
+    prefix: Option<PathBuf>,
+    proton: Option<String>,
+    steam_appid: Option<u32>,
+    workdir: Option<PathBuf>,
+    wrappers: cli::WrapperArgs,
+    cloud: Option<bool>,
+    backend: Option<String>,
+    remote: Option<String>,
+    post_restore_clean: Option<Vec<String>>,
+    tags: Option<Vec<String>>,
+    aliases: Option<Vec<String>>,
+    notes: Option<String>,
     game: Option<impl AsRef<str>>,
     mut games: Games,
 ) -> Result<()> {
     use std::io::Write;
 
     let original = games.try_get(game)?.clone();
-    let merged = original.clone().merged_with(
-        name,
-        root,
-        save_location,
-        executable,
-        executable_args,
-        environment_vars,
-        run_commands,
-    );
+
+    let cloud = if cloud.is_some() || backend.is_some() || remote.is_some() {
+        let mut settings = original.cloud().cloned().unwrap_or_default();
+        if let Some(enabled) = cloud {
+            settings.enabled = enabled;
+        }
+        match backend.as_deref() {
+            Some("global") => settings.set_backend(None)?,
+            Some(kind) => settings.set_backend(Some(kind))?,
+            None => {}
+        }
+        if let Some(remote) = remote {
+            settings.remote = (!remote.is_empty()).then_some(remote);
+        }
+        Some(settings)
+    } else {
+        None
+    };
+
+    let wrappers = wrappers.apply(original.wrappers());
+    let mut merged = original
+        .clone()
+        .merged_with(
+            name,
+            root,
+            save_location,
+            executable,
+            executable_args,
+            environment_vars,
+            run_commands,
+            cloud,
+            post_restore_clean,
+        )
+        .with_wine(prefix, proton);
+    if let Some(wrappers) = wrappers {
+        merged = merged.with_wrappers(wrappers);
+    }
+    if let Some(appid) = steam_appid {
+        merged = merged.with_steam_appid(appid);
+    }
+    if let Some(workdir) = workdir {
+        merged = merged.with_workdir(workdir);
+    }
+    if let Some(tags) = tags {
+        merged = merged.with_tags(tags);
+    }
+    if let Some(aliases) = aliases {
+        merged = merged.with_aliases(aliases);
+    }
+    if let Some(notes) = notes {
+        merged = merged.with_notes(notes);
+    }
 
     if original != merged {
+        games.check_aliases(&merged)?;
         let game = games.push(merged);
         println!("{:#?}", game);
         games.store()?;
@@ -221,210 +1138,2851 @@ fn edit(
     write!(tmp, "{original}")
         .context_with(|| format!("Could not write game config to {}", fpath.display()))?;
 
-    let cmd = games
-        .commands_to_process(&[format!("$EDITOR '{}'", fpath.display())], None)
-        .ok_or_report()
-        .context("Failed to create process from commands")?;
-    run_command(Some(cmd), "editing game", fpath.parent().ok_or_report()?)
+    let cmd = games.editor_command(&fpath)?;
+    games
+        .execute_interactive(Some(cmd), "editing game", fpath.parent().ok_or_report()?)
         .context_with(|| "Failed to edit game with commands")?;
 
-    tmp.seek(std::io::SeekFrom::Start(0))?;
-    let new_game = serde_saphyr::from_reader::<_, Game>(tmp)
+    // Read again by path, as many editors replace the file instead of writing to it
+    let contents = std::fs::read_to_string(&fpath)
+        .context_with(|| format!("Could not read temporary file {}", fpath.display()))?;
+    let new_game = serde_saphyr::from_str::<Game>(&contents)
         .context_with(|| format!("Could not parse temporary file {}", fpath.display()))?;
 
     let _ = games.delete(original.name());
+    games.check_aliases(&new_game)?;
     games.push(new_game);
     games.store()?;
 
     Ok(())
 }
 
-fn remove(game: String, mut games: Games) -> Result<()> {
-    match games.delete(&game) {
-        Ok(game) => println!("Deleted {game:#?} successfully"),
-        Err(_) => bail!("The game {game:#?} is not being managed"),
-    };
-    games.store()
-}
-
-fn list(games: Games) -> Result<()> {
-    println!("{games}");
-    Ok(())
-}
-
-/// The backup is compressed and called "GAME-IDX" by default.
-/// If a backup description is provided, the backup will be called "GAME-IDX-DESCRIPTION"
-fn backup(game: Option<&str>, desc: Option<&str>, skip_cloud: bool, games: &Games) -> Result<()> {
-    let game = games.try_get(game)?;
-    let backups_path = game.backups_path();
-    let name = game.name();
-    let idx = backups_path.read_dir()?.count();
-    let desc = if let Some(desc) = desc {
-        format!("-{desc}")
-    } else {
-        String::new()
-    };
-    let backups_path = backups_path.join(format!("{name}-{idx:0>3}{desc}"));
-
-    let zstd_path = backups_path.with_extension("tar.zst");
-    let zstd = std::fs::File::create(&zstd_path)
-        .context_with(|| format!("Could not create save backup {}", zstd_path.display()))?;
-    let zstd = zstd::Encoder::new(zstd, 9)?;
-
-    let mut tar_builder = tar::Builder::new(zstd);
-    if game.save_location().is_dir() {
-        tar_builder
-            .append_dir_all("", game.save_location())
-            .context_with(|| {
-                format!(
-                    "Could not archive directory {}",
-                    game.save_location().display()
-                )
-            })?;
-    } else {
-        tar_builder
-            .append_file(
-                game.save_location().file_name().ok_or_report()?,
-                &mut std::fs::File::open(game.save_location())?,
-            )
-            .context_with(|| {
-                format!("Could not archive file {}", game.save_location().display())
-            })?;
+/// Renames the game to `new_name` along with its backups, running the cloud rename commands to
+/// rename its cloud storage too.
+fn rename(game: String, new_name: String, cloud: CloudMode, mut games: Games) -> Result<()> {
+    let game = games.get_by_name(&game)?.clone();
+    let old_name = game.name().to_owned();
+    if slug::slugify(&new_name).is_empty() {
+        bail!("The new name {new_name:?} has no letters or digits")
+    }
+    if new_name == old_name {
+        println!("{old_name} is already called so");
+        return Ok(());
+    }
+    if let Some(existing) = games.get_exact(&new_name)
+        && existing.name() != old_name
+    {
+        bail!(Error::GameExists {
+            name: existing.name().to_owned()
+        })
+    }
+    let new_slug = slug::slugify(&new_name);
+    if let Some(other) = games.games().iter().find(|other| {
+        other.name() != old_name && other.aliases().iter().any(|a| slug::slugify(a) == new_slug)
+    }) {
+        bail!("{new_name:?} is already an alias of {:?}", other.name())
+    }
+    let _lock = status::lock(&old_name, "rename")?;
+    if let Some(pid) = game.running_pid() {
+        bail!("{old_name} is running (PID {pid}), close it before renaming it")
     }
-    tar_builder
-        .into_inner()
-        .and_then(|zstd| zstd.finish())
-        .context_with(|| format!("Could not create backup {}", zstd_path.display()))?;
 
-    println!("Created backup {}", zstd_path.display());
+    if cloud.transfers() {
+        games.cloud_pull(&game)?;
+    }
+    let renamed = game.rename_backups(&new_name)?;
+    for (from, to) in &renamed {
+        println!("Renamed {from} to {to}");
+    }
+    games.delete(&old_name)?;
+    let game = games.push(game.with_name(new_name)).clone();
+    games.store()?;
+    if let Err(e) = goodgame::metadata::rename(&old_name, game.name()) {
+        output::warning(format_args!("{e}"));
+    }
+    output::success(tr!(
+        "game-renamed",
+        from = old_name.as_str(),
+        to = game.name()
+    ));
 
-    if !skip_cloud {
-        run_command(
-            games.cloud_commit_command(game),
-            "cloud commit",
-            game.root(),
-        )?;
-        run_command(games.cloud_push_command(game), "cloud push", game.root())?;
+    games.cloud_rename(&game, &old_name)?;
+    games.share_backups(&game)?;
+    if cloud.commits() {
+        let message = format!("Rename {old_name} to {}", game.name());
+        games.cloud_commit(&game, None, Some(&message))?;
+    }
+    if cloud.transfers() {
+        games.cloud_push(&game, None)?;
+        // Otherwise the next pull would bring them back with their old names
+        for (from, _) in &renamed {
+            if let Err(e) = games.cloud_remove(&game, from) {
+                output::warning(format_args!(
+                    "Could not remove {from} from the cloud, remove it by hand: {e}"
+                ));
+            }
+        }
     }
 
+    if games.config()?.games.contains_key(&old_name) {
+        output::warning(format_args!(
+            "The config section games.{old_name} still uses the old name, rename it with 'gg config edit'"
+        ));
+    }
+    if desktop_path(&old_name)?.exists() {
+        output::warning(format_args!(
+            "The launcher of {old_name} still runs the old name, create it again with 'gg desktop'"
+        ));
+    }
     Ok(())
 }
 
-fn restore(game: String, target: String, skip_cloud: bool, games: Games) -> Result<()> {
-    let game = games.get_by_name(game)?;
-    let backups_path = game.backups_path();
-    let target_path = backups_path.join(&target);
-    target_path
-        .try_exists()
-        .context_with(|| format!("The backup {} does not exist", target_path.display()))?;
-    let target_idx = target
-        .split("-")
-        .nth(1)
-        .ok_or_report()?
-        .trim_end_matches(|c: char| !c.is_ascii_digit());
-    backup(
-        Some(game.name()),
-        Some(&format!("replaced-with-{target_idx}")),
-        skip_cloud,
-        &games,
-    )?;
-
-    let target = std::fs::File::open(&target_path)
-        .context_with(|| format!("Could not open backup {}", target_path.display()))?;
-    let zstd = zstd::Decoder::new(target)?;
-
-    let save_location = game.save_location();
-    tar::Archive::new(zstd)
-        .unpack(save_location)
-        .context_with(|| {
-            format!(
-                "Could not extract backup {} to {}",
-                target_path.display(),
-                save_location.display()
+/// Moves the game to the `root` and `save_location` its files have been moved to, taking along
+/// the backups still in the old root and the gg-save-loc symlink.
+fn move_game(
+    game: String,
+    root: Option<PathBuf>,
+    save_location: Option<PathBuf>,
+    mut games: Games,
+) -> Result<()> {
+    let original = games.get_by_name(&game)?.clone();
+    let _lock = status::lock(original.name(), "move")?;
+    if let Some(pid) = original.running_pid() {
+        bail!(
+            "{} is running (PID {pid}), close it before moving it",
+            original.name()
+        )
+    }
+    let backups_count = original.backups().map_or(0, |b| b.len());
+
+    let mut moved = original.clone();
+    if let Some(root) = root {
+        let root = root
+            .canonicalize()
+            .context_with(|| format!("Failed to get root {}", root.display()))?;
+        if !root.is_dir() {
+            bail!("The root must be a directory");
+        }
+        moved.remap(original.root(), &root);
+    }
+    if let Some(save_location) = save_location {
+        let save_location = save_location
+            .canonicalize()
+            .context_with(|| format!("Failed to get save location {}", save_location.display()))?;
+        moved = moved.merged_with(
+            None,
+            None,
+            Some(save_location),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+    }
+    if moved.root() == moved.save_location() {
+        bail!("The root and save locations can't be the same");
+    }
+    if moved == original {
+        println!("{} is already there", original.name());
+        return Ok(());
+    }
+
+    let (from, to) = (original.backups_path(), moved.backups_path());
+    if from != to && from.is_dir() {
+        if to.exists() {
+            output::warning(format_args!(
+                "{} already exists, the backups in {} are left there",
+                to.display(),
+                from.display()
+            ));
+        } else {
+            move_dir(&from, &to)?;
+            println!("Moved the backups to {}", to.display());
+        }
+    }
+    std::fs::create_dir_all(&to)
+        .context_with(|| format!("Could not create backups location {}", to.display()))?;
+
+    let old_symlink = original.root().join("gg-save-loc");
+    if old_symlink.is_symlink() {
+        std::fs::remove_file(&old_symlink)
+            .context_with(|| format!("Could not remove {}", old_symlink.display()))?;
+    }
+    let save_symlink = moved.root().join("gg-save-loc");
+    if save_symlink.is_symlink() {
+        std::fs::remove_file(&save_symlink)
+            .context_with(|| format!("Could not remove {}", save_symlink.display()))?;
+    }
+    std::os::unix::fs::symlink(moved.save_location(), &save_symlink).context_with(|| {
+        format!(
+            "Could not create symlink from {} to {}",
+            moved.save_location().display(),
+            save_symlink.display()
+        )
+    })?;
+
+    let game = games.push(moved).clone();
+    games.store()?;
+    println!("{game:#?}");
+
+    // Everything has already moved, so the problems are only warned about
+    let mut problems = Vec::new();
+    if !game.save_location().exists() {
+        problems.push(format!(
+            "The save location {} doesn't exist",
+            game.save_location().display()
+        ));
+    }
+    if let Some(executable) = game.executable()
+        && !executable.exists()
+    {
+        problems.push(format!(
+            "The executable {} doesn't exist",
+            executable.display()
+        ));
+    }
+    if std::fs::read_link(&save_symlink).ok().as_deref() != Some(game.save_location()) {
+        problems.push(format!(
+            "{} is not a link to the save",
+            save_symlink.display()
+        ));
+    }
+    if game.backups().map_or(0, |b| b.len()) < backups_count {
+        problems.push(format!(
+            "{} had {backups_count} backups, {} only has {}",
+            original.backups_path().display(),
+            game.backups_path().display(),
+            game.backups().map_or(0, |b| b.len())
+        ));
+    }
+    if problems.is_empty() {
+        output::success(tr!("game-moved", game = game.name()));
+    }
+    for problem in problems {
+        output::warning(format_args!("{problem}"));
+    }
+    Ok(())
+}
+
+/// Moves the directory `from` to `to`, copying it if they are in different filesystems.
+fn move_dir(from: &Path, to: &Path) -> Result<()> {
+    match std::fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {}
+        Err(e) => {
+            return Err(e).context_with(|| {
+                format!("Could not move {} to {}", from.display(), to.display())
+            })?;
+        }
+    }
+    for entry in walkdir::WalkDir::new(from) {
+        let entry = entry?;
+        let target = to.join(entry.path().strip_prefix(from)?);
+        let result = if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&target)
+        } else {
+            std::fs::copy(entry.path(), &target).map(drop)
+        };
+        result.context_with(|| format!("Could not copy {}", entry.path().display()))?;
+    }
+    std::fs::remove_dir_all(from)
+        .context_with(|| format!("Could not remove {} after copying it", from.display()))?;
+    Ok(())
+}
+
+/// Stops managing the selected games, removing their gg-save-loc symlink unless `keep_symlink` and
+/// their local backups if `purge_backups`.
+///
+/// Asks first when run in a terminal, and purging the backups elsewhere needs --yes.
+fn remove(
+    selection: cli::Selection,
+    purge_backups: bool,
+    keep_symlink: bool,
+    mut games: Games,
+) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if selection.games.is_empty() && selection.tags.is_empty() {
+        bail!("Provide the names or the tags of the games to remove")
+    }
+    let names = games
+        .select_exact(&selection.games, &selection.tags)?
+        .into_iter()
+        .map(|game| game.name().to_owned())
+        .collect::<Vec<_>>();
+    let question = if purge_backups {
+        format!(
+            "Stop managing {} and delete their local backups?",
+            names.join(", ")
+        )
+    } else {
+        format!("Stop managing {}?", names.join(", "))
+    };
+    if !selection.yes && (purge_backups || std::io::stdin().is_terminal()) {
+        confirm(&question)?;
+    } else {
+        confirm_destructive(&question, selection.yes, &games)?;
+    }
+
+    for name in names {
+        let _lock = status::lock(&name, "removal")?;
+        let game = games.delete(&name)?;
+        let symlink = game.root().join("gg-save-loc");
+        if !keep_symlink && symlink.is_symlink() {
+            match std::fs::remove_file(&symlink) {
+                Ok(()) => println!("Removed {}", symlink.display()),
+                Err(e) => {
+                    output::warning(format_args!("Could not remove {}: {e}", symlink.display()))
+                }
+            }
+        }
+        let backups_path = game.backups_path();
+        if purge_backups && backups_path.is_dir() {
+            match std::fs::remove_dir_all(&backups_path) {
+                Ok(()) => println!("Deleted the backups in {}", backups_path.display()),
+                Err(e) => output::warning(format_args!(
+                    "Could not delete the backups in {}: {e}",
+                    backups_path.display()
+                )),
+            }
+        }
+        if let Err(e) = goodgame::metadata::remove(game.name()) {
+            output::warning(format_args!("{e}"));
+        }
+        output::success(tr!("game-deleted", game = format!("{game:#?}")));
+    }
+    games.store()
+}
+
+/// Archives the games called `names`, or brings them back if not `archive`.
+fn archive(names: &[String], archive: bool, mut games: Games) -> Result<()> {
+    for name in names {
+        let game = games.get_by_name(name)?;
+        let name = game.name().to_owned();
+        if game.is_archived() == archive {
+            let state = if archive { "already" } else { "not" };
+            println!("{name} is {state} archived");
+            continue;
+        }
+        let game = games.delete(&name)?.with_archived(archive);
+        games.push(game);
+        if archive {
+            output::success(tr!(
+                "game-archived",
+                game = name.as_str(),
+                quoted = runner::shell_quote(&name)
+            ));
+        } else {
+            output::success(tr!("game-unarchived", game = name.as_str()));
+        }
+    }
+    games.store()
+}
+
+/// Prints the games in the `sort` order, or only the ones with any of the `tags` and whose name
+/// contains `filter`, each one followed by its playtime as a comment. The archived games are
+/// only included if `archived`.
+fn list(
+    tags: &[String],
+    filter: Option<&str>,
+    sort: cli::ListSort,
+    long: bool,
+    archived: bool,
+    games: Games,
+) -> Result<()> {
+    let filter = filter.map(str::to_lowercase);
+    let mut listed = games
+        .games()
+        .iter()
+        .filter(|game| archived || !game.is_archived())
+        .filter(|game| tags.is_empty() || tags.iter().any(|tag| game.has_tag(tag)))
+        .filter(|game| {
+            filter
+                .as_ref()
+                .is_none_or(|filter| game.name().to_lowercase().contains(filter))
+        })
+        .collect::<Vec<_>>();
+    match sort {
+        // Already sorted by name
+        cli::ListSort::Name => {}
+        cli::ListSort::LastPlayed => {
+            listed.sort_by_cached_key(|game| std::cmp::Reverse(game.playtime().last_played));
+        }
+        cli::ListSort::BackupCount => {
+            listed.sort_by_cached_key(|game| {
+                std::cmp::Reverse(game.backups().map_or(0, |backups| backups.len()))
+            });
+        }
+        cli::ListSort::Size => {
+            listed.sort_by_cached_key(|game| {
+                let size = game
+                    .backups()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|backup| backup.size)
+                    .sum::<u64>();
+                std::cmp::Reverse(size)
+            });
+        }
+    }
+    if json_output() {
+        let list = listed
+            .into_iter()
+            .map(goodgame::rpc::game_json)
+            .collect::<Vec<_>>();
+        return print_json(&list);
+    }
+    for game in listed {
+        let entry = serde_saphyr::to_string(&[game]).context("Could not serialize the games")?;
+        print!("{entry}");
+        let playtime = game.playtime();
+        if let Some(last) = playtime.last_played {
+            println!(
+                "  # Played {}, last {}",
+                goodgame::units::Duration::from_secs(playtime.total),
+                goodgame::games::ago(last)
+            );
+        }
+        if !long {
+            continue;
+        }
+        let backups = game.backups().unwrap_or_default();
+        match backups.last() {
+            Some(latest) => println!(
+                "  # {} backups ({}), the latest {}",
+                backups.len(),
+                Size(backups.iter().map(|backup| backup.size).sum()),
+                goodgame::games::ago(goodgame::games::unix_secs(latest.timestamp))
+            ),
+            None => println!("  # No backups"),
+        }
+        if let Some(metadata) = goodgame::metadata::load(game.name())? {
+            let released = metadata
+                .release_year()
+                .map(|year| format!(", released in {year}"))
+                .unwrap_or_default();
+            println!("  # {}{released}", metadata.title);
+            if let Some(cover) = metadata.cover_path() {
+                println!("  # Cover art {}", cover.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the playtime, the stored backups and the activity this month of the game and of the ones
+/// with any of the `tags`, or of every game and their totals if neither is given.
+fn stats(game: Option<String>, tags: &[String], games: Games) -> Result<()> {
+    use goodgame::units::Duration;
+
+    let all = game.is_none() && tags.is_empty();
+    let targets = if all {
+        games.active().collect()
+    } else {
+        games.select(game.as_slice(), tags)?
+    };
+    let months = last_months(goodgame::games::unix_time(), 12);
+    let stats = targets
+        .iter()
+        .map(|game| GameStats::new(game, &months))
+        .collect::<Vec<_>>();
+    if json_output() {
+        let report = targets
+            .iter()
+            .zip(&stats)
+            .map(|(game, stats)| {
+                serde_json::json!({
+                    "game": game.name(),
+                    "playtime": game.playtime(),
+                    "backups": stats.backups,
+                    "stored": stats.stored,
+                    "this_month": {
+                        "backups": stats.month_backups,
+                        "restores": stats.month_restores,
+                        "sessions": stats.month_sessions,
+                        "played": stats.month_played,
+                    },
+                    "stored_by_month": months
+                        .iter()
+                        .zip(&stats.stored_by_month)
+                        .map(|((year, month), stored)| {
+                            serde_json::json!({ "month": format!("{year}-{month:0>2}"), "stored": stored })
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        return print_json(&report);
+    }
+    for (i, (game, stats)) in targets.iter().zip(&stats).enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", game.name());
+        let playtime = game.playtime();
+        match playtime.last_played {
+            Some(last) => {
+                println!(
+                    "  Played:      {} in {} sessions, {} on average",
+                    Duration::from_secs(playtime.total),
+                    playtime.sessions,
+                    Duration::from_secs(playtime.total / playtime.sessions.max(1))
+                );
+                println!(
+                    "  Last played: {}, for {}",
+                    goodgame::games::ago(last),
+                    Duration::from_secs(playtime.last_session)
+                );
+            }
+            None => println!("  Never played with 'gg run' on this machine"),
+        }
+        stats.print();
+    }
+    if all && targets.len() > 1 {
+        let mut total = GameStats::default();
+        for stats in &stats {
+            total.add(stats);
+        }
+        println!();
+        println!("All games");
+        if let Some((game, played)) = targets
+            .iter()
+            .map(|game| (game.name(), game.playtime().total))
+            .filter(|(_, played)| *played > 0)
+            .max_by_key(|(_, played)| *played)
+        {
+            println!("  Most played: {game}, {}", Duration::from_secs(played));
+        }
+        total.print();
+    }
+    Ok(())
+}
+
+/// What 'gg stats' shows of the backups and the history of a game.
+#[derive(Debug, Default)]
+struct GameStats {
+    backups: usize,
+    /// Size of the backups in bytes.
+    stored: u64,
+    month_backups: usize,
+    month_restores: usize,
+    month_sessions: usize,
+    /// Seconds played this month.
+    month_played: u64,
+    /// Size of the backups at the end of each of the months, `None` before the history starts.
+    stored_by_month: Vec<Option<u64>>,
+}
+
+impl GameStats {
+    /// Returns the stats of the game, with the stored size at the end of each of the `months`.
+    fn new(game: &Game, months: &[(i64, u32)]) -> GameStats {
+        use goodgame::games::HistoryEvent;
+
+        let backups = game.all_backups().unwrap_or_default();
+        let mut stats = GameStats {
+            backups: backups.len(),
+            stored: backups.iter().map(|backup| backup.size).sum(),
+            stored_by_month: vec![None; months.len()],
+            ..GameStats::default()
+        };
+        let this_month = months.last().copied();
+        for event in game.history() {
+            let month = month_of(event.time());
+            match event {
+                HistoryEvent::Backup { stored, .. } => {
+                    // Carried over to the next months, until the next backup changes it
+                    for (i, _) in months.iter().enumerate().filter(|(_, m)| **m >= month) {
+                        stats.stored_by_month[i] = Some(stored);
+                    }
+                    if Some(month) == this_month {
+                        stats.month_backups += 1;
+                    }
+                }
+                HistoryEvent::Restore { .. } if Some(month) == this_month => {
+                    stats.month_restores += 1;
+                }
+                HistoryEvent::Session { duration, .. } if Some(month) == this_month => {
+                    stats.month_sessions += 1;
+                    stats.month_played += duration;
+                }
+                HistoryEvent::Restore { .. } | HistoryEvent::Session { .. } => {}
+            }
+        }
+        // Backups removed since the last one was made are not in the history
+        if let Some(current) = stats.stored_by_month.last_mut() {
+            *current = Some(stats.stored);
+        }
+        stats
+    }
+
+    /// Adds the stats of another game to these ones.
+    fn add(&mut self, other: &GameStats) {
+        self.backups += other.backups;
+        self.stored += other.stored;
+        self.month_backups += other.month_backups;
+        self.month_restores += other.month_restores;
+        self.month_sessions += other.month_sessions;
+        self.month_played += other.month_played;
+        self.stored_by_month
+            .resize(other.stored_by_month.len(), None);
+        for (total, stored) in self.stored_by_month.iter_mut().zip(&other.stored_by_month) {
+            if let Some(stored) = stored {
+                *total = Some(total.unwrap_or(0) + stored);
+            }
+        }
+    }
+
+    fn print(&self) {
+        println!(
+            "  Stored:      {} in {} backups",
+            Size(self.stored),
+            self.backups
+        );
+        println!(
+            "  This month:  {} backups, {} restores, {} played in {} sessions",
+            self.month_backups,
+            self.month_restores,
+            goodgame::units::Duration::from_secs(self.month_played),
+            self.month_sessions
+        );
+        if self
+            .stored_by_month
+            .iter()
+            .flatten()
+            .any(|stored| *stored > 0)
+        {
+            println!(
+                "  Growth:      {} over the last {} months",
+                sparkline(&self.stored_by_month),
+                self.stored_by_month.len()
+            );
+        }
+    }
+}
+
+/// Returns the year and month of `time`, in seconds since the Unix epoch.
+fn month_of(time: u64) -> (i64, u32) {
+    let (year, month, _) = goodgame::games::date(time as i64);
+    (year, month)
+}
+
+/// Returns the year and month of the `count` months up to the one of `now`, oldest first.
+fn last_months(now: u64, count: usize) -> Vec<(i64, u32)> {
+    let (mut year, mut month) = month_of(now);
+    let mut months = Vec::with_capacity(count);
+    for _ in 0..count {
+        months.push((year, month));
+        (year, month) = if month == 1 {
+            (year - 1, 12)
+        } else {
+            (year, month - 1)
+        };
+    }
+    months.reverse();
+    months
+}
+
+/// Draws the `values` as bars of the height relative to the biggest one, with a space for the
+/// missing ones.
+fn sparkline(values: &[Option<u64>]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().flatten().max().copied().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|value| match value {
+            Some(value) => BARS[(value * 7 / max) as usize],
+            None => ' ',
+        })
+        .collect()
+}
+
+/// Prints the history of the game and of the ones with any of the `tags`, or of every game if
+/// neither is given, only the events of the last `since` if given.
+fn history(
+    game: Option<String>,
+    tags: &[String],
+    since: Option<goodgame::units::Duration>,
+    games: Games,
+) -> Result<()> {
+    use goodgame::games::HistoryEvent;
+    use goodgame::units::Duration;
+
+    let targets = if game.is_none() && tags.is_empty() {
+        games.active().collect()
+    } else {
+        games.select(game.as_slice(), tags)?
+    };
+    let cutoff = since.map_or(0, |since| {
+        goodgame::games::unix_time().saturating_sub(std::time::Duration::from(since).as_secs())
+    });
+    let mut events = targets
+        .iter()
+        .flat_map(|game| game.history().into_iter().map(move |event| (game, event)))
+        .filter(|(_, event)| event.time() >= cutoff)
+        .collect::<Vec<_>>();
+    events.sort_by_key(|(_, event)| event.time());
+    if json_output() {
+        let events = events
+            .iter()
+            .map(|(game, event)| serde_json::json!({ "game": game.name(), "event": event }))
+            .collect::<Vec<_>>();
+        return print_json(&events);
+    }
+    for (game, event) in &events {
+        let what = match event {
+            HistoryEvent::Backup {
+                size,
+                backups,
+                stored,
+                ..
+            } => format!(
+                "backed up {}, {backups} backups of {}",
+                Size(*size),
+                Size(*stored)
+            ),
+            HistoryEvent::Restore { backup, .. } => format!("restored {backup}"),
+            HistoryEvent::Session { duration, .. } => {
+                format!("played for {}", Duration::from_secs(*duration))
+            }
+        };
+        println!("{}  {}  {what}", utc_time(event.time()), game.name());
+    }
+    Ok(())
+}
+
+/// Formats `time`, in seconds since the Unix epoch, as the date and time in UTC.
+fn utc_time(time: u64) -> String {
+    let (year, month, day) = goodgame::games::date(time as i64);
+    let secs = time % (24 * 60 * 60);
+    format!(
+        "{year}-{month:0>2}-{day:0>2} {:0>2}:{:0>2} UTC",
+        secs / 3600,
+        secs / 60 % 60
+    )
+}
+
+/// Prints the lines of the log, only the ones of the last `since` if given.
+fn logs(since: Option<goodgame::units::Duration>, games: &Games) -> Result<()> {
+    let lines = goodgame::logging::read(&games.config()?.log, since.map(Into::into))?;
+    if json_output() {
+        return print_json(&lines);
+    }
+    for line in lines {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// Prints the backups, the cloud state and the last session of the game and of the ones with any
+/// of the `tags`, or of every game if neither is given.
+fn game_status(game: Option<String>, tags: &[String], games: Games) -> Result<()> {
+    use goodgame::games::ago;
+
+    let targets = if game.is_none() && tags.is_empty() {
+        games.active().collect()
+    } else {
+        games.select(game.as_slice(), tags)?
+    };
+    let mut report = Vec::new();
+    let mut rows = Vec::new();
+    let mut notes = Vec::new();
+    for game in targets {
+        let backups = game.backups().unwrap_or_default();
+        let size = backups.iter().map(|backup| backup.size).sum::<u64>();
+        let last_backup = backups
+            .last()
+            .map(|backup| goodgame::games::unix_secs(backup.timestamp));
+        let mut cloud = serde_json::json!({ "enabled": game.cloud_enabled() });
+        let cloud_summary = if !game.cloud_enabled() {
+            "disabled".to_owned()
+        } else {
+            // A single unreachable remote shouldn't hide the status of the rest
+            match games.cloud_status(game) {
+                Ok(Some(status)) => {
+                    let mut summary = Vec::new();
+                    if !status.local_only.is_empty() {
+                        summary.push(format!("{} not pushed", status.local_only.len()));
+                    }
+                    if !status.remote_only.is_empty() {
+                        summary.push(format!("{} newer", status.remote_only.len()));
+                    }
+                    if !status.conflicts.is_empty() {
+                        summary.push(format!("{} in conflict", status.conflicts.len()));
+                    }
+                    cloud["local_only"] = status.local_only.into();
+                    cloud["remote_only"] = status.remote_only.into();
+                    cloud["conflicts"] = status.conflicts.into();
+                    if summary.is_empty() {
+                        "up to date".to_owned()
+                    } else {
+                        summary.join(", ")
+                    }
+                }
+                Ok(None) => {
+                    let pending = game.pending_uploads();
+                    let summary = if pending.is_empty() {
+                        "unknown".to_owned()
+                    } else {
+                        format!("{} pending upload", pending.len())
+                    };
+                    cloud["pending_uploads"] = pending.into();
+                    summary
+                }
+                Err(e) => {
+                    cloud["error"] = e.to_string().into();
+                    "unreachable".to_owned()
+                }
+            }
+        };
+        let playtime = game.playtime();
+        if json_output() {
+            report.push(serde_json::json!({
+                "game": game.name(),
+                "backups": backups.len(),
+                "size": size,
+                "last_backup": last_backup,
+                "last_played": playtime.last_played,
+                "cloud": cloud,
+                "notes": game.notes(),
+            }));
+            continue;
+        }
+        if let Some(game_notes) = game.notes() {
+            notes.push((game.name(), game_notes));
+        }
+        rows.push([
+            game.name().to_owned(),
+            backups.len().to_string(),
+            Size(size).to_string(),
+            last_backup.map_or("never".to_owned(), ago),
+            playtime.last_played.map_or("never".to_owned(), ago),
+            cloud_summary,
+        ]);
+    }
+    if json_output() {
+        return print_json(&report);
+    }
+
+    let width = rows
+        .iter()
+        .map(|[name, ..]| name.len())
+        .max()
+        .unwrap_or(0)
+        .max("GAME".len());
+    println!(
+        "{:<width$}  {:>7}  {:>10}  {:<16}  {:<16}  CLOUD",
+        "GAME", "BACKUPS", "SIZE", "LAST BACKUP", "LAST PLAYED"
+    );
+    for [name, backups, size, last_backup, last_played, cloud] in rows {
+        println!(
+            "{name:<width$}  {backups:>7}  {size:>10}  {last_backup:<16}  {last_played:<16}  {cloud}"
+        );
+    }
+    for (name, notes) in notes {
+        println!("\n{name}:");
+        for line in notes.lines() {
+            println!("  {line}");
+        }
+    }
+    Ok(())
+}
+
+/// Prints the games with a field that contains `query`, followed by those fields with the matches
+/// highlighted.
+fn search(query: &str, games: Games) -> Result<()> {
+    let mut results = Vec::new();
+    for game in games.games() {
+        let mut fields = vec![("name", game.name().to_owned())];
+        fields.extend(game.aliases().iter().map(|a| ("alias", a.clone())));
+        fields.push(("root", game.root().display().to_string()));
+        fields.push(("save location", game.save_location().display().to_string()));
+        if let Some(executable) = game.executable() {
+            fields.push(("executable", executable.display().to_string()));
+        }
+        fields.extend(game.tags().iter().map(|t| ("tag", t.clone())));
+        fields.extend(
+            game.notes()
+                .into_iter()
+                .flat_map(str::lines)
+                .map(|l| ("notes", l.to_owned())),
+        );
+        let matches = fields
+            .into_iter()
+            .filter_map(|(field, value)| {
+                let highlighted = output::highlight(&value, query)?;
+                Some((field, value, highlighted))
+            })
+            .collect::<Vec<_>>();
+        if !matches.is_empty() {
+            results.push((game.name(), matches));
+        }
+    }
+
+    if json_output() {
+        let results = results
+            .iter()
+            .map(|(game, matches)| {
+                let matches = matches
+                    .iter()
+                    .map(|(field, value, _)| serde_json::json!({ "field": field, "value": value }))
+                    .collect::<Vec<_>>();
+                serde_json::json!({ "game": game, "matches": matches })
+            })
+            .collect::<Vec<_>>();
+        return print_json(&results);
+    }
+    if results.is_empty() {
+        bail!("No game matches {query:?}")
+    }
+    for (game, matches) in results {
+        println!("{game}");
+        for (field, _, highlighted) in matches {
+            println!("  {field}: {highlighted}");
+        }
+    }
+    Ok(())
+}
+
+/// Prints the space used by the backups of every game, or only of `game` and the games with any of
+/// the `tags`, followed by the total and the `top` largest backups.
+fn disk_usage(game: Option<String>, tags: &[String], top: usize, games: Games) -> Result<()> {
+    let targets = if game.is_none() && tags.is_empty() {
+        games.games().iter().collect()
+    } else {
+        games.select(game.as_slice(), tags)?
+    };
+    let mut usage = Vec::new();
+    let mut archives = Vec::new();
+    for game in targets {
+        let backups = game.backups().unwrap_or_default();
+        let size = backups.iter().map(|backup| backup.size).sum::<u64>();
+        let disk = walkdir::WalkDir::new(game.backups_path())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.metadata().ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum::<u64>();
+        usage.push((game.name(), backups.len(), size, disk));
+        archives.extend(backups);
+    }
+    usage.sort_by_key(|&(_, _, _, disk)| std::cmp::Reverse(disk));
+    archives.sort_by_key(|backup| std::cmp::Reverse(backup.size));
+    archives.truncate(top);
+    let total = usage.iter().map(|&(.., disk)| disk).sum::<u64>();
+
+    if json_output() {
+        let games = usage
+            .iter()
+            .map(|(game, backups, size, disk)| {
+                serde_json::json!({
+                    "game": game,
+                    "backups": backups,
+                    "size": size,
+                    "disk": disk,
+                })
+            })
+            .collect::<Vec<_>>();
+        let largest = archives
+            .iter()
+            .map(|backup| {
+                serde_json::json!({
+                    "game": backup.game,
+                    "path": backup.path,
+                    "size": backup.size,
+                })
+            })
+            .collect::<Vec<_>>();
+        return print_json(&serde_json::json!({
+            "games": games,
+            "total": total,
+            "largest": largest,
+        }));
+    }
+
+    let width = usage
+        .iter()
+        .map(|(name, ..)| name.len())
+        .max()
+        .unwrap_or(0)
+        .max("TOTAL".len());
+    println!(
+        "{:<width$}  {:>7}  {:>10}  {:>10}  {:>5}",
+        "GAME", "BACKUPS", "SIZE", "ON DISK", "SHARE"
+    );
+    for (name, backups, size, disk) in &usage {
+        let share = if total == 0 {
+            0.0
+        } else {
+            *disk as f64 * 100.0 / total as f64
+        };
+        println!(
+            "{name:<width$}  {backups:>7}  {:>10}  {:>10}  {share:>4.0}%",
+            Size(*size).to_string(),
+            Size(*disk).to_string()
+        );
+    }
+    let backups = usage.iter().map(|&(_, backups, ..)| backups).sum::<usize>();
+    let size = usage.iter().map(|&(_, _, size, _)| size).sum::<u64>();
+    println!(
+        "{:<width$}  {backups:>7}  {:>10}  {:>10}",
+        "TOTAL",
+        Size(size).to_string(),
+        Size(total).to_string()
+    );
+    if !archives.is_empty() {
+        println!("\nLargest backups:");
+        for backup in archives {
+            println!(
+                "  {:>10}  {}  {}",
+                Size(backup.size).to_string(),
+                backup.game,
+                backup.path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Repacks the old backups of the selected games, all of them if none is, with the age and level
+/// of their 'gc' config unless given.
+fn gc(
+    names: &[String],
+    tags: &[String],
+    older_than: Option<goodgame::units::Duration>,
+    level: Option<u8>,
+    dry_run: bool,
+    games: &Games,
+) -> Result<()> {
+    let selected = if names.is_empty() && tags.is_empty() {
+        games.active().collect()
+    } else {
+        games.select(names, tags)?
+    };
+    let mut repacks = Vec::new();
+    let mut failed = 0;
+    for game in selected {
+        match games.gc(game, older_than, level, dry_run) {
+            Ok(repacked) => {
+                for repack in &repacked {
+                    match repack.repacked {
+                        Some(size) => println!(
+                            "Repacked {} from {} to {}",
+                            repack.path.display(),
+                            Size(repack.size),
+                            Size(size)
+                        ),
+                        None => println!("{} ({})", repack.path.display(), Size(repack.size)),
+                    }
+                }
+                repacks.extend(repacked);
+            }
+            Err(e) if matches!(goodgame::error::kind(&e), Some(Error::Locked { .. })) => {
+                output::warning(format_args!("Not repacking {}: {e}", game.name()));
+            }
+            Err(e) => {
+                output::warning(format_args!("Could not repack {}: {e}", game.name()));
+                failed += 1;
+            }
+        }
+    }
+
+    if json_output() {
+        let repacks = repacks
+            .iter()
+            .map(|repack| {
+                serde_json::json!({
+                    "backup": repack.path,
+                    "size": repack.size,
+                    "repacked_size": repack.repacked,
+                })
+            })
+            .collect::<Vec<_>>();
+        print_json(&repacks)?;
+    } else if dry_run {
+        println!(
+            "Would repack {} backups of {}",
+            repacks.len(),
+            Size(repacks.iter().map(|repack| repack.size).sum())
+        );
+    } else {
+        let reclaimed = repacks
+            .iter()
+            .filter_map(|repack| Some(repack.size - repack.repacked?))
+            .sum();
+        output::success(format_args!(
+            "Repacked {} backups, reclaiming {}",
+            repacks.len(),
+            Size(reclaimed)
+        ));
+    }
+    if failed > 0 {
+        bail!("Could not repack the backups of {failed} of the games")
+    }
+    Ok(())
+}
+
+/// Removes the backups of the selected games, all of them if none is, past their
+/// 'backup.retention' or the `keep` and `max_age` given.
+fn prune(
+    names: &[String],
+    tags: &[String],
+    keep: Option<usize>,
+    max_age: Option<goodgame::units::Duration>,
+    dry_run: bool,
+    games: &Games,
+) -> Result<()> {
+    let selected = if names.is_empty() && tags.is_empty() {
+        games.active().collect()
+    } else {
+        games.select(names, tags)?
+    };
+    let mut pruned = Vec::new();
+    let mut failed = 0;
+    for game in selected {
+        match games.prune(game, keep, max_age, dry_run) {
+            Ok(removed) => {
+                if dry_run && !json_output() {
+                    for backup in &removed {
+                        println!("{} ({})", backup.path.display(), Size(backup.size));
+                    }
+                }
+                pruned.extend(removed);
+            }
+            Err(e) if matches!(goodgame::error::kind(&e), Some(Error::Locked { .. })) => {
+                output::warning(format_args!("Not pruning {}: {e}", game.name()));
+            }
+            Err(e) => {
+                output::warning(format_args!("Could not prune {}: {e}", game.name()));
+                failed += 1;
+            }
+        }
+    }
+
+    let reclaimed = Size(pruned.iter().map(|backup| backup.size).sum());
+    if json_output() {
+        let pruned = pruned
+            .iter()
+            .map(|backup| serde_json::json!({ "backup": backup.path, "size": backup.size }))
+            .collect::<Vec<_>>();
+        print_json(&pruned)?;
+    } else if dry_run {
+        println!("Would remove {} backups of {reclaimed}", pruned.len());
+    } else {
+        output::success(format_args!(
+            "Removed {} backups, reclaiming {reclaimed}",
+            pruned.len()
+        ));
+    }
+    if failed > 0 {
+        bail!("Could not prune the backups of {failed} of the games")
+    }
+    Ok(())
+}
+
+/// Prints the cloud commands that 'gg backup' would run, without creating the backup.
+fn backup_dry_run(
+    game: Option<&str>,
+    desc: Option<&str>,
+    message: Option<&str>,
+    games: &Games,
+) -> Result<()> {
+    let game = games.try_get(game)?;
+    if !game.cloud_enabled() {
+        println!("{}: cloud disabled", game.name());
+        return Ok(());
+    }
+    if let Some(kind) = games.backend_type(game)? {
+        println!(
+            "{}: uses the built-in {kind} backend instead of the cloud commands",
+            game.name()
+        );
+        return Ok(());
+    }
+    let encrypt = games.game_config(game)?.backup.encrypt;
+    let archive_path = games.next_backup_path(game, desc, encrypt)?;
+    println!("{} (backup {}):", game.name(), archive_path.display());
+    let message = message.map_or_else(
+        || game.commit_message(Some(&archive_path), None),
+        String::from,
+    );
+    print_cloud_commands(&[
+        (
+            "commit",
+            games.cloud_commit_command(game, Some(&archive_path), &message)?,
+        ),
+        ("push", games.cloud_push_command(game, Some(&archive_path))?),
+    ]);
+    Ok(())
+}
+
+/// Restores the backup called `target`, or the one chosen from a list, deleting the post-restore
+/// clean paths of the game after asking unless `yes`, or only listing them if `dry_run_clean`.
+///
+/// Returns the name of the restored backup.
+#[allow(clippy::too_many_arguments)]
+fn restore(
+    game: String,
+    target: Option<String>,
+    slot: Option<String>,
+    player: Option<&str>,
+    cloud: CloudMode,
+    yes: bool,
+    dry_run_clean: bool,
+    games: &Games,
+) -> Result<String> {
+    let game = &games.get_by_name_or_alias(game)?.with_player(player)?;
+    games.check_broken(game, Event::RestoreDone)?;
+    let target = match target {
+        Some(target) => target,
+        None => pick_backup(game, cloud, games)?,
+    };
+    confirm_destructive(
+        &format!("Replace the save of {} with {target}?", game.name()),
+        yes,
+        games,
+    )?;
+    let clean = confirm_clean(game, yes, dry_run_clean)?;
+    games.restore(game, &target, slot.as_deref(), cloud, clean)?;
+    Ok(target)
+}
+
+/// Asks which backup of the game to restore, newest first, including the ones only in the cloud
+/// storage if the `cloud` mode transfers.
+fn pick_backup(game: &Game, cloud: CloudMode, games: &Games) -> Result<String> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "No backup given, pass the name of the backup of {} to restore",
+            game.name()
+        )
+    }
+    let mut entries = backup_entries(game, cloud, games)?;
+    if entries.is_empty() {
+        bail!(
+            "{} has no backups, create one with 'gg backup' first",
+            game.name()
+        )
+    }
+    entries.reverse();
+    let chosen = inquire::Select::new(&tr!("prompt-select-backup"), entries)
+        .prompt()
+        .context("Could not read the chosen backup")?;
+    Ok(chosen.name)
+}
+
+/// A backup of a game, in this machine or only in the cloud storage.
+struct BackupEntry {
+    name: String,
+    size: u64,
+    cloud_only: bool,
+}
+
+impl std::fmt::Display for BackupEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cloud = if self.cloud_only { " ☁" } else { "" };
+        write!(f, "{:<50} {:>10}{cloud}", self.name, Size(self.size))
+    }
+}
+
+/// Returns the local backups of the game merged with the ones only in the cloud storage, sorted
+/// by name. The cloud is only listed if the `cloud` mode transfers.
+fn backup_entries(game: &Game, cloud: CloudMode, games: &Games) -> Result<Vec<BackupEntry>> {
+    let mut entries = Vec::new();
+    if game.backups_path().exists() {
+        for backup in game.backups()? {
+            entries.push(BackupEntry {
+                name: backup.name().into_owned(),
+                size: backup.size,
+                cloud_only: false,
+            });
+        }
+    }
+    if cloud.transfers() {
+        let mut remote = games.cloud_only_backups(game)?;
+        // The ones of the other players too
+        remote.retain(|name, _| game.parse_backup_name(name).is_some());
+        entries.extend(remote.into_iter().map(|(name, size)| BackupEntry {
+            name,
+            size,
+            cloud_only: true,
+        }));
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Asks the `question` if the config enables 'confirmDestructiveOps', failing if the answer is no.
+///
+/// Doesn't ask if `yes`, and fails if there is no terminal to ask in.
+fn confirm_destructive(question: &str, yes: bool, games: &Games) -> Result<()> {
+    if yes || !games.config()?.confirm_destructive_ops {
+        return Ok(());
+    }
+    confirm(question)
+}
+
+/// Asks the `question`, failing if the answer is no or if there is no terminal to ask in.
+fn confirm(question: &str) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        bail!("Could not ask {question:?}, pass --yes to confirm it without asking")
+    }
+    let confirmed = inquire::Confirm::new(question)
+        .with_default(false)
+        .prompt()
+        .context("Could not read the confirmation")?;
+    if !confirmed {
+        bail!("{}", tr!("cancelled"))
+    }
+    Ok(())
+}
+
+/// Returns whether to delete the post-restore clean paths of the game after restoring it, listing
+/// them and asking first unless `yes`, or never if `dry_run`.
+fn confirm_clean(game: &Game, yes: bool, dry_run: bool) -> Result<bool> {
+    use std::io::IsTerminal;
+
+    let paths = game.post_restore_clean_paths();
+    if paths.is_empty() {
+        return Ok(false);
+    }
+    println!("Post-restore clean of {}:", game.name());
+    for path in &paths {
+        println!("  {}", path.display());
+    }
+    if dry_run {
+        println!("Dry run, nothing will be deleted");
+        return Ok(false);
+    }
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        println!("Nothing will be deleted, pass --yes to clean without asking");
+        return Ok(false);
+    }
+    Ok(inquire::Confirm::new(&tr!("prompt-delete-them"))
+        .with_default(true)
+        .prompt()
+        .context("Could not read the confirmation")?)
+}
+
+/// Copies the save of the latest backup of `from` into the save location of `to`.
+///
+/// A backup of the current save of `to` is created first.
+fn transfer(from: String, to: String, cloud: CloudMode, games: &Games) -> Result<()> {
+    let from = games.get_by_name_or_alias(from)?;
+    let to = games.get_by_name_or_alias(to)?;
+    let source = games.transfer(from, to, cloud)?;
+    println!(
+        "Successfully transferred backup {} to {}",
+        source.display(),
+        to.save_location().display()
+    );
+    Ok(())
+}
+
+fn backups_list(game: Option<String>, cloud: CloudMode, games: Games) -> Result<()> {
+    let game = games.try_get(game)?;
+    let metadata = game.backups_metadata();
+    let hostname = goodgame::games::hostname();
+    if json_output() {
+        let entries = backup_entries(game, cloud, &games)?
+            .into_iter()
+            .map(|entry| {
+                let metadata = metadata.get(&entry.name);
+                let parsed = game.parse_backup_name(&entry.name);
+                serde_json::json!({
+                    "name": entry.name,
+                    "index": parsed.map(|(index, _)| index),
+                    "description": parsed.and_then(|(_, desc)| desc),
+                    "size": entry.size,
+                    "cloud_only": entry.cloud_only,
+                    "commit": metadata.and_then(|m| m.commit.as_deref()),
+                    "pending_upload": metadata.is_some_and(|m| m.pending_upload),
+                    "host": metadata.and_then(|m| m.host.as_deref()),
+                })
+            })
+            .collect::<Vec<_>>();
+        return print_json(&entries);
+    }
+    for entry in backup_entries(game, cloud, &games)? {
+        if entry.cloud_only {
+            println!("{entry}");
+            continue;
+        }
+        let name = &entry.name;
+        let size = entry.size;
+        let commit = metadata
+            .get(name)
+            .and_then(|m| m.commit.as_deref())
+            .map_or("", |c| &c[..c.len().min(10)]);
+        let pending = if metadata.get(name).is_some_and(|m| m.pending_upload) {
+            " (pending upload)"
+        } else {
+            ""
+        };
+        let host = metadata
+            .get(name)
+            .and_then(|m| m.host.as_deref())
+            .filter(|host| *host != hostname)
+            .map_or(String::new(), |host| format!(" (from {host})"));
+        println!("{name:<50} {:>10} {commit}{pending}{host}", Size(size));
+    }
+    Ok(())
+}
+
+fn backups_dedupe(game: Option<String>, cloud: CloudMode, games: Games) -> Result<()> {
+    let game = games.try_get(game)?;
+    if cloud.transfers() {
+        games.cloud_pull(game)?;
+    }
+    let renamed = game.dedupe_backups()?;
+    if renamed.is_empty() {
+        println!("No backups of {} share an index", game.name());
+        return Ok(());
+    }
+    for (from, to) in &renamed {
+        println!("Renamed {from} to {to}");
+    }
+    games.share_backups(game)?;
+    if cloud.commits() {
+        games.cloud_commit(game, None, None)?;
+    }
+    if cloud.transfers() {
+        games.cloud_push(game, None)?;
+        // Otherwise the next pull would bring them back with their old names
+        for (from, _) in &renamed {
+            if let Err(e) = games.cloud_remove(game, from) {
+                output::warning(format_args!(
+                    "Could not remove {from} from the cloud, remove it by hand: {e}"
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn backups_cat(
+    game: String,
+    backup: String,
+    file: Option<PathBuf>,
+    slot: Option<String>,
+    games: Games,
+) -> Result<()> {
+    let game = games.get_by_name(game)?;
+    let path = game.backups_path().join(backup);
+    let save_location = game.save_location();
+    let local = if let Some(file) = file {
+        save_location.join(file)
+    } else if let Some(container) = game.container_path() {
+        container
+    } else if save_location.is_file() {
+        save_location.to_path_buf()
+    } else {
+        bail!("The save location is a directory, choose the file to print with --file")
+    };
+
+    match slot {
+        Some(slot) => games.extract_slot(game, &path, &local, &slot, None),
+        None => games.extract_file(&path, game, &local, &mut std::io::stdout().lock()),
+    }
+}
+
+fn open(selection: cli::Selection, save: bool, backups: bool, games: Games) -> Result<()> {
+    for game in select_games(&selection, &games)? {
+        let dir = if save {
+            game.save_location().to_path_buf()
+        } else if backups {
+            game.backups_path()
+        } else {
+            game.root().to_path_buf()
+        };
+        if let Some(mut cmd) = games.opener_command(&dir)? {
+            let status = cmd.status().context("Could not run the opener")?;
+            if !status.success() {
+                bail!("The opener of {} failed with {status}", dir.display());
+            }
+            continue;
+        }
+        let _ = Command::new(goodgame::paths::OPEN_COMMAND)
+            .arg(dir)
+            .spawn()
+            .context_with(|| format!("Could not run {}", goodgame::paths::OPEN_COMMAND))?;
+    }
+    Ok(())
+}
+
+/// Writes the .desktop launcher of the game, or removes it if `remove`.
+fn desktop(game: String, icon: Option<String>, remove: bool, games: Games) -> Result<()> {
+    let game = games.get_by_name(&game)?;
+    let path = desktop_path(game.name())?;
+    if remove {
+        std::fs::remove_file(&path)
+            .context_with(|| format!("Could not remove the launcher {}", path.display()))?;
+        println!("Removed the launcher {}", path.display());
+        return Ok(());
+    }
+    let icon = match icon {
+        Some(icon) => icon,
+        None => goodgame::metadata::load(game.name())?
+            .and_then(|metadata| metadata.icon_path())
+            .map_or_else(
+                || String::from("applications-games"),
+                |path| path.to_string_lossy().into_owned(),
+            ),
+    };
+    let exe = std::env::current_exe()?;
+    let exec = [&*exe.to_string_lossy(), "run", game.name()].map(desktop_exec_arg);
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name={}\n\
+         Comment=Play {} with its saves backed up by gg\n\
+         Exec={}\n\
+         Path={}\n\
+         Icon={}\n\
+         Terminal=false\n\
+         Categories=Game;\n",
+        game.name(),
+        game.name(),
+        exec.join(" "),
+        game.root().display(),
+        icon,
+    );
+    std::fs::create_dir_all(path.parent().ok_or_report()?)?;
+    std::fs::write(&path, entry)
+        .context_with(|| format!("Could not write the launcher {}", path.display()))?;
+    outcome::record_artifact(&path);
+    println!("Created the launcher {}", path.display());
+    Ok(())
+}
+
+/// Adds the game as a non-Steam shortcut to every Steam user, or removes it if `remove`.
+fn steam_shortcut(game: String, icon: Option<PathBuf>, remove: bool, games: Games) -> Result<()> {
+    use goodgame::shortcuts;
+
+    let game = games.get_by_name(&game)?;
+    let files = shortcuts::files();
+    if files.is_empty() {
+        bail!("Could not find the users of Steam, it must be started once before")
+    }
+    if shortcuts::steam_running() {
+        bail!("Steam is running and would overwrite the shortcuts when it exits, close it first")
+    }
+    let exe = std::env::current_exe()?;
+    let icon = match icon {
+        Some(icon) => Some(std::path::absolute(icon)?),
+        None => goodgame::metadata::load(game.name())?.and_then(|metadata| metadata.icon_path()),
+    };
+    let shortcut = shortcuts::Shortcut {
+        name: game.name(),
+        exe: &exe,
+        start_dir: game.root(),
+        launch_options: format!("run {}", goodgame::runner::shell_quote(game.name())),
+        icon: &icon
+            .as_deref()
+            .map(Path::to_string_lossy)
+            .unwrap_or_default(),
+    };
+    for file in files {
+        if !remove {
+            shortcuts::add(&file, &shortcut)?;
+            println!("Added {} to {}", game.name(), file.display());
+        } else if shortcuts::remove(&file, game.name())? {
+            println!("Removed {} from {}", game.name(), file.display());
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the metadata of the games, or of every game if neither `names` nor `tags` are given,
+/// skipping the ones that have it unless `refresh`.
+fn metadata(names: &[String], tags: &[String], refresh: bool, games: &Games) -> Result<()> {
+    let selected = if names.is_empty() && tags.is_empty() {
+        games.active().collect()
+    } else {
+        games.select(names, tags)?
+    };
+    let mut fetched = Vec::new();
+    let mut failed = 0;
+    for game in selected {
+        if !refresh && goodgame::metadata::load(game.name())?.is_some() {
+            continue;
+        }
+        let config = games.game_config(game)?;
+        let Some(key) = &config.metadata.steamgriddb_key else {
+            bail!(
+                "Set the API key of SteamGridDB in 'metadata.steamgriddbKey' with 'gg config edit'"
             )
-        })?;
+        };
+        let key = key.reveal(&config.shell)?;
+        output::progress(format_args!("Fetching the metadata of {}", game.name()));
+        match goodgame::metadata::fetch(game.name(), game.steam_appid(), &key) {
+            Ok(metadata) => {
+                let released = metadata
+                    .release_year()
+                    .map(|year| format!(", released in {year}"))
+                    .unwrap_or_default();
+                println!("{}: {}{released}", game.name(), metadata.title);
+                fetched.push(serde_json::json!({
+                    "game": game.name(),
+                    "title": metadata.title,
+                    "release_year": metadata.release_year(),
+                    "cover": metadata.cover_path(),
+                    "icon": metadata.icon_path(),
+                }));
+            }
+            Err(e) => {
+                output::warning(format_args!(
+                    "Could not fetch the metadata of {}: {e}",
+                    game.name()
+                ));
+                failed += 1;
+            }
+        }
+    }
+    if json_output() {
+        print_json(&fetched)?;
+    }
+    if failed > 0 {
+        bail!("Could not fetch the metadata of {failed} of the games")
+    }
+    Ok(())
+}
+
+/// Returns the path of the launcher 'gg desktop' creates for the game called `name`.
+fn desktop_path(name: &str) -> Result<PathBuf> {
+    let applications = goodgame::games::data_dir()?
+        .parent()
+        .context("Could not obtain the applications directory")?
+        .join("applications");
+    Ok(applications.join(format!("gg-{}.desktop", slug::slugify(name))))
+}
+
+/// Quotes `arg` for the Exec key of a .desktop file, following the Desktop Entry Specification.
+fn desktop_exec_arg(arg: &str) -> String {
+    const RESERVED: &[char] = &[
+        ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(',
+        ')', '`',
+    ];
+    let arg = arg.replace('%', "%%");
+    if !arg.contains(RESERVED) {
+        return arg;
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    // The value of the key is unescaped before the quotes are interpreted
+    quoted.replace('\\', "\\\\")
+}
+
+/// Runs the game with the extra `args`, backing up its save before it starts if `backup_before`
+/// and when it closes if `backup_after`.
+#[allow(clippy::too_many_arguments)]
+fn run(
+    game: Option<String>,
+    last: bool,
+    profile: Option<&str>,
+    player: Option<&str>,
+    args: &[String],
+    cloud: CloudMode,
+    backup_before: bool,
+    backup_after: bool,
+    autosave_interval: Option<std::time::Duration>,
+    games: Games,
+) -> Result<()> {
+    let game = &game_to_run(game, last, &games)?
+        .with_launch_profile(profile)?
+        .with_player(player)?;
+    games.check_broken(game, Event::RunDone)?;
+    if let Some(pid) = game.running_pid() {
+        bail!("{} is already running (PID {pid})", game.name())
+    }
+    let _lock = status::lock(game.name(), "run")?;
+    if cloud.transfers() {
+        games.cloud_pull(game)?;
+        games.share_backups(game)?;
+        if let Some(marker) = game.newer_backup() {
+            backup::warn_newer_backup(game, &marker);
+        }
+    }
+
+    games.run_hook(Hook::PreRun, game, None)?;
+    // There is nothing to back up on a machine where the game has never been played
+    if backup_before && game.save_location().exists() {
+        games.backup(game, Some("before-run"), None, None, cloud)?;
+    }
+    // Asked now instead of in the middle of the game
+    if autosave_interval.is_some() && games.game_config(game)?.backup.encrypt {
+        games.passphrase()?;
+    }
+    let _activity = status::begin(game.name(), "run");
+    let start = std::time::Instant::now();
+    let cmd = games.run_command(game, args);
+    let configured = matches!(cmd, Ok(Some(_)));
+    let (stop_autosaves, stopped) = std::sync::mpsc::channel();
+    let result = std::thread::scope(|scope| {
+        if let Some(interval) = autosave_interval {
+            let games = &games;
+            scope.spawn(move || autosave(game, interval, games, stopped));
+        }
+        let result =
+            cmd.and_then(|cmd| games.execute_interactive(cmd, "run game", &game.run_dir()));
+        if result.is_ok() && game.steam_appid().is_some() {
+            wait_for_steam_game(game);
+        }
+        drop(stop_autosaves);
+        result
+    });
+    let session = start.elapsed();
+    // Crashes count too, the game was played until then
+    if configured && let Err(e) = game.record_session(session) {
+        output::warning(format_args!(
+            "Could not record the playtime of {}: {e}",
+            game.name()
+        ));
+    }
+    games.run_hook(Hook::PostRun, game, None)?;
+    games.notify(Event::RunDone, game, None, session, result.as_ref().err())?;
+    // A command that couldn't be built never started the game, so there is no crash to back up
+    if !configured || !backup_after {
+        return result;
+    }
+    let desc = match &result {
+        Ok(()) => None,
+        Err(_) => match games.game_config(game)?.run.crash_backup {
+            CrashBackup::OnlyOnCleanExit => return result,
+            CrashBackup::Always => Some("crash"),
+            CrashBackup::Ask => {
+                use std::io::IsTerminal;
+
+                let question = tr!("prompt-crash-backup", game = game.name());
+                if !std::io::stdin().is_terminal()
+                    || !inquire::Confirm::new(&question)
+                        .with_default(false)
+                        .prompt()?
+                {
+                    return result;
+                }
+                Some("crash")
+            }
+        },
+    };
+    games.backup(game, desc, None, Some(session), cloud)?;
+
+    result
+}
+
+/// Backs up the save of the running game every `interval` until `stopped` is disconnected,
+/// keeping only the newest 'run.autosaveKeep' of these backups.
+///
+/// The game is still running, so the failures are only warned about.
+fn autosave(
+    game: &Game,
+    interval: std::time::Duration,
+    games: &Games,
+    stopped: std::sync::mpsc::Receiver<()>,
+) {
+    use goodgame::games::AUTOSAVE_DESCRIPTION;
+
+    while stopped.recv_timeout(interval) == Err(std::sync::mpsc::RecvTimeoutError::Timeout) {
+        let result = games
+            .create_backup(game, Some(AUTOSAVE_DESCRIPTION))
+            .and_then(|_| {
+                let keep = games.game_config(game)?.run.autosave_keep;
+                let autosaves = game.autosaves()?;
+                for old in &autosaves[..autosaves.len().saturating_sub(keep)] {
+                    std::fs::remove_file(&old.path)
+                        .context_with(|| format!("Could not remove {}", old.path.display()))?;
+                }
+                Ok(())
+            });
+        if let Err(e) = result {
+            tracing::warn!("Could not autosave {}: {e}", game.name());
+            output::warning(format_args!("Could not autosave {}: {e}", game.name()));
+        }
+    }
+}
+
+/// Backs up the saves of the selected games, or of all of them, whenever they settle after
+/// changing and at the times of their 'backup.schedule', until the process is stopped.
+#[cfg(feature = "watch")]
+fn watch(names: &[String], tags: &[String], cloud: CloudMode, games: &Games) -> Result<()> {
+    use goodgame::games::{SCHEDULED_DESCRIPTION, WATCH_DESCRIPTION};
+    use std::collections::HashMap;
+    use std::time::Instant;
+
+    let selected = if names.is_empty() && tags.is_empty() {
+        games.active().collect()
+    } else {
+        games.select(names, tags)?
+    };
+    let mut watched = Vec::new();
+    let mut scheduled = Vec::new();
+    let mut gc_scheduled = Vec::new();
+    for game in selected {
+        let config = games.game_config(game)?;
+        if !config.watch.enabled && config.backup.schedule.is_none() && config.gc.schedule.is_none()
+        {
+            continue;
+        }
+        if let Some(problem) = games.broken(game).first() {
+            output::warning(format_args!("Not watching {}: {problem}", game.name()));
+            continue;
+        }
+        // Asked now instead of at the first backup
+        if config.backup.encrypt {
+            games.passphrase()?;
+        }
+        if config.watch.enabled {
+            watched.push(game);
+        }
+        if let Some(schedule) = &config.backup.schedule {
+            scheduled.push((game, schedule));
+        }
+        if let Some(schedule) = &config.gc.schedule {
+            gc_scheduled.push((game, schedule, &config.gc));
+        }
+    }
+    if watched.is_empty() && scheduled.is_empty() && gc_scheduled.is_empty() {
+        bail!(
+            "There are no games to watch, 'watch.enabled' may be false and 'backup.schedule' and 'gc.schedule' unset for all of them"
+        )
+    }
+    let watcher = goodgame::watch::SaveWatcher::new(watched.iter().copied())?;
+    println!(
+        "Watching the saves of {} games and backing up {} on schedule, press Ctrl-C to stop",
+        watched.len(),
+        scheduled.len()
+    );
+
+    // Last change of the saves not backed up yet, and last backup of each game
+    let mut changed = HashMap::<String, Instant>::new();
+    let mut backed_up = HashMap::<String, Instant>::new();
+    let mut last_runs = goodgame::schedule::last_runs();
+    let mut last_gc_runs = goodgame::schedule::last_gc_runs();
+    loop {
+        for name in watcher.changes(std::time::Duration::from_secs(1))? {
+            // The other gg processes back up or restore the game themselves
+            if status::lock_holder(&name).is_none() {
+                changed.insert(name, Instant::now());
+            }
+        }
+
+        let now = std::time::SystemTime::now();
+        for (game, schedule) in &scheduled {
+            let Some(due) = schedule.last_due(now) else {
+                continue;
+            };
+            // Without a recorded run, the latest backup tells whether the last time was missed
+            let last_run = last_runs.get(game.name()).copied().or_else(|| {
+                let latest = game.backups().ok()?.pop()?;
+                Some(goodgame::games::unix_secs(latest.timestamp))
+            });
+            if last_run.is_some_and(|last| goodgame::games::unix_secs(due) <= last)
+                || status::lock_holder(game.name()).is_some()
+            {
+                continue;
+            }
+            let result = games.backup(game, Some(SCHEDULED_DESCRIPTION), None, None, cloud);
+            // Also when it failed, so it is retried at the next time instead of right away
+            let time = goodgame::games::unix_time();
+            last_runs.insert(game.name().to_owned(), time);
+            let result = result.and_then(|_| goodgame::schedule::record_run(game.name(), time));
+            if let Err(e) = result {
+                warn_watch_failure(game, "back up", &e);
+            }
+            // The changes made until now are in the backup
+            changed.remove(game.name());
+        }
+
+        for (game, schedule, config) in &gc_scheduled {
+            let Some(due) = schedule.last_due(now) else {
+                continue;
+            };
+            // Without a recorded run it is repacked right away
+            if last_gc_runs
+                .get(game.name())
+                .is_some_and(|last| goodgame::games::unix_secs(due) <= *last)
+                || status::lock_holder(game.name()).is_some()
+            {
+                continue;
+            }
+            let result = games.gc(game, Some(config.older_than), Some(config.level), false);
+            let time = goodgame::games::unix_time();
+            last_gc_runs.insert(game.name().to_owned(), time);
+            let result = result.and_then(|_| goodgame::schedule::record_gc_run(game.name(), time));
+            if let Err(e) = result {
+                warn_watch_failure(game, "repack", &e);
+            }
+        }
+
+        let mut due = Vec::new();
+        for (name, last_change) in &changed {
+            let game = games.get_by_name(name)?;
+            let config = &games.game_config(game)?.watch;
+            let throttled = backed_up
+                .get(name)
+                .is_some_and(|last| last.elapsed() < config.min_interval.into());
+            if last_change.elapsed() >= config.settle.into() && !throttled {
+                due.push(game);
+            }
+        }
+        for game in due {
+            changed.remove(game.name());
+            if let Some(holder) = status::lock_holder(game.name()) {
+                tracing::info!(
+                    "Not backing up {}, gg is busy with it: {holder:?}",
+                    game.name()
+                );
+                continue;
+            }
+            let result = games
+                .backup(game, Some(WATCH_DESCRIPTION), None, None, cloud)
+                .and_then(|_| {
+                    let keep = games.game_config(game)?.watch.keep;
+                    let mut old = game.backups()?;
+                    old.retain(|b| b.description.as_deref() == Some(WATCH_DESCRIPTION));
+                    for old in &old[..old.len().saturating_sub(keep)] {
+                        std::fs::remove_file(&old.path)
+                            .context_with(|| format!("Could not remove {}", old.path.display()))?;
+                    }
+                    Ok(())
+                });
+            backed_up.insert(game.name().to_owned(), Instant::now());
+            if let Err(e) = result {
+                warn_watch_failure(game, "back up", &e);
+            }
+        }
+    }
+}
+
+/// Reports that 'gg watch' could not `action` the game, going on with the rest of the games.
+#[cfg(feature = "watch")]
+fn warn_watch_failure(game: &Game, action: &str, e: &rootcause::Report) {
+    tracing::warn!("Could not {action} {}: {e}", game.name());
+    output::warning(format_args!("Could not {action} {}: {e}", game.name()));
+    let _ = status::record_failure(format!("gg watch {}", game.name()), e.to_string());
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch(_: &[String], _: &[String], _: CloudMode, _: &Games) -> Result<()> {
+    bail!("gg was built without the \"watch\" feature")
+}
+
+/// Runs 'gg run' again without --detach in the background, with its output in a file.
+fn run_detached(game: Option<String>, last: bool, games: &Games) -> Result<()> {
+    let picked = game.is_none() && !last;
+    let game = game_to_run(game, last, games)?;
+    // The arguments after "--" belong to the game
+    let args = std::env::args_os().skip(1).collect::<Vec<_>>();
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    // Without a terminal, the background gg can't ask for the game again
+    let name = picked.then(|| std::ffi::OsString::from(game.name()));
+    let args = args[..end]
+        .iter()
+        .filter(|arg| *arg != "--detach")
+        .chain(&name)
+        .chain(&args[end..]);
+    let (pid, log) = runner::spawn_gg(game, args)?;
+    println!(
+        "Started {} in the background (PID {pid}), the output goes to {}",
+        game.name(),
+        log.display()
+    );
+    Ok(())
+}
+
+/// Returns the game 'gg run' starts: the one played most recently if `last` is set.
+///
+/// If no name is given and the current directory doesn't belong to a game, it is asked starting at
+/// the one played most recently, which is used instead if there is no terminal to ask in.
+fn game_to_run(game: Option<String>, last: bool, games: &Games) -> Result<&Game> {
+    if !last
+        && game.is_none()
+        && games.get_by_current_dir().is_none()
+        && let Some(game) = games.pick(games.last_played().map(Game::name))?
+    {
+        return Ok(game);
+    }
+    if last || (game.is_none() && games.get_by_current_dir().is_none()) {
+        match games.last_played() {
+            Some(game) => {
+                if !last {
+                    output::progress(format_args!(
+                        "Running {}, the game played most recently",
+                        game.name()
+                    ));
+                }
+                return Ok(game);
+            }
+            None if last => bail!("No game has been played with 'gg run' yet"),
+            None => {}
+        }
+    }
+    games.try_get(game)
+}
+
+/// Waits until the processes Steam started for the game exit, as the Steam launch returns right
+/// away.
+fn wait_for_steam_game(game: &Game) {
+    const START_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(120);
+    const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+    let start = std::time::Instant::now();
+    println!("Waiting for Steam to start {}...", game.name());
+    while game.steam_pids().is_empty() {
+        if start.elapsed() > START_TIMEOUT {
+            output::warning(format_args!(
+                "Steam did not start {} in {}s, not waiting for it",
+                game.name(),
+                START_TIMEOUT.as_secs()
+            ));
+            return;
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    println!("{} is running, waiting for it to exit...", game.name());
+    while !game.steam_pids().is_empty() {
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn sync(game: Option<String>, games: Games) -> Result<()> {
+    let targets = match game {
+        Some(game) => vec![games.get_by_name(game)?],
+        None => games.active().collect(),
+    };
+
+    let results = games.for_each_parallel(&targets, |game| -> Result<_> {
+        let report = games.cloud_sync(game)?;
+        games.share_backups(game)?;
+        Ok(report)
+    })?;
+
+    // Printed once everything finishes, so the output of the games isn't interleaved
+    let mut conflicts = 0;
+    let mut failed = 0;
+    println!();
+    for (game, result) in targets.iter().zip(results) {
+        let report = match result {
+            Ok(Some(report)) => report,
+            Ok(None) => {
+                println!("Synced {}", game.name());
+                continue;
+            }
+            Err(e) => {
+                output::warning(format_args!("Could not sync {}: {e}", game.name()));
+                failed += 1;
+                continue;
+            }
+        };
+        println!(
+            "Synced {}: {} uploaded, {} downloaded",
+            game.name(),
+            report.uploaded.len(),
+            report.downloaded.len()
+        );
+        for name in &report.conflicts {
+            println!("  Conflict: {name} differs between this machine and the cloud");
+        }
+        conflicts += report.conflicts.len();
+    }
+
+    if failed > 0 {
+        bail!("{failed} games could not be synced")
+    }
+    if conflicts > 0 {
+        bail!(
+            "{conflicts} backups are in conflict, rename or remove one of the copies and sync again"
+        )
+    }
+    Ok(())
+}
+
+fn cloud_status(game: Option<String>, games: Games) -> Result<()> {
+    let targets = match game {
+        Some(game) => vec![games.get_by_name(game)?],
+        None => games.active().collect(),
+    };
+
+    if json_output() {
+        let report = targets
+            .into_iter()
+            .map(|game| {
+                let mut entry = serde_json::json!({
+                    "game": game.name(),
+                    "cloud": game.cloud_enabled(),
+                });
+                if !game.cloud_enabled() {
+                    return entry;
+                }
+                match games.cloud_status(game) {
+                    Ok(Some(status)) => {
+                        entry["local_only"] = status.local_only.into();
+                        entry["remote_only"] = status.remote_only.into();
+                        entry["conflicts"] = status.conflicts.into();
+                    }
+                    Ok(None) => entry["pending_uploads"] = game.pending_uploads().into(),
+                    Err(e) => entry["error"] = e.to_string().into(),
+                }
+                entry
+            })
+            .collect::<Vec<_>>();
+        return print_json(&report);
+    }
+
+    for game in targets {
+        if !game.cloud_enabled() {
+            println!("{}: cloud disabled", game.name());
+            continue;
+        }
+        // A single unreachable remote shouldn't hide the status of the rest
+        let status = match games.cloud_status(game) {
+            Ok(Some(status)) => status,
+            Ok(None) => {
+                let pending = game.pending_uploads().len();
+                if pending > 0 {
+                    println!("{}: {pending} pending upload", game.name());
+                } else {
+                    println!("{}: the backend can't list its backups", game.name());
+                }
+                continue;
+            }
+            Err(e) => {
+                output::warning(format_args!(
+                    "Could not read the cloud of {}: {e}",
+                    game.name()
+                ));
+                continue;
+            }
+        };
+
+        let mut summary = Vec::new();
+        if !status.local_only.is_empty() {
+            summary.push(format!("{} not pushed", status.local_only.len()));
+        }
+        if !status.remote_only.is_empty() {
+            summary.push(format!("{} newer in the cloud", status.remote_only.len()));
+        }
+        if !status.conflicts.is_empty() {
+            summary.push(format!("{} in conflict", status.conflicts.len()));
+        }
+        if summary.is_empty() {
+            println!("{}: up to date", game.name());
+        } else {
+            println!("{}: {}", game.name(), summary.join(", "));
+        }
+        for name in &status.local_only {
+            println!("  Not pushed: {name}");
+        }
+        for name in &status.remote_only {
+            println!("  In the cloud: {name}");
+        }
+        for name in &status.conflicts {
+            println!("  Conflict: {name} differs between this machine and the cloud");
+        }
+    }
+    Ok(())
+}
+
+fn clone(
+    index: PathBuf,
+    path_maps: Vec<(String, String)>,
+    restore_latest: bool,
+    mut games: Games,
+) -> Result<()> {
+    let mut cloned = Vec::new();
+    for mut game in Games::read_games(&index)? {
+        if games.get_exact(game.name()).is_some() {
+            println!("{} is already managed, skipping...", game.name());
+            continue;
+        }
+        for (from, to) in &path_maps {
+            game.remap(Path::new(from), Path::new(to));
+        }
+
+        let backups_path = game.backups_path();
+        std::fs::create_dir_all(&backups_path)
+            .context_with(|| format!("Could not create {}", backups_path.display()))?;
+        games.cloud_init(&game)?;
+        games.cloud_pull(&game)?;
+        games.share_backups(&game)?;
+        println!(
+            "Cloned {} with {} backups",
+            game.name(),
+            game.backups()?.len()
+        );
+
+        cloned.push(game.name().to_owned());
+        games.push(game);
+        games.store()?;
+    }
+
+    if restore_latest {
+        for name in cloned {
+            let game = games.get_by_name(&name)?;
+            let Some(latest) = game.backups()?.pop() else {
+                println!("{name} has no backups, skipping restore...");
+                continue;
+            };
+            let latest = latest.name().into_owned();
+            // The backups have just been pulled
+            restore(
+                name,
+                Some(latest),
+                None,
+                None,
+                CloudMode::Off,
+                false,
+                false,
+                &games,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn export(
+    file: &Path,
+    names: &[String],
+    tags: &[String],
+    with_backups: bool,
+    games: &Games,
+) -> Result<()> {
+    let selected = if names.is_empty() && tags.is_empty() {
+        games.games().iter().collect()
+    } else {
+        games.select(names, tags)?
+    };
+    let export = goodgame::games::Export::new(selected, with_backups);
+    export.write(file)?;
+    outcome::record_artifact(file);
+    println!(
+        "Exported {} games to {}",
+        export.games.len(),
+        file.display()
+    );
+    Ok(())
+}
+
+fn import(
+    file: &Path,
+    replace: bool,
+    path_maps: Vec<(String, String)>,
+    yes: bool,
+    mut games: Games,
+) -> Result<()> {
+    if is_backup_file(file, &games) {
+        return import_backup(file, &path_maps, yes, games);
+    }
+    let mut export = goodgame::games::Export::read(file)?;
+    for game in &mut export.games {
+        for (from, to) in &path_maps {
+            game.remap(Path::new(from), Path::new(to));
+        }
+    }
+    let report = games.import(export, replace)?;
+    games.store()?;
+
+    for name in &report.added {
+        println!("{}", tr!("game-added", game = name.as_str()));
+    }
+    for name in &report.replaced {
+        println!("Replaced the settings of {name}");
+    }
+    for name in &report.unchanged {
+        println!("{name} is already managed with the same settings");
+    }
+    for conflict in &report.conflicts {
+        println!(
+            "Conflict: {} is already managed with another {}, kept as it is",
+            conflict.game,
+            conflict.fields.join(", ")
+        );
+    }
+    if !report.conflicts.is_empty() {
+        println!("Run the import again with --replace to use the imported settings");
+    }
+    print_json(&report)
+}
+
+/// Returns `true` if the file at `path` is named like a backup archive of gg, not like an export.
+fn is_backup_file(path: &Path, games: &Games) -> bool {
+    if crypto::is_encrypted(path) {
+        return true;
+    }
+    games.backup_archiver(path).extension().is_some()
+        || path
+            .file_stem()
+            .map(Path::new)
+            .and_then(Path::extension)
+            .is_some_and(|e| e == "tar")
+}
+
+/// Copies the backup archive at `file` into the backups of the game it was made from, adding the
+/// game from what the archive records about it if it is not managed, with its paths moved by
+/// `path_maps`, after asking unless `yes`.
+fn import_backup(
+    file: &Path,
+    path_maps: &[(String, String)],
+    yes: bool,
+    mut games: Games,
+) -> Result<()> {
+    let bare = |name, root, save_location| {
+        Game::new(
+            name,
+            root,
+            save_location,
+            None,
+            None,
+            None::<Vec<(String, String)>>,
+            None,
+        )
+    };
+    // Reading the archive only needs the compression of the configured games
+    let probe = bare(String::new(), PathBuf::new(), PathBuf::new());
+    let Some(info) = games.read_backup_info(file, &probe)? else {
+        bail!(
+            "{} has no {}, as it was made by an older gg, copy it into the backups of its game instead",
+            file.display(),
+            backup::INFO_ENTRY
+        )
+    };
+    let added = games.get_exact(&info.game).is_none();
+    let game = match games.get_exact(&info.game) {
+        Some(game) => game.with_player(info.player.as_deref())?,
+        None if info.player.is_some() => bail!(
+            "{} is not managed, add it with 'gg add' and the player {:?} with 'gg edit' before importing the backup",
+            info.game,
+            info.player.unwrap_or_default()
+        ),
+        None => {
+            let mut game = bare(
+                info.game.clone(),
+                info.root.clone(),
+                info.save_location.clone(),
+            );
+            for (from, to) in path_maps {
+                game.remap(Path::new(from), Path::new(to));
+            }
+            if !game.root().is_dir() {
+                bail!(
+                    "{} is not managed and its root {} doesn't exist in this machine, move it with --map or add the game with 'gg add'",
+                    info.game,
+                    game.root().display()
+                )
+            }
+            // The archive decides where the save is restored, so the user checks it first
+            println!(
+                "{} is not managed, the backup would add it with:",
+                game.name()
+            );
+            println!("  root: {}", game.root().display());
+            println!("  save location: {}", game.save_location().display());
+            if !yes {
+                confirm(&tr!("prompt-add-game", game = game.name()))?;
+            }
+            game
+        }
+    };
+
+    let backups_location = game.backups_path();
+    std::fs::create_dir_all(&backups_location)
+        .context_with(|| format!("Could not create {}", backups_location.display()))?;
+    let file_name = file.file_name().ok_or_report()?.to_string_lossy();
+    let name = game.adopted_backup_name(&game, &file_name)?;
+    let dest = backups_location.join(&name);
+    std::fs::copy(file, &dest)
+        .context_with(|| format!("Could not copy {} to {}", file.display(), dest.display()))?;
+    games.share_backups(&game)?;
+    if added {
+        println!(
+            "Added {} with the save location {}",
+            game.name(),
+            game.save_location().display()
+        );
+        games.push(game.clone());
+        games.store()?;
+    }
+    println!(
+        "Imported {} as {name}, made {} by gg {}, run 'gg restore {} {name}' to use it",
+        file.display(),
+        goodgame::games::ago(info.created),
+        info.gg_version,
+        game.name()
+    );
+    print_json(&serde_json::json!({
+        "game": game.name(),
+        "player": game.player(),
+        "backup": dest,
+        "added": added,
+    }))
+}
+
+fn top(once: bool, socket: Option<PathBuf>, games: Games) -> Result<()> {
+    let socket = socket.or_else(goodgame::rpc::socket_path);
+    loop {
+        let mut out = String::new();
+        if !once {
+            // Clear the screen and go back to the top
+            out.push_str("\x1b[2J\x1b[H");
+            out.push_str("gg top, refreshing every second (Ctrl-C to exit)\n\n");
+        }
+
+        out.push_str("Running games\n");
+        let running = games
+            .games()
+            .iter()
+            .filter_map(|g| Some((g.name(), g.running_pid()?)))
+            .collect::<Vec<_>>();
+        if running.is_empty() {
+            out.push_str("  None\n");
+        }
+        for (name, pid) in running {
+            out.push_str(&format!("  {name} (PID {pid})\n"));
+        }
+
+        out.push_str("\nIn progress\n");
+        let activities = status::activities();
+        if activities.is_empty() {
+            out.push_str("  None\n");
+        }
+        for a in activities {
+            let elapsed = goodgame::games::unix_time().saturating_sub(a.started);
+            out.push_str(&format!(
+                "  {:>3}:{:02}  {:<12} {} (PID {})\n",
+                elapsed / 60,
+                elapsed % 60,
+                a.action,
+                a.game,
+                a.pid
+            ));
+        }
+
+        out.push_str("\nRecent failures\n");
+        let failures = status::failures();
+        if failures.is_empty() {
+            out.push_str("  None\n");
+        }
+        for f in failures.iter().rev().take(10) {
+            let error = f
+                .error
+                .lines()
+                .map(|l| l.trim_start_matches([' ', '●', '│']).trim())
+                .find(|l| !l.is_empty())
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "  {:<16} {}: {error}\n",
+                goodgame::games::ago(f.time),
+                f.command
+            ));
+        }
+
+        out.push_str("\nServer\n");
+        match &socket {
+            Some(path) if path.exists() => {
+                match goodgame::rpc::call(path, "status", serde_json::Value::Null) {
+                    Ok(status) => {
+                        let started = status["started"]
+                            .as_u64()
+                            .map(goodgame::games::ago)
+                            .unwrap_or_default();
+                        out.push_str(&format!(
+                            "  Listening on {} (PID {}), started {started}\n",
+                            path.display(),
+                            status["pid"]
+                        ));
+                        let activities = status["activities"].as_array().cloned();
+                        for a in activities.unwrap_or_default() {
+                            out.push_str(&format!(
+                                "    {:<12} {}\n",
+                                a["action"].as_str().unwrap_or_default(),
+                                a["game"].as_str().unwrap_or_default()
+                            ));
+                        }
+                    }
+                    Err(e) => out.push_str(&format!(
+                        "  Not answering: {}\n",
+                        e.format_current_context()
+                    )),
+                }
+            }
+            _ => out.push_str("  Not running\n"),
+        }
+
+        print!("{out}");
+        std::io::Write::flush(&mut std::io::stdout())?;
+        if once {
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+fn print_config(games: Games) -> Result<()> {
+    let config = games.config()?;
+    if json_output() {
+        return print_json(&serde_json::json!({
+            "profile": config.profile,
+            "settings": config.settings,
+        }));
+    }
+    if config.settings.is_empty() {
+        println!("{config:#?}");
+        println!("\nNo config file loaded, using the defaults");
+        return Ok(());
+    }
+    let width = config
+        .settings
+        .iter()
+        .map(|s| s.key.len())
+        .max()
+        .unwrap_or(0);
+    if let Some(profile) = &config.profile {
+        println!("Using the profile {profile:?}, its values take precedence over the rest\n");
+    }
+    println!("Values set by the config files:");
+    for setting in &config.settings {
+        println!(
+            "  {:<width$} = {}  ({})",
+            setting.key,
+            setting.value,
+            setting.file.display()
+        );
+    }
+    Ok(())
+}
 
-    if !skip_cloud {
-        run_command(
-            games.cloud_commit_command(game),
-            "cloud commit",
-            game.root(),
-        )?;
-        run_command(games.cloud_push_command(game), "cloud push", game.root())?;
-    }
+/// Opens the user config file, or the system one if `system`, in the editor, saving it only if the
+/// resulting config is valid.
+///
+/// An invalid config is offered to be edited again instead.
+fn edit_config(system: bool, games: Games) -> Result<()> {
+    let path = config_path(system)?;
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => new_config(&path, &games)?,
+        Err(e) => Err(e).context_with(|| format!("Could not read {}", path.display()))?,
+    };
 
-    println!(
-        "Successfully restored backup {} to {}",
-        target_path.display(),
-        save_location.display()
-    );
+    let fpath = private_temp_file("config.yaml")?;
+    // Removed whatever happens, as the config may have secrets
+    let edited = edit_config_file(&path, &fpath, contents, &games);
+    let _ = std::fs::remove_file(&fpath);
+    let contents = edited?;
 
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .context_with(|| format!("Could not create {}", parent.display()))?;
+    }
+    std::fs::write(&path, contents)
+        .context_with(|| format!("Could not write {}", path.display()))?;
+    println!("Saved {}", path.display());
     Ok(())
 }
 
-fn open(game: String, save: bool, games: Games) -> Result<()> {
-    let game = games.get_by_name(&game)?;
-    let dir = if save {
-        game.save_location()
-    } else {
-        game.root()
-    };
-    let _ = Command::new("xdg-open").arg(dir).spawn()?;
-    Ok(())
+/// Lets the user edit `contents` in the temporary file `fpath` until it is a valid config for
+/// `path`, returning the edited config.
+fn edit_config_file(
+    path: &Path,
+    fpath: &Path,
+    mut contents: String,
+    games: &Games,
+) -> Result<String> {
+    use std::io::IsTerminal;
+
+    loop {
+        std::fs::write(fpath, &contents)
+            .context_with(|| format!("Could not write the config to {}", fpath.display()))?;
+        let cmd = games.editor_command(fpath)?;
+        games
+            .execute_interactive(Some(cmd), "editing config", fpath.parent().ok_or_report()?)
+            .context("Failed to edit the config with commands")?;
+        contents = std::fs::read_to_string(fpath)
+            .context_with(|| format!("Could not read {}", fpath.display()))?;
+
+        let Err(e) = Config::check(path, &contents) else {
+            return Ok(contents);
+        };
+        output::warning(format_args!("The edited config is invalid: {e}"));
+        let again = std::io::stdin().is_terminal()
+            && inquire::Confirm::new(&tr!("prompt-edit-again"))
+                .with_default(true)
+                .prompt()
+                .context("Could not read the confirmation")?;
+        if !again {
+            bail!("The config has not been saved")
+        }
+    }
 }
 
-fn run(game: Option<String>, skip_cloud: bool, games: Games) -> Result<()> {
-    let game = games.try_get(game)?;
-    run_command(games.run_command(game), "run game", game.root())?;
+/// Creates an empty file named after `name` only readable by the user, in the runtime directory of
+/// gg, for the files with secrets like the config.
+///
+/// It is created anew, so a file or link placed there by another user can't be written instead.
+fn private_temp_file(name: &str) -> Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
 
-    backup(Some(game.name()), None, skip_cloud, &games)?;
+    let dir = goodgame::paths::runtime_dir()
+        .ok_or_report()
+        .context("Could not find the runtime directory of gg")?;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .context_with(|| format!("Could not create {}", dir.display()))?;
+    let path = dir.join(format!(".gg-{}-{name}", std::process::id()));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .context_with(|| format!("Could not create {}", path.display()))?;
+    Ok(path)
+}
 
+/// Writes the starter config to the user config file, or to the system one if `system`, unless it
+/// already exists and not `force`.
+fn init_config(system: bool, force: bool) -> Result<()> {
+    let path = config_path(system)?;
+    if path.exists() && !force {
+        bail!(
+            "{} already exists, edit it with 'gg config edit' or pass --force to overwrite it",
+            path.display()
+        )
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .context_with(|| format!("Could not create {}", parent.display()))?;
+    }
+    std::fs::write(&path, goodgame::config::TEMPLATE)
+        .context_with(|| format!("Could not write {}", path.display()))?;
+    outcome::record_artifact(&path);
+    println!("Wrote {}, edit it with 'gg config edit'", path.display());
     Ok(())
 }
 
-fn print_config(games: Games) -> Result<()> {
-    println!("{:#?}", games.config());
+/// Returns the user config file, or the system one if `system`.
+fn config_path(system: bool) -> Result<PathBuf> {
+    let paths = Config::paths();
+    let path = if system { paths.first() } else { paths.last() };
+    Ok(path.ok_or_report()?.clone())
+}
+
+/// Prints the JSON Schema of the config files, or of the games database if `games`.
+fn print_schema(games: bool) -> Result<()> {
+    let schema = if games {
+        Games::schema()
+    } else {
+        Config::schema()
+    };
+    let json = serde_json::to_string_pretty(&schema).context("Could not serialize the schema")?;
+    println!("{json}");
     Ok(())
 }
 
-fn run_command(cmd: Option<Command>, desc: &str, cwd: &Path) -> Result<()> {
-    let Some(mut cmd) = cmd else {
-        println!("Command {desc} not configured, skipping...");
-        return Ok(());
+/// Checks the config, printing every problem found and failing if there is any.
+fn check_config(games: Games) -> Result<()> {
+    let config = games.config()?;
+    let mut problems = Vec::new();
+
+    let shell = config.shell.program();
+    let shell_exists = if shell.contains('/') {
+        Path::new(shell).is_file()
+    } else {
+        std::env::var_os("PATH")
+            .is_some_and(|path| std::env::split_paths(&path).any(|p| p.join(shell).is_file()))
+    };
+    if !shell_exists {
+        problems.push(format!("The shell {} does not exist", config.shell));
+    }
+
+    for setting in config.unknown_keys()? {
+        problems.push(format!(
+            "Unknown key {} ({})",
+            setting.key,
+            setting.file.display()
+        ));
+    }
+
+    let templates = |config: &Config| {
+        use goodgame::games::CLOUD_TEMPLATE_VARIABLES as CLOUD;
+
+        let backup = &config.backup;
+        let mut templates = vec![
+            (
+                String::from("run.commands"),
+                config.run.commands.clone(),
+                goodgame::games::RUN_TEMPLATE_VARIABLES,
+            ),
+            (
+                String::from("backup.cloudInitCommands"),
+                backup.cloud_init_commands.clone(),
+                &[],
+            ),
+            (
+                String::from("backup.cloudCommitCommands"),
+                backup.cloud_commit_commands.clone(),
+                CLOUD,
+            ),
+            (
+                String::from("backup.cloudPushCommands"),
+                backup.cloud_push_commands.clone(),
+                CLOUD,
+            ),
+            (
+                String::from("backup.cloudPullCommands"),
+                backup.cloud_pull_commands.clone(),
+                &[],
+            ),
+            (
+                String::from("backup.cloudRenameCommands"),
+                backup.cloud_rename_commands.clone(),
+                goodgame::games::RENAME_TEMPLATE_VARIABLES,
+            ),
+        ];
+        for hook in Hook::ALL {
+            let extra = match hook {
+                Hook::PreRun | Hook::PostRun => &[][..],
+                _ => &["@BACKUP-FILE"],
+            };
+            let commands = config.hooks.commands(hook).to_vec();
+            templates.push((format!("hooks.{}", hook.name()), commands, extra));
+        }
+        templates
     };
+    let global = templates(config);
+    let overrides = config.games.iter().flat_map(|(name, game)| {
+        // Only the commands changed by the game section, the inherited ones are checked once
+        templates(game)
+            .into_iter()
+            .zip(&global)
+            .filter(|((_, commands, _), (_, global, _))| commands != global)
+            .map(move |((key, commands, extra), _)| {
+                (format!("games.{name}.{key}"), commands, extra)
+            })
+            .collect::<Vec<_>>()
+    });
+    for (key, commands, extra) in global.iter().cloned().chain(overrides) {
+        for (i, command) in commands.iter().enumerate() {
+            for variable in template_variables(command) {
+                let known = goodgame::games::TEMPLATE_VARIABLES.contains(&variable)
+                    || extra.contains(&variable);
+                if !known {
+                    problems.push(format!("{key}[{i}] uses the unknown variable {variable}"));
+                }
+            }
+        }
+    }
 
-    // let first_cmd = cmd
-    //     .get_args()
-    //     .nth(1)
-    //     .unwrap_or(std::ffi::OsStr::from_bytes(b"<EMPTY COMMAND>"))
-    //     .to_string_lossy()
-    //     .to_string();
-    let cmd_description = cmd
-        .get_args()
-        .fold(std::ffi::OsString::new(), |mut acc, c| {
-            acc.push(" ");
-            acc.push(c);
-            acc
-        });
-
-    println!("[gg] Running {desc}: {cmd_description:?}");
-
-    let original_dir = std::env::current_dir().context("Could not get current directory")?;
-    std::env::set_current_dir(cwd)
-        .context_with(|| format!("Could not access directory {}", cwd.display()))?;
-
-    let out = cmd
-        .status()
-        .context_with(|| format!("Failed to execute command '{desc}': {cmd_description:?}",))?;
-    if !out.success() {
-        bail!(
-            "Command '{desc}' exited with code {}: {cmd_description:?}",
-            out.code().unwrap_or(0),
-        )
+    for name in config.games.keys() {
+        if games.get_exact(name).is_none() {
+            problems.push(format!("games.{name} does not match any managed game"));
+        }
+    }
+
+    if problems.is_empty() {
+        println!("The config is valid");
+        return Ok(());
+    }
+    for problem in &problems {
+        eprintln!("{problem}");
+    }
+    bail!("Found {} problems in the config", problems.len())
+}
+
+/// Returns the variables used by the command `template`, like "@NAME".
+fn template_variables(template: &str) -> Vec<&str> {
+    let mut variables = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('@') {
+        let after = &rest[start + 1..];
+        let len = after
+            .find(|c: char| !c.is_ascii_uppercase() && c != '-')
+            .unwrap_or(after.len());
+        let variable = rest[start..start + 1 + len].trim_end_matches('-');
+        if variable.len() > 1 {
+            variables.push(variable);
+        }
+        rest = &rest[start + 1 + len..];
+    }
+    variables
+}
+
+/// Returns the contents of a new config file at `path`, listing as comments the values set by
+/// the other config files.
+fn new_config(path: &Path, games: &Games) -> Result<String> {
+    let mut contents = String::from("# goodgame config, values not set here keep their defaults\n");
+    let inherited = games
+        .config()?
+        .settings
+        .iter()
+        .filter(|s| s.file != path)
+        .collect::<Vec<_>>();
+    if !inherited.is_empty() {
+        contents.push_str("#\n# Values set by the other config files:\n");
+        for setting in inherited {
+            contents.push_str(&format!(
+                "#   {} = {}  ({})\n",
+                setting.key,
+                setting.value,
+                setting.file.display()
+            ));
+        }
     }
+    Ok(contents)
+}
+
+/// Re-encrypts the encrypted backups of the game, or of every game, with a new passphrase.
+fn rotate_key(game: Option<String>, cloud: CloudMode, games: Games) -> Result<()> {
+    let targets = match game {
+        Some(game) => vec![games.get_by_name(game)?],
+        None => games.active().collect(),
+    };
+    // The current passphrase is asked before the new one
+    games.passphrase()?;
+    let new = crypto::read_passphrase("GG_NEW_PASSPHRASE", "New backup passphrase:")?;
+    let rotated = games.rotate_key(&targets, &new, cloud)?;
+    println!("Re-encrypted {rotated} backups, use the new passphrase from now on");
+    Ok(())
+}
 
-    std::env::set_current_dir(original_dir)?;
+/// Stores the credential `name` in the system keyring, asking for it or reading it from stdin.
+fn set_secret(name: &str) -> Result<()> {
+    use std::io::IsTerminal;
 
+    let secret = if std::io::stdin().is_terminal() {
+        inquire::Password::new(&tr!("prompt-secret-value", name = name))
+            .with_display_mode(inquire::PasswordDisplayMode::Masked)
+            .prompt()
+            .context("Could not read the secret")?
+    } else {
+        let mut secret = String::new();
+        std::io::stdin()
+            .read_to_string(&mut secret)
+            .context("Could not read the secret from stdin")?;
+        secret.trim_end_matches(['\n', '\r']).to_owned()
+    };
+    if secret.is_empty() {
+        bail!("The secret is empty")
+    }
+    secrets::set(name, &secret)?;
+    println!("Stored the secret {name:?}, use it in the config with `keyring: {name}`");
     Ok(())
 }
 
@@ -435,24 +3993,114 @@ impl std::fmt::Display for PathBufDisplay {
     }
 }
 
-fn try_get_save_location(root: &Path) -> Option<PathBuf> {
-    std::env::set_current_dir(root).ok()?;
+/// Returns the save location that the Ludusavi manifest gives for the game called `title`,
+/// asking which one if it gives several.
+///
+/// Warns and returns `None` if none of them exists in this machine.
+fn manifest_save_location(
+    title: &str,
+    listed: &goodgame::manifest::ManifestGame,
+    root: &Path,
+    prefix: Option<&Path>,
+) -> Option<PathBuf> {
+    use std::io::IsTerminal;
+
+    let mut paths = listed.save_paths(root, prefix);
+    match paths.len() {
+        0 => {
+            output::warning(format_args!(
+                "None of the save locations of {title:?} in the Ludusavi manifest exists, has the game been run yet?"
+            ));
+            None
+        }
+        1 => paths.pop(),
+        // The saves tagged as such come first
+        _ if !std::io::stdin().is_terminal() => Some(paths.swap_remove(0)),
+        _ => {
+            let options = paths.into_iter().map(PathBufDisplay).collect();
+            inquire::Select::new(&tr!("prompt-save-location"), options)
+                .prompt()
+                .ok()
+                .map(|chosen| chosen.0)
+        }
+    }
+}
+
+/// Asks which of the likely save locations of the game called `name` is the right one, if any
+/// was found.
+///
+/// Answering "None of these" continues with the rest of the ways to find it.
+fn suggest_save_location(name: &str, root: &Path, prefix: Option<&Path>) -> Option<PathBuf> {
+    use std::io::IsTerminal;
+
+    const NONE: &str = "None of these";
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+    let mut candidates = goodgame::detect::save_candidates(name, root, prefix);
+    if candidates.is_empty() {
+        return None;
+    }
+    let options = candidates
+        .iter()
+        .map(ToString::to_string)
+        .chain([NONE.to_owned()])
+        .collect();
+    let chosen = inquire::Select::new(&tr!("prompt-save-location"), options)
+        .raw_prompt()
+        .ok()?;
+    (chosen.index < candidates.len()).then(|| candidates.swap_remove(chosen.index).path)
+}
 
-    fn exists(path: &str) -> bool {
-        Path::new(path).exists()
+/// Asks which of the directories of the Windows user of the wine `prefix` is the save location,
+/// like the ones in AppData or "Documents/My Games".
+fn prefix_save_location(prefix: &Path) -> Option<PathBuf> {
+    use std::io::IsTerminal;
+
+    const USER_DIRS: [&str; 6] = [
+        "AppData/Roaming",
+        "AppData/Local",
+        "AppData/LocalLow",
+        "Documents",
+        "Documents/My Games",
+        "Saved Games",
+    ];
+    const IGNORED: [&str; 4] = ["Microsoft", "Temp", "My Games", "openvr"];
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+    let users = std::fs::read_dir(prefix.join("drive_c/users")).ok()?;
+    let options = users
+        .flatten()
+        .flat_map(|user| USER_DIRS.map(|dir| user.path().join(dir)))
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_dir() && !IGNORED.iter().any(|i| e.file_name() == *i))
+        .map(|e| PathBufDisplay(e.path()))
+        .collect::<Vec<_>>();
+    if options.is_empty() {
+        return None;
     }
-    fn try_marker(
-        name: &str,
-        markers: impl IntoIterator<Item = &'static str>,
-        path: &str,
-    ) -> Option<PathBuf> {
+    inquire::Select::new(&tr!("prompt-prefix-save-location"), options)
+        .prompt()
+        .ok()
+        .map(|p| p.0)
+}
+
+/// Returns the save location of the known game engines in `root`, or asks for one of its
+/// directories.
+///
+/// Every path is relative to `root`, leaving the working directory of gg untouched.
+fn try_get_save_location(root: &Path) -> Option<PathBuf> {
+    let try_marker = |name: &str, markers: &[&str], path: &str| -> Option<PathBuf> {
         let path = markers
-            .into_iter()
-            .all(exists)
-            .then(|| Path::new(path).canonicalize().ok())?;
+            .iter()
+            .all(|marker| root.join(marker).exists())
+            .then(|| root.join(path).canonicalize().ok())?;
         eprintln!("Game type detected: {name}");
         path
-    }
+    };
     macro_rules! one_of {
         ( $($exprs:expr),+ ) => {
             $(
@@ -463,13 +4111,18 @@ fn try_get_save_location(root: &Path) -> Option<PathBuf> {
             None
         }
     }
+    // Shown relative to the root, like "./game/saves"
     let walk = || {
         inquire::Select::new(
-            "Select the game's save location",
-            walkdir::WalkDir::new(".")
+            &tr!("prompt-save-location"),
+            walkdir::WalkDir::new(root)
                 .into_iter()
                 .flatten()
-                .map(|e| PathBufDisplay(e.into_path()))
+                .filter_map(|e| {
+                    Some(PathBufDisplay(
+                        Path::new(".").join(e.path().strip_prefix(root).ok()?),
+                    ))
+                })
                 .collect(),
         )
         .prompt()
@@ -477,10 +4130,663 @@ fn try_get_save_location(root: &Path) -> Option<PathBuf> {
     };
 
     one_of! {
-        try_marker("RenPy", ["renpy"], "game/saves"),
-        try_marker("RPG Maker MV", ["nw.dll"], "www/save"),
-        walk().map(|p| p.0.to_path_buf())
+        try_marker("RenPy", &["renpy"], "game/saves"),
+        try_marker("RPG Maker MV", &["nw.dll"], "www/save"),
+        walk().map(|p| root.join(p.0))
+    }
+}
+
+/// Runs the game once and asks which of the directories it wrote to is the save location.
+///
+/// Instead of watching the filesystem while the game runs, the likely save prefixes are scanned
+/// afterwards for files modified since it was launched.
+fn discover_save_location(probe: &Game, games: &Games) -> Result<Option<PathBuf>> {
+    let start = std::time::SystemTime::now();
+    println!("Play until the game saves, then close it");
+    games.execute_interactive(games.run_command(probe, &[])?, "run game", &probe.run_dir())?;
+
+    let mut written = std::collections::BTreeSet::new();
+    for prefix in save_prefixes(probe) {
+        let entries = walkdir::WalkDir::new(prefix)
+            .into_iter()
+            .filter_entry(|e| !ignored_dir(e))
+            .flatten();
+        for entry in entries {
+            let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+            if entry.file_type().is_file() && modified.is_some_and(|m| m >= start) {
+                written.insert(entry.into_path());
+            }
+        }
+    }
+
+    let mut dirs = std::collections::HashMap::<PathBuf, usize>::new();
+    for file in &written {
+        if let Some(parent) = file.parent() {
+            *dirs.entry(parent.to_path_buf()).or_default() += 1;
+        }
+    }
+    if dirs.is_empty() {
+        println!("The game did not write any file");
+        return Ok(None);
+    }
+    let mut dirs = dirs.into_iter().collect::<Vec<_>>();
+    dirs.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let options = dirs
+        .iter()
+        .map(|(dir, count)| format!("{} ({count} files written)", dir.display()))
+        .collect();
+    let chosen = inquire::Select::new(&tr!("prompt-save-directory"), options)
+        .raw_prompt()
+        .ok();
+    Ok(chosen.map(|c| dirs.swap_remove(c.index).0))
+}
+
+/// Returns the existing directories where games usually store their saves, starting with `root`.
+fn save_prefixes(game: &Game) -> Vec<PathBuf> {
+    let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+    [
+        Some(game.root().to_path_buf()),
+        game.wine_prefix()
+            .map(|prefix| prefix.join("drive_c/users")),
+        goodgame::paths::data_home(),
+        goodgame::paths::config_home(),
+        Some(home.join(".local/share")),
+        Some(home.join(".config")),
+        Some(home.join("Documents")),
+        Some(home.join(".wine")),
+        Some(home.join(".steam/steam/steamapps/compatdata")),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|p| p.is_dir())
+    .collect()
+}
+
+/// Whether the save prefixes are searched without entering `e`, like caches and backups.
+fn ignored_dir(e: &walkdir::DirEntry) -> bool {
+    let name = e.file_name().to_string_lossy().to_lowercase();
+    name.contains("cache") || name == ".git" || name == "gg-saves" || name == "goodgame"
+}
+
+/// Checks every game, or only `game`, printing the problems found and repairing the safe ones if
+/// `fix`, then searches for the missing save locations.
+fn doctor(game: Option<String>, yes: bool, fix: bool, mut games: Games) -> Result<()> {
+    use std::io::IsTerminal;
+
+    let targets = match game {
+        Some(game) => vec![games.get_by_name(game)?.clone()],
+        None => games.active().cloned().collect(),
+    };
+    let mut problems = 0;
+    let mut fixable = 0;
+    let mut updated = false;
+    for mut game in targets {
+        if !game.root().exists() {
+            println!(
+                "{}: The root does not exist: {}",
+                game.name(),
+                game.root().display()
+            );
+            match fix_root(&game, yes, &mut games)? {
+                RootFix::Repointed(repointed) => {
+                    game = *repointed;
+                    updated = true;
+                }
+                RootFix::Retired => {
+                    updated = true;
+                    continue;
+                }
+                RootFix::Kept => {
+                    problems += 1;
+                    continue;
+                }
+            }
+        }
+        for problem in game_problems(&game, &games) {
+            println!("{}: {}", game.name(), problem.description);
+            let Some(repair) = problem.repair else {
+                problems += 1;
+                continue;
+            };
+            if !fix {
+                fixable += 1;
+                problems += 1;
+                continue;
+            }
+            match repair() {
+                Ok(done) => println!("  {done}"),
+                Err(e) => {
+                    eprintln!("  Could not fix it: {e}");
+                    problems += 1;
+                }
+            }
+        }
+
+        if game.save_location().exists() || !game.root().exists() {
+            continue;
+        }
+        println!(
+            "{}: The save location does not exist: {}",
+            game.name(),
+            game.save_location().display()
+        );
+        let mut candidates = find_moved_save(&game, &games)?;
+        if candidates.is_empty() {
+            println!(
+                "  No moved save found, set it with 'gg edit {} --save-location PATH'",
+                game.name()
+            );
+            problems += 1;
+            continue;
+        }
+        let chosen = if yes {
+            Some(0)
+        } else if std::io::stdin().is_terminal() {
+            let mut options = candidates
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            options.push(tr!("prompt-keep-save-location"));
+            inquire::Select::new(&tr!("prompt-new-save-location"), options)
+                .raw_prompt()
+                .ok()
+                .map(|c| c.index)
+                .filter(|&i| i < candidates.len())
+        } else {
+            for candidate in &candidates {
+                println!("  Found {candidate}");
+            }
+            None
+        };
+        let Some(chosen) = chosen else {
+            problems += 1;
+            continue;
+        };
+        let location = candidates.swap_remove(chosen).location;
+        println!("  Moved the save location to {}", location.display());
+        games.push(game.merged_with(
+            None,
+            None,
+            Some(location),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ));
+        updated = true;
+    }
+    if updated {
+        games.store()?;
+    }
+    if fixable > 0 {
+        println!("{fixable} of the problems can be repaired with 'gg doctor --fix'");
+    }
+    if problems > 0 {
+        bail!("{problems} problems were found")
+    }
+    println!("No problems found");
+    Ok(())
+}
+
+/// What was done with a game whose root does not exist.
+enum RootFix {
+    /// The root was moved, this is the game with the new one.
+    Repointed(Box<Game>),
+    /// The game is not managed anymore.
+    Retired,
+    Kept,
+}
+
+/// Asks what to do with `game`, whose root does not exist: moving it to the new root, which also
+/// moves the save location if it was inside, or stop managing it while keeping its backups.
+///
+/// Only suggests how to do it without a terminal or with `yes`, as the new root can't be guessed.
+fn fix_root(game: &Game, yes: bool, games: &mut Games) -> Result<RootFix> {
+    use std::io::IsTerminal;
+
+    const REPOINT: &str = "Enter its new root";
+    const RETIRE: &str = "Stop managing it, keeping its backups";
+    const KEEP: &str = "Keep it as it is";
+    if yes || !std::io::stdin().is_terminal() {
+        println!(
+            "  Set the new one with 'gg edit {0} --root PATH', or stop managing it with 'gg remove {0}'",
+            game.name()
+        );
+        return Ok(RootFix::Kept);
+    }
+    let chosen = inquire::Select::new("What to do with it?", vec![REPOINT, RETIRE, KEEP])
+        .prompt()
+        .unwrap_or(KEEP);
+    match chosen {
+        REPOINT => {
+            let Ok(root) = inquire::Text::new(&tr!("prompt-new-root")).prompt() else {
+                return Ok(RootFix::Kept);
+            };
+            let root = goodgame::paths::resolve(Path::new(root.trim()));
+            if !root.is_dir() {
+                eprintln!("  {} is not a directory", root.display());
+                return Ok(RootFix::Kept);
+            }
+            let root = root.canonicalize().unwrap_or(root);
+            let save_location = game
+                .save_location()
+                .strip_prefix(game.root())
+                .ok()
+                .map(|relative| root.join(relative));
+            let repointed = games
+                .push(game.clone().merged_with(
+                    None,
+                    Some(root),
+                    save_location,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                ))
+                .clone();
+            println!("  Moved the root to {}", repointed.root().display());
+            Ok(RootFix::Repointed(Box::new(repointed)))
+        }
+        RETIRE => {
+            games.delete(game.name())?;
+            println!(
+                "  Stopped managing {}, its backups are kept in {}",
+                game.name(),
+                game.backups_path().display()
+            );
+            Ok(RootFix::Retired)
+        }
+        _ => Ok(RootFix::Kept),
+    }
+}
+
+/// Problem found by 'gg doctor' in a game.
+struct Problem {
+    description: String,
+    /// Repairs the problem, returning what was done, if it can be done safely.
+    repair: Option<Box<dyn FnOnce() -> Result<String>>>,
+}
+
+impl Problem {
+    fn new(description: String) -> Self {
+        Self {
+            description,
+            repair: None,
+        }
+    }
+
+    fn fixable(description: String, repair: impl FnOnce() -> Result<String> + 'static) -> Self {
+        Self {
+            description,
+            repair: Some(Box::new(repair)),
+        }
+    }
+}
+
+/// Returns the problems of the root, backups and temporary files of the game.
+///
+/// The missing save location is left to [`doctor`], which searches for where it moved.
+fn game_problems(game: &Game, games: &Games) -> Vec<Problem> {
+    let root = game.root();
+    if !root.is_dir() {
+        return vec![Problem::new(format!(
+            "The root does not exist: {}",
+            root.display()
+        ))];
+    }
+    let mut problems = game
+        .validate()
+        .into_iter()
+        .filter(|problem| {
+            matches!(
+                problem,
+                Broken::MissingExecutable(_) | Broken::MissingWinePrefix(_)
+            )
+        })
+        .map(|problem| Problem::new(problem.to_string()))
+        .collect::<Vec<_>>();
+
+    let symlink = root.join("gg-save-loc");
+    let target = std::fs::read_link(&symlink).ok();
+    if target.as_deref() != Some(game.save_location()) && game.save_location().exists() {
+        let description = match &target {
+            Some(target) => format!(
+                "The gg-save-loc symlink points to {} instead of the save location",
+                target.display()
+            ),
+            None if symlink.exists() => format!("{} is not a symlink", symlink.display()),
+            None => String::from("The gg-save-loc symlink is missing"),
+        };
+        if target.is_some() || !symlink.exists() {
+            let save_location = game.save_location().to_owned();
+            problems.push(Problem::fixable(description, move || {
+                if symlink.is_symlink() {
+                    std::fs::remove_file(&symlink)
+                        .context_with(|| format!("Could not remove {}", symlink.display()))?;
+                }
+                std::os::unix::fs::symlink(&save_location, &symlink)
+                    .context_with(|| format!("Could not create symlink {}", symlink.display()))?;
+                Ok(format!(
+                    "Linked {} to {}",
+                    symlink.display(),
+                    save_location.display()
+                ))
+            }));
+        } else {
+            problems.push(Problem::new(description));
+        }
+    }
+
+    let backups_path = game.backups_path();
+    if !backups_path.is_dir() {
+        problems.push(Problem::fixable(
+            format!(
+                "The backups directory does not exist: {}",
+                backups_path.display()
+            ),
+            move || {
+                std::fs::create_dir_all(&backups_path)
+                    .context_with(|| format!("Could not create {}", backups_path.display()))?;
+                Ok(format!("Created {}", backups_path.display()))
+            },
+        ));
+        return problems;
+    }
+
+    for backup in game.backups().unwrap_or_default() {
+        // Checking an encrypted backup would ask for the passphrase
+        if !backup.path.is_file() || backup.is_encrypted() {
+            continue;
+        }
+        if let Err(e) = games.check_backup(&backup.path, game) {
+            problems.push(Problem::new(e.to_string()));
+        }
+    }
+
+    // The files of a running operation are not stale
+    if crate::status::lock_holder(game.name()).is_none() {
+        let temporary = game
+            .download_leftovers()
+            .into_iter()
+            .chain(game.temp_leftovers())
+            .filter(|path| path.symlink_metadata().is_ok());
+        for path in temporary {
+            problems.push(Problem::fixable(
+                format!("Stale temporary file {}", path.display()),
+                move || {
+                    let removed = if path.is_dir() {
+                        std::fs::remove_dir_all(&path)
+                    } else {
+                        std::fs::remove_file(&path)
+                    };
+                    removed.context_with(|| format!("Could not remove {}", path.display()))?;
+                    Ok(format!("Removed {}", path.display()))
+                },
+            ));
+        }
+    }
+    problems
+}
+
+/// Runs the sandbox cycle quietly, printing the output of the commands only if one fails.
+fn selftest() -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("gg-selftest-{}", std::process::id()));
+    let result = sandbox_cycle(&dir, false);
+    let _ = std::fs::remove_dir_all(&dir);
+    result?;
+    println!("Self-test passed");
+    Ok(())
+}
+
+/// Runs the sandbox cycle on a new sandbox, removing it afterwards unless `keep`.
+fn sandbox(keep: bool) -> Result<()> {
+    let dir = std::env::temp_dir().join(format!("gg-sandbox-{}", std::process::id()));
+    let result = sandbox_cycle(&dir, true);
+    if !keep {
+        let _ = std::fs::remove_dir_all(&dir);
+        return result;
+    }
+    result?;
+    let exports = sandbox_env(&dir)
+        .map(|(var, value)| {
+            format!(
+                "  export {var}={}\n",
+                runner::shell_quote(&value.to_string_lossy())
+            )
+        })
+        .collect::<String>();
+    println!(
+        "\nThe sandbox is kept in {}, try more commands on it with:\n{exports}  gg --ignore-config list",
+        dir.display(),
+    );
+    Ok(())
+}
+
+/// Variables of the home and the directories of gg, set for every command of the sandbox so it
+/// never touches the games, backups, locks and logs of the user.
+const SANDBOX_DIRS: [(&str, &str); 6] = [
+    ("HOME", ""),
+    ("XDG_DATA_HOME", "data"),
+    ("XDG_CONFIG_HOME", "config"),
+    ("XDG_STATE_HOME", "state"),
+    ("XDG_CACHE_HOME", "cache"),
+    ("XDG_RUNTIME_DIR", "run"),
+];
+
+/// Returns the [`SANDBOX_DIRS`] variables with their directories inside the sandbox `dir`.
+fn sandbox_env(dir: &Path) -> impl Iterator<Item = (&'static str, PathBuf)> {
+    SANDBOX_DIRS.into_iter().map(|(var, subdir)| match subdir {
+        "" => (var, dir.to_owned()),
+        subdir => (var, dir.join(subdir)),
+    })
+}
+
+/// Creates a game with sample saves in `dir`, managed with the home and the directories of gg
+/// inside `dir`, then
+/// adds, backs up, modifies, backs up and restores it, checking the restored save.
+///
+/// Every step runs this same gg without the config or the cloud, printing the commands and their
+/// output if `verbose`, or only the output of the failed one otherwise.
+fn sandbox_cycle(dir: &Path, verbose: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    const NAME: &str = "Sandbox";
+    let exe = std::env::current_exe().context("Could not get the path of gg")?;
+    let root = dir.join("game");
+    let saves = root.join("saves");
+    let sample = saves.join("slot1.sav");
+    std::fs::create_dir_all(saves.join("profiles"))
+        .context_with(|| format!("Could not create the sandbox in {}", dir.display()))?;
+    for (_, subdir) in sandbox_env(dir) {
+        std::fs::create_dir_all(&subdir)
+            .context_with(|| format!("Could not create {}", subdir.display()))?;
+    }
+    // Like the real runtime directory, only accessible by the user
+    std::fs::set_permissions(dir.join("run"), std::fs::Permissions::from_mode(0o700))?;
+    std::fs::write(&sample, "level 1")?;
+    std::fs::write(saves.join("options.ini"), "volume=80")?;
+    std::fs::write(saves.join("profiles/player.dat"), "player")?;
+    if verbose {
+        println!("Created the sandbox game in {}", root.display());
+    }
+
+    let gg = |args: &[&str]| -> Result<()> {
+        if verbose {
+            println!("$ gg {}", args.join(" "));
+        }
+        let mut cmd = Command::new(&exe);
+        cmd.args(args)
+            .arg("--ignore-config")
+            .envs(sandbox_env(dir))
+            .stdin(Stdio::null());
+        let output = if verbose {
+            cmd.status().map(|status| (status, Vec::new()))
+        } else {
+            cmd.output()
+                .map(|o| (o.status, [o.stdout, o.stderr].concat()))
+        };
+        let (status, output) =
+            output.context_with(|| format!("Could not run 'gg {}'", args.join(" ")))?;
+        if !status.success() {
+            eprint!("{}", String::from_utf8_lossy(&output));
+            bail!("'gg {}' failed with {status}", args.join(" "))
+        }
+        Ok(())
+    };
+    let root_arg = root.to_string_lossy();
+    let saves_arg = saves.to_string_lossy();
+    gg(&["add", NAME, &root_arg, &saves_arg, "--skip-cloud"])?;
+    gg(&["backup", NAME, "--skip-cloud"])?;
+    if verbose {
+        println!("Modifying {} and deleting options.ini", sample.display());
     }
+    std::fs::write(&sample, "level 2")?;
+    std::fs::remove_file(saves.join("options.ini"))?;
+    gg(&["backup", NAME, "--desc", "modified", "--skip-cloud"])?;
+
+    let backups = root.join("gg-saves");
+    let mut names = std::fs::read_dir(&backups)
+        .context_with(|| format!("Could not read {}", backups.display()))?
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with(NAME))
+        .collect::<Vec<_>>();
+    names.sort_unstable();
+    let first = names
+        .first()
+        .ok_or_report()
+        .context("No backup was created")?;
+    gg(&["restore", NAME, first, "--skip-cloud", "--yes"])?;
+
+    let restored = std::fs::read_to_string(&sample).unwrap_or_default();
+    if restored != "level 1" || !saves.join("options.ini").exists() {
+        bail!("The restored save does not match the first backup")
+    }
+    if verbose {
+        println!("The save of the first backup was restored correctly");
+    }
+    Ok(())
+}
+
+/// A place that contains the files of a save that has been moved.
+struct MovedSave {
+    location: PathBuf,
+    /// Files of the save that exist in the location.
+    found: usize,
+    /// Files of the save with the same contents as in the last backup.
+    identical: usize,
+    total: usize,
+}
+
+impl std::fmt::Display for MovedSave {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}/{} files, {} unchanged since the last backup)",
+            self.location.display(),
+            self.found,
+            self.total,
+            self.identical
+        )
+    }
+}
+
+/// Searches the save prefixes for the files of the manifest of the last backup of the game,
+/// returning the places that contain at least half of them, best first.
+fn find_moved_save(game: &Game, games: &Games) -> Result<Vec<MovedSave>> {
+    let mut manifest = None;
+    for backup in game.backups().unwrap_or_default().iter().rev() {
+        manifest = games.read_manifest(&backup.path, game)?;
+        if manifest.is_some() {
+            break;
+        }
+    }
+    let Some(manifest) = manifest else {
+        println!("  No backup of {} has a manifest to look for", game.name());
+        return Ok(Vec::new());
+    };
+
+    // A single file save is stored as the prefix itself
+    let single_file = manifest.contains_key(backup::SAVE_PREFIX);
+    let files = manifest
+        .iter()
+        .filter_map(|(entry, hash)| {
+            let rel = if single_file {
+                PathBuf::from(game.save_location().file_name()?)
+            } else {
+                Path::new(entry)
+                    .strip_prefix(backup::SAVE_PREFIX)
+                    .ok()?
+                    .to_path_buf()
+            };
+            Some((rel, hash))
+        })
+        .collect::<Vec<_>>();
+    let mut by_name = std::collections::HashMap::<_, Vec<&Path>>::new();
+    for (rel, _) in &files {
+        if let Some(name) = rel.file_name() {
+            by_name.entry(name.to_owned()).or_default().push(rel);
+        }
+    }
+
+    let mut locations = std::collections::BTreeSet::new();
+    for prefix in save_prefixes(game) {
+        let entries = walkdir::WalkDir::new(prefix)
+            .into_iter()
+            .filter_entry(|e| !ignored_dir(e))
+            .flatten()
+            .filter(|e| e.file_type().is_file());
+        for entry in entries {
+            let Some(rels) = by_name.get(entry.file_name()) else {
+                continue;
+            };
+            for rel in rels.iter().filter(|rel| entry.path().ends_with(rel)) {
+                let location = if single_file {
+                    Some(entry.path())
+                } else {
+                    entry.path().ancestors().nth(rel.components().count())
+                };
+                locations.extend(location.map(Path::to_path_buf));
+            }
+        }
+    }
+
+    let mut candidates = locations
+        .into_iter()
+        .filter(|location| location != game.save_location())
+        .map(|location| {
+            let (mut found, mut identical) = (0, 0);
+            for (rel, hash) in &files {
+                let path = if single_file {
+                    location.clone()
+                } else {
+                    location.join(rel)
+                };
+                if let Ok(actual) = backup::hash_file(&path) {
+                    found += 1;
+                    identical += usize::from(&actual == *hash);
+                }
+            }
+            MovedSave {
+                location,
+                found,
+                identical,
+                total: files.len(),
+            }
+        })
+        .filter(|c| c.found * 2 >= c.total)
+        .collect::<Vec<_>>();
+    candidates.sort_by(|a, b| {
+        (b.identical, b.found)
+            .cmp(&(a.identical, a.found))
+            .then_with(|| a.location.cmp(&b.location))
+    });
+    Ok(candidates)
 }
 
 fn try_get_executable_location(root: &Path) -> Option<PathBuf> {
@@ -499,8 +4805,149 @@ fn try_get_executable_location(root: &Path) -> Option<PathBuf> {
         }
         None
     });
-    inquire::Select::new("Select the game's main executable", options.collect())
+    inquire::Select::new(&tr!("prompt-executable"), options.collect())
         .prompt()
         .ok()
         .map(|p| p.0)
 }
+
+/// Answers the requests of 'gg serve' on the socket at `path`, or on the default one.
+fn serve(path: Option<PathBuf>, ignore_config: bool) -> Result<()> {
+    let path = match path {
+        Some(path) => path,
+        None => goodgame::rpc::socket_path().context("Could not obtain the runtime directory")?,
+    };
+    let listener = goodgame::rpc::bind(&path)?;
+    println!("Serving on {}, press Ctrl-C to stop", path.display());
+    goodgame::rpc::serve(listener, move |method, params| {
+        // Read for every request, so the changes made by the other gg processes are seen
+        let mut games = Games::load().map_err(goodgame::rpc::failure)?;
+        if ignore_config {
+            games.ignore_config();
+        }
+        goodgame::rpc::handle(&games, method, params)
+    })
+}
+
+/// Writes the man pages of gg into `dir`, or prints the help of every command if `help_all`.
+fn man(dir: &Path, help_all: bool) -> Result<()> {
+    use clap::CommandFactory;
+
+    fn print_help(command: &mut clap::Command) {
+        println!("{}", command.render_long_help());
+        for sub in command
+            .get_subcommands_mut()
+            .filter(|sub| !sub.is_hide_set())
+        {
+            print_help(sub);
+        }
+    }
+
+    if help_all {
+        let mut command = cli::localized_command()
+            .bin_name("gg")
+            .disable_help_subcommand(true);
+        command.build();
+        print_help(&mut command);
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    // Named after the binary, not the package, so the pages are gg.1, gg-restore.1...
+    clap_mangen::generate_to(cli::Cli::command().name("gg"), dir)
+        .context_with(|| format!("Could not write the man pages to {}", dir.display()))?;
+    outcome::record_artifact(dir);
+    println!("Wrote the man pages to {}", dir.display());
+    Ok(())
+}
+
+fn completions_doctor(shell: Option<cli::Shell>) -> Result<()> {
+    use cli::Shell;
+
+    let shell = match shell {
+        Some(shell) => shell,
+        None => {
+            let var = std::env::var("SHELL").unwrap_or_default();
+            match var.rsplit('/').next().unwrap_or_default() {
+                "bash" => Shell::Bash,
+                "zsh" => Shell::Zsh,
+                "fish" => Shell::Fish,
+                _ => {
+                    bail!("Could not detect the shell from $SHELL={var:?}, specify it with --shell")
+                }
+            }
+        }
+    };
+    let (name, rc_file, line) = match shell {
+        Shell::Bash => ("bash", ".bashrc", "source <(COMPLETE=bash gg)"),
+        Shell::Zsh => ("zsh", ".zshrc", "source <(COMPLETE=zsh gg)"),
+        Shell::Fish => (
+            "fish",
+            ".config/fish/config.fish",
+            "source (COMPLETE=fish gg | psub)",
+        ),
+    };
+    let home = PathBuf::from(std::env::var_os("HOME").ok_or_report()?);
+    let rc_file = home.join(rc_file);
+    let mut ok = true;
+
+    // The configuration runs 'gg', so it must be this same binary
+    let exe = std::env::current_exe().context("Could not get the path of gg")?;
+    let in_path = std::env::var_os("PATH")
+        .map(|path| {
+            std::env::split_paths(&path)
+                .map(|p| p.join("gg"))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .find(|p| p.is_file());
+    match in_path {
+        Some(p) if p.canonicalize().ok() == exe.canonicalize().ok() => {
+            println!("[ok] gg found in PATH at {}", p.display())
+        }
+        Some(p) => println!(
+            "[warn] gg in PATH ({}) is not this binary ({})",
+            p.display(),
+            exe.display()
+        ),
+        None => {
+            ok = false;
+            println!(
+                "[error] gg is not in PATH, add the directory of {} to it",
+                exe.display()
+            )
+        }
+    }
+
+    let configured = std::fs::read_to_string(&rc_file)
+        .is_ok_and(|rc| rc.contains(&format!("COMPLETE={name} gg")));
+    if configured {
+        println!("[ok] Completions are enabled in {}", rc_file.display());
+    } else {
+        ok = false;
+        println!(
+            "[error] Completions are not enabled in {}, add this line to it:\n\n    {line}\n",
+            rc_file.display()
+        );
+    }
+
+    let output = Command::new(&exe)
+        .env("COMPLETE", name)
+        .output()
+        .context_with(|| format!("Could not execute {}", exe.display()))?;
+    if output.status.success() && !output.stdout.is_empty() {
+        println!("[ok] 'COMPLETE={name} gg' outputs the {name} completion script");
+    } else {
+        ok = false;
+        println!(
+            "[error] 'COMPLETE={name} gg' did not output a completion script:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    if !ok {
+        bail!("The {name} completions are not set up correctly")
+    }
+    println!("Completions are set up, restart the shell if they are not working yet");
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Save locations of the games listed in the manifest of Ludusavi, a community list of where
+//! thousands of games keep their saves, used by 'gg add --from-manifest'.
+//!
+//! The manifest is downloaded into the cache directory and refreshed when it is a week old.
+
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+/// Where the manifest is downloaded from.
+pub const MANIFEST_URL: &str =
+    "https://raw.githubusercontent.com/mtkennerly/ludusavi-manifest/master/data/manifest.yaml";
+const MANIFEST_FILE: &str = "ludusavi-manifest.yaml";
+/// Age of the cached manifest after which it is downloaded again.
+const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The games of the manifest by their title.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Manifest(HashMap<String, ManifestGame>);
+
+/// A game of the manifest, with only what gg uses of it.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestGame {
+    /// Files and directories of the game by their path, with placeholders like `<base>`.
+    #[serde(default)]
+    files: BTreeMap<String, FileRule>,
+    /// Names of the directory the game is installed in.
+    #[serde(default)]
+    install_dir: BTreeMap<String, serde::de::IgnoredAny>,
+    #[serde(default)]
+    steam: Option<Steam>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct FileRule {
+    /// What the files are, like "save" or "config".
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Conditions of which one must hold for the files to apply, any if empty.
+    #[serde(default)]
+    when: Vec<Condition>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct Condition {
+    os: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+struct Steam {
+    id: Option<u32>,
+}
+
+impl Manifest {
+    /// Reads the cached manifest, downloading it first if it is missing or a week old.
+    pub fn load() -> Result<Manifest> {
+        let path = crate::paths::cache_dir().join(MANIFEST_FILE);
+        let stale = path
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age > MAX_AGE);
+        if stale {
+            match download(&path) {
+                Ok(()) => {}
+                // An old manifest is still good enough
+                Err(e) if path.exists() => crate::output::warning(format_args!(
+                    "Could not update the Ludusavi manifest, using the cached one: {e}"
+                )),
+                Err(e) => return Err(e),
+            }
+        }
+        let contents = std::fs::read_to_string(&path)
+            .context_with(|| format!("Could not read {}", path.display()))?;
+        Ok(serde_saphyr::from_str(&contents)
+            .context_with(|| format!("Could not parse the Ludusavi manifest {}", path.display()))?)
+    }
+
+    /// Returns the game called `title` along with its title in the manifest, ignoring the case if
+    /// no game has that exact title.
+    ///
+    /// Fails suggesting the titles that contain it if none matches.
+    pub fn get(&self, title: &str) -> Result<(&str, &ManifestGame)> {
+        if let Some((title, game)) = self.0.get_key_value(title) {
+            return Ok((title, game));
+        }
+        if let Some((title, game)) = self.0.iter().find(|(t, _)| t.eq_ignore_ascii_case(title)) {
+            return Ok((title, game));
+        }
+        let lowercase = title.to_lowercase();
+        let mut similar = self
+            .0
+            .keys()
+            .filter(|t| t.to_lowercase().contains(&lowercase))
+            .map(|t| format!("{t:?}"))
+            .collect::<Vec<_>>();
+        similar.sort_unstable();
+        similar.truncate(5);
+        if similar.is_empty() {
+            bail!("The Ludusavi manifest has no game called {title:?}")
+        }
+        bail!(
+            "The Ludusavi manifest has no game called {title:?}, did you mean {}?",
+            similar.join(" or ")
+        )
+    }
+}
+
+impl ManifestGame {
+    /// Returns the names of the directory the game is installed in, like "Hollow Knight".
+    pub fn install_dirs(&self) -> impl Iterator<Item = &str> {
+        self.install_dir.keys().map(String::as_str)
+    }
+
+    pub fn steam_appid(&self) -> Option<u32> {
+        self.steam.as_ref()?.id
+    }
+
+    /// Returns the existing files and directories with the saves of the game installed in `root`,
+    /// with its wine `prefix` if it has one, the ones tagged as saves first.
+    ///
+    /// The Windows paths are only used with a prefix, and the ones of the registry never.
+    pub fn save_paths(&self, root: &Path, prefix: Option<&Path>) -> Vec<PathBuf> {
+        let os = if cfg!(target_os = "macos") {
+            "mac"
+        } else {
+            "linux"
+        };
+        let mut paths = self
+            .files
+            .iter()
+            .filter(|(_, rule)| {
+                rule.when.is_empty()
+                    || rule.when.iter().any(|c| match c.os.as_deref() {
+                        None => true,
+                        Some("windows") => prefix.is_some(),
+                        Some(other) => other == os,
+                    })
+            })
+            .filter_map(|(path, rule)| {
+                let path = resolve(path, root, prefix)?;
+                let save = rule.tags.is_empty() || rule.tags.iter().any(|tag| tag == "save");
+                Some((!save, path))
+            })
+            .flat_map(|(config, path)| {
+                crate::games::expand_glob(&path)
+                    .into_iter()
+                    .map(move |path| (config, path))
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        let mut paths = paths.into_iter().map(|(_, path)| path).collect::<Vec<_>>();
+        // Kept in order, the first one being the most likely
+        let mut seen = std::collections::HashSet::new();
+        paths.retain(|path| seen.insert(path.clone()));
+        paths
+    }
+}
+
+/// Replaces the placeholders of the manifest path `path` with the directories of this machine,
+/// or returns `None` if it uses one that has none, like the registry.
+fn resolve(path: &str, root: &Path, prefix: Option<&Path>) -> Option<PathBuf> {
+    let home = crate::paths::home()?;
+    let users = prefix.map(|prefix| prefix.join("drive_c/users"));
+    let user = users.as_ref().map(|users| users.join("*"));
+    let dir = |dir: Option<PathBuf>| dir.map(|dir| dir.to_string_lossy().into_owned());
+    let placeholders = [
+        ("<base>", dir(Some(root.to_path_buf()))),
+        ("<root>", dir(root.parent().map(Path::to_path_buf))),
+        ("<game>", dir(root.file_name().map(PathBuf::from))),
+        ("<home>", dir(Some(home))),
+        ("<xdgData>", dir(crate::paths::data_home())),
+        ("<xdgConfig>", dir(crate::paths::config_home())),
+        ("<osUserName>", Some(crate::games::username())),
+        ("<storeUserId>", Some(String::from("*"))),
+        (
+            "<winAppData>",
+            dir(user.as_ref().map(|u| u.join("AppData/Roaming"))),
+        ),
+        (
+            "<winLocalAppData>",
+            dir(user.as_ref().map(|u| u.join("AppData/Local"))),
+        ),
+        (
+            "<winLocalAppDataLow>",
+            dir(user.as_ref().map(|u| u.join("AppData/LocalLow"))),
+        ),
+        (
+            "<winDocuments>",
+            dir(user.as_ref().map(|u| u.join("Documents"))),
+        ),
+        ("<winPublic>", dir(users.as_ref().map(|u| u.join("Public")))),
+        (
+            "<winProgramData>",
+            dir(prefix.map(|p| p.join("drive_c/ProgramData"))),
+        ),
+        ("<winDir>", dir(prefix.map(|p| p.join("drive_c/windows")))),
+    ];
+    let mut path = path.to_owned();
+    for (placeholder, value) in placeholders {
+        if path.contains(placeholder) {
+            path = path.replace(placeholder, &value?);
+        }
+    }
+    // Unknown placeholders, like the ones of the registry
+    if path.contains('<') {
+        return None;
+    }
+    Some(PathBuf::from(path))
+}
+
+/// Downloads the manifest into `path`, replacing it only once it is complete.
+#[cfg(feature = "manifest")]
+fn download(path: &Path) -> Result<()> {
+    crate::output::progress("Downloading the Ludusavi manifest...");
+    let mut response = ureq::get(MANIFEST_URL)
+        .call()
+        .context("Could not download the Ludusavi manifest")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+    }
+    let partial = path.with_extension("yaml.part");
+    let mut file = std::fs::File::create(&partial)
+        .context_with(|| format!("Could not create {}", partial.display()))?;
+    std::io::copy(&mut response.body_mut().as_reader(), &mut file)
+        .context("Could not download the Ludusavi manifest")?;
+    std::fs::rename(&partial, path)
+        .context_with(|| format!("Could not save the manifest in {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "manifest"))]
+fn download(path: &Path) -> Result<()> {
+    bail!(
+        "gg was built without the \"manifest\" feature, download {MANIFEST_URL} into {} to use it",
+        path.display()
+    )
+}
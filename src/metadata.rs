@@ -0,0 +1,237 @@
+//! Cover art and release date of the games from SteamGridDB, fetched by 'gg metadata' into the
+//! data directory and used by 'gg desktop', 'gg steam-shortcut' and 'gg list --long'.
+//!
+//! SteamGridDB needs an API key, set in the 'metadata.steamgriddbKey' section of the config.
+
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::path::PathBuf;
+
+/// Where the games and their images are looked up.
+pub const API_URL: &str = "https://www.steamgriddb.com/api/v2";
+/// Directory of the data directory with the metadata of every game.
+const METADATA_DIR: &str = "metadata";
+const INFO_FILE: &str = "info.yaml";
+
+/// What is known about a game in SteamGridDB, kept in its own directory along with the images.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    /// Name of the game in SteamGridDB, which may differ from the one in gg.
+    pub title: String,
+    /// Id of the game in SteamGridDB.
+    pub steamgriddb_id: u64,
+    /// When the game was released, in seconds since the Unix epoch.
+    pub release_date: Option<i64>,
+    /// When the metadata was fetched, in seconds since the Unix epoch.
+    pub fetched: u64,
+    /// File name of the cover art in the directory.
+    pub cover: Option<String>,
+    /// File name of the icon in the directory.
+    pub icon: Option<String>,
+    #[serde(skip)]
+    dir: PathBuf,
+}
+
+impl Metadata {
+    /// Returns the path of the cover art, a vertical image like the box of the game.
+    pub fn cover_path(&self) -> Option<PathBuf> {
+        Some(self.dir.join(self.cover.as_ref()?))
+    }
+
+    /// Returns the path of the icon, or of the cover art if there is no icon.
+    pub fn icon_path(&self) -> Option<PathBuf> {
+        Some(self.dir.join(self.icon.as_ref().or(self.cover.as_ref())?))
+    }
+
+    /// Returns the year the game was released.
+    pub fn release_year(&self) -> Option<i64> {
+        Some(crate::games::date(self.release_date?).0)
+    }
+}
+
+/// Returns the directory with the metadata of the game called `game`.
+pub fn dir(game: &str) -> Result<PathBuf> {
+    Ok(crate::games::data_dir()?
+        .join(METADATA_DIR)
+        .join(slug::slugify(game)))
+}
+
+/// Reads the metadata of the game called `game`, or returns `None` if it was never fetched.
+pub fn load(game: &str) -> Result<Option<Metadata>> {
+    let dir = dir(game)?;
+    let path = dir.join(INFO_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).context_with(|| format!("Could not read {}", path.display()))?,
+    };
+    let metadata: Metadata = serde_saphyr::from_str(&contents)
+        .context_with(|| format!("Could not parse {}", path.display()))?;
+    Ok(Some(Metadata { dir, ..metadata }))
+}
+
+/// Removes the metadata of the game called `game`, if it has any.
+pub fn remove(game: &str) -> Result<()> {
+    let dir = dir(game)?;
+    match std::fs::remove_dir_all(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context_with(|| format!("Could not remove {}", dir.display()))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Moves the metadata of the game called `from` to the one called `to`, if it has any.
+pub fn rename(from: &str, to: &str) -> Result<()> {
+    let (from, to) = (dir(from)?, dir(to)?);
+    if from == to || !from.exists() {
+        return Ok(());
+    }
+    std::fs::rename(&from, &to).context_with(|| {
+        format!(
+            "Could not move the metadata {} to {}",
+            from.display(),
+            to.display()
+        )
+    })?;
+    Ok(())
+}
+
+/// Fetches the metadata of the game called `name` from SteamGridDB with the API `key`, looked up
+/// by its Steam `appid` if it has one and else by its name, replacing the one it had.
+#[cfg(feature = "metadata")]
+pub fn fetch(name: &str, appid: Option<u32>, key: &str) -> Result<Metadata> {
+    use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
+    use rootcause::option_ext::OptionExt;
+
+    let client = Client::new(key);
+    let game: SgdbGame = match appid {
+        Some(appid) => client.get(&format!("/games/steam/{appid}"))?,
+        None => {
+            let term = utf8_percent_encode(name, NON_ALPHANUMERIC);
+            let found: Vec<SgdbGame> = client.get(&format!("/search/autocomplete/{term}"))?;
+            found
+                .into_iter()
+                .next()
+                .context_with(|| format!("SteamGridDB has no game called {name:?}"))?
+        }
+    };
+    let dir = dir(name)?;
+    std::fs::create_dir_all(&dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    let cover = client.image(
+        &format!("/grids/game/{}?dimensions=600x900", game.id),
+        &dir,
+        "cover",
+    )?;
+    let icon = client.image(&format!("/icons/game/{}", game.id), &dir, "icon")?;
+    let metadata = Metadata {
+        title: game.name,
+        steamgriddb_id: game.id,
+        release_date: game.release_date,
+        fetched: crate::games::unix_secs(std::time::SystemTime::now()),
+        cover,
+        icon,
+        dir,
+    };
+    let path = metadata.dir.join(INFO_FILE);
+    let contents =
+        serde_saphyr::to_string(&metadata).context("Could not serialize the metadata")?;
+    std::fs::write(&path, contents)
+        .context_with(|| format!("Could not write {}", path.display()))?;
+    Ok(metadata)
+}
+
+#[cfg(not(feature = "metadata"))]
+pub fn fetch(_: &str, _: Option<u32>, _: &str) -> Result<Metadata> {
+    bail!("gg was built without the \"metadata\" feature")
+}
+
+/// Seconds before giving up on a request, so a slow server doesn't hold gg.
+#[cfg(feature = "metadata")]
+const TIMEOUT: u64 = 30;
+
+#[cfg(feature = "metadata")]
+#[derive(serde::Deserialize)]
+struct SgdbResponse<T> {
+    data: T,
+}
+
+#[cfg(feature = "metadata")]
+#[derive(serde::Deserialize)]
+struct SgdbGame {
+    id: u64,
+    name: String,
+    release_date: Option<i64>,
+}
+
+#[cfg(feature = "metadata")]
+#[derive(serde::Deserialize)]
+struct SgdbImage {
+    url: String,
+}
+
+#[cfg(feature = "metadata")]
+struct Client {
+    agent: ureq::Agent,
+    authorization: String,
+}
+
+#[cfg(feature = "metadata")]
+impl Client {
+    fn new(key: &str) -> Client {
+        Client {
+            agent: ureq::Agent::config_builder()
+                .timeout_global(Some(std::time::Duration::from_secs(TIMEOUT)))
+                .build()
+                .into(),
+            authorization: format!("Bearer {key}"),
+        }
+    }
+
+    /// Returns the data of the response of the API to `path`.
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let url = format!("{API_URL}{path}");
+        let body = self
+            .agent
+            .get(&url)
+            .header("Authorization", &self.authorization)
+            .call()
+            .context_with(|| format!("Could not get {url} from SteamGridDB"))?
+            .into_body()
+            .read_to_string()
+            .context_with(|| format!("Could not get {url} from SteamGridDB"))?;
+        let response: SgdbResponse<T> = serde_json::from_str(&body)
+            .context_with(|| format!("Could not parse the response of SteamGridDB to {url}"))?;
+        Ok(response.data)
+    }
+
+    /// Downloads the first image listed by the API at `path` into `dir` as `name` with the
+    /// extension of the image, returning its file name or `None` if none is listed.
+    fn image(&self, path: &str, dir: &std::path::Path, name: &str) -> Result<Option<String>> {
+        let images: Vec<SgdbImage> = self.get(path)?;
+        let Some(image) = images.first() else {
+            return Ok(None);
+        };
+        let extension = std::path::Path::new(&image.url)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("png");
+        let file = format!("{name}.{extension}");
+        let mut response = self
+            .agent
+            .get(&image.url)
+            .call()
+            .context_with(|| format!("Could not download {}", image.url))?;
+        let path = dir.join(&file);
+        let partial = dir.join(format!("{file}.part"));
+        let mut out = std::fs::File::create(&partial)
+            .context_with(|| format!("Could not create {}", partial.display()))?;
+        std::io::copy(&mut response.body_mut().as_reader(), &mut out)
+            .context_with(|| format!("Could not download {}", image.url))?;
+        std::fs::rename(&partial, &path)
+            .context_with(|| format!("Could not write {}", path.display()))?;
+        Ok(Some(file))
+    }
+}
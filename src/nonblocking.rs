@@ -0,0 +1,188 @@
+//! Async variants of the operations of [`Games`] that block for long, like the backups, the cloud
+//! transfers and the configured commands, for frontends that can't stop their event loop while they run.
+//!
+//! Each one runs the sync operation in the blocking thread pool of the current tokio runtime, so
+//! they take the games in an [`Arc`] and the game by name instead of borrowing them. The events
+//! in between are received by the [`Observer`](crate::observer::Observer)s of the games.
+
+use crate::cloud::CloudMode;
+use crate::games::{Games, SyncReport};
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::{path::PathBuf, process::Command, sync::Arc};
+
+/// Runs `f` with the games in the blocking thread pool, returning what it returns.
+///
+/// Any operation of the library without an async variant can be run in the background with it,
+/// like [`Games::transfer`].
+pub async fn spawn<T: Send + 'static>(
+    games: Arc<Games>,
+    f: impl FnOnce(&Games) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    tokio::task::spawn_blocking(move || f(&games))
+        .await
+        .context("The background task of gg panicked")?
+}
+
+/// Async variant of [`Games::backup`].
+pub async fn backup(
+    games: Arc<Games>,
+    game: String,
+    desc: Option<String>,
+    message: Option<String>,
+    cloud: CloudMode,
+) -> Result<PathBuf> {
+    spawn(games, move |games| {
+        let game = games.get_by_name(&game)?;
+        games.backup(game, desc.as_deref(), message.as_deref(), None, cloud)
+    })
+    .await
+}
+
+/// Async variant of [`Games::create_backup`], which only creates the archive.
+pub async fn create_backup(
+    games: Arc<Games>,
+    game: String,
+    desc: Option<String>,
+) -> Result<PathBuf> {
+    spawn(games, move |games| {
+        games.create_backup(games.get_by_name(&game)?, desc.as_deref())
+    })
+    .await
+}
+
+/// Async variant of [`Games::restore`].
+pub async fn restore(
+    games: Arc<Games>,
+    game: String,
+    backup: String,
+    slot: Option<String>,
+    cloud: CloudMode,
+    clean: bool,
+) -> Result<()> {
+    spawn(games, move |games| {
+        let game = games.get_by_name(&game)?;
+        games.restore(game, &backup, slot.as_deref(), cloud, clean)
+    })
+    .await
+}
+
+/// Async variant of [`Games::cloud_push`].
+pub async fn cloud_push(games: Arc<Games>, game: String, backup: Option<PathBuf>) -> Result<()> {
+    spawn(games, move |games| {
+        games.cloud_push(games.get_by_name(&game)?, backup.as_deref())
+    })
+    .await
+}
+
+/// Async variant of [`Games::cloud_pull`].
+pub async fn cloud_pull(games: Arc<Games>, game: String) -> Result<()> {
+    spawn(games, move |games| {
+        games.cloud_pull(games.get_by_name(&game)?)
+    })
+    .await
+}
+
+/// Async variant of [`Games::cloud_fetch`].
+pub async fn cloud_fetch(games: Arc<Games>, game: String, name: String) -> Result<()> {
+    spawn(games, move |games| {
+        games.cloud_fetch(games.get_by_name(&game)?, &name)
+    })
+    .await
+}
+
+/// Async variant of [`Games::cloud_commit`].
+pub async fn cloud_commit(
+    games: Arc<Games>,
+    game: String,
+    backup: Option<PathBuf>,
+    message: Option<String>,
+) -> Result<()> {
+    spawn(games, move |games| {
+        let game = games.get_by_name(&game)?;
+        games.cloud_commit(game, backup.as_deref(), message.as_deref())
+    })
+    .await
+}
+
+/// Async variant of [`Games::cloud_sync`].
+pub async fn cloud_sync(games: Arc<Games>, game: String) -> Result<Option<SyncReport>> {
+    spawn(games, move |games| {
+        games.cloud_sync(games.get_by_name(&game)?)
+    })
+    .await
+}
+
+/// Async variant of [`Games::execute`], waiting for the process without blocking.
+pub async fn execute(
+    games: Arc<Games>,
+    cmd: Option<Command>,
+    desc: String,
+    cwd: PathBuf,
+) -> Result<()> {
+    spawn(games, move |games| games.execute(cmd, &desc, &cwd)).await
+}
+
+/// Async variant of [`Games::output`].
+pub async fn output(
+    games: Arc<Games>,
+    cmd: Command,
+    desc: String,
+    cwd: PathBuf,
+) -> Result<Vec<u8>> {
+    spawn(games, move |games| games.output(cmd, &desc, &cwd)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::games::Game;
+
+    #[test]
+    fn backs_up_and_restores_in_the_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("config.yaml");
+        // Without zstd, the backups are compressed by a command
+        let compression = if cfg!(feature = "zstd") {
+            ""
+        } else {
+            "backup:\n  compression:\n    compress: cat\n    decompress: cat\n    extension: raw\n"
+        };
+        std::fs::write(&config, compression).unwrap();
+        let mut games = Games::builder()
+            .config_path(config)
+            .in_memory()
+            .load()
+            .unwrap();
+        let game = games.push(Game::new(
+            String::from("Outer Wilds"),
+            dir.path().join("game"),
+            dir.path().join("game/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        ));
+        let save = game.save_location().join("data.owsave");
+        std::fs::create_dir_all(game.backups_path()).unwrap();
+        std::fs::create_dir_all(save.parent().unwrap()).unwrap();
+        std::fs::write(&save, "loop 1").unwrap();
+        let games = Arc::new(games);
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let name = String::from("Outer Wilds");
+            let backup = backup(games.clone(), name.clone(), None, None, CloudMode::Off)
+                .await
+                .unwrap();
+            std::fs::write(&save, "loop 2").unwrap();
+            let backup = backup.file_name().unwrap().to_string_lossy().into_owned();
+            restore(games, name, backup, None, CloudMode::Off, false)
+                .await
+                .unwrap();
+        });
+        assert_eq!(std::fs::read_to_string(&save).unwrap(), "loop 1");
+    }
+}
@@ -0,0 +1,44 @@
+use crate::events::Event;
+use crate::games::Game;
+use rootcause::Report;
+use std::{path::Path, process::Command};
+
+/// Receives what gg is doing while it runs, so a program using the library can show its progress
+/// without reading what is printed.
+///
+/// Registered with [`Games::add_observer`](crate::games::Games::add_observer). Every method does
+/// nothing by default, so an observer only implements the ones it cares about.
+pub trait Observer: std::fmt::Debug + Send + Sync {
+    /// Called before running a configured command, where `desc` is a short human readable
+    /// description of it, like "cloud push".
+    fn on_command_start(&self, desc: &str, cmd: &Command) {
+        let _ = (desc, cmd);
+    }
+
+    /// Called after the command described by `desc` finished, with its error if it failed.
+    fn on_command_finish(&self, desc: &str, error: Option<&Report>) {
+        let _ = (desc, error);
+    }
+
+    /// Called while a backup of `game` is written, with the bytes of the save archived so far out
+    /// of the `total` size of the save.
+    fn on_backup_progress(&self, game: &Game, archived: u64, total: u64) {
+        let _ = (game, archived, total);
+    }
+
+    /// Called before the backups of `game` are uploaded to the cloud storage.
+    fn on_cloud_push(&self, game: &Game) {
+        let _ = game;
+    }
+
+    /// Called before the backups of `game` are downloaded from the cloud storage.
+    fn on_cloud_pull(&self, game: &Game) {
+        let _ = game;
+    }
+
+    /// Called when a backup, restore, run or prune of `game` ends, with the backup `archive`
+    /// involved and the error if it failed, like the webhooks.
+    fn on_event(&self, event: Event, game: &Game, archive: Option<&Path>, error: Option<&Report>) {
+        let _ = (event, game, archive, error);
+    }
+}
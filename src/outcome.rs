@@ -0,0 +1,68 @@
+//! What the running operation of gg has done so far, for the programs that wrap gg and can't
+//! scrape the messages it prints.
+//!
+//! The games an operation locks, the files it creates and the warnings printed with
+//! [`output::warning`](crate::output::warning) are recorded while it runs, and returned by
+//! [`take`] when it ends. There is a single outcome per process, so operations run at the same
+//! time record into the same one.
+
+use std::{path::PathBuf, sync::Mutex};
+
+/// Structured result of an operation, serialized by 'gg --json' for the commands without output
+/// of their own and along with their error.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Outcome {
+    /// The operation, like "backup" or "cloud push".
+    pub operation: String,
+    /// Names of the games it worked on, in the order it started with them.
+    pub games: Vec<String>,
+    /// Files it created, like the backups.
+    pub artifacts: Vec<PathBuf>,
+    /// What went wrong without stopping it.
+    pub warnings: Vec<String>,
+}
+
+static CURRENT: Mutex<Outcome> = Mutex::new(Outcome {
+    operation: String::new(),
+    games: Vec::new(),
+    artifacts: Vec::new(),
+    warnings: Vec::new(),
+});
+
+fn current() -> std::sync::MutexGuard<'static, Outcome> {
+    // Recording can't panic halfway, so the outcome is whole even if another thread panicked
+    CURRENT
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Starts recording the outcome of `operation`, discarding what was recorded before.
+pub fn begin(operation: impl Into<String>) {
+    *current() = Outcome {
+        operation: operation.into(),
+        ..Outcome::default()
+    };
+}
+
+/// Records that the operation worked on the game called `name`.
+pub fn record_game(name: &str) {
+    let mut outcome = current();
+    if !outcome.games.iter().any(|game| game == name) {
+        outcome.games.push(name.to_owned());
+    }
+}
+
+/// Records that the operation created the file at `path`.
+pub fn record_artifact(path: impl Into<PathBuf>) {
+    current().artifacts.push(path.into());
+}
+
+/// Records a warning of the operation.
+pub fn record_warning(message: impl std::fmt::Display) {
+    current().warnings.push(message.to_string());
+}
+
+/// Returns what the operation has done, leaving an empty outcome for the next one.
+pub fn take() -> Outcome {
+    std::mem::take(&mut current())
+}
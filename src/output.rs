@@ -0,0 +1,109 @@
+//! Messages printed besides the results, prefixed by their kind and colored when printed to a
+//! terminal, unless disabled with the NO_COLOR environment variable or --no-color.
+
+use anstyle::{AnsiColor, Style};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Disables the colors of the messages, as done by --no-color.
+pub fn disable_color() {
+    COLOR_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Returns whether the messages printed to stderr are colored: it is a terminal and neither
+/// --no-color nor a non-empty NO_COLOR environment variable disable it.
+pub fn color() -> bool {
+    use std::io::IsTerminal;
+
+    color_enabled() && std::io::stderr().is_terminal()
+}
+
+fn color_enabled() -> bool {
+    !COLOR_DISABLED.load(Ordering::Relaxed)
+        && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+fn print(style: Style, prefix: &str, message: impl Display) {
+    if color() {
+        eprintln!("{style}{prefix}{style:#} {message}");
+    } else {
+        eprintln!("{prefix} {message}");
+    }
+}
+
+/// Prints what gg is doing, like the commands run and the transfers, unless run with -q.
+pub fn progress(message: impl Display) {
+    if !crate::logging::quiet() {
+        print(AnsiColor::Cyan.on_default(), "[gg]", message);
+    }
+}
+
+/// Prints something that went wrong without stopping the command.
+pub fn warning(message: impl Display) {
+    crate::outcome::record_warning(&message);
+    print(
+        AnsiColor::Yellow.on_default().bold(),
+        &crate::tr!("output-warning"),
+        message,
+    );
+}
+
+/// Prints the error that stopped the command, or one of its steps.
+pub fn error(message: impl Display) {
+    print(
+        AnsiColor::Red.on_default().bold(),
+        &crate::tr!("output-error"),
+        message,
+    );
+}
+
+/// Returns whether the results printed to stdout are colored.
+fn stdout_color() -> bool {
+    use std::io::IsTerminal;
+
+    color_enabled() && std::io::stdout().is_terminal()
+}
+
+/// Prints to stdout that the command finished what it was asked to, in green on a terminal.
+pub fn success(message: impl Display) {
+    if stdout_color() {
+        let style = AnsiColor::Green.on_default();
+        println!("{style}{message}{style:#}");
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Returns `text` with the occurrences of `query` highlighted when printed to a terminal, ignoring
+/// the ASCII case, or `None` if it has none.
+pub fn highlight(text: &str, query: &str) -> Option<String> {
+    if query.is_empty() {
+        return None;
+    }
+    let style = AnsiColor::Yellow.on_default().bold();
+    let color = stdout_color();
+    let mut highlighted = String::with_capacity(text.len());
+    let mut found = false;
+    let mut rest = text;
+    while let Some(start) = (0..=rest.len().saturating_sub(query.len())).find(|&i| {
+        rest.is_char_boundary(i)
+            && rest
+                .as_bytes()
+                .get(i..i + query.len())
+                .is_some_and(|s| s.eq_ignore_ascii_case(query.as_bytes()))
+    }) {
+        found = true;
+        let end = start + query.len();
+        highlighted.push_str(&rest[..start]);
+        if color {
+            highlighted.push_str(&format!("{style}{}{style:#}", &rest[start..end]));
+        } else {
+            highlighted.push_str(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+    highlighted.push_str(rest);
+    found.then_some(highlighted)
+}
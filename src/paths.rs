@@ -0,0 +1,273 @@
+//! Machine-independent game paths, stored relative to well-known directories like the Steam
+//! libraries or the home, so the game database can be shared between machines.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Steam installations, native, flatpak and macOS, relative to the home.
+const STEAM_ROOTS: [&str; 4] = [
+    ".local/share/Steam",
+    ".steam/steam",
+    ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+    "Library/Application Support/Steam",
+];
+
+/// Command that opens a file, a directory or a URL with the default application.
+pub const OPEN_COMMAND: &str = if cfg!(target_os = "macos") {
+    "open"
+} else {
+    "xdg-open"
+};
+
+/// Returns the directory of the user data of the applications: $XDG_DATA_HOME, or ~/.local/share,
+/// or ~/Library/Application Support on macOS.
+pub fn data_home() -> Option<PathBuf> {
+    Some(directories::BaseDirs::new()?.data_dir().to_path_buf())
+}
+
+/// Returns the directory of the user config of the applications: $XDG_CONFIG_HOME, or ~/.config,
+/// or ~/Library/Application Support on macOS.
+pub fn config_home() -> Option<PathBuf> {
+    Some(directories::BaseDirs::new()?.config_dir().to_path_buf())
+}
+
+/// Directories of gg, named "goodgame" inside the ones of the platform, like the XDG base
+/// directories on Linux.
+fn project_dirs() -> Option<directories::ProjectDirs> {
+    directories::ProjectDirs::from_path(PathBuf::from("goodgame"))
+}
+
+/// Returns the directory of the games database and the backups metadata of gg, like
+/// ~/.local/share/goodgame.
+pub fn data_dir() -> Option<PathBuf> {
+    Some(project_dirs()?.data_dir().to_path_buf())
+}
+
+/// Returns the directory of the config file of the user, like ~/.config/goodgame.
+pub fn config_dir() -> Option<PathBuf> {
+    Some(project_dirs()?.config_dir().to_path_buf())
+}
+
+/// Returns the directory of the temporary archives and files extracted from the backups, like
+/// ~/.cache/goodgame, or the temporary directory of the system if there is no home.
+pub fn cache_dir() -> PathBuf {
+    project_dirs().map_or_else(
+        || std::env::temp_dir().join(format!("goodgame-{}", crate::games::username())),
+        |dirs| dirs.cache_dir().to_path_buf(),
+    )
+}
+
+/// Returns the directory of the logs and the failures of gg, like ~/.local/state/goodgame, or the
+/// data directory on the platforms without one.
+pub fn state_dir() -> Option<PathBuf> {
+    let dirs = project_dirs()?;
+    Some(dirs.state_dir().unwrap_or(dirs.data_dir()).to_path_buf())
+}
+
+/// Returns the directory of the files that only matter while gg runs, like the locks of the games:
+/// $XDG_RUNTIME_DIR/goodgame, or a "run" directory in the state directory without one.
+pub fn runtime_dir() -> Option<PathBuf> {
+    let dirs = project_dirs()?;
+    match dirs.runtime_dir() {
+        Some(dir) => Some(dir.to_path_buf()),
+        None => Some(state_dir()?.join("run")),
+    }
+}
+
+/// Replaces the start of `path` with the variable of the longest directory that contains it,
+/// like `/home/user/.local/share/Steam/steamapps` with `$STEAM/steamapps`.
+///
+/// Returns `path` unchanged if no variable applies.
+pub fn contract(path: &Path) -> PathBuf {
+    static VARIABLES: OnceLock<Vec<(&str, PathBuf)>> = OnceLock::new();
+    VARIABLES
+        .get_or_init(variables)
+        .iter()
+        .filter_map(|(name, dir)| Some((dir.as_os_str().len(), name, path.strip_prefix(dir).ok()?)))
+        .max_by_key(|(len, ..)| *len)
+        .map_or_else(
+            || path.to_path_buf(),
+            |(_, name, rest)| Path::new(name).join(rest),
+        )
+}
+
+/// Replaces the variable at the start of `path` with its directory in this machine, and every
+/// environment variable with its value.
+///
+/// `$STEAM` is the first Steam library where the rest of the path exists, or the main one if
+/// none has it. `$WINEPREFIX` is only read from the databases of older versions, which stored the
+/// paths inside the wine prefix of gg itself relative to it.
+pub fn resolve(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    let resolved = text.strip_prefix("$STEAM").and_then(|rest| {
+        let rest = rest.strip_prefix('/').or(rest.is_empty().then_some(rest))?;
+        let libraries = steam_libraries();
+        libraries
+            .iter()
+            .map(|library| library.join(rest))
+            .find(|path| path.exists())
+            .or_else(|| Some(libraries.first()?.join(rest)))
+    });
+    resolved.unwrap_or_else(|| {
+        let wine = text
+            .strip_prefix("$WINEPREFIX")
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'));
+        match wine {
+            Some(rest) => PathBuf::from(format!(
+                "{}{}",
+                wine_prefix().display(),
+                crate::config::expand_env(rest)
+            )),
+            None => PathBuf::from(crate::config::expand_env(text)),
+        }
+    })
+}
+
+/// Returns the variables with their directory, canonicalized like the paths of the games.
+fn variables() -> Vec<(&'static str, PathBuf)> {
+    let mut variables = steam_libraries()
+        .iter()
+        .map(|library| ("$STEAM", library.clone()))
+        .collect::<Vec<_>>();
+    // Not $WINEPREFIX, as the prefix of gg is not the one of the game, which has it in its
+    // settings, and the prefixes in the home are already relative to $HOME
+    variables.extend(home().map(|home| ("$HOME", home)));
+    variables
+        .into_iter()
+        .map(|(name, dir)| (name, dir.canonicalize().unwrap_or(dir)))
+        .collect()
+}
+
+pub(crate) fn home() -> Option<PathBuf> {
+    Some(directories::BaseDirs::new()?.home_dir().to_path_buf())
+}
+
+fn wine_prefix() -> PathBuf {
+    std::env::var_os("WINEPREFIX")
+        .filter(|prefix| !prefix.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home().unwrap_or_default().join(".wine"))
+}
+
+/// Returns the wine prefixes that Proton created for the games of every Steam library.
+pub fn compatdata_prefixes() -> Vec<PathBuf> {
+    steam_libraries()
+        .iter()
+        .filter_map(|library| std::fs::read_dir(library.join("steamapps/compatdata")).ok())
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path().join("pfx"))
+        .filter(|pfx| pfx.is_dir())
+        .collect()
+}
+
+/// Returns the directory of a Steam library a game is installed in, named `install_dir`.
+pub fn steam_game_dir(install_dir: &str) -> Option<PathBuf> {
+    steam_libraries()
+        .iter()
+        .map(|library| library.join("steamapps/common").join(install_dir))
+        .find(|dir| dir.is_dir())
+}
+
+/// Returns the Steam libraries of the first Steam installation found, starting with the one of
+/// the installation itself.
+fn steam_libraries() -> &'static [PathBuf] {
+    static LIBRARIES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+    LIBRARIES.get_or_init(find_steam_libraries)
+}
+
+fn find_steam_libraries() -> Vec<PathBuf> {
+    let Some(root) = home().and_then(|home| {
+        STEAM_ROOTS
+            .iter()
+            .map(|root| home.join(root))
+            .find(|root| root.join("steamapps").is_dir())
+    }) else {
+        return Vec::new();
+    };
+    let folders =
+        std::fs::read_to_string(root.join("steamapps/libraryfolders.vdf")).unwrap_or_default();
+    let mut libraries = vec![root];
+    // Lines like: "path"		"/mnt/games/SteamLibrary"
+    for line in folders.lines() {
+        let Some(path) = line.trim().strip_prefix("\"path\"") else {
+            continue;
+        };
+        let path = PathBuf::from(path.trim().trim_matches('"'));
+        if !libraries.contains(&path) {
+            libraries.push(path);
+        }
+    }
+    libraries
+}
+
+/// Returns the directory of the Proton called `version`, like "Proton 9.0" or "GE-Proton9-20",
+/// installed in a Steam library or as a compatibility tool of Steam.
+pub fn proton(version: &str) -> Option<PathBuf> {
+    proton_dirs().into_iter().find(|dir| dir.ends_with(version))
+}
+
+/// Returns the names of the installed Proton versions.
+pub fn proton_versions() -> Vec<String> {
+    proton_dirs()
+        .iter()
+        .filter_map(|dir| Some(dir.file_name()?.to_string_lossy().into_owned()))
+        .collect()
+}
+
+/// Returns the directories with a `proton` script in the Steam libraries and in the compatibility
+/// tools of Steam.
+fn proton_dirs() -> Vec<PathBuf> {
+    let libraries = steam_libraries();
+    let tools = libraries
+        .first()
+        .map(|root| root.join("compatibilitytools.d"));
+    libraries
+        .iter()
+        .map(|library| library.join("steamapps/common"))
+        .chain(tools)
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| dir.join("proton").is_file())
+        .collect()
+}
+
+/// Returns the directory of the Steam installation, the first of the Steam libraries.
+pub fn steam_root() -> Option<&'static Path> {
+    steam_libraries().first().map(PathBuf::as_path)
+}
+
+/// Returns the Proton prefix Steam created for the game installed in `root`, like
+/// `$STEAM/steamapps/compatdata/APPID/pfx` for `$STEAM/steamapps/common/Game`.
+///
+/// The app id is taken from the manifest of the library whose "installdir" is the directory of the
+/// game.
+pub fn compatdata_prefix(root: &Path) -> Option<PathBuf> {
+    let (steamapps, installdir) = steam_libraries().iter().find_map(|library| {
+        let steamapps = library.canonicalize().ok()?.join("steamapps");
+        let rest = root.strip_prefix(steamapps.join("common")).ok()?;
+        Some((steamapps, rest.components().next()?.as_os_str().to_owned()))
+    })?;
+    // Lines like: "installdir"		"Celeste"
+    let app_id = std::fs::read_dir(&steamapps)
+        .ok()?
+        .flatten()
+        .find_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let app_id = name
+                .strip_prefix("appmanifest_")?
+                .strip_suffix(".acf")?
+                .to_owned();
+            let manifest = std::fs::read_to_string(entry.path()).ok()?;
+            manifest
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("\"installdir\""))
+                .any(|dir| dir.trim().trim_matches('"') == installdir)
+                .then_some(app_id)
+        })?;
+    Some(steamapps.join("compatdata").join(app_id).join("pfx")).filter(|pfx| pfx.is_dir())
+}
@@ -0,0 +1,160 @@
+//! Size-based rotation of the files that only grow, like the log and the history of the games.
+//!
+//! A file bigger than its maximum size is moved aside as a segment named after it and the time of
+//! the rotation, like "gg.log.1760700000.zst", compressed with zstd if gg is built with it. The
+//! segments are read back with the file, oldest first, as if they had never been split.
+
+use crate::compression::{self, Compressor};
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Suffix of the file moved aside by the versions of gg that kept a single old log.
+const OLD_SUFFIX: &str = "old";
+
+/// Moves the file at `path` aside as a new segment if it is bigger than `max_size` bytes,
+/// compressing it if `compress`, and removes the oldest segments past the newest `keep`.
+///
+/// Returns whether the file was rotated.
+pub fn rotate(path: &Path, max_size: u64, compress: bool, keep: usize) -> Result<bool> {
+    if !path.metadata().is_ok_and(|m| m.len() > max_size) {
+        return Ok(false);
+    }
+    let mut time = crate::games::unix_time();
+    let segment = loop {
+        let segment = segment_path(path, time);
+        if !segment.exists() && !compressed(&segment).exists() {
+            break segment;
+        }
+        time += 1;
+    };
+    std::fs::rename(path, &segment)
+        .context_with(|| format!("Could not move {} aside", path.display()))?;
+    if compress && cfg!(feature = "zstd") {
+        compress_segment(&segment)?;
+    }
+    let segments = segments(path);
+    for old in &segments[..segments.len().saturating_sub(keep)] {
+        std::fs::remove_file(old).context_with(|| format!("Could not remove {}", old.display()))?;
+    }
+    Ok(true)
+}
+
+/// Returns the segments of the file at `path` that still exist, oldest first, without the file.
+pub fn segments(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let mut segments = dir
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let rest = file_name.to_str()?.strip_prefix(&prefix)?;
+            let time = match rest.strip_suffix(&format!(".{}", compression::ZSTD_EXTENSION)) {
+                Some(time) => time,
+                None => rest,
+            };
+            let time = match time {
+                OLD_SUFFIX => 0,
+                time => time.parse::<u64>().ok()?,
+            };
+            Some((time, entry.path()))
+        })
+        .collect::<Vec<_>>();
+    segments.sort_unstable();
+    segments.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Returns the contents of the file at `path` and of its segments, oldest first, decompressing
+/// the compressed ones.
+///
+/// A missing file is read as empty, as it is created again after every rotation.
+pub fn read(path: &Path) -> Result<String> {
+    let mut contents = String::new();
+    for segment in segments(path).iter().map(PathBuf::as_path).chain([path]) {
+        if segment.exists() {
+            contents.push_str(&read_segment(segment)?);
+        }
+    }
+    Ok(contents)
+}
+
+/// Returns the contents of the file or segment at `path`, decompressing it if it is compressed.
+pub fn read_segment(path: &Path) -> Result<String> {
+    let file =
+        std::fs::File::open(path).context_with(|| format!("Could not open {}", path.display()))?;
+    let mut contents = String::new();
+    if path
+        .extension()
+        .is_some_and(|e| e == compression::ZSTD_EXTENSION)
+    {
+        compression::decompress(file, None)?.read_to_string(&mut contents)
+    } else {
+        std::io::BufReader::new(file).read_to_string(&mut contents)
+    }
+    .context_with(|| format!("Could not read {}", path.display()))?;
+    Ok(contents)
+}
+
+/// Path of the uncompressed segment of `path` rotated at `time`.
+fn segment_path(path: &Path, time: u64) -> PathBuf {
+    let mut segment = path.as_os_str().to_owned();
+    segment.push(format!(".{time}"));
+    PathBuf::from(segment)
+}
+
+/// Path of the segment at `segment` once compressed.
+fn compressed(segment: &Path) -> PathBuf {
+    let mut compressed = segment.as_os_str().to_owned();
+    compressed.push(format!(".{}", compression::ZSTD_EXTENSION));
+    PathBuf::from(compressed)
+}
+
+/// Replaces the segment at `segment` with its compressed version.
+fn compress_segment(segment: &Path) -> Result<()> {
+    let output = compressed(segment);
+    let mut input = std::fs::File::open(segment)
+        .context_with(|| format!("Could not open {}", segment.display()))?;
+    let file = std::fs::File::create(&output)
+        .context_with(|| format!("Could not create {}", output.display()))?;
+    let mut compressor = Compressor::zstd(file)?;
+    let compressed = std::io::copy(&mut input, &mut compressor).and_then(|_| compressor.finish());
+    if let Err(e) = compressed {
+        let _ = std::fs::remove_file(&output);
+        return Err(e).context_with(|| format!("Could not compress {}", segment.display()))?;
+    }
+    std::fs::remove_file(segment)
+        .context_with(|| format!("Could not remove {}", segment.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_rotated_segments_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gg.log");
+        std::fs::write(path.with_extension("log.old"), "oldest\n").unwrap();
+        for line in ["first\n", "second\n", "third\n"] {
+            std::fs::write(&path, line).unwrap();
+            assert!(rotate(&path, 0, true, 3).unwrap());
+        }
+        std::fs::write(&path, "current\n").unwrap();
+        assert!(!rotate(&path, 1024, true, 3).unwrap());
+
+        // The oldest ones are removed past the newest 3
+        assert_eq!(segments(&path).len(), 3);
+        assert_eq!(read(&path).unwrap(), "first\nsecond\nthird\ncurrent\n");
+        let compressed = segments(&path)
+            .iter()
+            .all(|s| s.extension().is_some_and(|e| e == "zst"));
+        assert_eq!(compressed, cfg!(feature = "zstd"));
+    }
+}
@@ -0,0 +1,461 @@
+//! The JSON-RPC 2.0 API of 'gg serve', spoken over a Unix socket with one JSON object per line,
+//! so frontends can list, back up, restore and run the games through a single gg process.
+//!
+//! A request like `{"jsonrpc": "2.0", "id": 1, "method": "backup", "params": {"game": "Celeste"}}`
+//! is answered with `{"jsonrpc": "2.0", "id": 1, "result": {...}}`, or with an `error` holding
+//! its `code`, `message` and, for the errors of [`Error`](crate::error::Error), their kind in
+//! `data`.
+
+use crate::games::{Game, Games};
+use rootcause::Result;
+use rootcause::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// The request is not valid JSON.
+pub const PARSE_ERROR: i64 = -32700;
+/// The request is JSON, but not a JSON-RPC request.
+pub const INVALID_REQUEST: i64 = -32600;
+/// There is no method with the name.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+/// The params are missing some field or have the wrong type.
+pub const INVALID_PARAMS: i64 = -32602;
+/// The operation itself failed, like a backup of a game that does not exist.
+pub const FAILED: i64 = -32000;
+
+/// Call of a method, answered unless it has no `id`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+/// Answer to a [`Request`], with its `result` or its `error`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// Result of a method, its JSON `result` by default.
+pub type RpcResult<T = Value> = std::result::Result<T, RpcError>;
+
+/// Why a [`Request`] failed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcError {
+    /// One of the codes of this module, like [`FAILED`].
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> RpcError {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (code {})", self.message, self.code)
+    }
+}
+
+/// Reads the `params` of a request as `T`, failing with [`INVALID_PARAMS`] if they don't fit.
+pub fn params<T: serde::de::DeserializeOwned>(params: Value) -> RpcResult<T> {
+    // Methods without required params can be called without them
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// Returns the socket 'gg serve' listens on by default, gg.sock in the
+/// [runtime directory](crate::paths::runtime_dir).
+pub fn socket_path() -> Option<PathBuf> {
+    Some(crate::paths::runtime_dir()?.join("gg.sock"))
+}
+
+/// Listens on the socket at `path`, replacing it if no other process is, and only accessible by
+/// the current user.
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            bail!("Another gg serve is listening on {}", path.display())
+        }
+        // Left behind by a gg serve that was stopped
+        std::fs::remove_file(path)
+            .context_with(|| format!("Could not remove the old socket {}", path.display()))?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+    }
+    let listener = UnixListener::bind(path)
+        .context_with(|| format!("Could not listen on {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .context_with(|| format!("Could not restrict the access to {}", path.display()))?;
+    Ok(listener)
+}
+
+/// Seconds since the Unix epoch when this process started serving, for the "status" method.
+static STARTED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+
+/// Answers the requests of every client of `listener` with `handle`, called with the method and
+/// the params of each one, serving each client in a thread of its own until it disconnects.
+pub fn serve<F>(listener: UnixListener, handle: F) -> Result<()>
+where
+    F: Fn(&str, Value) -> RpcResult + Send + Sync + 'static,
+{
+    STARTED.get_or_init(crate::games::unix_time);
+    let handle = std::sync::Arc::new(handle);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("Could not accept a client: {e}");
+                continue;
+            }
+        };
+        let handle = handle.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = serve_client(stream, &*handle) {
+                tracing::warn!("Stopped serving a client: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+fn serve_client(
+    stream: UnixStream,
+    handle: &dyn Fn(&str, Value) -> RpcResult,
+) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some(response) = answer(&line, handle) else {
+            continue;
+        };
+        let mut response = serde_json::to_string(&response)?;
+        response.push('\n');
+        writer.write_all(response.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// Returns the response to the request in `line`, or `None` if it is a notification.
+fn answer(line: &str, handle: &dyn Fn(&str, Value) -> RpcResult) -> Option<Response> {
+    let response = |id, outcome: RpcResult| {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Response {
+            jsonrpc: String::from("2.0"),
+            id,
+            result,
+            error,
+        }
+    };
+    let value = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(response(
+                Value::Null,
+                Err(RpcError::new(PARSE_ERROR, e.to_string())),
+            ));
+        }
+    };
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let request = match serde_json::from_value::<Request>(value) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(_) => {
+            let error = RpcError::new(INVALID_REQUEST, "Only JSON-RPC 2.0 is supported");
+            return Some(response(id, Err(error)));
+        }
+        Err(e) => {
+            return Some(response(
+                id,
+                Err(RpcError::new(INVALID_REQUEST, e.to_string())),
+            ));
+        }
+    };
+    tracing::info!("Serving {}", request.method);
+    let outcome = handle(&request.method, request.params);
+    if let Err(error) = &outcome {
+        tracing::warn!("{} failed: {error}", request.method);
+    }
+    Some(response(request.id?, outcome))
+}
+
+/// Calls `method` with `params` on the gg serve listening on the socket at `path`, returning its
+/// result.
+pub fn call(path: &Path, method: &str, params: Value) -> Result<Value> {
+    let stream = UnixStream::connect(path)
+        .context_with(|| format!("Could not connect to gg serve at {}", path.display()))?;
+    let request = Request {
+        jsonrpc: String::from("2.0"),
+        id: Some(Value::from(1)),
+        method: method.to_owned(),
+        params,
+    };
+    let mut request = serde_json::to_string(&request).context("Could not serialize the request")?;
+    request.push('\n');
+    (&stream)
+        .write_all(request.as_bytes())
+        .context("Could not send the request")?;
+    let mut line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut line)
+        .context("Could not read the response")?;
+    let response = serde_json::from_str::<Response>(&line)
+        .context_with(|| format!("Invalid response of gg serve: {line:?}"))?;
+    match (response.result, response.error) {
+        (_, Some(error)) => bail!("{method} failed: {error}"),
+        (Some(result), None) => Ok(result),
+        (None, None) => Ok(Value::Null),
+    }
+}
+
+/// Answers the request of 'gg serve' calling `method` with `params` on `games`.
+pub fn handle(games: &Games, method: &str, params: Value) -> RpcResult {
+    #[derive(Deserialize)]
+    struct List {
+        #[serde(default)]
+        archived: bool,
+    }
+    #[derive(Deserialize)]
+    struct Backup {
+        game: String,
+        desc: Option<String>,
+        message: Option<String>,
+        player: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Restore {
+        game: String,
+        backup: String,
+        slot: Option<String>,
+        player: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct Run {
+        game: String,
+        profile: Option<String>,
+        player: Option<String>,
+        #[serde(default)]
+        args: Vec<String>,
+    }
+
+    let cloud = games.cloud_mode(None).map_err(failure)?;
+    let game = |name: &str, player: Option<&str>| {
+        games
+            .get_by_name_or_alias(name)
+            .and_then(|game| game.with_player(player))
+            .map_err(failure)
+    };
+    match method {
+        "list" => {
+            let params = self::params::<List>(params)?;
+            let list = games
+                .games()
+                .iter()
+                .filter(|game| params.archived || !game.is_archived())
+                .map(game_json)
+                .collect::<Vec<_>>();
+            Ok(Value::from(list))
+        }
+        "backup" => {
+            let params = self::params::<Backup>(params)?;
+            let game = game(&params.game, params.player.as_deref())?;
+            let path = games
+                .backup(
+                    &game,
+                    params.desc.as_deref(),
+                    params.message.as_deref(),
+                    None,
+                    cloud,
+                )
+                .map_err(failure)?;
+            Ok(serde_json::json!({ "game": game.name(), "backup": path }))
+        }
+        "restore" => {
+            let params = self::params::<Restore>(params)?;
+            let game = game(&params.game, params.player.as_deref())?;
+            games
+                .restore(&game, &params.backup, params.slot.as_deref(), cloud, true)
+                .map_err(failure)?;
+            Ok(serde_json::json!({ "backup": params.backup }))
+        }
+        "run" => {
+            let params = self::params::<Run>(params)?;
+            let game = game(&params.game, params.player.as_deref())?;
+            let mut args = vec![String::from("run"), game.name().to_owned()];
+            if let Some(profile) = params.profile {
+                args.extend([String::from("--profile"), profile]);
+            }
+            if let Some(player) = game.player() {
+                args.extend([String::from("--player"), player.to_owned()]);
+            }
+            args.push(String::from("--"));
+            args.extend(params.args);
+            let (pid, log) = crate::runner::spawn_gg(&game, args).map_err(failure)?;
+            Ok(serde_json::json!({ "game": game.name(), "pid": pid, "log": log }))
+        }
+        "status" => {
+            let pid = std::process::id();
+            let activities = crate::status::activities()
+                .into_iter()
+                .filter(|activity| activity.pid == pid)
+                .collect::<Vec<_>>();
+            Ok(serde_json::json!({
+                "pid": pid,
+                "started": STARTED.get(),
+                "activities": activities,
+            }))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("There is no method {method:?}, only list, backup, restore, run and status"),
+        )),
+    }
+}
+
+/// Returns the error of the failed operation, with the kind of its error.
+pub fn failure(report: rootcause::Report) -> RpcError {
+    let mut error = RpcError::new(FAILED, report.format_current_context().to_string());
+    error.data = serde_json::to_value(crate::error::kind(&report))
+        .ok()
+        .filter(Value::is_object);
+    error
+}
+
+/// Returns the game as listed by the "list" method and 'gg list --json'.
+pub fn game_json(game: &Game) -> Value {
+    serde_json::json!({
+        "name": game.name(),
+        "root": game.root(),
+        "save_location": game.save_location(),
+        "executable": game.executable(),
+        "tags": game.tags(),
+        "aliases": game.aliases(),
+        "notes": game.notes(),
+        "players": game.players().iter().map(|player| &player.name).collect::<Vec<_>>(),
+        "archived": game.is_archived(),
+        "playtime": game.playtime(),
+        "metadata": crate::metadata::load(game.name()).ok().flatten().map(|metadata| {
+            serde_json::json!({
+                "title": metadata.title,
+                "release_year": metadata.release_year(),
+                "cover": metadata.cover_path(),
+                "icon": metadata.icon_path(),
+            })
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo(method: &str, params: Value) -> RpcResult {
+        match method {
+            "echo" => Ok(params),
+            _ => Err(RpcError::new(METHOD_NOT_FOUND, method)),
+        }
+    }
+
+    fn answer_json(line: &str) -> Value {
+        serde_json::to_value(answer(line, &echo).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn answers_the_requests() {
+        assert_eq!(
+            answer_json(r#"{"jsonrpc": "2.0", "id": 7, "method": "echo", "params": [1]}"#),
+            serde_json::json!({ "jsonrpc": "2.0", "id": 7, "result": [1] })
+        );
+        let error = |line| answer_json(line)["error"]["code"].clone();
+        assert_eq!(error("{"), PARSE_ERROR);
+        assert_eq!(error(r#"{"id": 1, "method": "echo"}"#), INVALID_REQUEST);
+        assert_eq!(
+            error(r#"{"jsonrpc": "1.0", "id": 1, "method": "echo"}"#),
+            INVALID_REQUEST
+        );
+        assert_eq!(
+            error(r#"{"jsonrpc": "2.0", "id": 1, "method": "nope"}"#),
+            METHOD_NOT_FOUND
+        );
+        // Notifications are not answered
+        assert!(answer(r#"{"jsonrpc": "2.0", "method": "echo"}"#, &echo).is_none());
+    }
+
+    #[test]
+    fn handles_the_methods_of_gg() {
+        let mut games = Games::builder().in_memory().load().unwrap();
+        games.push(Game::new(
+            String::from("Hades"),
+            PathBuf::from("/games/hades"),
+            PathBuf::from("/games/hades/saves"),
+            None,
+            None,
+            None::<[(String, String); 0]>,
+            None,
+        ));
+        let list = handle(&games, "list", Value::Null).unwrap();
+        assert_eq!(list[0]["name"], "Hades");
+        assert_eq!(list[0]["save_location"], "/games/hades/saves");
+
+        // Failures keep the kind of their error
+        let params = serde_json::json!({ "game": "Celeste" });
+        let error = handle(&games, "backup", params).unwrap_err();
+        assert_eq!(error.code, FAILED);
+        assert_eq!(error.data.unwrap()["kind"], "game-not-found");
+
+        let error = handle(&games, "backup", serde_json::json!({})).unwrap_err();
+        assert_eq!(error.code, INVALID_PARAMS);
+        let status = handle(&games, "status", Value::Null).unwrap();
+        assert_eq!(status["pid"], std::process::id());
+        assert!(status["activities"].is_array());
+        let error = handle(&games, "delete", Value::Null).unwrap_err();
+        assert_eq!(error.code, METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn calls_over_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gg.sock");
+        let listener = bind(&path).unwrap();
+        // Another server can't take the socket while this one listens
+        assert!(bind(&path).is_err());
+        std::thread::spawn(move || serve(listener, echo));
+
+        let params = serde_json::json!({ "game": "Celeste" });
+        assert_eq!(call(&path, "echo", params.clone()).unwrap(), params);
+        assert!(call(&path, "nope", Value::Null).is_err());
+    }
+}
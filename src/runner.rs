@@ -0,0 +1,319 @@
+use rootcause::Result;
+use rootcause::option_ext::OptionExt;
+use rootcause::prelude::*;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+/// Executes the processes created from the configured commands.
+///
+/// [`Games`](crate::games::Games) sends every external command through its runner, so the way they
+/// are executed (or not executed at all) can be swapped from a single place.
+pub trait CommandRunner: std::fmt::Debug + Send + Sync {
+    /// Runs `cmd` inside `cwd` and waits for it to finish.
+    ///
+    /// `desc` is a short human readable description of the command, like "cloud push".
+    fn run(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<()>;
+
+    /// Runs `cmd` like [`run`](Self::run), but always leaving it the terminal of the current
+    /// process, as games and editors need it.
+    fn run_interactive(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<()> {
+        self.run(cmd, desc, cwd)
+    }
+
+    /// Runs `cmd` inside `cwd` and returns what it printed to stdout.
+    fn output(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<Vec<u8>>;
+}
+
+/// Runs the commands as child processes of the current one.
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+impl SystemRunner {
+    /// Runs `cmd` inside `cwd`, copying what it prints to the log if `log_output` and the log
+    /// records debug messages.
+    fn run_child(&self, mut cmd: Command, desc: &str, cwd: &Path, log_output: bool) -> Result<()> {
+        let cmd_description = describe(&cmd);
+        crate::output::progress(format_args!("Running {desc}: {cmd_description:?}"));
+        tracing::info!("Running {desc} in {}: {}", cwd.display(), shell_line(&cmd));
+
+        cmd.current_dir(cwd);
+        let out = if log_output && tracing::enabled!(tracing::Level::DEBUG) {
+            status_logged(cmd, desc)
+        } else {
+            cmd.status()
+        }
+        .context_with(|| format!("Failed to execute command '{desc}': {cmd_description:?}",))?;
+        if !out.success() {
+            tracing::warn!("{desc} failed with {out}");
+            bail!(
+                "Command '{desc}' exited with code {}: {cmd_description:?}",
+                out.code().unwrap_or(0),
+            )
+        }
+
+        Ok(())
+    }
+}
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<()> {
+        self.run_child(cmd, desc, cwd, true)
+    }
+
+    // Capturing the output would take the terminal away from the command
+    fn run_interactive(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<()> {
+        self.run_child(cmd, desc, cwd, false)
+    }
+
+    fn output(&self, mut cmd: Command, desc: &str, cwd: &Path) -> Result<Vec<u8>> {
+        let cmd_description = describe(&cmd);
+        crate::output::progress(format_args!("Running {desc}: {cmd_description:?}"));
+        tracing::info!("Running {desc} in {}: {}", cwd.display(), shell_line(&cmd));
+
+        let out = cmd
+            .current_dir(cwd)
+            .stderr(std::process::Stdio::inherit())
+            .output()
+            .context_with(|| format!("Failed to execute command '{desc}': {cmd_description:?}",))?;
+        if !out.status.success() {
+            tracing::warn!("{desc} failed with {}", out.status);
+            bail!(
+                "Command '{desc}' exited with code {}: {cmd_description:?}",
+                out.status.code().unwrap_or(0),
+            )
+        }
+        tracing::trace!("{desc} printed: {}", String::from_utf8_lossy(&out.stdout));
+        Ok(out.stdout)
+    }
+}
+
+/// Runs `cmd` like [`Command::status`], copying what it prints both to the terminal and to the
+/// log.
+fn status_logged(mut cmd: Command, desc: &str) -> std::io::Result<std::process::ExitStatus> {
+    let mut child = cmd
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    std::thread::scope(|s| {
+        if let Some(stdout) = stdout {
+            s.spawn(|| forward(stdout, std::io::stdout(), desc));
+        }
+        if let Some(stderr) = stderr {
+            s.spawn(|| forward(stderr, std::io::stderr(), desc));
+        }
+    });
+    child.wait()
+}
+
+/// Writes the lines read `from` a command to `to`, recording them in the log too.
+fn forward(from: impl std::io::Read, mut to: impl std::io::Write, desc: &str) {
+    use std::io::BufRead;
+
+    for line in std::io::BufReader::new(from)
+        .split(b'\n')
+        .map_while(std::io::Result::ok)
+    {
+        let _ = to.write_all(&line).and_then(|()| to.write_all(b"\n"));
+        tracing::debug!(
+            target: crate::logging::COMMAND_OUTPUT,
+            "{desc}: {}",
+            String::from_utf8_lossy(&line)
+        );
+    }
+}
+
+/// Records the commands instead of running them.
+///
+/// Clones share the same record, so a clone can be given to [`Games`](crate::games::Games) and
+/// inspected afterwards.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingRunner {
+    commands: Arc<Mutex<Vec<RecordedCommand>>>,
+}
+
+/// A command received by a [`RecordingRunner`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedCommand {
+    pub desc: String,
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    pub envs: Vec<(OsString, Option<OsString>)>,
+    pub cwd: PathBuf,
+}
+
+impl RecordedCommand {
+    /// Formats the command as the shell line that would run it, like [`shell_line`].
+    pub fn shell_line(&self) -> String {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args);
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => cmd.env(key, value),
+                None => cmd.env_remove(key),
+            };
+        }
+        shell_line(&cmd)
+    }
+}
+
+impl RecordingRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the commands recorded so far, in the order they were run.
+    pub fn commands(&self) -> Vec<RecordedCommand> {
+        self.commands.lock().unwrap().clone()
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<()> {
+        self.commands.lock().unwrap().push(RecordedCommand {
+            desc: desc.to_owned(),
+            program: cmd.get_program().to_owned(),
+            args: cmd.get_args().map(ToOwned::to_owned).collect(),
+            envs: cmd
+                .get_envs()
+                .map(|(k, v)| (k.to_owned(), v.map(ToOwned::to_owned)))
+                .collect(),
+            cwd: cwd.to_path_buf(),
+        });
+        Ok(())
+    }
+
+    /// Records the command, returning an empty output.
+    fn output(&self, cmd: Command, desc: &str, cwd: &Path) -> Result<Vec<u8>> {
+        self.run(cmd, desc, cwd)?;
+        Ok(Vec::new())
+    }
+}
+
+/// Formats `cmd` as the shell line that would run it, with its environment variables and every
+/// argument quoted when needed.
+pub fn shell_line(cmd: &Command) -> String {
+    let envs = cmd.get_envs().filter_map(|(k, v)| {
+        Some(format!(
+            "{}={}",
+            k.to_string_lossy(),
+            shell_quote(&v?.to_string_lossy())
+        ))
+    });
+    let args = std::iter::once(cmd.get_program())
+        .chain(cmd.get_args())
+        .map(|arg| shell_quote(&arg.to_string_lossy()));
+    envs.chain(args).collect::<Vec<_>>().join(" ")
+}
+
+/// Encloses `s` in single quotes if the shell would split or expand it.
+pub fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        return s.to_owned();
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Joins `args` into a shell line, quoting the ones the shell would split or expand.
+pub fn shell_join(args: &[impl AsRef<str>]) -> String {
+    args.iter()
+        .map(|arg| shell_quote(arg.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Joins the arguments of `cmd` for displaying it to the user.
+pub fn describe(cmd: &Command) -> OsString {
+    cmd.get_args().fold(OsString::new(), |mut acc, c| {
+        acc.push(" ");
+        acc.push(c);
+        acc
+    })
+}
+
+/// Starts gg with `args` in the background to run `game`, writing its output to a log in the
+/// state directory, returning its PID and the log.
+///
+/// It is the executable of the current process when that is gg, or else the gg of the `PATH`,
+/// as programs using this library are not.
+pub fn spawn_gg(
+    game: &crate::games::Game,
+    args: impl IntoIterator<Item = impl AsRef<std::ffi::OsStr>>,
+) -> Result<(u32, PathBuf)> {
+    use std::os::unix::process::CommandExt;
+
+    let dir = crate::paths::state_dir().context("Could not obtain state directory")?;
+    std::fs::create_dir_all(&dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    let log = dir.join(format!("run-{}.log", slug::slugify(game.name())));
+    let output = std::fs::File::create(&log)
+        .context_with(|| format!("Could not create {}", log.display()))?;
+    let gg = std::env::current_exe()
+        .ok()
+        .filter(|exe| exe.file_name().is_some_and(|name| name == "gg"))
+        .unwrap_or_else(|| PathBuf::from("gg"));
+    let child = Command::new(gg)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output)
+        // Out of the process group of the terminal, so closing it doesn't stop the watcher
+        .process_group(0)
+        .spawn()
+        .context("Could not start gg in the background")?;
+    Ok((child.id(), log))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_for_the_shell() {
+        let cases = [
+            ("plain", "plain"),
+            ("a/b-c_d.e=f:g,h+i@j%k", "a/b-c_d.e=f:g,h+i@j%k"),
+            ("", "''"),
+            ("two words", "'two words'"),
+            ("$(id)", "'$(id)'"),
+            ("It's", r"'It'\''s'"),
+            ("a;b|c&d", "'a;b|c&d'"),
+            ("~", "'~'"),
+            ("ñ", "'ñ'"),
+        ];
+        for (arg, quoted) in cases {
+            assert_eq!(shell_quote(arg), quoted, "{arg}");
+        }
+        assert_eq!(shell_join(&["cp", "a b", "c"]), "cp 'a b' c");
+    }
+
+    #[test]
+    fn records_the_commands() {
+        let runner = RecordingRunner::new();
+        let mut cmd = Command::new("rclone");
+        cmd.args(["copy", "My Saves", "remote:gg"])
+            .env("RCLONE_X", "1 2");
+        runner
+            .clone()
+            .run(cmd, "cloud push", Path::new("/games"))
+            .unwrap();
+        let output = runner
+            .output(Command::new("true"), "cloud list", Path::new("/"))
+            .unwrap();
+        assert!(output.is_empty());
+
+        let commands = runner.commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].desc, "cloud push");
+        assert_eq!(commands[0].cwd, Path::new("/games"));
+        assert_eq!(
+            commands[0].shell_line(),
+            "RCLONE_X='1 2' rclone copy 'My Saves' remote:gg"
+        );
+        assert_eq!(commands[1].desc, "cloud list");
+    }
+}
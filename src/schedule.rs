@@ -0,0 +1,155 @@
+//! Schedules of the backups made and repacked by 'gg watch' at set times, like every day, and the
+//! last time each game was backed up or repacked by them.
+//!
+//! The last runs are kept in the state directory, so a time missed while the machine was off is
+//! caught up once 'gg watch' runs again.
+
+use rootcause::Result;
+use rootcause::option_ext::OptionExt;
+use rootcause::prelude::*;
+use std::{collections::BTreeMap, fmt, path::PathBuf, str::FromStr};
+
+/// Examples of schedules, shown in the errors.
+pub const SCHEDULE_EXAMPLES: &[&str] = &["hourly", "daily", "weekly", "0 20 * * *"];
+
+/// Last runs of the schedules, in the state directory.
+const LAST_RUNS_FILE: &str = "schedule.yaml";
+/// Last runs of the schedules of 'gg gc', in the state directory.
+const LAST_GC_RUNS_FILE: &str = "gc-schedule.yaml";
+
+/// When a game is backed up: "hourly", "daily", "weekly", "monthly" or a cron expression with
+/// the minute, hour, day of the month, month and day of the week, like "0 20 * * 1-5".
+///
+/// The times are in the local time zone of the machine.
+#[derive(Clone, Debug)]
+pub struct Schedule {
+    expression: String,
+    #[cfg(feature = "watch")]
+    cron: croner::Cron,
+}
+
+impl Schedule {
+    /// Returns the last time the schedule was due, at or before `now`.
+    #[cfg(feature = "watch")]
+    pub fn last_due(&self, now: std::time::SystemTime) -> Option<std::time::SystemTime> {
+        let now = chrono::DateTime::<chrono::Local>::from(now);
+        let due = self.cron.find_previous_occurrence(&now, true).ok()?;
+        Some(due.into())
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = crate::units::ParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let expression = s.trim();
+        let pattern = match expression {
+            "hourly" | "daily" | "weekly" | "monthly" | "yearly" => format!("@{expression}"),
+            _ => expression.to_owned(),
+        };
+        #[cfg(feature = "watch")]
+        let cron = croner::Cron::from_str(&pattern).map_err(|_| {
+            crate::units::ParseError::new("schedule", expression, SCHEDULE_EXAMPLES)
+        })?;
+        #[cfg(not(feature = "watch"))]
+        if pattern.is_empty() {
+            return Err(crate::units::ParseError::new(
+                "schedule",
+                expression,
+                SCHEDULE_EXAMPLES,
+            ));
+        }
+        Ok(Self {
+            expression: expression.to_owned(),
+            #[cfg(feature = "watch")]
+            cron,
+        })
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Schedule {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for Schedule {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl schemars::JsonSchema for Schedule {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Schedule".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "\"hourly\", \"daily\", \"weekly\", \"monthly\" or a cron expression",
+            "type": "string",
+            "examples": SCHEDULE_EXAMPLES,
+        })
+    }
+}
+
+fn runs_path(file: &str) -> Option<PathBuf> {
+    Some(crate::paths::state_dir()?.join(file))
+}
+
+fn read_runs(file: &str) -> BTreeMap<String, u64> {
+    runs_path(file)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|runs| serde_saphyr::from_str(&runs).ok())
+        .unwrap_or_default()
+}
+
+fn write_run(file: &str, game: &str, time: u64) -> Result<()> {
+    let path = runs_path(file).context("Could not obtain the state directory")?;
+    let mut runs = read_runs(file);
+    runs.insert(game.to_owned(), time);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .context_with(|| format!("Could not create {}", dir.display()))?;
+    }
+    let contents = serde_saphyr::to_string(&runs).context("Could not serialize the last runs")?;
+    std::fs::write(&path, contents)
+        .context_with(|| format!("Could not write {}", path.display()))?;
+    Ok(())
+}
+
+/// Returns when the schedule of every game last backed it up, in seconds since the Unix epoch.
+pub fn last_runs() -> BTreeMap<String, u64> {
+    read_runs(LAST_RUNS_FILE)
+}
+
+/// Records that the schedule of the game called `game` backed it up at `time`, in seconds since
+/// the Unix epoch.
+pub fn record_run(game: &str, time: u64) -> Result<()> {
+    write_run(LAST_RUNS_FILE, game, time)
+}
+
+/// Returns when the 'gc.schedule' of every game last repacked its backups, in seconds since the
+/// Unix epoch.
+pub fn last_gc_runs() -> BTreeMap<String, u64> {
+    read_runs(LAST_GC_RUNS_FILE)
+}
+
+/// Records that the 'gc.schedule' of the game called `game` repacked its backups at `time`, in
+/// seconds since the Unix epoch.
+pub fn record_gc_run(game: &str, time: u64) -> Result<()> {
+    write_run(LAST_GC_RUNS_FILE, game, time)
+}
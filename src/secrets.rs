@@ -0,0 +1,119 @@
+//! Credentials of the config, like the WebDAV password, which can be kept out of the config files
+//! by reading them from a command (e.g. "pass show gg/webdav") or from the system keyring.
+
+use crate::config::Shell;
+use rootcause::Result;
+use rootcause::prelude::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Service of the entries of the system keyring used by gg.
+#[cfg(feature = "keyring")]
+const KEYRING_SERVICE: &str = "goodgame";
+
+#[cfg(not(feature = "keyring"))]
+const KEYRING_FEATURE_MISSING: &str =
+    "The system keyring is used, but gg was built without the \"keyring\" feature";
+
+/// A credential, written in the config, printed by a command or stored in the system keyring.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Secret {
+    /// The credential itself.
+    Plain(String),
+    /// Command run with the configured shell that prints the credential, like
+    /// "pass show gg/webdav".
+    Command { command: String },
+    /// Name of the entry of the system keyring with the credential, stored with 'gg secret set'.
+    ///
+    /// Requires the "keyring" feature.
+    Keyring { keyring: String },
+}
+
+impl Secret {
+    /// Returns the credential, running its command with `shell` or reading it from the keyring.
+    ///
+    /// The output of a command is only read once, so it can ask for a password without
+    /// repeating the question for every game.
+    pub fn reveal(&self, shell: &Shell) -> Result<String> {
+        static OUTPUTS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+        match self {
+            Secret::Plain(secret) => Ok(secret.clone()),
+            Secret::Command { command } => {
+                let mut outputs = OUTPUTS.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(secret) = outputs.get(command) {
+                    return Ok(secret.clone());
+                }
+                let secret = run(command, shell)?;
+                outputs.insert(command.clone(), secret.clone());
+                Ok(secret)
+            }
+            Secret::Keyring { keyring } => get(keyring),
+        }
+    }
+}
+
+/// Runs `command` with `shell`, returning the first line of its output.
+fn run(command: &str, shell: &Shell) -> Result<String> {
+    let args = shell.args(command.to_owned())?;
+    // stdin and stderr are inherited, so the command can ask for a password
+    let output = std::process::Command::new(&args[0])
+        .args(&args[1..])
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .context_with(|| format!("Could not run the secret command {command:?}"))?;
+    if !output.status.success() {
+        bail!(
+            "The secret command {command:?} failed with {}",
+            output.status
+        )
+    }
+    let output = String::from_utf8(output.stdout)
+        .context_with(|| format!("The secret command {command:?} printed invalid UTF-8"))?;
+    // Like pass, the secret is the first line and the rest are notes
+    match output.lines().next() {
+        Some(secret) if !secret.is_empty() => Ok(secret.to_owned()),
+        _ => bail!("The secret command {command:?} printed nothing"),
+    }
+}
+
+/// Returns the secret stored in the system keyring as `name`.
+#[cfg(feature = "keyring")]
+pub fn get(name: &str) -> Result<String> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, name)
+        .and_then(|entry| entry.get_password())
+        .context_with(|| format!("Could not read the secret {name:?} from the system keyring"))?)
+}
+
+/// Stores `secret` in the system keyring as `name`, replacing the previous one.
+#[cfg(feature = "keyring")]
+pub fn set(name: &str, secret: &str) -> Result<()> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, name)
+        .and_then(|entry| entry.set_password(secret))
+        .context_with(|| format!("Could not store the secret {name:?} in the system keyring"))?)
+}
+
+/// Removes the secret stored in the system keyring as `name`.
+#[cfg(feature = "keyring")]
+pub fn remove(name: &str) -> Result<()> {
+    Ok(keyring::Entry::new(KEYRING_SERVICE, name)
+        .and_then(|entry| entry.delete_credential())
+        .context_with(|| format!("Could not remove the secret {name:?} from the system keyring"))?)
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn get(_: &str) -> Result<String> {
+    bail!(KEYRING_FEATURE_MISSING)
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn set(_: &str, _: &str) -> Result<()> {
+    bail!(KEYRING_FEATURE_MISSING)
+}
+
+#[cfg(not(feature = "keyring"))]
+pub fn remove(_: &str) -> Result<()> {
+    bail!(KEYRING_FEATURE_MISSING)
+}
@@ -0,0 +1,303 @@
+//! Non-Steam shortcuts of the games, written to the binary shortcuts.vdf of the Steam users so
+//! they can be launched from Big Picture and the Game Mode of the Steam Deck.
+
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::path::{Path, PathBuf};
+
+const MAP: u8 = 0;
+const STRING: u8 = 1;
+const INT: u8 = 2;
+const END: u8 = 8;
+
+/// Value of a binary VDF file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Value {
+    Map(Vec<(String, Value)>),
+    String(String),
+    Int(u32),
+}
+
+/// A shortcut of Steam launching a program outside of the Steam library.
+pub struct Shortcut<'a> {
+    pub name: &'a str,
+    pub exe: &'a Path,
+    pub start_dir: &'a Path,
+    pub launch_options: String,
+    /// Path of the icon, the one of `exe` if empty.
+    pub icon: &'a str,
+}
+
+impl Shortcut<'_> {
+    fn to_value(&self) -> Value {
+        let exe = format!("\"{}\"", self.exe.display());
+        // The id Steam gives to the shortcut, used by the artwork in the grid
+        let appid = crc32(format!("{exe}{}", self.name).as_bytes()) | 0x8000_0000;
+        let string = |s: &str| Value::String(s.to_owned());
+        let fields = [
+            ("appid", Value::Int(appid)),
+            ("AppName", string(self.name)),
+            ("Exe", Value::String(exe)),
+            (
+                "StartDir",
+                Value::String(format!("\"{}\"", self.start_dir.display())),
+            ),
+            ("icon", string(self.icon)),
+            ("ShortcutPath", string("")),
+            ("LaunchOptions", string(&self.launch_options)),
+            ("IsHidden", Value::Int(0)),
+            ("AllowDesktopConfig", Value::Int(1)),
+            ("AllowOverlay", Value::Int(1)),
+            ("OpenVR", Value::Int(0)),
+            ("Devkit", Value::Int(0)),
+            ("DevkitGameID", string("")),
+            ("DevkitOverrideAppID", Value::Int(0)),
+            ("LastPlayTime", Value::Int(0)),
+            ("FlatpakAppID", string("")),
+            ("tags", Value::Map(Vec::new())),
+        ];
+        Value::Map(Vec::from(fields.map(|(k, v)| (k.to_owned(), v))))
+    }
+}
+
+/// Returns the shortcut files of the Steam users, like `$STEAM/userdata/ID/config/shortcuts.vdf`.
+pub fn files() -> Vec<PathBuf> {
+    let Some(userdata) = crate::paths::steam_root().map(|root| root.join("userdata")) else {
+        return Vec::new();
+    };
+    let Ok(users) = std::fs::read_dir(userdata) else {
+        return Vec::new();
+    };
+    users
+        .flatten()
+        .map(|user| user.path().join("config"))
+        .filter(|config| config.is_dir())
+        .map(|config| config.join("shortcuts.vdf"))
+        .collect()
+}
+
+/// Returns whether Steam is running, which overwrites the shortcut files when it exits.
+pub fn steam_running() -> bool {
+    let Ok(processes) = std::fs::read_dir("/proc") else {
+        return false;
+    };
+    processes.flatten().any(|process| {
+        std::fs::read_to_string(process.path().join("comm"))
+            .is_ok_and(|comm| comm.trim() == "steam")
+    })
+}
+
+/// Adds `shortcut` to the shortcut `file`, replacing the one with the same name.
+pub fn add(file: &Path, shortcut: &Shortcut) -> Result<()> {
+    let mut shortcuts = read(file)?;
+    match shortcuts
+        .iter_mut()
+        .find(|s| name(s) == Some(shortcut.name))
+    {
+        Some(existing) => *existing = shortcut.to_value(),
+        None => shortcuts.push(shortcut.to_value()),
+    }
+    write(file, shortcuts)
+}
+
+/// Removes the shortcut called `shortcut` from the shortcut `file`, returning whether it was
+/// there.
+pub fn remove(file: &Path, shortcut: &str) -> Result<bool> {
+    let mut shortcuts = read(file)?;
+    let len = shortcuts.len();
+    shortcuts.retain(|s| name(s) != Some(shortcut));
+    if shortcuts.len() == len {
+        return Ok(false);
+    }
+    write(file, shortcuts)?;
+    Ok(true)
+}
+
+/// Returns the name of a shortcut, written as "AppName" or "appname" depending on the version of
+/// Steam.
+fn name(shortcut: &Value) -> Option<&str> {
+    let Value::Map(fields) = shortcut else {
+        return None;
+    };
+    fields.iter().find_map(|(key, value)| match value {
+        Value::String(name) if key.eq_ignore_ascii_case("appname") => Some(name.as_str()),
+        _ => None,
+    })
+}
+
+/// Returns the shortcuts of `file`, none if it doesn't exist.
+fn read(file: &Path) -> Result<Vec<Value>> {
+    let bytes = match std::fs::read(file) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context_with(|| format!("Could not read {}", file.display()))?,
+    };
+    let root = parse_map(&mut bytes.as_slice())
+        .context_with(|| format!("Could not parse {}", file.display()))?;
+    Ok(root
+        .into_iter()
+        .find_map(|(key, value)| match value {
+            Value::Map(shortcuts) if key.eq_ignore_ascii_case("shortcuts") => Some(shortcuts),
+            _ => None,
+        })
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, shortcut)| shortcut)
+        .collect())
+}
+
+/// Writes `shortcuts` to `file`, numbering them in order.
+///
+/// Steam drops every shortcut of a file it can't parse, so the new one is written next to it and
+/// renamed over it, and the previous one is kept as shortcuts.vdf.bak.
+fn write(file: &Path, shortcuts: Vec<Value>) -> Result<()> {
+    let shortcuts = shortcuts
+        .into_iter()
+        .enumerate()
+        .map(|(i, shortcut)| (i.to_string(), shortcut))
+        .collect();
+    let mut bytes = Vec::new();
+    write_map(
+        &[("shortcuts".to_owned(), Value::Map(shortcuts))],
+        &mut bytes,
+    );
+    let temp = file.with_extension("vdf.gg-tmp");
+    std::fs::write(&temp, bytes).context_with(|| format!("Could not write {}", temp.display()))?;
+    if file.exists() {
+        let backup = file.with_extension("vdf.bak");
+        if let Err(e) = std::fs::copy(file, &backup) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e).context_with(|| format!("Could not back up {}", file.display()))?;
+        }
+    }
+    if let Err(e) = std::fs::rename(&temp, file) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e).context_with(|| format!("Could not write {}", file.display()))?;
+    }
+    Ok(())
+}
+
+fn parse_map(bytes: &mut &[u8]) -> Result<Vec<(String, Value)>> {
+    let mut map = Vec::new();
+    loop {
+        let Some((&kind, rest)) = bytes.split_first() else {
+            bail!("The file ends in the middle of a map")
+        };
+        *bytes = rest;
+        if kind == END {
+            return Ok(map);
+        }
+        let key = parse_string(bytes)?;
+        let value = match kind {
+            MAP => Value::Map(parse_map(bytes)?),
+            STRING => Value::String(parse_string(bytes)?),
+            INT => {
+                let Some((int, rest)) = bytes.split_first_chunk::<4>() else {
+                    bail!("The file ends in the middle of the integer {key:?}")
+                };
+                *bytes = rest;
+                Value::Int(u32::from_le_bytes(*int))
+            }
+            kind => bail!("The value {key:?} has the unknown type {kind}"),
+        };
+        map.push((key, value));
+    }
+}
+
+fn parse_string(bytes: &mut &[u8]) -> Result<String> {
+    let Some(end) = bytes.iter().position(|&b| b == 0) else {
+        bail!("The file ends in the middle of a string")
+    };
+    let string = String::from_utf8(bytes[..end].to_vec()).context("Invalid UTF-8 string")?;
+    *bytes = &bytes[end + 1..];
+    Ok(string)
+}
+
+fn write_map(map: &[(String, Value)], bytes: &mut Vec<u8>) {
+    for (key, value) in map {
+        bytes.push(match value {
+            Value::Map(_) => MAP,
+            Value::String(_) => STRING,
+            Value::Int(_) => INT,
+        });
+        bytes.extend(key.as_bytes());
+        bytes.push(0);
+        match value {
+            Value::Map(map) => write_map(map, bytes),
+            Value::String(string) => {
+                bytes.extend(string.as_bytes());
+                bytes.push(0);
+            }
+            Value::Int(int) => bytes.extend(int.to_le_bytes()),
+        }
+    }
+    bytes.push(END);
+}
+
+/// CRC-32 of `bytes`, as computed by zlib.
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+            (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// shortcuts.vdf written by Steam, with a shortcut of an old version named by "appname".
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/shortcuts.vdf");
+
+    fn shortcuts_file() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shortcuts.vdf");
+        std::fs::copy(FIXTURE, &file).unwrap();
+        (dir, file)
+    }
+
+    #[test]
+    fn write_keeps_the_file_of_steam() {
+        let (_dir, file) = shortcuts_file();
+        let shortcuts = read(&file).unwrap();
+        let names = shortcuts.iter().map(name).collect::<Vec<_>>();
+        assert_eq!(names, [Some("Celeste"), Some("Hollow Knight")]);
+
+        write(&file, shortcuts.clone()).unwrap();
+        assert_eq!(read(&file).unwrap(), shortcuts);
+        let original = std::fs::read(FIXTURE).unwrap();
+        assert_eq!(std::fs::read(&file).unwrap(), original);
+        assert_eq!(
+            std::fs::read(file.with_extension("vdf.bak")).unwrap(),
+            original
+        );
+        assert!(!file.with_extension("vdf.gg-tmp").exists());
+    }
+
+    #[test]
+    fn add_and_remove_by_name() {
+        let (_dir, file) = shortcuts_file();
+        let shortcut = Shortcut {
+            name: "Hollow Knight",
+            exe: Path::new("/usr/bin/gg"),
+            start_dir: Path::new("/home/deck"),
+            launch_options: String::from("run hollow-knight"),
+            icon: "",
+        };
+        add(&file, &shortcut).unwrap();
+        let shortcuts = read(&file).unwrap();
+        assert_eq!(shortcuts.len(), 2);
+        assert_eq!(shortcuts[1], shortcut.to_value());
+
+        assert!(remove(&file, "Celeste").unwrap());
+        assert!(!remove(&file, "Celeste").unwrap());
+        assert_eq!(read(&file).unwrap(), [shortcut.to_value()]);
+    }
+
+    #[test]
+    fn missing_file_has_no_shortcuts() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read(&dir.path().join("shortcuts.vdf")).unwrap(), []);
+    }
+}
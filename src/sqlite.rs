@@ -0,0 +1,239 @@
+//! Games database stored in SQLite, along with the metadata of the backups, used instead of
+//! games.yaml and the metadata files of the backups directories when built with the "sqlite"
+//! feature.
+//!
+//! Each game is stored as its settings in YAML, like in games.yaml, next to the columns that can
+//! be queried: its name, root, save location and tags.
+
+use crate::games::{BackupMetadata, Game, Games};
+use rootcause::Result;
+use rootcause::prelude::*;
+use rusqlite::{Connection, OpenFlags, params};
+use std::{collections::BTreeMap, path::Path};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS games (
+    name TEXT PRIMARY KEY,
+    root TEXT NOT NULL,
+    save_location TEXT NOT NULL,
+    settings TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tags (
+    game TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (game, tag)
+);
+CREATE TABLE IF NOT EXISTS backups (
+    game TEXT NOT NULL,
+    name TEXT NOT NULL,
+    git_commit TEXT,
+    host TEXT,
+    time INTEGER,
+    pending_upload INTEGER NOT NULL DEFAULT 0,
+    save_size INTEGER,
+    repacked INTEGER,
+    PRIMARY KEY (game, name)
+);
+";
+
+/// Returns `true` if the file at `path` is a SQLite database, not a games.yaml.
+pub fn is_database(path: &Path) -> bool {
+    use std::io::Read;
+
+    let mut header = [0; 16];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| header == *b"SQLite format 3\0")
+}
+
+/// Opens the database at `path`, creating its tables if they don't exist.
+fn open(path: &Path) -> Result<Connection> {
+    let conn =
+        Connection::open(path).context_with(|| format!("Could not open {}", path.display()))?;
+    // Another gg may be writing it, as only the commands changing the games lock them
+    conn.busy_timeout(std::time::Duration::from_secs(10))?;
+    check_version(&conn, path)?;
+    conn.execute_batch(SCHEMA)
+        .context_with(|| format!("Could not create the tables of {}", path.display()))?;
+    // Added after the table was created by older versions
+    for column in ["save_size", "repacked"] {
+        if conn
+            .prepare(&format!("SELECT {column} FROM backups"))
+            .is_err()
+        {
+            conn.execute(
+                &format!("ALTER TABLE backups ADD COLUMN {column} INTEGER"),
+                [],
+            )
+            .context_with(|| format!("Could not update the tables of {}", path.display()))?;
+        }
+    }
+    conn.pragma_update(None, "user_version", crate::games::GAMES_VERSION)?;
+    Ok(conn)
+}
+
+fn check_version(conn: &Connection, path: &Path) -> Result<()> {
+    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    if version > crate::games::GAMES_VERSION {
+        bail!(
+            "{} is version {version}, which is newer than this gg supports, update gg to use it",
+            path.display()
+        )
+    }
+    Ok(())
+}
+
+/// Returns the path of the database in the data directory of gg.
+fn database_path() -> Result<std::path::PathBuf> {
+    Ok(crate::games::data_dir()?.join(Games::games_file_name()))
+}
+
+/// Reads the games of the database in `data_dir`.
+///
+/// The first time, the games of the games.yaml in `data_dir` are moved into it, keeping the old
+/// file as games.pre-sqlite.yaml.
+pub fn load(data_dir: &Path) -> Result<Vec<Game>> {
+    let path = data_dir.join(Games::games_file_name());
+    let mut conn = open(&path)?;
+    let yaml = data_dir.join(crate::games::GAMES_FILE);
+    let empty = conn.query_row("SELECT COUNT(*) FROM games", [], |row| row.get::<_, i64>(0))? == 0;
+    if empty && yaml.exists() {
+        let mut games = Games::read_games(&yaml)?;
+        games.sort_unstable();
+        write(&mut conn, &games)
+            .context_with(|| format!("Could not save to {}", path.display()))?;
+        let old_path = yaml.with_extension("pre-sqlite.yaml");
+        std::fs::rename(&yaml, &old_path)
+            .context_with(|| format!("Could not rename {}", yaml.display()))?;
+        eprintln!(
+            "Moved the games of {} into {}, the old file is kept in {}",
+            yaml.display(),
+            path.display(),
+            old_path.display()
+        );
+        return Ok(games);
+    }
+    read(&conn, &path)
+}
+
+/// Reads the games of the database at `path`, like the one of another machine, without changing
+/// it.
+pub fn read_games(path: &Path) -> Result<Vec<Game>> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .context_with(|| format!("Could not open {}", path.display()))?;
+    check_version(&conn, path)?;
+    read(&conn, path)
+}
+
+fn read(conn: &Connection, path: &Path) -> Result<Vec<Game>> {
+    let mut statement = conn.prepare("SELECT name, settings FROM games ORDER BY name")?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut games = Vec::new();
+    for row in rows {
+        let (name, settings) = row.context_with(|| format!("Could not read {}", path.display()))?;
+        games.push(
+            serde_saphyr::from_str::<Game>(&settings).context_with(|| {
+                format!("Could not parse the game {name:?} of {}", path.display())
+            })?,
+        );
+    }
+    Ok(games)
+}
+
+/// Replaces the games of the database at `path` with `games`.
+pub fn store(path: &Path, games: &[Game]) -> Result<()> {
+    let mut conn = open(path)?;
+    write(&mut conn, games).context_with(|| format!("Could not save to {}", path.display()))?;
+    Ok(())
+}
+
+fn write(conn: &mut Connection, games: &[Game]) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch("DELETE FROM games; DELETE FROM tags;")?;
+    {
+        let mut insert_game = tx.prepare(
+            "INSERT INTO games (name, root, save_location, settings) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        let mut insert_tag =
+            tx.prepare("INSERT OR IGNORE INTO tags (game, tag) VALUES (?1, ?2)")?;
+        for game in games {
+            let settings = serde_saphyr::to_string(game)
+                .context_with(|| format!("Could not serialize {}", game.name()))?;
+            insert_game.execute(params![
+                game.name(),
+                game.root().to_string_lossy(),
+                game.save_location().to_string_lossy(),
+                settings
+            ])?;
+            for tag in game.tags() {
+                insert_tag.execute(params![game.name(), tag])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns the metadata of the backups of `game`, or `None` if the database has none.
+pub fn backups_metadata(game: &str) -> Result<Option<BTreeMap<String, BackupMetadata>>> {
+    let conn = open(&database_path()?)?;
+    let mut statement = conn.prepare(
+        "SELECT name, git_commit, host, time, pending_upload, save_size, repacked FROM backups WHERE game = ?1",
+    )?;
+    let rows = statement.query_map([game], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            BackupMetadata {
+                commit: row.get(1)?,
+                host: row.get(2)?,
+                time: row.get::<_, Option<i64>>(3)?.map(|time| time as u64),
+                pending_upload: row.get(4)?,
+                save_size: row.get::<_, Option<i64>>(5)?.map(|size| size as u64),
+                repacked: row.get(6)?,
+            },
+        ))
+    })?;
+    let metadata = rows.collect::<rusqlite::Result<BTreeMap<_, _>>>()?;
+    Ok((!metadata.is_empty()).then_some(metadata))
+}
+
+/// Replaces the metadata of the backups of `game` with `metadata`.
+pub fn set_backups_metadata(game: &str, metadata: &BTreeMap<String, BackupMetadata>) -> Result<()> {
+    let path = database_path()?;
+    let mut conn = open(&path)?;
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM backups WHERE game = ?1", [game])?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO backups (game, name, git_commit, host, time, pending_upload, save_size, repacked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        for (name, m) in metadata {
+            insert.execute(params![
+                game,
+                name,
+                m.commit,
+                m.host,
+                m.time.map(|time| time as i64),
+                m.pending_upload,
+                m.save_size.map(|size| size as i64),
+                m.repacked
+            ])?;
+        }
+    }
+    tx.commit()
+        .context_with(|| format!("Could not save to {}", path.display()))?;
+    Ok(())
+}
+
+/// Moves the metadata of the backups of the game called `old_name` to `new_name`.
+pub fn rename_game(old_name: &str, new_name: &str) -> Result<()> {
+    let conn = open(&database_path()?)?;
+    conn.execute(
+        "UPDATE backups SET game = ?2 WHERE game = ?1",
+        [old_name, new_name],
+    )?;
+    Ok(())
+}
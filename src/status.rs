@@ -0,0 +1,280 @@
+use crate::games::unix_time;
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Number of failures kept in the log.
+const MAX_FAILURES: usize = 20;
+const FAILURES_FILE: &str = "failures.yaml";
+
+/// An operation in progress, like a backup or an upload.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Activity {
+    pub pid: u32,
+    pub game: String,
+    pub action: String,
+    /// Seconds since the Unix epoch when it started.
+    pub started: u64,
+}
+
+/// A command that finished with an error.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Failure {
+    /// Seconds since the Unix epoch when it failed.
+    pub time: u64,
+    pub command: String,
+    pub error: String,
+}
+
+/// Removes its activity from the status directory when dropped, or releases its lock.
+#[must_use = "the activity ends when the guard is dropped"]
+pub struct ActivityGuard {
+    path: Option<PathBuf>,
+    /// Lock file held with an advisory lock, released when closed.
+    lock: Option<std::fs::File>,
+}
+
+impl Drop for ActivityGuard {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+        // Emptied instead of removed, as another process may have opened it already
+        if let Some(lock) = &self.lock {
+            let _ = lock.set_len(0);
+        }
+    }
+}
+
+/// Directory shared by every gg process to report what it is doing.
+pub fn status_dir() -> PathBuf {
+    crate::paths::runtime_dir().unwrap_or_else(|| {
+        std::env::temp_dir()
+            .join(format!("gg-{}", crate::games::username()))
+            .join("goodgame")
+    })
+}
+
+/// Directory of the failures, kept across reboots unlike the status.
+fn failures_dir() -> PathBuf {
+    crate::paths::state_dir().unwrap_or_else(status_dir)
+}
+
+/// Reports that this process started `action` on `game`, until the returned guard is dropped.
+///
+/// The status is only informative, so failing to write it is not an error.
+pub fn begin(game: &str, action: &str) -> ActivityGuard {
+    crate::outcome::record_game(game);
+    let activity = Activity {
+        pid: std::process::id(),
+        game: game.to_owned(),
+        action: action.to_owned(),
+        started: unix_time(),
+    };
+    let dir = status_dir();
+    let path = dir.join(format!(
+        "{}-{}-{}.yaml",
+        activity.pid,
+        slug::slugify(action),
+        slug::slugify(game)
+    ));
+    let written = std::fs::create_dir_all(&dir).is_ok()
+        && serde_saphyr::to_string(&activity)
+            .is_ok_and(|contents| std::fs::write(&path, contents).is_ok());
+    ActivityGuard {
+        path: written.then_some(path),
+        lock: None,
+    }
+}
+
+/// Returns the activities in progress, oldest first.
+///
+/// Activities of processes that no longer exist are removed.
+pub fn activities() -> Vec<Activity> {
+    let Ok(entries) = std::fs::read_dir(status_dir()) else {
+        return Vec::new();
+    };
+    let mut activities = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().is_some_and(|e| e == "yaml")
+                && p.file_name().is_some_and(|n| n != FAILURES_FILE)
+        })
+        .filter_map(|path| {
+            let activity = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|a| serde_saphyr::from_str::<Activity>(&a).ok());
+            match activity {
+                Some(a) if Path::new("/proc").join(a.pid.to_string()).exists() => Some(a),
+                _ => {
+                    let _ = std::fs::remove_file(&path);
+                    None
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    activities.sort_by_key(|a| a.started);
+    activities
+}
+
+/// File of the lock of `game`, held by the process running, backing up or restoring it.
+fn lock_path(game: &str) -> PathBuf {
+    status_dir().join(format!("{}.lock", slug::slugify(game)))
+}
+
+/// Returns the operation of the other process that holds the lock of `game`, if any.
+pub fn lock_holder(game: &str) -> Option<Activity> {
+    let file = std::fs::File::open(lock_path(game)).ok()?;
+    match file.try_lock_shared() {
+        // Nobody holds it, the file only has what the last holder wrote
+        Ok(()) => None,
+        Err(_) => read_holder(&file).filter(|holder| holder.pid != std::process::id()),
+    }
+}
+
+/// Reads the activity that the holder of the lock `file` wrote into it.
+fn read_holder(mut file: &std::fs::File) -> Option<Activity> {
+    use std::io::{Read, Seek};
+
+    let mut contents = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    serde_saphyr::from_str(&contents).ok()
+}
+
+/// Prevents the other gg processes from running, backing up or restoring `game` until the
+/// returned guard is dropped, failing with the process that holds the lock.
+///
+/// The lock is an advisory lock of a file in the status directory, so it is released even if
+/// the process is killed. A process can lock a game it has already locked.
+pub fn lock(game: &str, action: &str) -> Result<ActivityGuard> {
+    use std::io::Write;
+
+    crate::outcome::record_game(game);
+    let activity = Activity {
+        pid: std::process::id(),
+        game: game.to_owned(),
+        action: action.to_owned(),
+        started: unix_time(),
+    };
+    let dir = status_dir();
+    std::fs::create_dir_all(&dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    let path = lock_path(game);
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .truncate(false)
+        .create(true)
+        .open(&path)
+        .context_with(|| format!("Could not open {}", path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(std::fs::TryLockError::WouldBlock) => {
+            // The holder writes its activity right after locking, so it may not be there yet
+            let holder = (0..10).find_map(|_| {
+                read_holder(&file).or_else(|| {
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    None
+                })
+            });
+            match holder {
+                Some(holder) if holder.pid == activity.pid => {
+                    return Ok(ActivityGuard {
+                        path: None,
+                        lock: None,
+                    });
+                }
+                Some(holder) => bail!(crate::error::Error::Locked {
+                    game: game.to_owned(),
+                    action: holder.action,
+                    pid: holder.pid,
+                }),
+                None => bail!("{game} is locked by another gg, wait for it to finish"),
+            }
+        }
+        Err(std::fs::TryLockError::Error(e)) => {
+            return Err(e).context_with(|| format!("Could not lock {game}"))?;
+        }
+    }
+    let contents = serde_saphyr::to_string(&activity).context("Could not serialize the lock")?;
+    file.set_len(0)
+        .and_then(|()| (&file).write_all(contents.as_bytes()))
+        .context_with(|| format!("Could not write {}", path.display()))?;
+    Ok(ActivityGuard {
+        path: None,
+        lock: Some(file),
+    })
+}
+
+/// Appends a failure of `command` to the log, keeping only the most recent ones.
+pub fn record_failure(command: String, error: String) -> Result<()> {
+    let mut failures = failures();
+    failures.push(Failure {
+        time: unix_time(),
+        command,
+        error,
+    });
+    failures.drain(..failures.len().saturating_sub(MAX_FAILURES));
+    let dir = failures_dir();
+    std::fs::create_dir_all(&dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    let contents =
+        serde_saphyr::to_string(&failures).context("Could not serialize the failures")?;
+    std::fs::write(dir.join(FAILURES_FILE), contents).context("Could not write the failures")?;
+    Ok(())
+}
+
+/// Returns the most recent failures, oldest first.
+pub fn failures() -> Vec<Failure> {
+    std::fs::read_to_string(failures_dir().join(FAILURES_FILE))
+        .ok()
+        .and_then(|f| serde_saphyr::from_str(&f).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn locks_the_game_for_the_other_processes() {
+        use std::io::Write;
+
+        let game = format!("Lock test {}", std::process::id());
+        {
+            let _lock = lock(&game, "backup").unwrap();
+            // This process can lock it again, and is not another holder
+            let _again = lock(&game, "restore").unwrap();
+            assert!(lock_holder(&game).is_none());
+        }
+
+        // Another process holds the lock, with its activity written into the file
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(lock_path(&game))
+            .unwrap();
+        file.try_lock().unwrap();
+        let other = Activity {
+            pid: 1,
+            game: game.clone(),
+            action: String::from("run"),
+            started: unix_time(),
+        };
+        file.write_all(serde_saphyr::to_string(&other).unwrap().as_bytes())
+            .unwrap();
+        assert_eq!(lock_holder(&game).map(|holder| holder.pid), Some(1));
+        let Err(e) = lock(&game, "backup") else {
+            panic!("{game} was locked twice")
+        };
+        assert!(
+            matches!(crate::error::kind(&e), Some(Error::Locked { action, pid: 1, .. }) if action == "run"),
+            "{e}"
+        );
+
+        drop(file);
+        assert!(lock_holder(&game).is_none());
+        drop(lock(&game, "backup").unwrap());
+        let _ = std::fs::remove_file(lock_path(&game));
+    }
+}
@@ -0,0 +1,48 @@
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::path::Path;
+
+/// Patterns of the files of gg that are local to the machine, so Syncthing must not share them.
+const IGNORED: &[&str] = &[".gg-*", ".*.part"];
+
+/// Marker of the copies Syncthing makes when two machines change the same file.
+const CONFLICT: &str = ".sync-conflict-";
+
+/// Adds the local files of gg to the .stignore of the synced folder `dir`, keeping its other
+/// patterns.
+pub fn init(dir: &Path) -> Result<()> {
+    let path = dir.join(".stignore");
+    let mut contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).context_with(|| format!("Could not read {}", path.display()))?,
+    };
+    let missing = IGNORED
+        .iter()
+        .filter(|pattern| !contents.lines().any(|line| line.trim() == **pattern))
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if !contents.is_empty() && !contents.ends_with('\n') {
+        contents.push('\n');
+    }
+    contents.push_str("// Local files of gg\n");
+    for pattern in missing {
+        contents.push_str(pattern);
+        contents.push('\n');
+    }
+    std::fs::write(&path, contents)
+        .context_with(|| format!("Could not write {}", path.display()))?;
+    Ok(())
+}
+
+/// Returns the name of the file that `name` is a conflict copy of, if it is one.
+///
+/// Syncthing names them like "game-005.tar.sync-conflict-20240102-150405-ABCDEFG.zst".
+pub fn conflict_original(name: &str) -> Option<String> {
+    let start = name.find(CONFLICT)?;
+    let rest = &name[start + CONFLICT.len()..];
+    let extension = rest.find('.').map_or("", |i| &rest[i..]);
+    Some(format!("{}{extension}", &name[..start]))
+}
@@ -0,0 +1,386 @@
+//! Human-friendly durations ("90m", "2h30m") and sizes ("1.5G"), shared by the config file and
+//! the command line.
+
+use std::{fmt, str::FromStr};
+
+/// Examples of durations, offered as completions and shown in the errors.
+pub const DURATION_EXAMPLES: &[&str] = &["30s", "15m", "1h", "2h30m", "1d", "1w"];
+/// Examples of sizes, offered as completions and shown in the errors.
+pub const SIZE_EXAMPLES: &[&str] = &["512K", "100M", "1.5G", "1T"];
+
+const DURATION_UNITS: [(&str, u64); 5] = [
+    ("w", 7 * 24 * 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("h", 60 * 60),
+    ("m", 60),
+    ("s", 1),
+];
+const SIZE_UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+/// A duration written as numbers followed by a unit (s, m, h, d or w), like "2h30m".
+///
+/// A number without unit is a number of seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub std::time::Duration);
+
+impl Duration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(std::time::Duration::from_secs(secs))
+    }
+}
+
+impl From<Duration> for std::time::Duration {
+    fn from(duration: Duration) -> Self {
+        duration.0
+    }
+}
+
+impl FromStr for Duration {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseError::new("duration", s, DURATION_EXAMPLES);
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(error());
+        }
+        if let Ok(secs) = s.parse::<f64>() {
+            return seconds(secs).ok_or_else(error);
+        }
+        let mut total = 0.0;
+        let mut rest = s;
+        while !rest.is_empty() {
+            let (number, after) = split_number(rest);
+            let unit_end = after
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(after.len());
+            let (unit, after) = after.split_at(unit_end);
+            let number = number.parse::<f64>().map_err(|_| error())?;
+            let Some((_, secs)) = DURATION_UNITS
+                .iter()
+                .find(|(name, _)| unit.eq_ignore_ascii_case(name))
+            else {
+                return Err(error());
+            };
+            total += number * *secs as f64;
+            rest = after.trim_start();
+        }
+        seconds(total).ok_or_else(error)
+    }
+}
+
+impl fmt::Display for Duration {
+    /// Writes the duration with the largest units first, like "2h30m".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Rounded to milliseconds first, so 0.9999s carries into the seconds instead of
+        // being written as "01.s"
+        let rounded = self
+            .0
+            .checked_add(std::time::Duration::from_micros(500))
+            .unwrap_or(self.0);
+        let mut secs = rounded.as_secs();
+        let millis = rounded.subsec_millis();
+        if secs == 0 && millis == 0 {
+            return f.write_str("0s");
+        }
+        for (name, unit) in &DURATION_UNITS[..DURATION_UNITS.len() - 1] {
+            if secs >= *unit {
+                write!(f, "{}{name}", secs / unit)?;
+                secs %= unit;
+            }
+        }
+        if millis != 0 {
+            let fraction = format!("{millis:03}");
+            write!(f, "{secs}.{}s", fraction.trim_end_matches('0'))
+        } else if secs != 0 {
+            write!(f, "{secs}s")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Duration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match NumberOrString::deserialize(deserializer)? {
+            NumberOrString::Number(secs) => Ok(Self::from_secs(secs)),
+            NumberOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl serde::Serialize for Duration {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl schemars::JsonSchema for Duration {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Duration".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "A number of seconds, or numbers followed by a unit (s, m, h, d or w)",
+            "type": ["integer", "string"],
+            "examples": DURATION_EXAMPLES,
+        })
+    }
+}
+
+/// A number of bytes written with an optional binary unit (K, M, G or T), like "1.5G".
+///
+/// The units are powers of 1024, with or without the "B" or "iB" suffix ("1.5GiB", "1.5GB").
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Size(pub u64);
+
+impl From<Size> for u64 {
+    fn from(size: Size) -> Self {
+        size.0
+    }
+}
+
+impl FromStr for Size {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseError::new("size", s, SIZE_EXAMPLES);
+        let (number, unit) = split_number(s.trim());
+        let number = number.parse::<f64>().map_err(|_| error())?;
+        let unit = unit.trim().to_ascii_uppercase();
+        let unit = unit
+            .strip_suffix("IB")
+            .or_else(|| unit.strip_suffix('B'))
+            .unwrap_or(&unit);
+        let exponent = match unit {
+            "" => 0,
+            "K" => 1,
+            "M" => 2,
+            "G" => 3,
+            "T" => 4,
+            _ => return Err(error()),
+        };
+        let bytes = number * 1024f64.powi(exponent);
+        if !bytes.is_finite() || bytes < 0.0 || bytes > u64::MAX as f64 {
+            return Err(error());
+        }
+        Ok(Self(bytes.round() as u64))
+    }
+}
+
+impl fmt::Display for Size {
+    /// Writes the size with a binary unit, like "1.5 MiB".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut size = self.0 as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{size:.1} {}", SIZE_UNITS[unit])
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Size {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match NumberOrString::deserialize(deserializer)? {
+            NumberOrString::Number(bytes) => Ok(Self(bytes)),
+            NumberOrString::String(s) => s.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl serde::Serialize for Size {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl schemars::JsonSchema for Size {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "Size".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "A number of bytes, with an optional unit (K, M, G or T)",
+            "type": ["integer", "string"],
+            "examples": SIZE_EXAMPLES,
+        })
+    }
+}
+
+/// A duration or size that could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    kind: &'static str,
+    value: String,
+    examples: &'static [&'static str],
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: &'static str, value: &str, examples: &'static [&'static str]) -> Self {
+        Self {
+            kind,
+            value: value.to_owned(),
+            examples,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid {} {:?}, expected something like {}",
+            self.kind,
+            self.value,
+            self.examples.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Config values that can be written as a plain number or with units.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum NumberOrString {
+    Number(u64),
+    String(String),
+}
+
+/// Splits `s` after the number it starts with.
+fn split_number(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+fn seconds(secs: f64) -> Option<Duration> {
+    std::time::Duration::try_from_secs_f64(secs)
+        .ok()
+        .map(Duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let cases = [
+            ("30", 30.0),
+            ("30s", 30.0),
+            ("1.5", 1.5),
+            ("15m", 15.0 * 60.0),
+            ("2h30m", 2.5 * 60.0 * 60.0),
+            ("2h 30m", 2.5 * 60.0 * 60.0),
+            ("1D", 24.0 * 60.0 * 60.0),
+            ("1w", 7.0 * 24.0 * 60.0 * 60.0),
+            ("0.5m", 30.0),
+        ];
+        for (input, secs) in cases {
+            assert_eq!(
+                input.parse::<Duration>(),
+                Ok(Duration(std::time::Duration::from_secs_f64(secs))),
+                "{input}"
+            );
+        }
+        for input in ["", "m", "1x", "1h-", "-1s", "1.2.3s"] {
+            assert!(input.parse::<Duration>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn displays_durations() {
+        let cases = [
+            (std::time::Duration::ZERO, "0s"),
+            (std::time::Duration::from_secs(45), "45s"),
+            (std::time::Duration::from_secs(60 * 60), "1h"),
+            (std::time::Duration::from_secs(9000), "2h30m"),
+            (
+                std::time::Duration::from_secs(8 * 24 * 60 * 60 + 1),
+                "1w1d1s",
+            ),
+            (std::time::Duration::from_millis(1500), "1.5s"),
+            (std::time::Duration::from_millis(60_250), "1m0.25s"),
+            (std::time::Duration::from_nanos(999_999_999), "1s"),
+            (std::time::Duration::from_nanos(59_999_999_999), "1m"),
+            (std::time::Duration::from_nanos(100), "0s"),
+        ];
+        for (duration, expected) in cases {
+            assert_eq!(Duration(duration).to_string(), expected, "{duration:?}");
+        }
+    }
+
+    #[test]
+    fn durations_round_trip() {
+        for example in DURATION_EXAMPLES {
+            let duration = example.parse::<Duration>().unwrap();
+            assert_eq!(duration.to_string(), *example);
+            assert_eq!(duration.to_string().parse(), Ok(duration));
+        }
+        for millis in [1, 10, 999, 1001, 61_500, 90_061_001] {
+            let duration = Duration(std::time::Duration::from_millis(millis));
+            assert_eq!(duration.to_string().parse(), Ok(duration), "{duration}");
+        }
+    }
+
+    #[test]
+    fn parses_sizes() {
+        let cases = [
+            ("0", 0),
+            ("512", 512),
+            ("512B", 512),
+            ("512K", 512 * 1024),
+            ("100 MB", 100 * 1024 * 1024),
+            ("1.5G", 3 * 512 * 1024 * 1024),
+            ("1.5gib", 3 * 512 * 1024 * 1024),
+            ("1T", 1024 * 1024 * 1024 * 1024),
+        ];
+        for (input, bytes) in cases {
+            assert_eq!(input.parse::<Size>(), Ok(Size(bytes)), "{input}");
+        }
+        for input in ["", "G", "1P", "1.5.5K", "-1K", "99999999999T"] {
+            assert!(input.parse::<Size>().is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn displays_sizes() {
+        let cases = [
+            (0, "0 B"),
+            (1023, "1023 B"),
+            (1024, "1.0 KiB"),
+            (3 * 512 * 1024 * 1024, "1.5 GiB"),
+            (u64::MAX, "16777216.0 TiB"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(Size(bytes).to_string(), expected, "{bytes}");
+        }
+    }
+
+    #[test]
+    fn sizes_round_trip() {
+        for bytes in [0, 1, 1023, 1024, 1536, 3 * 512 * 1024 * 1024] {
+            let display = Size(bytes).to_string();
+            assert_eq!(display.parse(), Ok(Size(bytes)), "{display}");
+        }
+    }
+
+    #[test]
+    fn deserializes_numbers_and_strings() {
+        let config: std::collections::BTreeMap<String, Duration> =
+            serde_saphyr::from_str("a: 90\nb: 1m30s").unwrap();
+        assert_eq!(config["a"], config["b"]);
+        let sizes: Vec<Size> = serde_saphyr::from_str("[1024, 1K, \"1 KiB\"]").unwrap();
+        assert_eq!(sizes, [Size(1024); 3]);
+    }
+}
@@ -0,0 +1,98 @@
+//! Watching of the save locations of the games, with which 'gg watch' backs up the saves that
+//! change outside 'gg run'.
+
+use crate::games::Game;
+use notify::Watcher;
+use notify::event::{AccessKind, AccessMode, EventKind, MetadataKind, ModifyKind};
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::{
+    collections::BTreeSet,
+    path::PathBuf,
+    sync::mpsc::{Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+/// Watches the save locations of some games for changes.
+pub struct SaveWatcher {
+    // Stops watching when dropped
+    _watcher: notify::RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// Watched save locations, with the name of their game.
+    saves: Vec<(PathBuf, String)>,
+}
+
+impl SaveWatcher {
+    /// Watches the save locations of `games`, with everything inside the directories.
+    ///
+    /// The saves that are a single file are watched through their directory, which must exist.
+    pub fn new<'a>(games: impl IntoIterator<Item = &'a Game>) -> Result<SaveWatcher> {
+        let (sender, events) = std::sync::mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Could not start watching the saves")?;
+        let mut saves = Vec::new();
+        for game in games {
+            let save = game.save_location();
+            let (watched, mode) = if save.is_file() {
+                let dir = save.parent().unwrap_or(save);
+                (dir, notify::RecursiveMode::NonRecursive)
+            } else {
+                (save, notify::RecursiveMode::Recursive)
+            };
+            watcher.watch(watched, mode).context_with(|| {
+                format!(
+                    "Could not watch the save of {}: {}",
+                    game.name(),
+                    save.display()
+                )
+            })?;
+            saves.push((save.to_path_buf(), game.name().to_owned()));
+        }
+        Ok(SaveWatcher {
+            _watcher: watcher,
+            events,
+            saves,
+        })
+    }
+
+    /// Waits up to `timeout` for changes, returning the names of the games whose save changed
+    /// then, empty if none did.
+    ///
+    /// Only writes count, reading the saves like a backup does is not a change.
+    pub fn changes(&self, timeout: Duration) -> Result<BTreeSet<String>> {
+        let mut changed = BTreeSet::new();
+        let mut next = match self.events.recv_timeout(timeout) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => return Ok(changed),
+            Err(RecvTimeoutError::Disconnected) => bail!("Stopped watching the saves"),
+        };
+        // The rest of the burst, as a save is usually several writes
+        loop {
+            let event = next.context("Could not watch the saves")?;
+            if is_write(&event.kind) {
+                for path in &event.paths {
+                    changed.extend(
+                        self.saves
+                            .iter()
+                            .filter(|(save, _)| path.starts_with(save))
+                            .map(|(_, name)| name.clone()),
+                    );
+                }
+            }
+            match self.events.try_recv() {
+                Ok(event) => next = event,
+                Err(_) => return Ok(changed),
+            }
+        }
+    }
+}
+
+fn is_write(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(MetadataKind::AccessTime)) => false,
+        EventKind::Modify(_) => true,
+        EventKind::Access(access) => *access == AccessKind::Close(AccessMode::Write),
+        EventKind::Any | EventKind::Other => false,
+    }
+}
@@ -0,0 +1,225 @@
+use base64::Engine;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use rootcause::Result;
+use rootcause::prelude::*;
+use std::path::Path;
+use ureq::http::{Request, Response, StatusCode};
+
+/// Characters escaped in the names of the uploaded files.
+const SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Client for the directory of a WebDAV server where the backups of a game are stored.
+pub struct Client {
+    agent: ureq::Agent,
+    url: String,
+    authorization: Option<String>,
+}
+
+impl Client {
+    pub fn new(url: &str, username: Option<&str>, password: Option<&str>) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .allow_non_standard_methods(true)
+            .http_status_as_error(false)
+            .build()
+            .into();
+        let authorization = username.map(|user| {
+            let credentials = format!("{user}:{}", password.unwrap_or_default());
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            )
+        });
+        Self {
+            agent,
+            url: url.trim_end_matches('/').to_owned(),
+            authorization,
+        }
+    }
+
+    /// Creates the directory and all its parents, doing nothing if they already exist.
+    pub fn init(&self) -> Result<()> {
+        let Some(start) = self.url.find("://").map(|i| i + 3) else {
+            bail!("Invalid WebDAV URL {:?}", self.url)
+        };
+        let path_start = self.url[start..]
+            .find('/')
+            .map(|i| start + i)
+            .unwrap_or(self.url.len());
+        let ends = self.url[path_start..]
+            .match_indices('/')
+            .skip(1)
+            .map(|(i, _)| path_start + i)
+            .chain(std::iter::once(self.url.len()));
+        for end in ends {
+            let url = format!("{}/", &self.url[..end]);
+            let response = self.send("MKCOL", &url, |r| r, ())?;
+            // 405 means the collection already exists
+            if !response.status().is_success()
+                && response.status() != StatusCode::METHOD_NOT_ALLOWED
+            {
+                bail!("Could not create {url}: {}", response.status())
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the names and sizes of the files in the directory.
+    pub fn list(&self) -> Result<Vec<(String, u64)>> {
+        let url = format!("{}/", self.url);
+        let response = self.send("PROPFIND", &url, |r| r.header("Depth", "1"), ())?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let response = check(response, || format!("Could not list {url}"))?;
+        let body = response
+            .into_body()
+            .read_to_string()
+            .context_with(|| format!("Could not read the listing of {url}"))?;
+
+        // Every entry has an <href> (with any namespace prefix) holding its path, followed by its
+        // properties until the next one
+        let parts = body.split("href>").skip(1).collect::<Vec<_>>();
+        let files = parts
+            .chunks(2)
+            .filter_map(|entry| {
+                let href = entry[0].split('<').next()?;
+                if href.ends_with('/') {
+                    return None;
+                }
+                let name = percent_decode_str(href.rsplit('/').next()?)
+                    .decode_utf8_lossy()
+                    .into_owned();
+                let size = entry
+                    .get(1)
+                    .and_then(|props| props.split("getcontentlength>").nth(1))
+                    .and_then(|len| len.split('<').next()?.trim().parse().ok())
+                    .unwrap_or(0);
+                Some((name, size))
+            })
+            .collect();
+        Ok(files)
+    }
+
+    /// Uploads the file at `path` to the directory, keeping its name.
+    pub fn upload(&self, path: &Path) -> Result<()> {
+        let url = self.file_url(path)?;
+        let file = std::fs::File::open(path)
+            .context_with(|| format!("Could not open {}", path.display()))?;
+        let response = self.send("PUT", &url, |r| r, file)?;
+        check(response, || format!("Could not upload {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Downloads the file called `name` into `dir`, failing if `name` is not a plain file name, as
+    /// the names come from the listing of the server.
+    pub fn download(&self, name: &str, dir: &Path) -> Result<()> {
+        let mut components = Path::new(name).components();
+        let plain = matches!(
+            (components.next(), components.next()),
+            (Some(std::path::Component::Normal(_)), None)
+        );
+        if !plain || name.contains(['/', '\\']) || name.contains("..") {
+            bail!("Refusing to download {name:?}, it is not a file name")
+        }
+        let url = format!("{}/{}", self.url, utf8_percent_encode(name, SEGMENT));
+        let response = self.send("GET", &url, |r| r, ())?;
+        let response = check(response, || format!("Could not download {url}"))?;
+
+        // Written to a hidden file first, so an interrupted download is not taken for a backup
+        let path = dir.join(name);
+        let tmp_path = dir.join(format!(".{name}.part"));
+        let mut file = std::fs::File::create(&tmp_path)
+            .context_with(|| format!("Could not create {}", tmp_path.display()))?;
+        let result = std::io::copy(&mut response.into_body().into_reader(), &mut file);
+        if result.is_err() {
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result.context_with(|| format!("Could not download {url}"))?;
+        std::fs::rename(&tmp_path, &path)
+            .context_with(|| format!("Could not create {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Removes the file called `name` from the directory, doing nothing if it doesn't exist.
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let url = format!("{}/{}", self.url, utf8_percent_encode(name, SEGMENT));
+        let response = self.send("DELETE", &url, |r| r, ())?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response, || format!("Could not remove {url}"))?;
+        }
+        Ok(())
+    }
+
+    fn file_url(&self, path: &Path) -> Result<String> {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            bail!("Invalid file name {}", path.display())
+        };
+        Ok(format!(
+            "{}/{}",
+            self.url,
+            utf8_percent_encode(name, SEGMENT)
+        ))
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        url: &str,
+        headers: impl FnOnce(ureq::http::request::Builder) -> ureq::http::request::Builder,
+        body: impl ureq::AsSendBody,
+    ) -> Result<Response<ureq::Body>> {
+        let mut request = Request::builder().method(method).uri(url);
+        if let Some(authorization) = &self.authorization {
+            request = request.header("Authorization", authorization);
+        }
+        let request = headers(request)
+            .body(body)
+            .context_with(|| format!("Invalid WebDAV URL {url:?}"))?;
+        Ok(self
+            .agent
+            .run(request)
+            .context_with(|| format!("Could not connect to {url}"))?)
+    }
+}
+
+/// Uploads the `backups` that are missing in the server.
+pub fn push(client: &Client, backups: &[crate::games::Backup]) -> Result<()> {
+    let remote = client.list()?;
+    for backup in backups {
+        let name = backup.name();
+        if remote.iter().any(|(r, _)| *r == name) {
+            continue;
+        }
+        crate::output::progress(format_args!("Uploading {name}"));
+        client.upload(&backup.path)?;
+    }
+    Ok(())
+}
+
+/// Downloads the backups in the server that are missing in `dir`, and the `refresh` files even if
+/// they exist.
+pub fn pull(client: &Client, dir: &Path, refresh: &[&str]) -> Result<()> {
+    std::fs::create_dir_all(dir).context_with(|| format!("Could not create {}", dir.display()))?;
+    for (name, _) in client.list()? {
+        if name.starts_with('.') || (dir.join(&name).exists() && !refresh.contains(&&*name)) {
+            continue;
+        }
+        crate::output::progress(format_args!("Downloading {name}"));
+        client.download(&name, dir)?;
+    }
+    Ok(())
+}
+
+fn check(
+    response: Response<ureq::Body>,
+    msg: impl FnOnce() -> String,
+) -> Result<Response<ureq::Body>> {
+    if !response.status().is_success() {
+        bail!("{}: {}", msg(), response.status())
+    }
+    Ok(response)
+}